@@ -0,0 +1,177 @@
+use crate::app::launch::{resolve_launch_target, LaunchTarget};
+use crate::config::load::ColorMode;
+use clap::{ArgGroup, Parser};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_MAX_PREVIEW_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: Option<usize>,
+}
+
+#[derive(Debug, Clone, Parser)]
+#[command(name = "fpv", version, about)]
+#[command(group(ArgGroup::new("output").args(["print", "export_html"])))]
+pub struct CliArgs {
+    /// Directory to open, or FILE[:LINE] to open with that file selected
+    #[arg(value_name = "PATH")]
+    pub target: Option<String>,
+
+    /// Show hidden files on startup
+    #[arg(short = 'a', long)]
+    pub show_hidden: bool,
+
+    /// Start with the selected file in fullscreen preview
+    #[arg(short = 'f', long)]
+    pub fullscreen: bool,
+
+    /// File to select on startup, relative to the opened directory (FILE[:LINE])
+    #[arg(short = 's', long, value_name = "FILE")]
+    pub select: Option<String>,
+
+    /// Maximum bytes read for a file preview (accepts K/M/G suffixes)
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value_t = DEFAULT_MAX_PREVIEW_BYTES,
+        value_parser = parse_byte_size
+    )]
+    pub max_preview_bytes: usize,
+
+    /// Disable git status indicators
+    #[arg(long)]
+    pub no_git: bool,
+
+    /// Do not capture the mouse (keeps terminal text selection working)
+    #[arg(long)]
+    pub no_mouse: bool,
+
+    /// Config file to use instead of ~/.config/fpv/config
+    #[arg(short = 'c', long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Print the highlighted FILE to stdout and exit without starting the TUI
+    #[arg(short = 'p', long, value_name = "FILE", conflicts_with_all = ["fullscreen", "select"])]
+    pub print: Option<PathBuf>,
+
+    /// Write FILE as a standalone highlighted HTML document to stdout and exit
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["fullscreen", "select", "print"]
+    )]
+    pub export_html: Option<PathBuf>,
+
+    /// Only export lines A-B (1-based, inclusive; `A-` runs to the end) (--export-html)
+    #[arg(
+        long,
+        value_name = "A-B",
+        requires = "export_html",
+        conflicts_with = "print",
+        value_parser = parse_line_range
+    )]
+    pub lines: Option<LineRange>,
+
+    /// Prefix output lines with line numbers (--print, --export-html)
+    #[arg(short = 'n', long, requires = "output")]
+    pub line_numbers: bool,
+
+    /// Wrap printed lines at COLS content columns (--print)
+    #[arg(
+        long,
+        value_name = "COLS",
+        requires = "print",
+        conflicts_with = "export_html",
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    pub wrap_width: Option<u16>,
+
+    /// When to use colors; overrides the `color` config option [default: auto]
+    #[arg(long, value_enum, value_name = "WHEN")]
+    pub color: Option<ColorMode>,
+}
+
+pub fn parse_byte_size(value: &str) -> Result<usize, String> {
+    let trimmed = value.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (digits, unit) = trimmed.split_at(split);
+    let base = digits
+        .parse::<usize>()
+        .map_err(|_| format!("invalid byte size: {value}"))?;
+    let multiplier: usize = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => 1024 * 1024,
+        "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+        _ => return Err(format!("invalid byte size unit: {value}")),
+    };
+    let bytes = base
+        .checked_mul(multiplier)
+        .ok_or_else(|| format!("byte size too large: {value}"))?;
+    if bytes == 0 {
+        return Err("byte size must be greater than zero".to_string());
+    }
+    Ok(bytes)
+}
+
+pub fn parse_line_range(value: &str) -> Result<LineRange, String> {
+    let parse = |part: &str| {
+        part.trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|line| *line > 0)
+            .ok_or_else(|| format!("invalid line range: {value}"))
+    };
+    let range = match value.split_once('-') {
+        Some((start, "")) => LineRange {
+            start: parse(start)?,
+            end: None,
+        },
+        Some((start, end)) => LineRange {
+            start: parse(start)?,
+            end: Some(parse(end)?),
+        },
+        None => {
+            let line = parse(value)?;
+            LineRange {
+                start: line,
+                end: Some(line),
+            }
+        }
+    };
+    if range.end.is_some_and(|end| end < range.start) {
+        return Err(format!("line range end is before start: {value}"));
+    }
+    Ok(range)
+}
+
+impl CliArgs {
+    /// Combines the positional path and `--select` into the startup location.
+    pub fn launch_target(&self) -> LaunchTarget {
+        let target = self
+            .target
+            .as_deref()
+            .map(resolve_launch_target)
+            .unwrap_or_else(|| LaunchTarget::directory(PathBuf::from(".")));
+        let Some(select) = &self.select else {
+            return target;
+        };
+        let joined = if Path::new(select).is_absolute() {
+            PathBuf::from(select)
+        } else {
+            target.root.join(select)
+        };
+        let selected = resolve_launch_target(&joined.to_string_lossy());
+        if selected.selected_file.is_none() {
+            return target;
+        }
+        LaunchTarget {
+            root: target.root,
+            ..selected
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::app::state::{PreviewDocument, SessionState};
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyTarget {
+    AbsolutePath,
+    RelativePath,
+    FileName,
+    PreviewSelection,
+    SelectionReference,
+}
+
+impl CopyTarget {
+    pub fn label(self) -> &'static str {
+        match self {
+            CopyTarget::AbsolutePath => "absolute path",
+            CopyTarget::RelativePath => "relative path",
+            CopyTarget::FileName => "file name",
+            CopyTarget::PreviewSelection => "preview selection",
+            CopyTarget::SelectionReference => "selection reference",
+        }
+    }
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+pub fn absolute_path(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return normalize_path(path);
+    }
+    std::env::current_dir()
+        .map(|cwd| normalize_path(&cwd.join(path)))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+pub fn path_relative_to_root(root: &Path, path: &Path) -> PathBuf {
+    let root = absolute_path(root);
+    let path = absolute_path(path);
+    match path.strip_prefix(&root) {
+        Ok(rel) if rel.as_os_str().is_empty() => PathBuf::from("."),
+        Ok(rel) => rel.to_path_buf(),
+        Err(_) => path,
+    }
+}
+
+/// Text of the visual selection when one is active, otherwise the whole preview.
+pub fn preview_selection_text(
+    doc: &PreviewDocument,
+    range: Option<(usize, usize)>,
+) -> Option<String> {
+    let lines = doc.source_lines();
+    if lines.is_empty() {
+        return None;
+    }
+    let Some((start, end)) = range else {
+        return Some(lines.join("\n"));
+    };
+    let selected = doc.source_lines_between(start, end);
+    if selected.is_empty() {
+        return None;
+    }
+    Some(selected.join("\n"))
+}
+
+pub fn selection_reference(root: &Path, path: &Path, range: (usize, usize)) -> String {
+    let rel = path_relative_to_root(root, path);
+    let (start, end) = (range.0 + 1, range.1 + 1);
+    if start == end {
+        format!("{}:{start}", rel.display())
+    } else {
+        format!("{}:{start}-{end}", rel.display())
+    }
+}
+
+pub fn copy_text_for_target(
+    state: &SessionState,
+    doc: &PreviewDocument,
+    target: CopyTarget,
+) -> Option<String> {
+    let path = &state.selected_path;
+    match target {
+        CopyTarget::AbsolutePath => Some(absolute_path(path).display().to_string()),
+        CopyTarget::RelativePath => Some(
+            path_relative_to_root(&state.root_path, path)
+                .display()
+                .to_string(),
+        ),
+        CopyTarget::FileName => path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string()),
+        CopyTarget::PreviewSelection => {
+            preview_selection_text(doc, state.preview_selection_range())
+        }
+        CopyTarget::SelectionReference => {
+            if doc.source_line_count() == 0 {
+                return None;
+            }
+            let range = state.preview_selection_range().unwrap_or((0, 0));
+            Some(selection_reference(&state.root_path, path, range))
+        }
+    }
+}
+
+pub fn copy_confirmation(target: CopyTarget, text: &str) -> String {
+    match target {
+        CopyTarget::PreviewSelection => {
+            let lines = text.split('\n').count();
+            let suffix = if lines == 1 { "" } else { "s" };
+            format!("Copied {} ({lines} line{suffix}).", target.label())
+        }
+        CopyTarget::AbsolutePath
+        | CopyTarget::RelativePath
+        | CopyTarget::FileName
+        | CopyTarget::SelectionReference => format!("Copied {}: {text}", target.label()),
+    }
+}
@@ -0,0 +1,67 @@
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchTarget {
+    pub root: PathBuf,
+    pub start_dir: PathBuf,
+    pub selected_file: Option<PathBuf>,
+    pub line: Option<usize>,
+}
+
+impl LaunchTarget {
+    pub fn directory(root: PathBuf) -> Self {
+        Self {
+            start_dir: root.clone(),
+            root,
+            selected_file: None,
+            line: None,
+        }
+    }
+}
+
+fn file_target(file: &Path, line: Option<usize>) -> LaunchTarget {
+    let root = match file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let selected_file = file.file_name().map(|name| root.join(name));
+    LaunchTarget {
+        start_dir: root.clone(),
+        root,
+        selected_file,
+        line,
+    }
+}
+
+/// Splits `path:line` or `path:line:column` (as printed by grep and compilers).
+fn split_line_suffix(arg: &str) -> Option<(&str, usize)> {
+    let (rest, last) = arg.rsplit_once(':')?;
+    let last_number = last.parse::<usize>().ok()?;
+    if let Some((path, line)) = rest.rsplit_once(':') {
+        if let Ok(line) = line.parse::<usize>() {
+            if Path::new(path).is_file() {
+                return Some((path, line));
+            }
+        }
+    }
+    Some((rest, last_number))
+}
+
+/// Resolves a positional command-line argument into the directory to open and
+/// an optional file (and line) to select on startup.
+pub fn resolve_launch_target(arg: &str) -> LaunchTarget {
+    let path = Path::new(arg);
+    if path.is_dir() {
+        return LaunchTarget::directory(path.to_path_buf());
+    }
+    if path.is_file() {
+        return file_target(path, None);
+    }
+    if let Some((file, line)) = split_line_suffix(arg) {
+        let file = Path::new(file);
+        if file.is_file() {
+            return file_target(file, Some(line.max(1)));
+        }
+    }
+    LaunchTarget::directory(path.to_path_buf())
+}
@@ -1,9 +1,14 @@
+pub mod cli;
+pub mod clipboard;
 pub mod current_dir_state;
 pub mod focus;
+pub mod launch;
 pub mod navigation;
 pub mod navigation_result;
 pub mod preview_controller;
 pub mod preview_errors;
+pub mod print;
 pub mod run;
+pub mod selection_pipe;
 pub mod state;
 pub mod status;
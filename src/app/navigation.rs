@@ -1,5 +1,6 @@
 use crate::app::navigation_result::NavigationActionResult;
 use crate::app::state::{NodeType, SessionState, TreeNode};
+use crate::fs::archive::is_archive_file;
 use crate::fs::current_dir::{
     is_filesystem_root, list_current_directory_with_visibility, parent_path,
 };
@@ -45,6 +46,13 @@ pub fn collapse_selected(nodes: &mut [TreeNode], selected_index: usize) {
     }
 }
 
+/// Whether `node` can be entered like a directory: directories, and archive
+/// files whose members are listed in place.
+pub fn is_enterable(node: &TreeNode) -> bool {
+    node.node_type == NodeType::Directory
+        || (node.node_type == NodeType::File && is_archive_file(&node.path))
+}
+
 pub fn enter_selected_directory(
     state: &mut SessionState,
     nodes: &mut Vec<TreeNode>,
@@ -57,7 +65,7 @@ pub fn enter_selected_directory(
         ));
     };
 
-    if node.node_type != NodeType::Directory {
+    if !is_enterable(node) {
         return Ok(NavigationActionResult::no_change(
             "enter_directory",
             state.current_path.clone(),
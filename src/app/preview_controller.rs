@@ -1,11 +1,21 @@
 use crate::app::state::{LoadState, NodeType, PreviewDocument, SessionState, TreeNode};
-use crate::fs::current_dir::{list_current_directory_with_visibility, selected_entry_metadata};
-use crate::fs::preview::load_preview;
+use crate::fs::archive::{archive_entries_with_cancel, is_archive_file, MAX_TAR_SCAN_BYTES};
+use crate::fs::background::BackgroundLoad;
+use crate::fs::current_dir::{
+    format_modified_timestamp, format_size, list_current_directory_with_visibility,
+    selected_entry_metadata,
+};
+use crate::fs::external::{previewer_for, start_external_preview};
+use crate::fs::preview::{start_preview, PendingPreview};
 use crate::highlight::syntax::HighlightContext;
 use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 use std::time::Instant;
 
 const DIRECTORY_PREVIEW_MAX_ENTRIES: usize = 2000;
+/// Shown while wrap is on for a preview that cannot wrap.
+pub const PAGED_WRAP_NOTICE: &str = "Wrap is off for files paged from disk.";
 
 fn directory_entry_label(node: &TreeNode) -> String {
     match node.node_type {
@@ -41,6 +51,81 @@ fn directory_preview(path: &Path, show_hidden: bool) -> PreviewDocument {
     }
 }
 
+/// Listing of every entry in an archive: a summary line, then one line per
+/// entry with its uncompressed size, modification time and path.
+fn archive_preview(path: &Path, cancel: &AtomicBool) -> PreviewDocument {
+    let listing = match archive_entries_with_cancel(path, cancel) {
+        Ok(listing) => listing,
+        Err(err) => {
+            return PreviewDocument {
+                source_path: path.to_path_buf(),
+                load_state: LoadState::Error,
+                error_message: Some(format!("Cannot read archive: {err}")),
+                ..PreviewDocument::default()
+            }
+        }
+    };
+    let entries = &listing.entries;
+    let files = entries.iter().filter(|entry| !entry.is_dir).count();
+    let total = entries.iter().map(|entry| entry.size).sum::<u64>();
+    let plural = |count: usize, one: &str, many: &str| {
+        if count == 1 {
+            format!("1 {one}")
+        } else {
+            format!("{count} {many}")
+        }
+    };
+    let mut lines = Vec::with_capacity(entries.len().saturating_add(2));
+    lines.push(format!(
+        "{}, {}, {} uncompressed",
+        plural(files, "file", "files"),
+        plural(entries.len() - files, "directory", "directories"),
+        format_size(total)
+    ));
+    if listing.partial {
+        lines.push(format!(
+            "Listing stops after the first {} of the archive.",
+            format_size(MAX_TAR_SCAN_BYTES)
+        ));
+    }
+    lines.push(String::new());
+    lines.extend(entries.iter().map(|entry| {
+        let size = if entry.is_dir {
+            String::new()
+        } else {
+            format_size(entry.size)
+        };
+        let modified = entry
+            .modified
+            .map(format_modified_timestamp)
+            .unwrap_or_default();
+        let suffix = if entry.is_dir { "/" } else { "" };
+        format!("{size:>10}  {modified:<17}  {}{suffix}", entry.path)
+    }));
+    PreviewDocument {
+        source_path: path.to_path_buf(),
+        load_state: LoadState::Ready,
+        content_excerpt: lines.join("\n"),
+        ..PreviewDocument::default()
+    }
+}
+
+/// Lists an archive in the background, loading until
+/// [`poll_pending_preview`](crate::fs::preview::poll_pending_preview) shows
+/// the listing.
+fn start_archive_preview(path: &Path) -> PreviewDocument {
+    let archive = path.to_path_buf();
+    PreviewDocument {
+        source_path: path.to_path_buf(),
+        load_state: LoadState::Loading,
+        content_excerpt: "Reading archive…".to_string(),
+        pending: Some(Arc::new(PendingPreview::Document(BackgroundLoad::spawn(
+            move |cancel| archive_preview(&archive, cancel),
+        )))),
+        ..PreviewDocument::default()
+    }
+}
+
 pub fn refresh_preview(
     state: &mut SessionState,
     nodes: &[TreeNode],
@@ -53,8 +138,13 @@ pub fn refresh_preview(
         state.selected_metadata = selected_entry_metadata(node);
         if node.node_type == NodeType::Directory {
             directory_preview(&node.path, state.show_hidden)
+        } else if let Some(previewer) = previewer_for(&state.external_previewers, &node.path) {
+            start_external_preview(&node.path, previewer, max_bytes)
+        } else if is_archive_file(&node.path) {
+            start_archive_preview(&node.path)
         } else {
-            load_preview(&node.path, max_bytes, ctx)
+            let forced = state.forced_encoding_for(&node.path);
+            start_preview(&node.path, max_bytes, ctx, forced)
         }
     } else {
         state.selected_metadata = Default::default();
@@ -65,5 +155,8 @@ pub fn refresh_preview(
         }
     };
     state.last_preview_latency_ms = started.elapsed().as_millis();
+    if preview.paged.is_some() && state.preview_wrap_enabled {
+        state.status_notice = Some(PAGED_WRAP_NOTICE.to_string());
+    }
     preview
 }
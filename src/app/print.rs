@@ -0,0 +1,237 @@
+use crate::app::cli::LineRange;
+use crate::app::state::{
+    ContentType, LoadState, PreviewDocument, StyledPreviewLine, StyledPreviewSegment,
+};
+use crate::export::ansi::{render_ansi_lines, AnsiRenderOptions};
+use crate::export::html::{render_html_document, HtmlExportOptions};
+use crate::fs::preview::load_preview_head;
+use crate::highlight::syntax::HighlightContext;
+use crate::highlight::theme::PageColors;
+use crate::highlight::viewport::{highlight_viewport, unstyled_line};
+use anyhow::{bail, Result};
+use ratatui::style::{Modifier, Style};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Styled lines for non-interactive output, without the TUI fallback notice.
+pub fn printable_lines(doc: &PreviewDocument) -> Result<Vec<StyledPreviewLine>> {
+    if matches!(doc.load_state, LoadState::Error | LoadState::Binary) {
+        bail!(
+            "{}: {}",
+            doc.source_path.display(),
+            doc.error_message
+                .clone()
+                .unwrap_or_else(|| "Unable to render preview".to_string())
+        );
+    }
+
+    let mut lines = if matches!(doc.content_type, ContentType::Highlighted) {
+        doc.styled_lines.clone()
+    } else {
+        doc.source_lines()
+            .into_iter()
+            .map(|text| {
+                if text.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![StyledPreviewSegment {
+                        text,
+                        style: Style::default(),
+                    }]
+                }
+            })
+            .collect()
+    };
+    if lines.len() > 1 && lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+    if doc.truncated {
+        lines.push(vec![StyledPreviewSegment {
+            text: "[truncated]".to_string(),
+            style: Style::default().add_modifier(Modifier::DIM),
+        }]);
+    }
+    Ok(lines)
+}
+
+/// Slices `lines` to a 1-based inclusive range, returning the first line number kept.
+pub fn select_line_range(
+    mut lines: Vec<StyledPreviewLine>,
+    range: Option<LineRange>,
+) -> Result<(Vec<StyledPreviewLine>, usize)> {
+    let Some(range) = range else {
+        return Ok((lines, 1));
+    };
+    if range.start > lines.len() {
+        bail!(
+            "line {} is past the end of the file ({} lines)",
+            range.start,
+            lines.len()
+        );
+    }
+    let end = range.end.unwrap_or(lines.len()).min(lines.len());
+    lines.truncate(end);
+    lines.drain(..range.start - 1);
+    Ok((lines, range.start))
+}
+
+/// Lines of a paged preview in `range`, read from disk and styled where the
+/// loaded window covers them.
+fn paged_line_range(
+    doc: &PreviewDocument,
+    range: LineRange,
+) -> Result<(Vec<StyledPreviewLine>, usize)> {
+    let start = range.start.max(1) - 1;
+    let end = range.end.map_or(usize::MAX, |end| end.saturating_sub(1));
+    let texts = doc.source_lines_between(start, end);
+    if texts.is_empty() {
+        bail!(
+            "line {} is past the end of the file ({} lines)",
+            range.start,
+            doc.source_line_count()
+        );
+    }
+    let offset = doc.line_offset();
+    let lines = texts
+        .iter()
+        .enumerate()
+        .map(|(index, text)| {
+            (start + index)
+                .checked_sub(offset)
+                .and_then(|held| doc.styled_lines.get(held))
+                .cloned()
+                .unwrap_or_else(|| unstyled_line(text))
+        })
+        .collect();
+    Ok((lines, start + 1))
+}
+
+fn export_title(doc: &PreviewDocument, first_line: usize, line_count: usize) -> String {
+    let name = doc
+        .source_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| doc.source_path.display().to_string());
+    if first_line == 1 {
+        name
+    } else {
+        format!(
+            "{name}:{first_line}-{}",
+            first_line + line_count.saturating_sub(1)
+        )
+    }
+}
+
+pub fn html_for_document(
+    doc: &PreviewDocument,
+    range: Option<LineRange>,
+    line_numbers: bool,
+    colors: PageColors,
+) -> Result<String> {
+    let (lines, first_line_number) = match (&doc.paged, range) {
+        (Some(_), Some(range)) => paged_line_range(doc, range)?,
+        (Some(_), None) => (printable_lines(doc)?, doc.line_offset() + 1),
+        (None, _) => select_line_range(printable_lines(doc)?, range)?,
+    };
+    let title = export_title(doc, first_line_number, lines.len());
+    Ok(render_html_document(
+        &title,
+        &lines,
+        HtmlExportOptions {
+            line_numbers,
+            first_line_number,
+            colors,
+        },
+    ))
+}
+
+pub fn export_html(
+    path: &Path,
+    max_bytes: usize,
+    ctx: &HighlightContext,
+    range: Option<LineRange>,
+    line_numbers: bool,
+    out: &mut impl Write,
+) -> Result<()> {
+    if !path.is_file() {
+        bail!("not a file: {}", path.display());
+    }
+    let mut doc = load_preview_head(path, max_bytes, ctx, None);
+    highlight_viewport(&mut doc, ctx, 0..usize::MAX);
+    let colors = ctx.syntax_theme().page_colors();
+    out.write_all(html_for_document(&doc, range, line_numbers, colors)?.as_bytes())?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Writes the HTML export of a preview into `dir` and returns the written path.
+pub fn export_html_file(
+    doc: &PreviewDocument,
+    range: Option<LineRange>,
+    line_numbers: bool,
+    colors: PageColors,
+    dir: &Path,
+) -> Result<PathBuf> {
+    let html = html_for_document(doc, range, line_numbers, colors)?;
+    let stem = doc
+        .source_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| "preview".to_string());
+    let file_name = match range {
+        Some(LineRange {
+            start,
+            end: Some(end),
+        }) => format!("{stem}.{start}-{end}.html"),
+        _ => format!("{stem}.html"),
+    };
+    create_private_dir(dir)?;
+    let target = dir.join(file_name);
+    std::fs::write(&target, html)?;
+    Ok(target)
+}
+
+/// Creates `dir` and any missing parents, readable only by the user.
+fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    let mut builder = std::fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+    builder.create(dir)
+}
+
+/// Where the TUI writes HTML exports: `$XDG_CACHE_HOME/fpv/export`, or
+/// `~/.cache/fpv/export`. Both belong to the user, unlike a directory in a
+/// shared `/tmp` that anyone could create first.
+pub fn default_export_dir() -> Result<PathBuf> {
+    let cache = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| {
+            std::env::var_os("HOME")
+                .filter(|home| !home.is_empty())
+                .map(|home| PathBuf::from(home).join(".cache"))
+        });
+    match cache {
+        Some(cache) => Ok(cache.join("fpv").join("export")),
+        None => bail!("no cache directory; set HOME or XDG_CACHE_HOME"),
+    }
+}
+
+pub fn print_preview(
+    path: &Path,
+    max_bytes: usize,
+    ctx: &HighlightContext,
+    options: AnsiRenderOptions,
+    out: &mut impl Write,
+) -> Result<()> {
+    if !path.is_file() {
+        bail!("not a file: {}", path.display());
+    }
+    let mut doc = load_preview_head(path, max_bytes, ctx, None);
+    highlight_viewport(&mut doc, ctx, 0..usize::MAX);
+    let lines = printable_lines(&doc)?;
+    out.write_all(render_ansi_lines(&lines, options).as_bytes())?;
+    out.flush()?;
+    Ok(())
+}
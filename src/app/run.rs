@@ -1,22 +1,44 @@
+use crate::app::cli::CliArgs;
 use crate::app::preview_controller::refresh_preview;
-use crate::app::state::SessionState;
-use crate::config::keymap::{default_keymap, UserKeymap};
+use crate::app::print::{export_html, print_preview};
+use crate::app::selection_pipe::poll_selection_pipe;
+use crate::app::state::{FocusPane, NodeType, SessionState};
+use crate::config::icons::compile_icon_theme;
+use crate::config::keymap::{default_keymap, Action, UserKeymap};
 use crate::config::load::{
-    default_config_path, ensure_default_config_exists, load_user_config, StatusDisplayMode,
-    ThemeProfile, UserConfig,
+    default_config_path, ensure_default_config_exists, load_theme_color_scheme, load_user_config,
+    ColorMode, ImageProtocolMode, StatusDisplayMode, ThemeProfile,
 };
-use crate::config::merge::{merge_keymaps, merge_theme_profile};
+use crate::config::ls_colors::LsColors;
+use crate::config::merge::{merge_keymaps, merge_syntax_theme, merge_theme_profile};
+use crate::config::style_rules::compile_style_rules;
 use crate::config::validate::validate_bindings;
+use crate::export::ansi::AnsiRenderOptions;
 use crate::fs::current_dir::list_current_directory_with_visibility;
+use crate::fs::external::{
+    compile_previewers, poll_external_preview, ExternalPreviewer, DEFAULT_PREVIEWER_TIMEOUT,
+};
 use crate::fs::git::git_repo_status_for_path;
+use crate::fs::preview::{page_viewport, poll_pending_preview};
+use crate::highlight::detect::compile_language_mappings;
+use crate::highlight::markdown::render_markdown_view;
+use crate::highlight::structured::structured_view;
 use crate::highlight::syntax::HighlightContext;
+use crate::highlight::viewport::highlight_viewport;
+use crate::tui::color_support::{adapt_buffer, resolve_color_support, ColorEnvironment};
 use crate::tui::event_loop::process_once;
-use crate::tui::preview_pane::{draw_preview, preview_total_lines};
+use crate::tui::image::{repaint_cells, resolve_image_protocol, ImageEnvironment, ImageGraphics};
+use crate::tui::preview_pane::{
+    draw_preview, preview_header_rows, preview_image_area, preview_total_lines,
+    refresh_wrapped_rows,
+};
 use crate::tui::status_bar::{compose_shortcut_help_text, draw_status};
 use crate::tui::tree_pane::{draw_current_directory_header, draw_tree};
-use anyhow::Result;
+use anyhow::{bail, Result};
+use clap::Parser;
 use crossterm::event::DisableMouseCapture;
 use crossterm::event::EnableMouseCapture;
+use crossterm::event::KeyEvent;
 use crossterm::execute;
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
@@ -25,55 +47,84 @@ use ratatui::backend::CrosstermBackend;
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
 use ratatui::Terminal;
-use std::env;
-use std::io;
+use std::collections::HashMap;
+use std::io::{self, IsTerminal};
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
 
-fn parse_args() -> (PathBuf, Option<PathBuf>) {
-    let mut root = PathBuf::from(".");
-    let mut cfg = None;
-    let mut args = env::args().skip(1);
-
-    while let Some(arg) = args.next() {
-        if arg == "--config" {
-            if let Some(v) = args.next() {
-                cfg = Some(PathBuf::from(v));
-            }
-        } else if !arg.starts_with("--") {
-            root = PathBuf::from(arg);
-        }
-    }
-
-    (root, cfg)
+struct LoadedSettings {
+    bindings: HashMap<Action, KeyEvent>,
+    theme: ThemeProfile,
+    highlight: HighlightContext,
+    status_mode: StatusDisplayMode,
+    selection_pipe_command: Option<String>,
+    color_mode: ColorMode,
+    image_protocol: ImageProtocolMode,
+    previewers: Vec<ExternalPreviewer>,
+    warnings: Vec<String>,
 }
 
-fn load_bindings_and_theme(
-    config_path: Option<PathBuf>,
-) -> (
-    std::collections::HashMap<crate::config::keymap::Action, crossterm::event::KeyEvent>,
-    ThemeProfile,
-    StatusDisplayMode,
-    Vec<String>,
-) {
+fn load_settings(config_path: Option<PathBuf>, interactive: bool) -> LoadedSettings {
     let defaults = default_keymap();
     let using_default_path = config_path.is_none();
     let path = config_path.unwrap_or_else(default_config_path);
-    if using_default_path {
+    if using_default_path && interactive {
         let _ = ensure_default_config_exists(&path);
     }
-    let user_config = load_user_config(&path).unwrap_or(UserConfig {
-        mappings: Default::default(),
-        theme: Default::default(),
-        status_display_mode: None,
-    });
+    let user_config = load_user_config(&path).unwrap_or_default();
     let user_keymap = UserKeymap {
         mappings: user_config.mappings,
     };
     let status_mode = user_config.status_display_mode.unwrap_or_default();
     let (merged, mut warnings) = merge_keymaps(defaults, &user_keymap);
-    let theme = merge_theme_profile(ThemeProfile::default(), &user_config.theme);
+    let imported = load_theme_color_scheme(&user_config.theme, &path).unwrap_or_else(|err| {
+        warnings.push(format!("color scheme ignored: {err:#}"));
+        None
+    });
+    let base_theme = match &imported {
+        Some(scheme) => scheme.apply_to_profile(ThemeProfile::default()),
+        None => ThemeProfile::default(),
+    };
+    let mut theme = merge_theme_profile(base_theme, &user_config.theme);
+    if user_config.theme.use_ls_colors.unwrap_or(false) {
+        theme.ls_colors = Some(LsColors::from_env());
+    }
+    let (rules, rule_warnings) = compile_style_rules(&user_config.theme.rules);
+    theme.rules = rules;
+    warnings.extend(rule_warnings);
+    let (icons, icon_warnings) = compile_icon_theme(&user_config.theme.icons);
+    theme.icons = icons;
+    warnings.extend(icon_warnings);
+    let (syntax_theme, syntax_warnings) = merge_syntax_theme(&user_config.theme, imported.as_ref());
+    warnings.extend(syntax_warnings);
+    let (language_mappings, mapping_warnings) =
+        compile_language_mappings(&user_config.language_mappings);
+    warnings.extend(mapping_warnings);
+    let mut highlight = HighlightContext::new()
+        .with_syntax_theme(syntax_theme)
+        .with_language_mappings(language_mappings);
+    warnings.extend(highlight.load_custom_grammars(&user_config.grammars, &path));
+    warnings.extend(highlight.load_query_overrides(&path));
+    let (previewers, previewer_warnings) = compile_previewers(
+        &user_config.previewers,
+        user_config
+            .previewer_timeout_ms
+            .map_or(DEFAULT_PREVIEWER_TIMEOUT, Duration::from_millis),
+    );
+    warnings.extend(previewer_warnings);
     warnings.extend(validate_bindings(&merged));
-    (merged, theme, status_mode, warnings)
+    LoadedSettings {
+        bindings: merged,
+        theme,
+        highlight,
+        status_mode,
+        selection_pipe_command: user_config.selection_pipe_command,
+        color_mode: user_config.color.unwrap_or_default(),
+        image_protocol: user_config.image_protocol.unwrap_or_default(),
+        previewers,
+        warnings,
+    }
 }
 
 fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
@@ -96,19 +147,123 @@ fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
         .split(vertical[1])[1]
 }
 
+/// Rows of the frame: directory header, panes and status bar.
+fn frame_rows(size: Rect) -> Rc<[Rect]> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(1),
+        ])
+        .split(size)
+}
+
+/// Tree and preview panes side by side in `panes`.
+fn pane_columns(state: &SessionState, panes: Rect) -> Rc<[Rect]> {
+    let (tree_width, preview_width) = state.panel_widths(panes.width);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Length(tree_width),
+            Constraint::Length(preview_width),
+        ])
+        .split(panes)
+}
+
+/// Where the preview pane is drawn in a frame of `size`.
+fn preview_pane_area(state: &SessionState, size: Rect) -> Rect {
+    let panes = frame_rows(size)[1];
+    if state.preview_fullscreen {
+        panes
+    } else {
+        pane_columns(state, panes)[1]
+    }
+}
+
 pub fn run() -> Result<()> {
-    let (root, cfg_path) = parse_args();
-    let mut state = SessionState::new(root);
+    let args = CliArgs::parse();
+    let LoadedSettings {
+        bindings,
+        theme,
+        highlight,
+        status_mode,
+        selection_pipe_command,
+        color_mode,
+        image_protocol,
+        previewers,
+        warnings,
+    } = load_settings(
+        args.config.clone(),
+        args.print.is_none() && args.export_html.is_none(),
+    );
+    let color_mode = args.color.unwrap_or(color_mode);
+    let color_env = ColorEnvironment::from_env();
+    if let Some(path) = &args.print {
+        let stdout = io::stdout();
+        let options = AnsiRenderOptions {
+            color: resolve_color_support(color_mode, stdout.is_terminal(), &color_env),
+            line_numbers: args.line_numbers,
+            wrap_width: args.wrap_width.map(usize::from),
+        };
+        return print_preview(
+            path,
+            args.max_preview_bytes,
+            &highlight,
+            options,
+            &mut stdout.lock(),
+        );
+    }
+    if let Some(path) = &args.export_html {
+        return export_html(
+            path,
+            args.max_preview_bytes,
+            &highlight,
+            args.lines,
+            args.line_numbers,
+            &mut io::stdout().lock(),
+        );
+    }
+    let target = args.launch_target();
+    if !target.start_dir.is_dir() {
+        bail!("not a directory or file: {}", target.start_dir.display());
+    }
+    let mut state = SessionState::new(target.root.clone());
+    state.current_path = target.start_dir.clone();
+    state.show_hidden = args.show_hidden;
     let mut nodes =
         list_current_directory_with_visibility(&state.current_path, 2000, state.show_hidden)?;
     state.revalidate_selection(&nodes);
+    if target.selected_file.is_some() {
+        state.restore_or_default_selection(&nodes, target.selected_file.as_ref());
+    }
     state.update_selected_path(&nodes);
 
-    let highlight = HighlightContext::new();
-    let (bindings, theme, status_mode, warnings) = load_bindings_and_theme(cfg_path);
     state.status_display_mode = status_mode;
-    state.git_status = git_repo_status_for_path(&state.current_path);
-    let mut preview = refresh_preview(&mut state, &nodes, &highlight, 1024 * 1024);
+    state.selection_pipe_command = selection_pipe_command;
+    state.external_previewers = previewers;
+    state.page_colors = highlight.syntax_theme().page_colors();
+    let git_enabled = !args.no_git;
+    if git_enabled {
+        state.git_status = git_repo_status_for_path(&state.current_path);
+    }
+    let max_preview_bytes = args.max_preview_bytes;
+    let mut preview = refresh_preview(&mut state, &nodes, &highlight, max_preview_bytes);
+    if args.fullscreen
+        && nodes
+            .get(state.selected_index)
+            .is_some_and(|node| node.node_type == NodeType::File)
+    {
+        state.preview_fullscreen = true;
+        state.focus_pane = FocusPane::Preview;
+    }
+    if let Some(line) = target.line {
+        state.go_to_preview_line(
+            line,
+            preview.source_line_count(),
+            preview_header_rows(&preview),
+        );
+    }
     state.status_message = if warnings.is_empty() {
         format!("Ready. Path: {}", state.current_path.display())
     } else {
@@ -121,7 +276,14 @@ pub fn run() -> Result<()> {
 
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen)?;
+    let mouse_enabled = !args.no_mouse;
+    if mouse_enabled {
+        execute!(stdout, EnableMouseCapture)?;
+    }
+    let color_support = resolve_color_support(color_mode, true, &color_env);
+    state.image_protocol = resolve_image_protocol(image_protocol, &ImageEnvironment::from_env());
+    let mut graphics = ImageGraphics::new(state.image_protocol);
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
@@ -129,19 +291,56 @@ pub fn run() -> Result<()> {
         let frame_size = terminal.size()?;
         state.normalize_preview_width(frame_size.width);
         let preview_viewport_rows = frame_size.height.saturating_sub(4) as usize;
+        poll_external_preview(&mut preview);
+        poll_pending_preview(&mut preview, &highlight);
+        poll_selection_pipe(&mut state);
+        if state.preview_render_markdown {
+            let preview_cols = if state.preview_fullscreen {
+                frame_size.width
+            } else {
+                state.panel_widths(frame_size.width).1
+            };
+            // Borders and the scroll indicator take three columns.
+            render_markdown_view(
+                &mut preview,
+                &highlight,
+                preview_cols.saturating_sub(3).into(),
+            );
+        }
+        if !state.preview_table_view {
+            preview.table = None;
+        }
+        if state.preview_structured {
+            let folds = state.structured_folds_for(&preview.source_path);
+            if let Some(err) = structured_view(&mut preview, &highlight, &folds) {
+                state.status_notice = Some(err);
+            }
+        }
+        let image_area = preview_image_area(preview_pane_area(&state, frame_size));
+        refresh_wrapped_rows(&mut preview, &state, image_area.width);
         let total_preview_lines = preview_total_lines(&preview);
         state.clamp_preview_scroll(total_preview_lines, preview_viewport_rows);
+        let first_row = state.preview_scroll_row;
+        let visible_rows = first_row..first_row + preview_viewport_rows;
+        let visible_lines = match &preview.wrapped_rows {
+            Some(rows) => rows.lines_in_rows(visible_rows),
+            None => visible_rows,
+        };
+        page_viewport(&mut preview, visible_lines.clone());
+        highlight_viewport(&mut preview, &highlight, visible_lines);
+
+        let wanted_image = preview
+            .image
+            .as_ref()
+            .filter(|_| !state.help_overlay_visible);
+        graphics.clear_stale(
+            &mut terminal,
+            wanted_image.map(|_| (preview.source_path.as_path(), image_area)),
+        )?;
 
-        terminal.draw(|f| {
+        let frame = terminal.draw(|f| {
             f.render_widget(Clear, f.size());
-            let chunks = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([
-                    Constraint::Length(1),
-                    Constraint::Min(3),
-                    Constraint::Length(1),
-                ])
-                .split(f.size());
+            let chunks = frame_rows(f.size());
             f.render_widget(Clear, chunks[1]);
 
             if state.layout_regions.top_directory_header && !state.preview_fullscreen {
@@ -151,20 +350,11 @@ pub fn run() -> Result<()> {
             if state.preview_fullscreen {
                 draw_preview(f, chunks[1], &preview, &state, &theme);
             } else {
-                let main = Layout::default()
-                    .direction(Direction::Horizontal)
-                    .constraints({
-                        let (tree_width, preview_width) = state.panel_widths(chunks[1].width);
-                        [
-                            Constraint::Length(tree_width),
-                            Constraint::Length(preview_width),
-                        ]
-                    })
-                    .split(chunks[1]);
+                let main = pane_columns(&state, chunks[1]);
                 draw_tree(f, main[0], &nodes, &state, &theme);
                 draw_preview(f, main[1], &preview, &state, &theme);
             }
-            draw_status(f, chunks[2], &state, &bindings);
+            draw_status(f, chunks[2], &state, &bindings, &theme);
 
             if state.help_overlay_visible {
                 let modal = centered_rect(72, 78, f.size());
@@ -180,33 +370,45 @@ pub fn run() -> Result<()> {
                 f.render_widget(Clear, modal);
                 f.render_widget(help, modal);
             }
+            adapt_buffer(f.buffer_mut(), color_support);
         })?;
+        let stale = graphics.take_stale_cells(frame.buffer);
+        repaint_cells(terminal.backend_mut(), &stale)?;
+        if let Some(image) = wanted_image {
+            graphics.show(
+                terminal.backend_mut(),
+                &preview.source_path,
+                image,
+                image_area,
+            )?;
+        }
 
         let previous_path = state.current_path.clone();
         let (should_quit, should_refresh_preview) = process_once(
             &mut state,
             &mut nodes,
             &bindings,
+            &preview,
             total_preview_lines,
             preview_viewport_rows,
         )?;
         if should_quit {
             break;
         }
-        if state.current_path != previous_path {
+        if git_enabled && state.current_path != previous_path {
             state.git_status = git_repo_status_for_path(&state.current_path);
         }
         if should_refresh_preview {
-            preview = refresh_preview(&mut state, &nodes, &highlight, 1024 * 1024);
+            preview = refresh_preview(&mut state, &nodes, &highlight, max_preview_bytes);
         }
     }
 
+    graphics.clear_stale(&mut terminal, None)?;
     disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
+    if mouse_enabled {
+        execute!(terminal.backend_mut(), DisableMouseCapture)?;
+    }
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
     terminal.show_cursor()?;
     Ok(())
 }
@@ -0,0 +1,70 @@
+use crate::app::state::SessionState;
+use crate::fs::external::ExternalRun;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Time a `selection_pipe_command` gets before it is killed.
+pub const SELECTION_PIPE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Bytes of output kept; only its first line is shown.
+const MAX_PIPE_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// A preview selection being piped to a command in the background.
+#[derive(Debug, Clone)]
+pub struct SelectionPipe {
+    pub command: String,
+    line_count: usize,
+    run: Arc<ExternalRun>,
+}
+
+impl SelectionPipe {
+    /// Status message for the finished command; `None` while it runs.
+    pub fn poll(&self) -> Option<String> {
+        Some(match self.run.take_output()? {
+            Ok(stdout) => pipe_confirmation(
+                &self.command,
+                self.line_count,
+                stdout.lines().next().unwrap_or("").trim(),
+            ),
+            Err(err) => format!("Pipe failed: {err}"),
+        })
+    }
+}
+
+/// Starts `command` with `text` on its stdin; dropping the returned pipe
+/// kills it.
+pub fn start_pipe_selection(command: &str, text: &str, timeout: Duration) -> SelectionPipe {
+    let mut input = text.to_string();
+    if !input.ends_with('\n') {
+        input.push('\n');
+    }
+    SelectionPipe {
+        command: command.to_string(),
+        line_count: text.split('\n').count(),
+        run: Arc::new(ExternalRun::with_input(
+            command.to_string(),
+            input,
+            timeout,
+            MAX_PIPE_OUTPUT_BYTES,
+        )),
+    }
+}
+
+/// Shows the result of a finished selection pipe in the status bar.
+/// Returns whether one finished.
+pub fn poll_selection_pipe(state: &mut SessionState) -> bool {
+    let Some(message) = state.selection_pipe.as_ref().and_then(SelectionPipe::poll) else {
+        return false;
+    };
+    state.selection_pipe = None;
+    state.status_notice = Some(message);
+    true
+}
+
+pub fn pipe_confirmation(command: &str, line_count: usize, first_output_line: &str) -> String {
+    let suffix = if line_count == 1 { "" } else { "s" };
+    if first_output_line.is_empty() {
+        format!("Piped {line_count} line{suffix} to `{command}`.")
+    } else {
+        format!("Piped {line_count} line{suffix} to `{command}`: {first_output_line}")
+    }
+}
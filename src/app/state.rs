@@ -1,9 +1,25 @@
+use crate::app::selection_pipe::SelectionPipe;
 use crate::config::load::StatusDisplayMode;
+use crate::fs::encoding::{TextEncoding, TextFormat, SELECTABLE_ENCODINGS};
+use crate::fs::entry_kind::EntryClass;
+use crate::fs::external::{ExternalPreviewer, ExternalRun};
 use crate::fs::git::GitRepoStatus;
+use crate::fs::image::ImagePreview;
+use crate::fs::paged::PagedFile;
+use crate::fs::preview::{sanitize_terminal_control_chars, PendingPreview};
+use crate::fs::table::TablePreview;
+use crate::highlight::structured::StructuredView;
+use crate::highlight::theme::PageColors;
+use crate::highlight::viewport::BackgroundParse;
+use crate::tui::image::ImageProtocol;
 use ratatui::style::Style;
-use std::path::PathBuf;
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum NodeType {
     File,
     Directory,
@@ -20,6 +36,17 @@ pub struct TreeNode {
     pub expanded: bool,
     pub readable: bool,
     pub children_loaded: bool,
+    /// Type and mode bits read when the entry was listed; `None` for
+    /// entries known only by type, such as files inside archives.
+    pub entry_class: Option<EntryClass>,
+}
+
+impl TreeNode {
+    /// The entry's class as listed, or as far as its node type tells.
+    pub fn class(&self) -> EntryClass {
+        self.entry_class
+            .unwrap_or_else(|| EntryClass::for_node_type(&self.node_type))
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -42,10 +69,31 @@ pub enum ContentType {
 pub enum PreviewFallbackReason {
     UnsupportedExtension,
     EngineFailure,
-    TooLarge,
     DecodeUncertain,
 }
 
+/// How the highlight language of a preview was chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectionReason {
+    Extension,
+    FileName,
+    Shebang,
+    Modeline,
+    ConfigMapping,
+}
+
+impl DetectionReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Extension => "extension",
+            Self::FileName => "file name",
+            Self::Shebang => "shebang",
+            Self::Modeline => "modeline",
+            Self::ConfigMapping => "config",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct StyledPreviewSegment {
     pub text: String,
@@ -54,17 +102,201 @@ pub struct StyledPreviewSegment {
 
 pub type StyledPreviewLine = Vec<StyledPreviewSegment>;
 
+/// The file behind a paged preview and the line its loaded window starts at.
+#[derive(Debug, Clone)]
+pub struct PagedSource {
+    pub file: Arc<PagedFile>,
+    pub first_line: usize,
+}
+
+/// Rows of a wrapped preview: the row each line of its text starts on.
+/// Text lines include the rows above the first source line.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WrappedRows {
+    /// `starts[i]` is the first row of line `i`; the last entry is the
+    /// number of rows.
+    starts: Vec<usize>,
+}
+
+/// Preview settings that decide how its text wraps into rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WrapLayout {
+    pub width: u16,
+    pub wraps: bool,
+    pub line_numbers: bool,
+}
+
+impl WrappedRows {
+    /// Rows from the number each line wraps to, at least one per line.
+    pub fn from_row_counts(counts: impl IntoIterator<Item = usize>) -> Self {
+        let mut starts = vec![0];
+        let mut total = 0_usize;
+        for count in counts {
+            total = total.saturating_add(count.max(1));
+            starts.push(total);
+        }
+        Self { starts }
+    }
+
+    pub fn total_rows(&self) -> usize {
+        self.starts.last().copied().unwrap_or(0)
+    }
+
+    fn line_count(&self) -> usize {
+        self.starts.len() - 1
+    }
+
+    /// First row of `line`, or the row count past the last line.
+    pub fn first_row(&self, line: usize) -> usize {
+        self.starts[line.min(self.line_count())]
+    }
+
+    /// Last row of `line`, clamped to the last line.
+    pub fn last_row(&self, line: usize) -> usize {
+        let line = line.min(self.line_count().saturating_sub(1));
+        self.starts
+            .get(line + 1)
+            .map_or(0, |next| next.saturating_sub(1))
+    }
+
+    /// Lines with at least one row in `rows`.
+    pub fn lines_in_rows(&self, rows: Range<usize>) -> Range<usize> {
+        if rows.is_empty() {
+            let line = self.line_at_row(rows.start);
+            return line..line;
+        }
+        self.line_at_row(rows.start)..self.line_at_row(rows.end - 1) + 1
+    }
+
+    /// Line shown on `row`, clamped to the last line.
+    pub fn line_at_row(&self, row: usize) -> usize {
+        self.starts
+            .partition_point(|&start| start <= row)
+            .saturating_sub(1)
+            .min(self.line_count().saturating_sub(1))
+    }
+}
+
+/// Source of a Markdown preview shown rendered, and the width it was
+/// rendered at.
+#[derive(Debug, Clone)]
+pub struct RenderedMarkdown {
+    pub source: String,
+    pub width: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct PreviewDocument {
     pub source_path: PathBuf,
     pub load_state: LoadState,
     pub content_type: ContentType,
     pub language_id: Option<String>,
+    pub language_reason: Option<DetectionReason>,
     pub content_excerpt: String,
     pub styled_lines: Vec<StyledPreviewLine>,
     pub fallback_reason: Option<PreviewFallbackReason>,
     pub truncated: bool,
     pub error_message: Option<String>,
+    /// Lines styled so far in a large file highlighted one viewport at a
+    /// time; `None` when `styled_lines` were all highlighted up front.
+    pub viewport_highlight: Option<Range<usize>>,
+    /// Syntax tree of the text held by a document highlighted per
+    /// viewport, which highlight windows start and end on the nodes of.
+    pub viewport_tree: Option<Arc<BackgroundParse>>,
+    /// Set for files over the preview size limit: the document then holds a
+    /// window of lines and line numbers count from the start of the file.
+    pub paged: Option<PagedSource>,
+    /// Encoding and line endings of a text preview.
+    pub text_format: Option<TextFormat>,
+    /// Set while a Markdown preview shows its rendering instead of source.
+    pub rendered_markdown: Option<RenderedMarkdown>,
+    /// Set once a JSON, TOML or YAML preview was parsed for the structured
+    /// view; an error is kept so the source is not parsed again.
+    pub structured: Option<Result<StructuredView, String>>,
+    /// Cells of a CSV/TSV or other delimited preview shown as a table.
+    pub table: Option<TablePreview>,
+    /// Decoded picture of a PNG, JPEG or GIF preview.
+    pub image: Option<ImagePreview>,
+    /// Set while a `[previewers]` command renders the preview; dropping it
+    /// stops the command.
+    pub external: Option<Arc<ExternalRun>>,
+    /// Set while wrapping makes preview rows differ from its text lines.
+    pub wrapped_rows: Option<WrappedRows>,
+    /// Layout `wrapped_rows` was computed for; cleared when the text changes
+    /// in place so it is wrapped again.
+    pub wrapped_layout: Option<WrapLayout>,
+    /// Set while the preview is read or built in a background thread;
+    /// dropping it stops the work.
+    pub pending: Option<Arc<PendingPreview>>,
+}
+
+impl PreviewDocument {
+    /// Line number (0-based) of the first line held in the document.
+    pub fn line_offset(&self) -> usize {
+        self.paged.as_ref().map_or(0, |paged| paged.first_line)
+    }
+
+    pub(crate) fn plain_source(&self) -> &str {
+        self.content_excerpt
+            .strip_suffix("\n\n[truncated]")
+            .unwrap_or(&self.content_excerpt)
+    }
+
+    pub(crate) fn has_source_text(&self) -> bool {
+        !matches!(self.load_state, LoadState::Error | LoadState::Binary)
+    }
+
+    fn uses_styled_lines(&self) -> bool {
+        matches!(self.content_type, ContentType::Highlighted) && !self.styled_lines.is_empty()
+    }
+
+    pub fn source_line_count(&self) -> usize {
+        if !self.has_source_text() {
+            0
+        } else if let Some(paged) = &self.paged {
+            paged.file.line_count()
+        } else if self.uses_styled_lines() {
+            self.styled_lines.len()
+        } else {
+            self.plain_source().split('\n').count()
+        }
+    }
+
+    pub fn source_lines(&self) -> Vec<String> {
+        if !self.has_source_text() {
+            Vec::new()
+        } else if self.uses_styled_lines() {
+            self.styled_lines
+                .iter()
+                .map(|line| line.iter().map(|segment| segment.text.as_str()).collect())
+                .collect()
+        } else {
+            self.plain_source()
+                .split('\n')
+                .map(str::to_string)
+                .collect()
+        }
+    }
+
+    /// Source lines `start..=end`, read from disk for paged previews.
+    pub fn source_lines_between(&self, start: usize, end: usize) -> Vec<String> {
+        if let Some(paged) = &self.paged {
+            let lines = paged
+                .file
+                .read_lines(start..end.saturating_add(1))
+                .unwrap_or_default();
+            return lines
+                .iter()
+                .map(|line| sanitize_terminal_control_chars(line))
+                .collect();
+        }
+        let lines = self.source_lines();
+        let end = end.min(lines.len().saturating_sub(1));
+        lines
+            .get(start..=end)
+            .map(<[String]>::to_vec)
+            .unwrap_or_default()
+    }
 }
 
 impl Default for PreviewDocument {
@@ -74,11 +306,24 @@ impl Default for PreviewDocument {
             load_state: LoadState::Idle,
             content_type: ContentType::PlainText,
             language_id: None,
+            language_reason: None,
             content_excerpt: String::new(),
             styled_lines: Vec::new(),
             fallback_reason: None,
             truncated: false,
             error_message: None,
+            viewport_highlight: None,
+            viewport_tree: None,
+            paged: None,
+            text_format: None,
+            rendered_markdown: None,
+            structured: None,
+            table: None,
+            image: None,
+            external: None,
+            wrapped_rows: None,
+            wrapped_layout: None,
+            pending: None,
         }
     }
 }
@@ -131,6 +376,12 @@ impl Default for LayoutRegions {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PreviewLineFlash {
+    pub line: usize,
+    pub expires_at: Instant,
+}
+
 #[derive(Debug, Clone)]
 pub struct SessionState {
     pub root_path: PathBuf,
@@ -139,6 +390,7 @@ pub struct SessionState {
     pub selected_path: PathBuf,
     pub focus_pane: FocusPane,
     pub status_message: String,
+    pub status_notice: Option<String>,
     pub last_preview_latency_ms: u128,
     pub last_child_path: Option<PathBuf>,
     pub show_hidden: bool,
@@ -151,17 +403,40 @@ pub struct SessionState {
     pub preview_scroll_row: usize,
     pub preview_show_line_numbers: bool,
     pub preview_wrap_enabled: bool,
+    pub preview_render_markdown: bool,
+    pub preview_structured: bool,
+    pub preview_table_view: bool,
+    /// First column shown by a table preview scrolled sideways.
+    pub preview_table_column: usize,
+    /// How image previews are drawn; set from the terminal at startup.
+    pub image_protocol: ImageProtocol,
     pub preview_fullscreen: bool,
+    pub preview_cursor_line: usize,
+    pub preview_visual_anchor: Option<usize>,
+    pub selection_pipe_command: Option<String>,
+    /// The selection pipe command while it runs.
+    pub selection_pipe: Option<SelectionPipe>,
+    /// Compiled `[previewers]`, tried in config order.
+    pub external_previewers: Vec<ExternalPreviewer>,
+    /// Page colors of the syntax theme, for HTML exports.
+    pub page_colors: PageColors,
+    pub preview_line_flash: Option<PreviewLineFlash>,
+    pub line_prompt: Option<String>,
     pub divider_drag_active: bool,
     pub help_overlay_visible: bool,
     pub status_display_mode: StatusDisplayMode,
     pub git_status: Option<GitRepoStatus>,
+    /// Encoding the user forced for one file; forcing one elsewhere replaces it.
+    pub forced_encoding: Option<(PathBuf, TextEncoding)>,
+    /// Folded node paths in the structured view of one file.
+    pub structured_folds: Option<(PathBuf, BTreeSet<String>)>,
 }
 
 impl SessionState {
     const DEFAULT_TREE_WIDTH_DENOMINATOR: u16 = 6;
     const DEFAULT_PANEL_MIN_WIDTH: u16 = 20;
     const DEFAULT_RESIZE_STEP: u16 = 2;
+    const LINE_FLASH_DURATION: Duration = Duration::from_millis(1200);
 
     pub fn new(root_path: PathBuf) -> Self {
         Self {
@@ -171,6 +446,7 @@ impl SessionState {
             selected_path: root_path,
             focus_pane: FocusPane::Tree,
             status_message: String::new(),
+            status_notice: None,
             last_preview_latency_ms: 0,
             last_child_path: None,
             show_hidden: false,
@@ -183,11 +459,26 @@ impl SessionState {
             preview_scroll_row: 0,
             preview_show_line_numbers: true,
             preview_wrap_enabled: false,
+            preview_render_markdown: false,
+            preview_structured: false,
+            preview_table_view: true,
+            preview_table_column: 0,
+            image_protocol: ImageProtocol::default(),
             preview_fullscreen: false,
+            preview_cursor_line: 0,
+            preview_visual_anchor: None,
+            selection_pipe_command: None,
+            selection_pipe: None,
+            external_previewers: Vec::new(),
+            page_colors: PageColors::default(),
+            preview_line_flash: None,
+            line_prompt: None,
             divider_drag_active: false,
             help_overlay_visible: false,
             status_display_mode: StatusDisplayMode::Bar,
             git_status: None,
+            forced_encoding: None,
+            structured_folds: None,
         }
     }
 
@@ -207,6 +498,131 @@ impl SessionState {
 
     pub fn reset_preview_scroll(&mut self) {
         self.preview_scroll_row = 0;
+        self.preview_table_column = 0;
+        self.preview_line_flash = None;
+        self.cancel_visual_selection();
+    }
+
+    /// Scrolls the preview so `line_number` (1-based) is the top row and flashes it.
+    /// Returns the line actually targeted after clamping to the document.
+    pub fn go_to_preview_line(
+        &mut self,
+        line_number: usize,
+        total_source_lines: usize,
+        header_rows: usize,
+    ) -> Option<usize> {
+        self.go_to_preview_line_with_rows(line_number, total_source_lines, header_rows, None)
+    }
+
+    /// Like [`Self::go_to_preview_line`]; while the preview wraps, `rows`
+    /// gives the first row the line is drawn on.
+    pub fn go_to_preview_line_with_rows(
+        &mut self,
+        line_number: usize,
+        total_source_lines: usize,
+        header_rows: usize,
+        rows: Option<&WrappedRows>,
+    ) -> Option<usize> {
+        if total_source_lines == 0 {
+            return None;
+        }
+        let index = line_number.saturating_sub(1).min(total_source_lines - 1);
+        self.preview_scroll_row = rows.map_or(index + header_rows, |rows| {
+            rows.first_row(index + header_rows)
+        });
+        if self.visual_selection_active() {
+            self.preview_cursor_line = index;
+        }
+        self.preview_line_flash = Some(PreviewLineFlash {
+            line: index,
+            expires_at: Instant::now() + Self::LINE_FLASH_DURATION,
+        });
+        Some(index + 1)
+    }
+
+    pub fn flashed_preview_line(&self, now: Instant) -> Option<usize> {
+        self.preview_line_flash
+            .filter(|flash| now < flash.expires_at)
+            .map(|flash| flash.line)
+    }
+
+    pub fn visual_selection_active(&self) -> bool {
+        self.preview_visual_anchor.is_some()
+    }
+
+    pub fn start_visual_selection(&mut self, total_source_lines: usize, header_rows: usize) {
+        self.start_visual_selection_with_rows(total_source_lines, header_rows, None);
+    }
+
+    /// Starts the selection on the line at the top of the preview; `rows`
+    /// maps the scroll row back to that line while the preview wraps.
+    pub fn start_visual_selection_with_rows(
+        &mut self,
+        total_source_lines: usize,
+        header_rows: usize,
+        rows: Option<&WrappedRows>,
+    ) {
+        if total_source_lines == 0 {
+            return;
+        }
+        let top_line = rows.map_or(self.preview_scroll_row, |rows| {
+            rows.line_at_row(self.preview_scroll_row)
+        });
+        let cursor = top_line
+            .saturating_sub(header_rows)
+            .min(total_source_lines - 1);
+        self.preview_cursor_line = cursor;
+        self.preview_visual_anchor = Some(cursor);
+    }
+
+    pub fn cancel_visual_selection(&mut self) {
+        self.preview_visual_anchor = None;
+        self.preview_cursor_line = 0;
+    }
+
+    /// Returns the inclusive, zero-based source line range of the visual selection.
+    pub fn preview_selection_range(&self) -> Option<(usize, usize)> {
+        let anchor = self.preview_visual_anchor?;
+        Some((
+            anchor.min(self.preview_cursor_line),
+            anchor.max(self.preview_cursor_line),
+        ))
+    }
+
+    pub fn move_preview_cursor(&mut self, delta: isize, total_source_lines: usize) {
+        let max_line = total_source_lines.saturating_sub(1);
+        if delta < 0 {
+            self.preview_cursor_line = self.preview_cursor_line.saturating_sub((-delta) as usize);
+        } else {
+            self.preview_cursor_line = self
+                .preview_cursor_line
+                .saturating_add(delta as usize)
+                .min(max_line);
+        }
+    }
+
+    pub fn ensure_preview_cursor_visible(&mut self, header_rows: usize, viewport_rows: usize) {
+        self.ensure_preview_cursor_visible_with_rows(header_rows, viewport_rows, None);
+    }
+
+    /// Scrolls the preview until every row of the cursor line is in view,
+    /// or its first row when it wraps to more rows than the viewport has.
+    pub fn ensure_preview_cursor_visible_with_rows(
+        &mut self,
+        header_rows: usize,
+        viewport_rows: usize,
+        rows: Option<&WrappedRows>,
+    ) {
+        let line = self.preview_cursor_line + header_rows;
+        let (first, last) = rows.map_or((line, line), |rows| {
+            (rows.first_row(line), rows.last_row(line))
+        });
+        let viewport_rows = viewport_rows.max(1);
+        if first < self.preview_scroll_row {
+            self.preview_scroll_row = first;
+        } else if last >= self.preview_scroll_row + viewport_rows {
+            self.preview_scroll_row = (last + 1 - viewport_rows).min(first);
+        }
     }
 
     pub fn clamp_preview_scroll(&mut self, total_lines: usize, viewport_rows: usize) {
@@ -314,6 +730,58 @@ impl SessionState {
             .map(|n| n.path.clone())
             .unwrap_or_else(|| self.current_path.clone());
     }
+
+    /// Encoding the user forced for `path`, if any.
+    pub fn forced_encoding_for(&self, path: &Path) -> Option<TextEncoding> {
+        self.forced_encoding
+            .as_ref()
+            .filter(|(forced_path, _)| forced_path == path)
+            .map(|(_, encoding)| *encoding)
+    }
+
+    /// Forces the encoding after `current` in [`SELECTABLE_ENCODINGS`] for
+    /// `path`; stepping past the last forced one returns to detection.
+    /// Returns the encoding now forced.
+    pub fn cycle_forced_encoding(
+        &mut self,
+        path: &Path,
+        current: Option<TextEncoding>,
+    ) -> Option<TextEncoding> {
+        let forced = self.forced_encoding_for(path).is_some();
+        let position = current.and_then(|current| {
+            SELECTABLE_ENCODINGS
+                .iter()
+                .position(|encoding| *encoding == current)
+        });
+        let next = match position {
+            Some(index) if index + 1 == SELECTABLE_ENCODINGS.len() && forced => None,
+            Some(index) => Some(SELECTABLE_ENCODINGS[(index + 1) % SELECTABLE_ENCODINGS.len()]),
+            None => Some(SELECTABLE_ENCODINGS[0]),
+        };
+        self.forced_encoding = next.map(|encoding| (path.to_path_buf(), encoding));
+        next
+    }
+
+    /// Node paths folded in the structured view of `path`.
+    pub fn structured_folds_for(&self, path: &Path) -> BTreeSet<String> {
+        self.structured_folds
+            .as_ref()
+            .filter(|(folded_path, _)| folded_path == path)
+            .map(|(_, folds)| folds.clone())
+            .unwrap_or_default()
+    }
+
+    /// Folds or unfolds `node` in the structured view of `path`, forgetting
+    /// the folds of any other file.
+    pub fn set_structured_fold(&mut self, path: &Path, node: &str, collapsed: bool) {
+        let mut folds = self.structured_folds_for(path);
+        if collapsed {
+            folds.insert(node.to_string());
+        } else {
+            folds.remove(node);
+        }
+        self.structured_folds = Some((path.to_path_buf(), folds));
+    }
 }
 
 fn max_scroll_row(total_lines: usize, viewport_rows: usize) -> usize {
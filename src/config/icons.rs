@@ -0,0 +1,318 @@
+use crate::app::state::{NodeType, TreeNode};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// `[theme.icons]`: which icon set to draw and per-entry overrides.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UserIconConfig {
+    pub set: Option<String>,
+    #[serde(default)]
+    pub types: HashMap<String, String>,
+    #[serde(default)]
+    pub extensions: HashMap<String, String>,
+    #[serde(default)]
+    pub names: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IconSet {
+    /// No icons; entries keep the `entry_prefix` markers.
+    #[default]
+    Off,
+    Nerd,
+    Unicode,
+    Ascii,
+}
+
+impl IconSet {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "off" | "none" => Some(Self::Off),
+            "nerd" | "nerd-font" | "nerdfont" => Some(Self::Nerd),
+            "unicode" => Some(Self::Unicode),
+            "ascii" => Some(Self::Ascii),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IconKind {
+    Directory,
+    File,
+    Symlink,
+    Unknown,
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    C,
+    Cpp,
+    Java,
+    Ruby,
+    Lua,
+    Shell,
+    Markdown,
+    Json,
+    Config,
+    Html,
+    Css,
+    Text,
+    Image,
+    Archive,
+    Lock,
+    Git,
+    Docker,
+    License,
+}
+
+fn kind_for_name(name: &str) -> Option<IconKind> {
+    let kind = match name.to_ascii_lowercase().as_str() {
+        "cargo.toml" => IconKind::Rust,
+        "cargo.lock" | "package-lock.json" | "yarn.lock" | "pnpm-lock.yaml" | "poetry.lock"
+        | "gemfile.lock" | "go.sum" => IconKind::Lock,
+        "dockerfile"
+        | "containerfile"
+        | ".dockerignore"
+        | "docker-compose.yml"
+        | "docker-compose.yaml"
+        | "compose.yml"
+        | "compose.yaml" => IconKind::Docker,
+        ".git" | ".github" | ".gitignore" | ".gitattributes" | ".gitmodules" | ".gitkeep" => {
+            IconKind::Git
+        }
+        "license" | "license.md" | "license.txt" | "licence" | "copying" => IconKind::License,
+        "makefile" | "gnumakefile" | "cmakelists.txt" | "justfile" | ".editorconfig" => {
+            IconKind::Config
+        }
+        "gemfile" | "rakefile" => IconKind::Ruby,
+        "go.mod" => IconKind::Go,
+        ".bashrc" | ".zshrc" | ".profile" | ".bash_profile" => IconKind::Shell,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+fn kind_for_extension(ext: &str) -> Option<IconKind> {
+    let kind = match ext {
+        "rs" => IconKind::Rust,
+        "py" | "pyi" => IconKind::Python,
+        "js" | "mjs" | "cjs" | "jsx" => IconKind::JavaScript,
+        "ts" | "mts" | "cts" | "tsx" => IconKind::TypeScript,
+        "go" => IconKind::Go,
+        "c" | "h" => IconKind::C,
+        "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => IconKind::Cpp,
+        "java" | "jar" | "class" => IconKind::Java,
+        "rb" => IconKind::Ruby,
+        "lua" => IconKind::Lua,
+        "sh" | "bash" | "zsh" | "fish" => IconKind::Shell,
+        "md" | "markdown" => IconKind::Markdown,
+        "json" | "jsonc" => IconKind::Json,
+        "toml" | "yaml" | "yml" | "ini" | "cfg" | "conf" | "xml" => IconKind::Config,
+        "html" | "htm" => IconKind::Html,
+        "css" | "scss" | "sass" | "less" => IconKind::Css,
+        "txt" | "log" | "csv" | "tsv" => IconKind::Text,
+        "png" | "jpg" | "jpeg" | "gif" | "bmp" | "webp" | "svg" | "ico" => IconKind::Image,
+        "zip" | "tar" | "gz" | "tgz" | "xz" | "bz2" | "zst" | "7z" | "rar" => IconKind::Archive,
+        "lock" => IconKind::Lock,
+        _ => return None,
+    };
+    Some(kind)
+}
+
+fn kind_for_type(node_type: &NodeType) -> IconKind {
+    match node_type {
+        NodeType::Directory => IconKind::Directory,
+        NodeType::File => IconKind::File,
+        NodeType::Symlink => IconKind::Symlink,
+        NodeType::Unknown => IconKind::Unknown,
+    }
+}
+
+fn nerd_glyph(kind: IconKind) -> &'static str {
+    match kind {
+        IconKind::Directory => "\u{f07b}",
+        IconKind::File => "\u{f15b}",
+        IconKind::Symlink => "\u{f0c1}",
+        IconKind::Unknown => "\u{f128}",
+        IconKind::Rust => "\u{e7a8}",
+        IconKind::Python => "\u{e73c}",
+        IconKind::JavaScript => "\u{e74e}",
+        IconKind::TypeScript => "\u{e628}",
+        IconKind::Go => "\u{e626}",
+        IconKind::C => "\u{e61e}",
+        IconKind::Cpp => "\u{e61d}",
+        IconKind::Java => "\u{e738}",
+        IconKind::Ruby => "\u{e739}",
+        IconKind::Lua => "\u{e620}",
+        IconKind::Shell => "\u{f489}",
+        IconKind::Markdown => "\u{e73e}",
+        IconKind::Json => "\u{e60b}",
+        IconKind::Config => "\u{e615}",
+        IconKind::Html => "\u{e736}",
+        IconKind::Css => "\u{e749}",
+        IconKind::Text => "\u{f15c}",
+        IconKind::Image => "\u{f1c5}",
+        IconKind::Archive => "\u{f410}",
+        IconKind::Lock => "\u{f023}",
+        IconKind::Git => "\u{e702}",
+        IconKind::Docker => "\u{f308}",
+        IconKind::License => "\u{f0e3}",
+    }
+}
+
+// Emoji with default emoji presentation, so every glyph is two cells wide.
+fn unicode_glyph(kind: IconKind) -> &'static str {
+    match kind {
+        IconKind::Directory => "📁",
+        IconKind::Symlink => "🔗",
+        IconKind::Unknown => "❓",
+        IconKind::Rust => "🦀",
+        IconKind::Python => "🐍",
+        IconKind::Ruby => "💎",
+        IconKind::Lua => "🌙",
+        IconKind::JavaScript
+        | IconKind::TypeScript
+        | IconKind::Go
+        | IconKind::C
+        | IconKind::Cpp
+        | IconKind::Java
+        | IconKind::Shell => "📜",
+        IconKind::Markdown | IconKind::Text => "📝",
+        IconKind::Json | IconKind::Config => "🔧",
+        IconKind::Html => "🌐",
+        IconKind::Css => "🎨",
+        IconKind::Image => "📷",
+        IconKind::Archive => "📦",
+        IconKind::Lock => "🔒",
+        IconKind::Git => "🌿",
+        IconKind::Docker => "🐳",
+        IconKind::License => "📃",
+        IconKind::File => "📄",
+    }
+}
+
+fn ascii_glyph(kind: IconKind) -> &'static str {
+    match kind {
+        IconKind::Directory => "/",
+        IconKind::Symlink => "@",
+        IconKind::Unknown => "?",
+        IconKind::Rust
+        | IconKind::Python
+        | IconKind::JavaScript
+        | IconKind::TypeScript
+        | IconKind::Go
+        | IconKind::C
+        | IconKind::Cpp
+        | IconKind::Java
+        | IconKind::Ruby
+        | IconKind::Lua
+        | IconKind::Html
+        | IconKind::Css => "*",
+        IconKind::Shell => "$",
+        IconKind::Markdown | IconKind::Text | IconKind::License => "#",
+        IconKind::Json | IconKind::Config | IconKind::Docker | IconKind::Git => "=",
+        IconKind::Image => "%",
+        IconKind::Archive => "&",
+        IconKind::Lock => "!",
+        IconKind::File => "-",
+    }
+}
+
+/// Resolved icon set plus user overrides, consulted for every tree entry.
+#[derive(Debug, Clone, Default)]
+pub struct IconTheme {
+    pub set: IconSet,
+    types: HashMap<NodeType, String>,
+    extensions: HashMap<String, String>,
+    names: HashMap<String, String>,
+}
+
+impl IconTheme {
+    pub fn new(set: IconSet) -> Self {
+        Self {
+            set,
+            ..Self::default()
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.set != IconSet::Off
+    }
+
+    /// Icon for `node`, or `None` when icons are off. Lookup order is user
+    /// name, user extension, built-in name, built-in extension, then type.
+    pub fn icon_for<'a>(&'a self, node: &TreeNode) -> Option<&'a str> {
+        let glyph: fn(IconKind) -> &'static str = match self.set {
+            IconSet::Off => return None,
+            IconSet::Nerd => nerd_glyph,
+            IconSet::Unicode => unicode_glyph,
+            IconSet::Ascii => ascii_glyph,
+        };
+        if let Some(icon) = self.names.get(&node.name) {
+            return Some(icon);
+        }
+        let ext = if node.node_type == NodeType::Directory {
+            None
+        } else {
+            node.path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(str::to_ascii_lowercase)
+        };
+        if let Some(icon) = ext.as_ref().and_then(|ext| self.extensions.get(ext)) {
+            return Some(icon);
+        }
+        let builtin = kind_for_name(&node.name)
+            .or_else(|| ext.as_deref().and_then(kind_for_extension))
+            // Of the name table, only `.git`/`.github` apply to directories.
+            .filter(|kind| *kind == IconKind::Git || node.node_type != NodeType::Directory);
+        if let Some(kind) = builtin {
+            return Some(glyph(kind));
+        }
+        if let Some(icon) = self.types.get(&node.node_type) {
+            return Some(icon);
+        }
+        Some(glyph(kind_for_type(&node.node_type)))
+    }
+}
+
+fn node_type_from_name(name: &str) -> Option<NodeType> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "file" => Some(NodeType::File),
+        "dir" | "directory" => Some(NodeType::Directory),
+        "link" | "symlink" => Some(NodeType::Symlink),
+        "unknown" => Some(NodeType::Unknown),
+        _ => None,
+    }
+}
+
+/// Builds the icon theme from `[theme.icons]`, warning about unknown names.
+pub fn compile_icon_theme(user: &UserIconConfig) -> (IconTheme, Vec<String>) {
+    let mut warnings = Vec::new();
+    let set = match user.set.as_deref() {
+        None => IconSet::default(),
+        Some(name) => IconSet::from_name(name).unwrap_or_else(|| {
+            warnings.push(format!("unknown icon set '{name}' ignored"));
+            IconSet::default()
+        }),
+    };
+    let mut theme = IconTheme::new(set);
+    let mut types = user.types.iter().collect::<Vec<_>>();
+    types.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, icon) in types {
+        match node_type_from_name(name) {
+            Some(node_type) => {
+                theme.types.insert(node_type, icon.clone());
+            }
+            None => warnings.push(format!("unknown icon type '{name}' ignored")),
+        }
+    }
+    for (ext, icon) in &user.extensions {
+        let ext = ext.trim_start_matches('.').to_ascii_lowercase();
+        theme.extensions.insert(ext, icon.clone());
+    }
+    theme.names = user.names.clone();
+    (theme, warnings)
+}
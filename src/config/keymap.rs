@@ -18,10 +18,23 @@ pub enum Action {
     PreviewScrollDown,
     TogglePreviewLineNumbers,
     TogglePreviewWrap,
+    ToggleMarkdownRender,
+    ToggleStructuredView,
+    ToggleTableView,
     ToggleHelp,
     ToggleHidden,
     ResizePreviewNarrower,
     ResizePreviewWider,
+    CopyAbsolutePath,
+    CopyRelativePath,
+    CopyFileName,
+    CopyPreviewSelection,
+    ToggleVisualSelection,
+    CopySelectionReference,
+    PipeSelection,
+    GoToLine,
+    ExportHtml,
+    CycleEncoding,
     Quit,
 }
 
@@ -86,6 +99,18 @@ pub fn default_keymap() -> HashMap<Action, KeyEvent> {
             Action::TogglePreviewWrap,
             KeyEvent::new(KeyCode::Char('w'), KeyModifiers::NONE),
         ),
+        (
+            Action::ToggleMarkdownRender,
+            KeyEvent::new(KeyCode::Char('m'), KeyModifiers::NONE),
+        ),
+        (
+            Action::ToggleStructuredView,
+            KeyEvent::new(KeyCode::Char('t'), KeyModifiers::NONE),
+        ),
+        (
+            Action::ToggleTableView,
+            KeyEvent::new(KeyCode::Char('g'), KeyModifiers::NONE),
+        ),
         (
             Action::ToggleHelp,
             KeyEvent::new(KeyCode::Char('?'), KeyModifiers::NONE),
@@ -102,6 +127,46 @@ pub fn default_keymap() -> HashMap<Action, KeyEvent> {
             Action::ResizePreviewWider,
             KeyEvent::new(KeyCode::Right, KeyModifiers::CONTROL),
         ),
+        (
+            Action::CopyAbsolutePath,
+            KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE),
+        ),
+        (
+            Action::CopyRelativePath,
+            KeyEvent::new(KeyCode::Char('r'), KeyModifiers::NONE),
+        ),
+        (
+            Action::CopyFileName,
+            KeyEvent::new(KeyCode::Char('n'), KeyModifiers::NONE),
+        ),
+        (
+            Action::CopyPreviewSelection,
+            KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE),
+        ),
+        (
+            Action::ToggleVisualSelection,
+            KeyEvent::new(KeyCode::Char('v'), KeyModifiers::NONE),
+        ),
+        (
+            Action::CopySelectionReference,
+            KeyEvent::new(KeyCode::Char('x'), KeyModifiers::NONE),
+        ),
+        (
+            Action::PipeSelection,
+            KeyEvent::new(KeyCode::Char('|'), KeyModifiers::NONE),
+        ),
+        (
+            Action::GoToLine,
+            KeyEvent::new(KeyCode::Char(':'), KeyModifiers::NONE),
+        ),
+        (
+            Action::ExportHtml,
+            KeyEvent::new(KeyCode::Char('e'), KeyModifiers::NONE),
+        ),
+        (
+            Action::CycleEncoding,
+            KeyEvent::new(KeyCode::Char('u'), KeyModifiers::NONE),
+        ),
         (
             Action::Quit,
             KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE),
@@ -124,10 +189,23 @@ pub fn action_from_name(name: &str) -> Option<Action> {
         "preview_scroll_down" => Some(Action::PreviewScrollDown),
         "toggle_preview_line_numbers" => Some(Action::TogglePreviewLineNumbers),
         "toggle_preview_wrap" => Some(Action::TogglePreviewWrap),
+        "toggle_markdown_render" => Some(Action::ToggleMarkdownRender),
+        "toggle_structured_view" => Some(Action::ToggleStructuredView),
+        "toggle_table_view" => Some(Action::ToggleTableView),
         "toggle_help" => Some(Action::ToggleHelp),
         "toggle_hidden" => Some(Action::ToggleHidden),
         "resize_preview_narrower" => Some(Action::ResizePreviewNarrower),
         "resize_preview_wider" => Some(Action::ResizePreviewWider),
+        "copy_absolute_path" => Some(Action::CopyAbsolutePath),
+        "copy_relative_path" => Some(Action::CopyRelativePath),
+        "copy_file_name" => Some(Action::CopyFileName),
+        "copy_preview_selection" => Some(Action::CopyPreviewSelection),
+        "toggle_visual_selection" => Some(Action::ToggleVisualSelection),
+        "copy_selection_reference" => Some(Action::CopySelectionReference),
+        "pipe_selection" => Some(Action::PipeSelection),
+        "go_to_line" => Some(Action::GoToLine),
+        "export_html" => Some(Action::ExportHtml),
+        "cycle_encoding" => Some(Action::CycleEncoding),
         "quit" => Some(Action::Quit),
         _ => None,
     }
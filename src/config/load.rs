@@ -1,5 +1,13 @@
+use crate::config::icons::{IconTheme, UserIconConfig};
 use crate::config::keymap::UserKeymap;
+use crate::config::ls_colors::LsColors;
+use crate::config::scheme::{load_color_scheme, ImportedScheme};
+use crate::config::style_rules::{StyleRule, UserStyleRule};
+use crate::highlight::custom::UserGrammarConfig;
+use crate::highlight::detect::UserLanguageMapping;
+use crate::highlight::theme::SyntaxStyleConfig;
 use anyhow::Result;
+use indexmap::IndexMap;
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
@@ -12,6 +20,25 @@ pub fn default_config_path() -> PathBuf {
         .join(".config/fpv/config")
 }
 
+/// Resolves a path given in the config, such as `color_scheme` or a grammar
+/// `library`: `~/` is expanded and relative paths are taken from the
+/// directory holding the config file.
+pub fn resolve_config_path(value: &str, config_path: &Path) -> PathBuf {
+    if let Some(rest) = value.strip_prefix("~/") {
+        if let Ok(home) = std::env::var("HOME") {
+            return PathBuf::from(home).join(rest);
+        }
+    }
+    let path = PathBuf::from(value);
+    if path.is_absolute() {
+        return path;
+    }
+    config_path
+        .parent()
+        .map(|dir| dir.join(&path))
+        .unwrap_or(path)
+}
+
 pub fn ensure_default_config_exists(path: &Path) -> Result<()> {
     if path.exists() {
         return Ok(());
@@ -30,6 +57,17 @@ pub struct UserThemeConfig {
     pub hidden_dim_enabled: Option<bool>,
     #[serde(default)]
     pub file_type_colors: HashMap<String, String>,
+    pub status_foreground_color: Option<String>,
+    pub status_background_color: Option<String>,
+    pub color_scheme: Option<String>,
+    pub use_ls_colors: Option<bool>,
+    #[serde(default)]
+    pub rules: Vec<UserStyleRule>,
+    #[serde(default)]
+    pub icons: UserIconConfig,
+    pub syntax_palette: Option<String>,
+    #[serde(default)]
+    pub syntax: HashMap<String, SyntaxStyleConfig>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -39,6 +77,18 @@ pub struct UserConfig {
     #[serde(default)]
     pub theme: UserThemeConfig,
     pub status_display_mode: Option<StatusDisplayMode>,
+    pub selection_pipe_command: Option<String>,
+    pub color: Option<ColorMode>,
+    pub image_protocol: Option<ImageProtocolMode>,
+    /// Glob or MIME type to the command previewing matching files, in file
+    /// order since the first match wins.
+    #[serde(default)]
+    pub previewers: IndexMap<String, String>,
+    pub previewer_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub language_mappings: Vec<UserLanguageMapping>,
+    #[serde(default)]
+    pub grammars: Vec<UserGrammarConfig>,
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
@@ -49,12 +99,41 @@ pub enum StatusDisplayMode {
     Title,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorMode {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// How images are drawn in the preview; `auto` picks the best protocol the
+/// terminal advertises.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageProtocolMode {
+    #[default]
+    Auto,
+    Halfblocks,
+    Kitty,
+    Sixel,
+}
+
 #[derive(Debug, Clone)]
 pub struct ThemeProfile {
     pub directory_color: String,
     pub fallback_file_color: String,
     pub hidden_dim_enabled: bool,
     pub file_type_colors: HashMap<String, String>,
+    pub status_foreground_color: String,
+    pub status_background_color: String,
+    /// Parsed `LS_COLORS`, set when the `use_ls_colors` option is enabled.
+    pub ls_colors: Option<LsColors>,
+    /// Compiled `[[theme.rules]]`, first match wins.
+    pub rules: Vec<StyleRule>,
+    /// Tree entry icons from `[theme.icons]`; off by default.
+    pub icons: IconTheme,
 }
 
 impl Default for ThemeProfile {
@@ -69,6 +148,11 @@ impl Default for ThemeProfile {
             fallback_file_color: "white".to_string(),
             hidden_dim_enabled: true,
             file_type_colors,
+            status_foreground_color: "white".to_string(),
+            status_background_color: "darkgray".to_string(),
+            ls_colors: None,
+            rules: Vec::new(),
+            icons: IconTheme::default(),
         }
     }
 }
@@ -79,6 +163,17 @@ pub fn load_user_config(path: &Path) -> Result<UserConfig> {
     Ok(parsed)
 }
 
+/// Loads the base16 or `.tmTheme` scheme named by `theme.color_scheme`, if any.
+pub fn load_theme_color_scheme(
+    theme: &UserThemeConfig,
+    config_path: &Path,
+) -> Result<Option<ImportedScheme>> {
+    let Some(value) = &theme.color_scheme else {
+        return Ok(None);
+    };
+    load_color_scheme(&resolve_config_path(value, config_path)).map(Some)
+}
+
 pub fn load_user_keymap(path: &Path) -> Result<UserKeymap> {
     let parsed = load_user_config(path)?;
     Ok(UserKeymap {
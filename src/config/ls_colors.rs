@@ -0,0 +1,233 @@
+use crate::fs::entry_kind::{EntryClass, EntryKind};
+use globset::{GlobBuilder, GlobMatcher};
+use ratatui::style::{Color, Modifier, Style};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// GNU `dircolors` defaults, used when `LS_COLORS` is unset.
+pub const DEFAULT_LS_COLORS: &str = "rs=0:di=01;34:ln=01;36:mh=00:pi=40;33:so=01;35:do=01;35:\
+bd=40;33;01:cd=40;33;01:or=40;31;01:mi=00:su=37;41:sg=30;43:ca=00:tw=30;42:ow=34;42:st=37;44:ex=01;32";
+
+#[derive(Debug, Clone)]
+enum NamePattern {
+    Suffix(String),
+    Glob(GlobMatcher),
+}
+
+impl NamePattern {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Self::Suffix(suffix) => name.to_ascii_lowercase().ends_with(suffix),
+            Self::Glob(glob) => glob.is_match(name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LsColors {
+    types: HashMap<String, Style>,
+    link_as_target: bool,
+    patterns: Vec<(NamePattern, Style)>,
+}
+
+fn basic_color(index: u16) -> Color {
+    [
+        Color::Black,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::Gray,
+    ][index as usize % 8]
+}
+
+fn bright_color(index: u16) -> Color {
+    [
+        Color::DarkGray,
+        Color::LightRed,
+        Color::LightGreen,
+        Color::LightYellow,
+        Color::LightBlue,
+        Color::LightMagenta,
+        Color::LightCyan,
+        Color::White,
+    ][index as usize % 8]
+}
+
+/// Converts an SGR parameter list such as `01;38;5;208` into a style.
+pub fn sgr_style(codes: &str) -> Style {
+    let params = codes
+        .split(';')
+        .map(|code| code.trim().parse::<u16>().unwrap_or(0))
+        .collect::<Vec<_>>();
+    let mut style = Style::default();
+    let mut i = 0;
+    while i < params.len() {
+        let code = params[i];
+        match code {
+            0 => style = Style::default(),
+            1 => style = style.add_modifier(Modifier::BOLD),
+            2 => style = style.add_modifier(Modifier::DIM),
+            3 => style = style.add_modifier(Modifier::ITALIC),
+            4 => style = style.add_modifier(Modifier::UNDERLINED),
+            5 => style = style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style = style.add_modifier(Modifier::REVERSED),
+            8 => style = style.add_modifier(Modifier::HIDDEN),
+            9 => style = style.add_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style = style.fg(basic_color(code - 30)),
+            40..=47 => style = style.bg(basic_color(code - 40)),
+            90..=97 => style = style.fg(bright_color(code - 90)),
+            100..=107 => style = style.bg(bright_color(code - 100)),
+            39 => style.fg = None,
+            49 => style.bg = None,
+            38 | 48 => {
+                let color = match params.get(i + 1) {
+                    Some(5) => params.get(i + 2).map(|n| {
+                        i += 2;
+                        Color::Indexed(*n as u8)
+                    }),
+                    Some(2) if i + 4 < params.len() => {
+                        let rgb = (params[i + 2], params[i + 3], params[i + 4]);
+                        i += 4;
+                        Some(Color::Rgb(rgb.0 as u8, rgb.1 as u8, rgb.2 as u8))
+                    }
+                    _ => None,
+                };
+                if let Some(color) = color {
+                    style = if code == 38 {
+                        style.fg(color)
+                    } else {
+                        style.bg(color)
+                    };
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    style
+}
+
+impl LsColors {
+    /// Parses an `LS_COLORS` value (`di=01;34:*.tar=01;31:...`).
+    pub fn parse(spec: &str) -> Self {
+        let mut colors = Self::default();
+        for entry in spec.split(':') {
+            let Some((key, codes)) = entry.split_once('=') else {
+                continue;
+            };
+            if key.is_empty() {
+                continue;
+            }
+            if key == "ln" && codes == "target" {
+                colors.link_as_target = true;
+                continue;
+            }
+            let style = sgr_style(codes);
+            if !key.contains(['*', '?', '[']) {
+                colors.types.insert(key.to_string(), style);
+                continue;
+            }
+            let pattern = match key.strip_prefix('*') {
+                Some(suffix) if !suffix.contains(['*', '?', '[']) => {
+                    NamePattern::Suffix(suffix.to_ascii_lowercase())
+                }
+                _ => match GlobBuilder::new(key).literal_separator(true).build() {
+                    Ok(glob) => NamePattern::Glob(glob.compile_matcher()),
+                    Err(_) => continue,
+                },
+            };
+            colors.patterns.push((pattern, style));
+        }
+        colors
+    }
+
+    /// Reads `LS_COLORS`, falling back to the GNU defaults like `ls` does.
+    pub fn from_env() -> Self {
+        match std::env::var("LS_COLORS") {
+            Ok(spec) if !spec.trim().is_empty() => Self::parse(&spec),
+            _ => Self::parse(DEFAULT_LS_COLORS),
+        }
+    }
+
+    fn type_style(&self, key: &str) -> Option<Style> {
+        self.types
+            .get(key)
+            .copied()
+            .filter(|style| *style != Style::default())
+    }
+
+    fn pattern_style(&self, name: &str) -> Option<Style> {
+        self.patterns
+            .iter()
+            .rev()
+            .find(|(pattern, _)| pattern.matches(name))
+            .map(|(_, style)| *style)
+    }
+
+    fn style_for_kind(&self, kind: EntryKind, name: &str) -> Option<Style> {
+        match kind {
+            EntryKind::Directory {
+                sticky,
+                other_writable,
+            } => {
+                let special = match (sticky, other_writable) {
+                    (true, true) => self.type_style("tw"),
+                    (false, true) => self.type_style("ow"),
+                    (true, false) => self.type_style("st"),
+                    (false, false) => None,
+                };
+                special.or_else(|| self.type_style("di"))
+            }
+            EntryKind::Symlink { orphan: true } => {
+                self.type_style("or").or_else(|| self.type_style("ln"))
+            }
+            EntryKind::Symlink { orphan: false } => self.type_style("ln"),
+            EntryKind::Fifo => self.type_style("pi"),
+            EntryKind::Socket => self.type_style("so"),
+            EntryKind::BlockDevice => self.type_style("bd"),
+            EntryKind::CharDevice => self.type_style("cd"),
+            EntryKind::Missing => self.type_style("mi"),
+            EntryKind::File {
+                setuid,
+                setgid,
+                executable,
+                multi_link,
+            } => {
+                let special = [
+                    (setuid, "su"),
+                    (setgid, "sg"),
+                    (executable, "ex"),
+                    (multi_link, "mh"),
+                ]
+                .into_iter()
+                .filter(|(applies, _)| *applies)
+                .find_map(|(_, key)| self.type_style(key));
+                special
+                    .or_else(|| self.pattern_style(name))
+                    .or_else(|| self.type_style("fi"))
+            }
+        }
+    }
+
+    /// Style `ls` would use for an entry named `name` of class `class`, or
+    /// `None` when LS_COLORS leaves it uncolored.
+    pub fn style_for_class(&self, class: EntryClass, name: &str) -> Option<Style> {
+        let kind = match (class.kind, class.target) {
+            (EntryKind::Symlink { orphan: false }, Some(target)) if self.link_as_target => target,
+            (kind, _) => kind,
+        };
+        self.style_for_kind(kind, name)
+    }
+
+    /// Style `ls` would use for `path`, read from the file system.
+    pub fn style_for_path(&self, path: &Path) -> Option<Style> {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.style_for_class(EntryClass::of(path), &name)
+    }
+}
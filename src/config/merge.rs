@@ -1,5 +1,7 @@
 use crate::config::keymap::{action_from_name, parse_key_combo, Action, UserKeymap};
 use crate::config::load::{ThemeProfile, UserThemeConfig};
+use crate::config::scheme::ImportedScheme;
+use crate::highlight::theme::{is_known_capture, style_from_config, SyntaxPalette, SyntaxTheme};
 use crossterm::event::KeyEvent;
 use std::collections::HashMap;
 
@@ -37,6 +39,12 @@ pub fn merge_theme_profile(defaults: ThemeProfile, user: &UserThemeConfig) -> Th
     if let Some(dim) = user.hidden_dim_enabled {
         merged.hidden_dim_enabled = dim;
     }
+    if let Some(color) = &user.status_foreground_color {
+        merged.status_foreground_color = color.to_ascii_lowercase();
+    }
+    if let Some(color) = &user.status_background_color {
+        merged.status_background_color = color.to_ascii_lowercase();
+    }
     for (ext, color) in &user.file_type_colors {
         merged
             .file_type_colors
@@ -44,3 +52,35 @@ pub fn merge_theme_profile(defaults: ThemeProfile, user: &UserThemeConfig) -> Th
     }
     merged
 }
+
+/// Resolves the built-in palette named by `syntax_palette`, then layers an
+/// imported color scheme and the per-capture `[theme.syntax]` overrides on top.
+pub fn merge_syntax_theme(
+    user: &UserThemeConfig,
+    imported: Option<&ImportedScheme>,
+) -> (SyntaxTheme, Vec<String>) {
+    let mut warnings = Vec::new();
+    let palette = match user.syntax_palette.as_deref() {
+        None => SyntaxPalette::default(),
+        Some(name) => SyntaxPalette::from_name(name).unwrap_or_else(|| {
+            warnings.push(format!("unknown syntax palette '{name}' ignored"));
+            SyntaxPalette::default()
+        }),
+    };
+    let mut theme = SyntaxTheme::builtin(palette);
+    if let Some(imported) = imported {
+        imported.apply_to_syntax(&mut theme);
+    }
+    let mut captures = user.syntax.iter().collect::<Vec<_>>();
+    captures.sort_by(|a, b| a.0.cmp(b.0));
+    for (capture, entry) in captures {
+        let capture = capture.to_ascii_lowercase();
+        if !is_known_capture(&capture) {
+            warnings.push(format!("unknown syntax capture '{capture}' ignored"));
+            continue;
+        }
+        let style = style_from_config(&capture, entry, &mut warnings);
+        theme.set_style(&capture, style);
+    }
+    (theme, warnings)
+}
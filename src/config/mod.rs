@@ -1,4 +1,8 @@
+pub mod icons;
 pub mod keymap;
 pub mod load;
+pub mod ls_colors;
 pub mod merge;
+pub mod scheme;
+pub mod style_rules;
 pub mod validate;
@@ -0,0 +1,311 @@
+use crate::config::load::ThemeProfile;
+use crate::highlight::theme::{parse_modifier, PageColors, SyntaxTheme};
+use anyhow::{anyhow, bail, Context, Result};
+use ratatui::style::{Color, Modifier, Style};
+use std::fs;
+use std::path::Path;
+
+/// Colors imported from an editor color scheme, already mapped onto fpv's
+/// capture names and tree/status color slots.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportedScheme {
+    pub syntax: Vec<(String, Style)>,
+    pub directory_color: Option<String>,
+    pub fallback_file_color: Option<String>,
+    pub status_foreground_color: Option<String>,
+    pub status_background_color: Option<String>,
+    /// The scheme's editor background and default text color.
+    pub page_colors: Option<PageColors>,
+}
+
+impl ImportedScheme {
+    pub fn apply_to_syntax(&self, theme: &mut SyntaxTheme) {
+        for (capture, style) in &self.syntax {
+            theme.set_style(capture, *style);
+        }
+        if let Some(page) = self.page_colors {
+            theme.set_page_colors(page);
+        }
+    }
+
+    pub fn apply_to_profile(&self, mut profile: ThemeProfile) -> ThemeProfile {
+        let slots = [
+            (&self.directory_color, &mut profile.directory_color),
+            (&self.fallback_file_color, &mut profile.fallback_file_color),
+            (
+                &self.status_foreground_color,
+                &mut profile.status_foreground_color,
+            ),
+            (
+                &self.status_background_color,
+                &mut profile.status_background_color,
+            ),
+        ];
+        for (imported, slot) in slots {
+            if let Some(color) = imported {
+                *slot = color.clone();
+            }
+        }
+        profile
+    }
+}
+
+pub fn load_color_scheme(path: &Path) -> Result<ImportedScheme> {
+    let data = fs::read(path).with_context(|| format!("cannot read {}", path.display()))?;
+    let ext = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let scheme = match ext.as_str() {
+        "tmtheme" | "plist" | "xml" => parse_tm_theme(&data),
+        "yaml" | "yml" => parse_base16_scheme(&String::from_utf8_lossy(&data)),
+        _ => bail!("unsupported color scheme format: {}", path.display()),
+    };
+    scheme.with_context(|| format!("invalid color scheme {}", path.display()))
+}
+
+fn hex_color(value: &str) -> Option<Color> {
+    let hex = value.trim().trim_start_matches('#');
+    if hex.len() < 6 || !hex.is_char_boundary(6) {
+        return None;
+    }
+    // tmTheme colors may carry a trailing alpha byte, which terminals cannot use.
+    let channel = |range| u8::from_str_radix(&hex[range], 16).ok();
+    Some(Color::Rgb(channel(0..2)?, channel(2..4)?, channel(4..6)?))
+}
+
+fn color_name(color: Color) -> Option<String> {
+    match color {
+        Color::Rgb(r, g, b) => Some(format!("#{r:02x}{g:02x}{b:02x}")),
+        _ => None,
+    }
+}
+
+/// base16 slot each capture takes its foreground from, following the base16
+/// styling guidelines (base08 variables/tags, base09 constants, base0A types,
+/// base0B strings, base0C support/escapes, base0D functions, base0E keywords).
+const BASE16_CAPTURES: &[(&str, &str, Modifier)] = &[
+    ("attribute", "base09", Modifier::empty()),
+    ("comment", "base03", Modifier::ITALIC),
+    ("constant", "base09", Modifier::empty()),
+    ("constructor", "base0A", Modifier::empty()),
+    ("embedded", "base0F", Modifier::empty()),
+    ("escape", "base0C", Modifier::empty()),
+    ("function", "base0D", Modifier::empty()),
+    ("function.builtin", "base0C", Modifier::empty()),
+    ("keyword", "base0E", Modifier::empty()),
+    ("module", "base0A", Modifier::empty()),
+    ("number", "base09", Modifier::empty()),
+    ("operator", "base05", Modifier::empty()),
+    ("property", "base08", Modifier::empty()),
+    ("punctuation.special", "base0F", Modifier::empty()),
+    ("string", "base0B", Modifier::empty()),
+    ("tag", "base08", Modifier::empty()),
+    ("text.emphasis", "base0E", Modifier::ITALIC),
+    ("text.literal", "base0B", Modifier::empty()),
+    ("text.reference", "base08", Modifier::UNDERLINED),
+    ("text.strong", "base0A", Modifier::BOLD),
+    ("text.title", "base0D", Modifier::BOLD),
+    ("text.uri", "base0C", Modifier::UNDERLINED),
+    ("type", "base0A", Modifier::empty()),
+    ("variable.builtin", "base08", Modifier::empty()),
+    ("variable.parameter", "base08", Modifier::empty()),
+];
+
+/// Parses a base16 scheme in either the classic flat layout
+/// (`base00: "2b303b"`) or the newer layout with a nested `palette:` map.
+pub fn parse_base16_scheme(text: &str) -> Result<ImportedScheme> {
+    let root: serde_yaml::Value = serde_yaml::from_str(text)?;
+    let palette = root.get("palette").unwrap_or(&root);
+    let slot = |name: &str| -> Result<Color> {
+        let value = palette
+            .get(name)
+            .or_else(|| palette.get(name.to_ascii_lowercase()))
+            .and_then(serde_yaml::Value::as_str)
+            .ok_or_else(|| anyhow!("missing {name}"))?;
+        hex_color(value).ok_or_else(|| anyhow!("invalid color for {name}: {value}"))
+    };
+
+    let mut syntax = Vec::with_capacity(BASE16_CAPTURES.len());
+    for (capture, name, modifier) in BASE16_CAPTURES {
+        let style = Style::default().fg(slot(name)?).add_modifier(*modifier);
+        syntax.push((capture.to_string(), style));
+    }
+    Ok(ImportedScheme {
+        syntax,
+        directory_color: color_name(slot("base0D")?),
+        fallback_file_color: color_name(slot("base05")?),
+        status_foreground_color: color_name(slot("base04")?),
+        status_background_color: color_name(slot("base01")?),
+        page_colors: Some(PageColors {
+            background: slot("base00")?,
+            foreground: slot("base05")?,
+        }),
+    })
+}
+
+/// TextMate scopes tried, in order, for each capture name.
+const TM_CAPTURE_SCOPES: &[(&str, &[&str])] = &[
+    ("attribute", &["entity.other.attribute-name"]),
+    ("comment", &["comment"]),
+    ("constant", &["constant", "support.constant"]),
+    ("constant.builtin", &["constant.language"]),
+    ("constructor", &["entity.name.class", "entity.name.type"]),
+    ("embedded", &["meta.embedded"]),
+    ("escape", &["constant.character.escape"]),
+    ("function", &["entity.name.function"]),
+    ("function.builtin", &["support.function"]),
+    ("keyword", &["keyword", "storage"]),
+    ("module", &["entity.name.namespace", "entity.name.module"]),
+    ("number", &["constant.numeric"]),
+    ("operator", &["keyword.operator"]),
+    (
+        "property",
+        &["variable.other.property", "support.type.property-name"],
+    ),
+    ("punctuation", &["punctuation"]),
+    ("string", &["string"]),
+    ("tag", &["entity.name.tag"]),
+    ("text.emphasis", &["markup.italic"]),
+    ("text.literal", &["markup.raw", "markup.inline.raw"]),
+    (
+        "text.reference",
+        &["markup.underline.link", "string.other.link"],
+    ),
+    ("text.strong", &["markup.bold"]),
+    ("text.title", &["markup.heading", "entity.name.section"]),
+    ("text.uri", &["markup.underline.link"]),
+    (
+        "type",
+        &["entity.name.type", "storage.type", "support.type"],
+    ),
+    ("type.builtin", &["support.type", "storage.type"]),
+    ("variable", &["variable"]),
+    ("variable.builtin", &["variable.language"]),
+    ("variable.parameter", &["variable.parameter"]),
+];
+
+struct TmRule {
+    selectors: Vec<String>,
+    style: Style,
+}
+
+impl TmRule {
+    /// Specificity of the best selector matching `scope`; plain dotted
+    /// selectors only, descendant and exclusion selectors are skipped.
+    fn score(&self, scope: &str) -> Option<usize> {
+        self.selectors
+            .iter()
+            .filter(|selector| {
+                scope == selector.as_str()
+                    || scope
+                        .strip_prefix(selector.as_str())
+                        .is_some_and(|rest| rest.starts_with('.'))
+            })
+            .map(|selector| selector.split('.').count())
+            .max()
+    }
+}
+
+fn tm_style(settings: &plist::Dictionary) -> Style {
+    let color = |key: &str| {
+        settings
+            .get(key)
+            .and_then(plist::Value::as_string)
+            .and_then(hex_color)
+    };
+    let mut style = Style::default();
+    if let Some(fg) = color("foreground") {
+        style = style.fg(fg);
+    }
+    if let Some(bg) = color("background") {
+        style = style.bg(bg);
+    }
+    if let Some(font_style) = settings.get("fontStyle").and_then(plist::Value::as_string) {
+        for name in font_style.split_whitespace() {
+            if let Some(modifier) = parse_modifier(name) {
+                style = style.add_modifier(modifier);
+            }
+        }
+    }
+    style
+}
+
+fn tm_style_for_capture(rules: &[TmRule], scopes: &[&str]) -> Option<Style> {
+    scopes.iter().find_map(|scope| {
+        let mut best: Option<(usize, &TmRule)> = None;
+        for rule in rules {
+            // Later rules win ties, matching TextMate's cascade.
+            if let Some(score) = rule.score(scope) {
+                if best.is_none_or(|(current, _)| score >= current) {
+                    best = Some((score, rule));
+                }
+            }
+        }
+        best.map(|(_, rule)| rule.style)
+    })
+}
+
+/// Parses a TextMate/Sublime `.tmTheme` property list.
+pub fn parse_tm_theme(data: &[u8]) -> Result<ImportedScheme> {
+    let root = plist::Value::from_reader(std::io::Cursor::new(data))?;
+    let entries = root
+        .as_dictionary()
+        .and_then(|dict| dict.get("settings"))
+        .and_then(plist::Value::as_array)
+        .ok_or_else(|| anyhow!("missing settings array"))?;
+
+    let mut global = None;
+    let mut rules = Vec::new();
+    for entry in entries.iter().filter_map(plist::Value::as_dictionary) {
+        let Some(settings) = entry.get("settings").and_then(plist::Value::as_dictionary) else {
+            continue;
+        };
+        match entry.get("scope").and_then(plist::Value::as_string) {
+            None if global.is_none() => global = Some(settings),
+            None => {}
+            Some(scope) => rules.push(TmRule {
+                selectors: scope
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|s| !s.is_empty() && !s.contains(' ') && !s.starts_with('-'))
+                    .map(str::to_string)
+                    .collect(),
+                style: tm_style(settings),
+            }),
+        }
+    }
+
+    let syntax = TM_CAPTURE_SCOPES
+        .iter()
+        .filter_map(|(capture, scopes)| {
+            tm_style_for_capture(&rules, scopes).map(|style| (capture.to_string(), style))
+        })
+        .collect::<Vec<_>>();
+    let global_hex = |key: &str| {
+        global
+            .and_then(|settings| settings.get(key))
+            .and_then(plist::Value::as_string)
+            .and_then(hex_color)
+    };
+    let global_color = |key: &str| global_hex(key).and_then(color_name);
+    let directory_color = syntax
+        .iter()
+        .find(|(capture, _)| capture == "function")
+        .and_then(|(_, style)| style.fg)
+        .and_then(color_name);
+    Ok(ImportedScheme {
+        syntax,
+        directory_color,
+        fallback_file_color: global_color("foreground"),
+        status_foreground_color: global_color("foreground"),
+        status_background_color: global_color("lineHighlight")
+            .or_else(|| global_color("selection")),
+        page_colors: global_hex("background").zip(global_hex("foreground")).map(
+            |(background, foreground)| PageColors {
+                background,
+                foreground,
+            },
+        ),
+    })
+}
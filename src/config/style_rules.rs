@@ -0,0 +1,186 @@
+use crate::app::state::{NodeType, TreeNode};
+use crate::fs::entry_kind::EntryKind;
+use crate::fs::git::GitFileStatus;
+use crate::highlight::theme::{parse_color, parse_modifier};
+use globset::{GlobBuilder, GlobMatcher};
+use ratatui::style::Modifier;
+use serde::Deserialize;
+
+/// One `[[theme.rules]]` entry. Every criterion that is set must match.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct UserStyleRule {
+    pub glob: Option<String>,
+    pub name: Option<String>,
+    #[serde(rename = "type")]
+    pub node_type: Option<String>,
+    pub executable: Option<bool>,
+    pub git_status: Option<String>,
+    pub color: Option<String>,
+    #[serde(default)]
+    pub modifiers: Vec<String>,
+    pub icon: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct StyleRule {
+    glob: Option<GlobMatcher>,
+    glob_matches_path: bool,
+    name: Option<String>,
+    node_type: Option<NodeType>,
+    executable: Option<bool>,
+    git_status: Option<GitFileStatus>,
+    pub color: Option<String>,
+    pub modifiers: Modifier,
+    pub icon: Option<String>,
+}
+
+fn node_type_from_name(name: &str) -> Option<NodeType> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "file" => Some(NodeType::File),
+        "dir" | "directory" => Some(NodeType::Directory),
+        "link" | "symlink" => Some(NodeType::Symlink),
+        _ => None,
+    }
+}
+
+fn git_status_from_name(name: &str) -> Option<GitFileStatus> {
+    match name.trim().to_ascii_lowercase().as_str() {
+        "added" => Some(GitFileStatus::Added),
+        "modified" => Some(GitFileStatus::Modified),
+        "deleted" => Some(GitFileStatus::Deleted),
+        "renamed" => Some(GitFileStatus::Renamed),
+        "copied" => Some(GitFileStatus::Copied),
+        "untracked" => Some(GitFileStatus::Untracked),
+        "conflicted" => Some(GitFileStatus::Conflicted),
+        "ignored" => Some(GitFileStatus::Ignored),
+        _ => None,
+    }
+}
+
+fn compile_rule(rule: &UserStyleRule) -> Result<StyleRule, String> {
+    let mut node_type = match &rule.node_type {
+        Some(name) => {
+            Some(node_type_from_name(name).ok_or_else(|| format!("unknown node type '{name}'"))?)
+        }
+        None => None,
+    };
+    let (glob, glob_matches_path) = match &rule.glob {
+        Some(pattern) => {
+            // `target/` matches directories only, like .gitignore.
+            let pattern = match pattern.strip_suffix('/') {
+                Some(dir) => {
+                    node_type.get_or_insert(NodeType::Directory);
+                    dir
+                }
+                None => pattern.as_str(),
+            };
+            let matcher = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|err| format!("invalid glob '{pattern}': {err}"))?
+                .compile_matcher();
+            (Some(matcher), pattern.contains('/'))
+        }
+        None => (None, false),
+    };
+    let git_status = match &rule.git_status {
+        Some(name) => {
+            Some(git_status_from_name(name).ok_or_else(|| format!("unknown git status '{name}'"))?)
+        }
+        None => None,
+    };
+    if let Some(color) = &rule.color {
+        if parse_color(color).is_none() {
+            return Err(format!("invalid color '{color}'"));
+        }
+    }
+    let mut modifiers = Modifier::empty();
+    for name in &rule.modifiers {
+        modifiers |= parse_modifier(name).ok_or_else(|| format!("unknown modifier '{name}'"))?;
+    }
+    let compiled = StyleRule {
+        glob,
+        glob_matches_path,
+        name: rule.name.clone(),
+        node_type,
+        executable: rule.executable,
+        git_status,
+        color: rule.color.as_ref().map(|color| color.to_ascii_lowercase()),
+        modifiers,
+        icon: rule.icon.clone().filter(|icon| !icon.is_empty()),
+    };
+    if compiled.glob.is_none()
+        && compiled.name.is_none()
+        && compiled.node_type.is_none()
+        && compiled.executable.is_none()
+        && compiled.git_status.is_none()
+    {
+        return Err("no match criteria".to_string());
+    }
+    Ok(compiled)
+}
+
+/// Compiles `[[theme.rules]]` in order, skipping invalid rules with a warning.
+pub fn compile_style_rules(rules: &[UserStyleRule]) -> (Vec<StyleRule>, Vec<String>) {
+    let mut compiled = Vec::with_capacity(rules.len());
+    let mut warnings = Vec::new();
+    for (index, rule) in rules.iter().enumerate() {
+        match compile_rule(rule) {
+            Ok(rule) => compiled.push(rule),
+            Err(reason) => warnings.push(format!("theme rule {} ignored: {reason}", index + 1)),
+        }
+    }
+    (compiled, warnings)
+}
+
+/// Whether `node` is an executable file, from the mode bits read when its
+/// directory was listed.
+fn is_executable(node: &TreeNode) -> bool {
+    node.node_type == NodeType::File
+        && match node.class().kind {
+            EntryKind::File { executable, .. } => executable,
+            _ => false,
+        }
+}
+
+impl StyleRule {
+    pub fn matches(&self, node: &TreeNode, git_status: Option<GitFileStatus>) -> bool {
+        if self
+            .node_type
+            .as_ref()
+            .is_some_and(|kind| *kind != node.node_type)
+        {
+            return false;
+        }
+        if self.name.as_ref().is_some_and(|name| *name != node.name) {
+            return false;
+        }
+        if self
+            .git_status
+            .is_some_and(|status| Some(status) != git_status)
+        {
+            return false;
+        }
+        if let Some(glob) = &self.glob {
+            let matched = if self.glob_matches_path {
+                glob.is_match(&node.path)
+            } else {
+                glob.is_match(&node.name)
+            };
+            if !matched {
+                return false;
+            }
+        }
+        self.executable
+            .is_none_or(|executable| executable == is_executable(node))
+    }
+}
+
+/// First rule matching `node`; rules are evaluated in config order.
+pub fn matching_rule<'a>(
+    rules: &'a [StyleRule],
+    node: &TreeNode,
+    git_status: Option<GitFileStatus>,
+) -> Option<&'a StyleRule> {
+    rules.iter().find(|rule| rule.matches(node, git_status))
+}
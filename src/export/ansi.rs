@@ -0,0 +1,114 @@
+use crate::app::state::StyledPreviewLine;
+use crate::tui::color_support::{adapt_style, ColorSupport};
+use crate::tui::preview_pane::wrap_styled_spans;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnsiRenderOptions {
+    pub color: ColorSupport,
+    pub line_numbers: bool,
+    pub wrap_width: Option<usize>,
+}
+
+fn color_sgr(color: Color, foreground: bool) -> Option<String> {
+    let base = if foreground { 30 } else { 40 };
+    let code = match color {
+        Color::Reset => return None,
+        Color::Black => base,
+        Color::Red => base + 1,
+        Color::Green => base + 2,
+        Color::Yellow => base + 3,
+        Color::Blue => base + 4,
+        Color::Magenta => base + 5,
+        Color::Cyan => base + 6,
+        Color::Gray => base + 7,
+        Color::DarkGray => base + 60,
+        Color::LightRed => base + 61,
+        Color::LightGreen => base + 62,
+        Color::LightYellow => base + 63,
+        Color::LightBlue => base + 64,
+        Color::LightMagenta => base + 65,
+        Color::LightCyan => base + 66,
+        Color::White => base + 67,
+        Color::Rgb(r, g, b) => return Some(format!("{};2;{r};{g};{b}", base + 8)),
+        Color::Indexed(index) => return Some(format!("{};5;{index}", base + 8)),
+    };
+    Some(code.to_string())
+}
+
+/// SGR parameters (without the `ESC [` prefix and `m` suffix) for a style.
+pub fn style_sgr_params(style: Style) -> String {
+    let mut params = Vec::new();
+    let modifiers = [
+        (Modifier::BOLD, "1"),
+        (Modifier::DIM, "2"),
+        (Modifier::ITALIC, "3"),
+        (Modifier::UNDERLINED, "4"),
+        (Modifier::REVERSED, "7"),
+        (Modifier::CROSSED_OUT, "9"),
+    ];
+    for (modifier, code) in modifiers {
+        if style.add_modifier.contains(modifier) {
+            params.push(code.to_string());
+        }
+    }
+    if let Some(fg) = style.fg.and_then(|c| color_sgr(c, true)) {
+        params.push(fg);
+    }
+    if let Some(bg) = style.bg.and_then(|c| color_sgr(c, false)) {
+        params.push(bg);
+    }
+    params.join(";")
+}
+
+fn push_styled(out: &mut String, text: &str, style: Style, color: ColorSupport) {
+    let params = match color {
+        ColorSupport::Monochrome => String::new(),
+        _ => style_sgr_params(adapt_style(style, color)),
+    };
+    if params.is_empty() {
+        out.push_str(text);
+    } else {
+        out.push_str(&format!("\x1b[{params}m{text}\x1b[0m"));
+    }
+}
+
+fn gutter_style() -> Style {
+    Style::default().fg(Color::DarkGray)
+}
+
+/// Renders preview lines as terminal text, optionally with ANSI colors reduced
+/// to the given color support, a line number gutter and hard wrapping at
+/// `wrap_width` content columns.
+pub fn render_ansi_lines(lines: &[StyledPreviewLine], options: AnsiRenderOptions) -> String {
+    let number_width = lines.len().max(1).to_string().len().max(2);
+    let mut out = String::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        let spans = line
+            .iter()
+            .map(|segment| Span::styled(segment.text.clone(), segment.style))
+            .collect::<Vec<_>>();
+        let rows = match options.wrap_width {
+            Some(width) => wrap_styled_spans(spans, width),
+            None => vec![spans],
+        };
+
+        for (row_index, row) in rows.iter().enumerate() {
+            if options.line_numbers {
+                let gutter = if row_index == 0 {
+                    format!("{:>number_width$} ", index + 1)
+                } else {
+                    format!("{:number_width$} ", "")
+                };
+                push_styled(&mut out, &gutter, gutter_style(), options.color);
+            }
+            for span in row {
+                push_styled(&mut out, &span.content, span.style, options.color);
+            }
+            out.push('\n');
+        }
+    }
+    out
+}
@@ -0,0 +1,147 @@
+use crate::app::state::StyledPreviewLine;
+use crate::highlight::theme::PageColors;
+use ratatui::style::{Color, Modifier, Style};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HtmlExportOptions {
+    pub line_numbers: bool,
+    /// Number shown for the first exported line.
+    pub first_line_number: usize,
+    /// Page colors of the active syntax theme.
+    pub colors: PageColors,
+}
+
+pub fn escape_html(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(ch),
+        }
+    }
+    out
+}
+
+/// Approximates the xterm palette for named terminal colors.
+pub fn css_color(color: Color) -> Option<String> {
+    let hex = match color {
+        Color::Reset => return None,
+        Color::Black => "#000000",
+        Color::Red => "#cd3131",
+        Color::Green => "#0dbc79",
+        Color::Yellow => "#e5e510",
+        Color::Blue => "#2472c8",
+        Color::Magenta => "#bc3fbc",
+        Color::Cyan => "#11a8cd",
+        Color::Gray => "#e5e5e5",
+        Color::DarkGray => "#666666",
+        Color::LightRed => "#f14c4c",
+        Color::LightGreen => "#23d18b",
+        Color::LightYellow => "#f5f543",
+        Color::LightBlue => "#3b8eea",
+        Color::LightMagenta => "#d670d6",
+        Color::LightCyan => "#29b8db",
+        Color::White => "#ffffff",
+        Color::Rgb(r, g, b) => return Some(format!("#{r:02x}{g:02x}{b:02x}")),
+        Color::Indexed(index) => return Some(indexed_css_color(index)),
+    };
+    Some(hex.to_string())
+}
+
+fn indexed_css_color(index: u8) -> String {
+    match index {
+        0..=15 => {
+            const BASE: [&str; 16] = [
+                "#000000", "#cd3131", "#0dbc79", "#e5e510", "#2472c8", "#bc3fbc", "#11a8cd",
+                "#e5e5e5", "#666666", "#f14c4c", "#23d18b", "#f5f543", "#3b8eea", "#d670d6",
+                "#29b8db", "#ffffff",
+            ];
+            BASE[index as usize].to_string()
+        }
+        16..=231 => {
+            let value = index - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            format!(
+                "#{:02x}{:02x}{:02x}",
+                level(value / 36),
+                level((value / 6) % 6),
+                level(value % 6)
+            )
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            format!("#{gray:02x}{gray:02x}{gray:02x}")
+        }
+    }
+}
+
+pub fn css_for_style(style: Style) -> String {
+    let mut rules = Vec::new();
+    if let Some(fg) = style.fg.and_then(css_color) {
+        rules.push(format!("color:{fg}"));
+    }
+    if let Some(bg) = style.bg.and_then(css_color) {
+        rules.push(format!("background-color:{bg}"));
+    }
+    if style.add_modifier.contains(Modifier::BOLD) {
+        rules.push("font-weight:bold".to_string());
+    }
+    if style.add_modifier.contains(Modifier::DIM) {
+        rules.push("opacity:0.6".to_string());
+    }
+    if style.add_modifier.contains(Modifier::ITALIC) {
+        rules.push("font-style:italic".to_string());
+    }
+    let underlined = style.add_modifier.contains(Modifier::UNDERLINED);
+    let crossed = style.add_modifier.contains(Modifier::CROSSED_OUT);
+    match (underlined, crossed) {
+        (true, true) => rules.push("text-decoration:underline line-through".to_string()),
+        (true, false) => rules.push("text-decoration:underline".to_string()),
+        (false, true) => rules.push("text-decoration:line-through".to_string()),
+        (false, false) => {}
+    }
+    rules.join(";")
+}
+
+/// Renders styled lines as a standalone HTML document with inline CSS.
+pub fn render_html_document(
+    title: &str,
+    lines: &[StyledPreviewLine],
+    options: HtmlExportOptions,
+) -> String {
+    let last_number = options.first_line_number + lines.len().saturating_sub(1);
+    let number_width = last_number.max(1).to_string().len();
+    let mut body = String::new();
+
+    for (index, line) in lines.iter().enumerate() {
+        if options.line_numbers {
+            body.push_str(&format!(
+                "<span class=\"ln\">{:>number_width$}</span> ",
+                options.first_line_number + index
+            ));
+        }
+        for segment in line {
+            let text = escape_html(&segment.text);
+            let css = css_for_style(segment.style);
+            if css.is_empty() {
+                body.push_str(&text);
+            } else {
+                body.push_str(&format!("<span style=\"{css}\">{text}</span>"));
+            }
+        }
+        body.push('\n');
+    }
+
+    // CSS system colors stand in for the terminal's own.
+    let background = css_color(options.colors.background).unwrap_or_else(|| "Canvas".to_string());
+    let foreground =
+        css_color(options.colors.foreground).unwrap_or_else(|| "CanvasText".to_string());
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\nbody {{ margin: 0; background: {background}; }}\npre.fpv {{ margin: 0; padding: 1em; background: {background}; color: {foreground}; font-family: ui-monospace, SFMono-Regular, Menlo, Consolas, monospace; font-size: 13px; line-height: 1.4; }}\npre.fpv .ln {{ opacity: 0.5; user-select: none; }}\n</style>\n</head>\n<body>\n<pre class=\"fpv\">{body}</pre>\n</body>\n</html>\n",
+        title = escape_html(title)
+    )
+}
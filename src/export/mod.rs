@@ -0,0 +1,2 @@
+pub mod ansi;
+pub mod html;
@@ -0,0 +1,654 @@
+use crate::app::state::{NodeType, TreeNode};
+use flate2::read::GzDecoder;
+use std::cell::Cell;
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+use time::{Date, Month, PrimitiveDateTime, Time};
+use zip::ZipArchive;
+
+/// Archive formats that can be listed and browsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+    TarXz,
+}
+
+impl ArchiveKind {
+    /// Format named by the file name of `path`; `.jar` files are zips.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else if name.ends_with(".tar.xz") || name.ends_with(".txz") {
+            Some(Self::TarXz)
+        } else if name.ends_with(".tar") {
+            Some(Self::Tar)
+        } else if name.ends_with(".zip") || name.ends_with(".jar") {
+            Some(Self::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// Uncompressed bytes of a tarball read to list it; entries past this are
+/// left out so a huge tarball cannot stall the interface.
+pub const MAX_TAR_SCAN_BYTES: u64 = 256 * 1024 * 1024;
+/// Size of the chunks an xz stream is decoded in.
+const XZ_CHUNK_BYTES: usize = 64 * 1024;
+/// Decoded chunks an xz reader may fall behind by.
+const XZ_CHUNKS_IN_FLIGHT: usize = 4;
+/// Size of the chunks member data is copied in, between checks for a
+/// cancelled read.
+const COPY_CHUNK_BYTES: usize = 64 * 1024;
+/// Archives whose listing is kept.
+const LISTINGS_KEPT: usize = 4;
+/// Member reads whose data is kept.
+const MEMBERS_KEPT: usize = 8;
+
+/// A file or directory stored in an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// `/`-separated path inside the archive, without a trailing slash.
+    pub path: String,
+    pub is_dir: bool,
+    /// Uncompressed size in bytes.
+    pub size: u64,
+    /// Modification time in seconds since the Unix epoch, if recorded.
+    pub modified: Option<i64>,
+    /// Where the member's data is: its index in a zip, or its offset in the
+    /// uncompressed tar stream. `None` for implied directories.
+    location: Option<u64>,
+}
+
+impl ArchiveEntry {
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+
+    /// Directory holding the entry, `""` at the top level.
+    pub fn parent(&self) -> &str {
+        self.path.rsplit_once('/').map_or("", |(parent, _)| parent)
+    }
+}
+
+/// Every entry of an archive, sorted by path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchiveListing {
+    pub entries: Vec<ArchiveEntry>,
+    /// Set when a tarball was only read up to [`MAX_TAR_SCAN_BYTES`].
+    pub partial: bool,
+}
+
+/// Modification time and length, which tell whether an archive changed.
+type Stamp = (Option<SystemTime>, u64);
+
+struct CachedListing {
+    archive: PathBuf,
+    stamp: Stamp,
+    listing: Arc<ArchiveListing>,
+}
+
+/// The archives listed last, most recent at the end. Browsing an archive
+/// lists it on every move, and compressed tarballs must be decompressed from
+/// the start to be listed.
+static LISTING_CACHE: Mutex<Vec<CachedListing>> = Mutex::new(Vec::new());
+
+struct CachedMember {
+    archive: PathBuf,
+    stamp: Stamp,
+    member: String,
+    data: Vec<u8>,
+    /// The data is the whole member rather than its head.
+    whole: bool,
+}
+
+/// The members read last, most recent at the end.
+static MEMBER_CACHE: Mutex<VecDeque<CachedMember>> = Mutex::new(VecDeque::new());
+
+/// The uncompressed stream of a compressed tarball, left where the last
+/// member read stopped so a member further on continues from there.
+struct OpenTarStream {
+    archive: PathBuf,
+    stamp: Stamp,
+    stream: Box<dyn Read + Send>,
+    position: u64,
+}
+
+static TAR_STREAM: Mutex<Option<OpenTarStream>> = Mutex::new(None);
+
+fn invalid_data(err: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, err.to_string())
+}
+
+fn cancelled() -> io::Error {
+    io::Error::other("cancelled")
+}
+
+fn archive_stamp(archive: &Path) -> io::Result<Stamp> {
+    let meta = fs::metadata(archive)?;
+    Ok((meta.modified().ok(), meta.len()))
+}
+
+/// Copies `len` bytes of `reader` into `out`, counting them in `position`
+/// as they go and stopping once `cancel` is set.
+fn copy_chunks(
+    reader: &mut dyn Read,
+    len: u64,
+    out: &mut impl Write,
+    position: &mut u64,
+    cancel: &AtomicBool,
+) -> io::Result<()> {
+    let mut chunk = vec![0; COPY_CHUNK_BYTES];
+    let mut left = len;
+    while left > 0 {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(cancelled());
+        }
+        let wanted = chunk.len().min(usize::try_from(left).unwrap_or(usize::MAX));
+        let read = match reader.read(&mut chunk[..wanted]) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        out.write_all(&chunk[..read])?;
+        *position += read as u64;
+        left -= read as u64;
+    }
+    Ok(())
+}
+
+/// Whether `path` is an archive file on disk that can be entered.
+pub fn is_archive_file(path: &Path) -> bool {
+    ArchiveKind::for_path(path).is_some() && path.is_file()
+}
+
+/// Splits a path below an archive into the archive file and the member
+/// path inside it (`""` for the archive itself). Returns `None` for paths
+/// not inside an archive.
+pub fn split_archive_path(path: &Path) -> Option<(PathBuf, String)> {
+    // A path in a directory on disk is at most the archive itself, so its
+    // ancestors need not be looked at.
+    if path.parent().is_none_or(Path::is_dir) {
+        return is_archive_file(path).then(|| (path.to_path_buf(), String::new()));
+    }
+    let archive = path
+        .ancestors()
+        .find(|ancestor| is_archive_file(ancestor))?;
+    let member = path
+        .strip_prefix(archive)
+        .ok()?
+        .components()
+        .map(|part| part.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    Some((archive.to_path_buf(), member))
+}
+
+/// Member path with `./` segments and surrounding slashes dropped; `None`
+/// for names that would escape the archive.
+fn normalize_member(name: &str) -> Option<String> {
+    let parts = name
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .collect::<Vec<_>>();
+    if parts.is_empty() || parts.contains(&"..") {
+        return None;
+    }
+    Some(parts.join("/"))
+}
+
+fn zip_timestamp(datetime: zip::DateTime) -> Option<i64> {
+    let date = Date::from_calendar_date(
+        datetime.year().into(),
+        Month::try_from(datetime.month()).ok()?,
+        datetime.day(),
+    )
+    .ok()?;
+    let time = Time::from_hms(datetime.hour(), datetime.minute(), datetime.second()).ok()?;
+    Some(
+        PrimitiveDateTime::new(date, time)
+            .assume_utc()
+            .unix_timestamp(),
+    )
+}
+
+fn open_zip(path: &Path) -> io::Result<ZipArchive<BufReader<File>>> {
+    ZipArchive::new(BufReader::new(File::open(path)?)).map_err(invalid_data)
+}
+
+/// Sends decoded xz data on to an [`XzReader`] a chunk at a time. Writes
+/// fail once the reader is gone, which stops the decoder.
+struct ChunkSender {
+    chunks: SyncSender<io::Result<Vec<u8>>>,
+    pending: Vec<u8>,
+}
+
+impl ChunkSender {
+    fn send_pending(&mut self) -> io::Result<()> {
+        let chunk = std::mem::replace(&mut self.pending, Vec::with_capacity(XZ_CHUNK_BYTES));
+        self.chunks
+            .send(Ok(chunk))
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))
+    }
+}
+
+impl Write for ChunkSender {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        if self.pending.len() >= XZ_CHUNK_BYTES {
+            self.send_pending()?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// An xz stream decoded on a background thread, so a tarball is never held
+/// in memory whole.
+struct XzReader {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    current: Cursor<Vec<u8>>,
+}
+
+impl XzReader {
+    fn new(mut input: BufReader<File>) -> Self {
+        let (sender, chunks) = mpsc::sync_channel(XZ_CHUNKS_IN_FLIGHT);
+        thread::spawn(move || {
+            let mut output = ChunkSender {
+                chunks: sender.clone(),
+                pending: Vec::with_capacity(XZ_CHUNK_BYTES),
+            };
+            let result = lzma_rs::xz_decompress(&mut input, &mut output)
+                .map_err(invalid_data)
+                .and_then(|()| output.send_pending());
+            if let Err(err) = result {
+                let _ = sender.send(Err(err));
+            }
+        });
+        Self {
+            chunks,
+            current: Cursor::new(Vec::new()),
+        }
+    }
+}
+
+impl Read for XzReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.current.read(buf)?;
+            if read > 0 || buf.is_empty() {
+                return Ok(read);
+            }
+            match self.chunks.recv() {
+                Ok(chunk) => self.current = Cursor::new(chunk?),
+                // The decoder finished and every chunk was read.
+                Err(_) => return Ok(0),
+            }
+        }
+    }
+}
+
+/// Reads at most `remaining` bytes of `inner`, noting in `reached` when
+/// more were asked for, and fails once `cancel` is set.
+struct ScanLimit<'a, R> {
+    inner: R,
+    remaining: u64,
+    reached: Rc<Cell<bool>>,
+    cancel: &'a AtomicBool,
+}
+
+impl<R: Read> Read for ScanLimit<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(cancelled());
+        }
+        if self.remaining == 0 {
+            self.reached.set(!buf.is_empty());
+            return Ok(0);
+        }
+        let wanted = buf
+            .len()
+            .min(usize::try_from(self.remaining).unwrap_or(usize::MAX));
+        let read = self.inner.read(&mut buf[..wanted])?;
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+/// The uncompressed tar stream of `path`.
+fn open_tar_stream(path: &Path, kind: ArchiveKind) -> io::Result<Box<dyn Read + Send>> {
+    let file = BufReader::new(File::open(path)?);
+    Ok(match kind {
+        ArchiveKind::TarGz => Box::new(GzDecoder::new(file)),
+        ArchiveKind::TarXz => Box::new(XzReader::new(file)),
+        ArchiveKind::Tar | ArchiveKind::Zip => Box::new(file),
+    })
+}
+
+fn read_listing(path: &Path, kind: ArchiveKind, cancel: &AtomicBool) -> io::Result<ArchiveListing> {
+    // Keyed by path so a member stored twice is listed once, as its last copy.
+    let mut entries = BTreeMap::new();
+    let mut partial = false;
+    if kind == ArchiveKind::Zip {
+        let mut zip = open_zip(path)?;
+        for index in 0..zip.len() {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(cancelled());
+            }
+            let file = zip.by_index_raw(index).map_err(invalid_data)?;
+            let Some(member) = normalize_member(file.name()) else {
+                continue;
+            };
+            let entry = ArchiveEntry {
+                path: member.clone(),
+                is_dir: file.is_dir(),
+                size: file.size(),
+                modified: zip_timestamp(file.last_modified()),
+                location: Some(index as u64),
+            };
+            entries.insert(member, entry);
+        }
+    } else {
+        let limited = ScanLimit {
+            inner: open_tar_stream(path, kind)?,
+            remaining: MAX_TAR_SCAN_BYTES,
+            reached: Rc::default(),
+            cancel,
+        };
+        let reached = Rc::clone(&limited.reached);
+        let mut archive = tar::Archive::new(limited);
+        for entry in archive.entries()? {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) if reached.get() => break,
+                Err(err) => return Err(err),
+            };
+            let header = entry.header();
+            let entry_type = header.entry_type();
+            if entry_type.is_pax_global_extensions() {
+                continue;
+            }
+            let Some(member) = normalize_member(&String::from_utf8_lossy(&entry.path_bytes()))
+            else {
+                continue;
+            };
+            let entry = ArchiveEntry {
+                path: member.clone(),
+                is_dir: entry_type.is_dir(),
+                size: header.size().unwrap_or(0),
+                modified: header.mtime().ok().map(|mtime| mtime as i64),
+                location: Some(entry.raw_file_position()),
+            };
+            entries.insert(member, entry);
+        }
+        partial = reached.get();
+    }
+
+    // Archives need not store the directories their files sit in.
+    let implied = entries
+        .keys()
+        .flat_map(|member| {
+            member
+                .match_indices('/')
+                .map(|(at, _)| member[..at].to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    for directory in implied {
+        entries.entry(directory.clone()).or_insert(ArchiveEntry {
+            path: directory,
+            is_dir: true,
+            size: 0,
+            modified: None,
+            location: None,
+        });
+    }
+    Ok(ArchiveListing {
+        entries: entries.into_values().collect(),
+        partial,
+    })
+}
+
+/// Every entry of `archive`, sorted by path. The last few archives listed
+/// are cached until their file changes.
+pub fn archive_entries(archive: &Path) -> io::Result<Arc<ArchiveListing>> {
+    archive_entries_with_cancel(archive, &AtomicBool::new(false))
+}
+
+/// [`archive_entries`], giving up once `cancel` is set. Another listing
+/// waits for this one, which is usually of the same archive.
+pub fn archive_entries_with_cancel(
+    archive: &Path,
+    cancel: &AtomicBool,
+) -> io::Result<Arc<ArchiveListing>> {
+    let kind =
+        ArchiveKind::for_path(archive).ok_or_else(|| invalid_data("not a supported archive"))?;
+    let stamp = archive_stamp(archive)?;
+    let mut cache = LISTING_CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(at) = cache
+        .iter()
+        .position(|cached| cached.archive == archive && cached.stamp == stamp)
+    {
+        let cached = cache.remove(at);
+        let listing = Arc::clone(&cached.listing);
+        cache.push(cached);
+        return Ok(listing);
+    }
+    let listing = Arc::new(read_listing(archive, kind, cancel)?);
+    cache.retain(|cached| cached.archive != archive);
+    if cache.len() >= LISTINGS_KEPT {
+        cache.remove(0);
+    }
+    cache.push(CachedListing {
+        archive: archive.to_path_buf(),
+        stamp,
+        listing: Arc::clone(&listing),
+    });
+    Ok(listing)
+}
+
+/// The entry stored at `member` in `archive`.
+pub fn archive_entry(archive: &Path, member: &str) -> Option<ArchiveEntry> {
+    archive_entries(archive)
+        .ok()?
+        .entries
+        .iter()
+        .find(|entry| entry.path == member)
+        .cloned()
+}
+
+/// Tree nodes for the entries directly inside the member directory `dir` of
+/// `archive` (`""` for its top level), with paths below the archive's own.
+pub fn list_archive_directory(
+    archive: &Path,
+    dir: &str,
+    max_entries: usize,
+    show_hidden: bool,
+) -> io::Result<Vec<TreeNode>> {
+    Ok(archive_entries(archive)?
+        .entries
+        .iter()
+        .filter(|entry| entry.parent() == dir)
+        .filter(|entry| show_hidden || !entry.name().starts_with('.'))
+        .take(max_entries)
+        .map(|entry| TreeNode {
+            path: archive.join(&entry.path),
+            name: entry.name().to_string(),
+            node_type: if entry.is_dir {
+                NodeType::Directory
+            } else {
+                NodeType::File
+            },
+            depth: 0,
+            expanded: false,
+            readable: true,
+            children_loaded: false,
+            entry_class: None,
+        })
+        .collect())
+}
+
+/// Reads at most `max_bytes` of the file `member` of `archive` into memory,
+/// decompressing it on the way; nothing is extracted to disk. The member is
+/// found through the cached listing, so only a plain tar can seek to it;
+/// compressed tarballs are decompressed up to its data, continuing from the
+/// member read before when it lies further on. The last few members read
+/// are cached.
+pub fn read_archive_member(archive: &Path, member: &str, max_bytes: usize) -> io::Result<Vec<u8>> {
+    read_archive_member_with_cancel(archive, member, max_bytes, &AtomicBool::new(false))
+}
+
+/// [`read_archive_member`], giving up once `cancel` is set.
+pub fn read_archive_member_with_cancel(
+    archive: &Path,
+    member: &str,
+    max_bytes: usize,
+    cancel: &AtomicBool,
+) -> io::Result<Vec<u8>> {
+    let kind =
+        ArchiveKind::for_path(archive).ok_or_else(|| invalid_data("not a supported archive"))?;
+    let listing = archive_entries_with_cancel(archive, cancel)?;
+    let Some((location, size)) = listing
+        .entries
+        .iter()
+        .find(|entry| !entry.is_dir && entry.path == member)
+        .and_then(|entry| Some((entry.location?, entry.size)))
+    else {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{member} is not in the archive"),
+        ));
+    };
+    let stamp = archive_stamp(archive)?;
+    let limit = size.min(max_bytes as u64);
+    if let Some(data) = cached_member(archive, stamp, member, limit) {
+        return Ok(data);
+    }
+    let mut data = Vec::new();
+    let mut position = 0;
+    match kind {
+        ArchiveKind::Zip => {
+            let mut zip = open_zip(archive)?;
+            let index = usize::try_from(location).map_err(invalid_data)?;
+            let mut file = zip.by_index(index).map_err(invalid_data)?;
+            copy_chunks(&mut file, limit, &mut data, &mut position, cancel)?;
+        }
+        ArchiveKind::Tar => {
+            let mut file = File::open(archive)?;
+            file.seek(SeekFrom::Start(location))?;
+            copy_chunks(&mut file, limit, &mut data, &mut position, cancel)?;
+        }
+        ArchiveKind::TarGz | ArchiveKind::TarXz => {
+            read_from_tar_stream(archive, kind, stamp, location, limit, &mut data, cancel)?;
+        }
+    }
+    remember_member(archive, stamp, member, &data, limit == size);
+    Ok(data)
+}
+
+/// Reads `limit` bytes at `location` of the uncompressed stream of a
+/// compressed tarball, reusing the stream left by the last read when it has
+/// not passed `location` yet.
+fn read_from_tar_stream(
+    archive: &Path,
+    kind: ArchiveKind,
+    stamp: Stamp,
+    location: u64,
+    limit: u64,
+    data: &mut Vec<u8>,
+    cancel: &AtomicBool,
+) -> io::Result<()> {
+    let mut open = TAR_STREAM
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let reusable = open.as_ref().is_some_and(|open| {
+        open.archive == archive && open.stamp == stamp && open.position <= location
+    });
+    if !reusable {
+        *open = Some(OpenTarStream {
+            archive: archive.to_path_buf(),
+            stamp,
+            stream: open_tar_stream(archive, kind)?,
+            position: 0,
+        });
+    }
+    let Some(stream) = open.as_mut() else {
+        return Err(cancelled());
+    };
+    let skip = location - stream.position;
+    let result = copy_chunks(
+        &mut stream.stream,
+        skip,
+        &mut io::sink(),
+        &mut stream.position,
+        cancel,
+    )
+    .and_then(|()| {
+        copy_chunks(
+            &mut stream.stream,
+            limit,
+            data,
+            &mut stream.position,
+            cancel,
+        )
+    });
+    // A cancelled read leaves the stream where it stopped, ready for the
+    // next; after any other error it cannot be trusted.
+    if result.is_err() && !cancel.load(Ordering::Relaxed) {
+        *open = None;
+    }
+    result
+}
+
+/// The first `limit` bytes of `member` if a cached read holds them.
+fn cached_member(archive: &Path, stamp: Stamp, member: &str, limit: u64) -> Option<Vec<u8>> {
+    let mut cache = MEMBER_CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let at = cache.iter().position(|cached| {
+        cached.archive == archive
+            && cached.stamp == stamp
+            && cached.member == member
+            && (cached.whole || cached.data.len() as u64 >= limit)
+    })?;
+    let cached = cache.remove(at)?;
+    let len =
+        usize::try_from(limit).map_or(cached.data.len(), |limit| limit.min(cached.data.len()));
+    let data = cached.data[..len].to_vec();
+    cache.push_back(cached);
+    Some(data)
+}
+
+fn remember_member(archive: &Path, stamp: Stamp, member: &str, data: &[u8], whole: bool) {
+    let mut cache = MEMBER_CACHE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    cache.retain(|cached| cached.archive != archive || cached.member != member);
+    if cache.len() >= MEMBERS_KEPT {
+        cache.pop_front();
+    }
+    cache.push_back(CachedMember {
+        archive: archive.to_path_buf(),
+        stamp,
+        member: member.to_string(),
+        data: data.to_vec(),
+        whole,
+    });
+}
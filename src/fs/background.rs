@@ -0,0 +1,45 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Work for a preview running in a background thread, such as decompressing
+/// an archive member or decoding an image. Dropping the last handle sets the
+/// flag the work is given, which it checks to stop early.
+#[derive(Debug)]
+pub struct BackgroundLoad<T> {
+    result: Arc<Mutex<Option<T>>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl<T: Send + 'static> BackgroundLoad<T> {
+    pub fn spawn(work: impl FnOnce(&AtomicBool) -> T + Send + 'static) -> Self {
+        let result = Arc::new(Mutex::new(None));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (worker_result, worker_cancel) = (Arc::clone(&result), Arc::clone(&cancel));
+        thread::spawn(move || {
+            let output = work(&worker_cancel);
+            if !worker_cancel.load(Ordering::Relaxed) {
+                *worker_result
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(output);
+            }
+        });
+        Self { result, cancel }
+    }
+}
+
+impl<T> BackgroundLoad<T> {
+    /// The finished result; `None` while the work runs and once it was taken.
+    pub fn take(&self) -> Option<T> {
+        self.result
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take()
+    }
+}
+
+impl<T> Drop for BackgroundLoad<T> {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
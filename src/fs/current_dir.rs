@@ -1,5 +1,7 @@
 use crate::app::current_dir_state::METADATA_FALLBACK;
 use crate::app::state::{NodeType, SelectedEntryMetadata, TreeNode};
+use crate::fs::archive::{archive_entry, list_archive_directory, split_archive_path};
+use crate::fs::entry_kind::EntryClass;
 use anyhow::Result;
 use std::cmp::Ordering;
 use std::fs;
@@ -12,8 +14,31 @@ pub fn list_current_directory(path: &Path, max_entries: usize) -> Result<Vec<Tre
 }
 
 pub fn selected_entry_metadata(node: &TreeNode) -> SelectedEntryMetadata {
-    let metadata = fs::symlink_metadata(&node.path).ok();
     let filename = node.name.clone();
+    let hidden_text = if is_hidden_name(&node.name) {
+        "on"
+    } else {
+        "off"
+    }
+    .to_string();
+    if let Some((archive, member)) = split_archive_path(&node.path).filter(|(_, m)| !m.is_empty()) {
+        let entry = archive_entry(&archive, &member);
+        return SelectedEntryMetadata {
+            filename,
+            size_text: entry
+                .as_ref()
+                .map(|e| format_size(e.size))
+                .unwrap_or_else(|| METADATA_FALLBACK.to_string()),
+            permission_text: METADATA_FALLBACK.to_string(),
+            modified_text: entry
+                .and_then(|e| e.modified)
+                .map(format_modified_timestamp)
+                .unwrap_or_else(|| METADATA_FALLBACK.to_string()),
+            hidden_text,
+        };
+    }
+
+    let metadata = fs::symlink_metadata(&node.path).ok();
     let size_text = metadata
         .as_ref()
         .map(|m| format_size(m.len()))
@@ -28,12 +53,6 @@ pub fn selected_entry_metadata(node: &TreeNode) -> SelectedEntryMetadata {
         .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
         .map(|d| format_modified_timestamp(d.as_secs() as i64))
         .unwrap_or_else(|| METADATA_FALLBACK.to_string());
-    let hidden_text = if is_hidden_name(&node.name) {
-        "on"
-    } else {
-        "off"
-    }
-    .to_string();
 
     SelectedEntryMetadata {
         filename,
@@ -49,6 +68,12 @@ pub fn list_current_directory_with_visibility(
     max_entries: usize,
     show_hidden: bool,
 ) -> Result<Vec<TreeNode>> {
+    if let Some((archive, member)) = split_archive_path(path) {
+        let mut nodes = list_archive_directory(&archive, &member, max_entries, show_hidden)?;
+        nodes.sort_by(directory_first_cmp);
+        return Ok(nodes);
+    }
+
     let mut nodes = Vec::new();
     let entries = fs::read_dir(path)?;
 
@@ -83,6 +108,7 @@ pub fn list_current_directory_with_visibility(
             NodeType::Directory => fs::read_dir(&path).is_ok(),
             _ => fs::File::open(&path).is_ok(),
         };
+        let entry_class = EntryClass::from_symlink_metadata(&path, metadata.as_ref());
 
         nodes.push(TreeNode {
             name,
@@ -92,6 +118,7 @@ pub fn list_current_directory_with_visibility(
             expanded: false,
             readable,
             children_loaded: false,
+            entry_class: Some(entry_class),
         });
     }
 
@@ -103,7 +130,7 @@ fn is_hidden_name(name: &str) -> bool {
     name.starts_with('.')
 }
 
-fn format_size(size: u64) -> String {
+pub(crate) fn format_size(size: u64) -> String {
     const KIB: f64 = 1024.0;
     const MIB: f64 = 1024.0 * 1024.0;
     let n = size as f64;
@@ -152,7 +179,7 @@ fn symbolic_permissions(mode: u32) -> String {
     chars.iter().collect()
 }
 
-fn format_modified_timestamp(epoch_seconds: i64) -> String {
+pub(crate) fn format_modified_timestamp(epoch_seconds: i64) -> String {
     let Ok(dt) = OffsetDateTime::from_unix_timestamp(epoch_seconds) else {
         return epoch_seconds.to_string();
     };
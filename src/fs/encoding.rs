@@ -0,0 +1,195 @@
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, GB18030, GBK, SHIFT_JIS, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
+use std::borrow::Cow;
+use std::fmt;
+
+/// Character encoding a preview was decoded with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Windows1252,
+    ShiftJis,
+    Gbk,
+    /// Another legacy encoding guessed from the content.
+    Other(&'static Encoding),
+}
+
+/// Encodings offered when forcing one, in cycling order.
+pub const SELECTABLE_ENCODINGS: [TextEncoding; 7] = [
+    TextEncoding::Utf8,
+    TextEncoding::Utf16Le,
+    TextEncoding::Utf16Be,
+    TextEncoding::Latin1,
+    TextEncoding::Windows1252,
+    TextEncoding::ShiftJis,
+    TextEncoding::Gbk,
+];
+
+impl TextEncoding {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf8Bom => "UTF-8 BOM",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+            Self::Latin1 => "Latin-1",
+            Self::Windows1252 => "Windows-1252",
+            Self::ShiftJis => "Shift_JIS",
+            Self::Gbk => "GBK",
+            Self::Other(encoding) => encoding.name(),
+        }
+    }
+
+    /// UTF-16 text is full of NUL bytes and cannot be split on `\n` bytes.
+    pub fn is_utf16(self) -> bool {
+        matches!(self, Self::Utf16Le | Self::Utf16Be)
+    }
+
+    /// Decodes `bytes`, dropping a leading byte order mark for the Unicode
+    /// encodings. Returns whether any malformed sequence was replaced.
+    pub fn decode(self, bytes: &[u8]) -> (Cow<'_, str>, bool) {
+        let encoding = match self {
+            Self::Latin1 => return (bytes.iter().map(|b| char::from(*b)).collect(), false),
+            Self::Utf8 | Self::Utf8Bom => UTF_8,
+            Self::Utf16Le => UTF_16LE,
+            Self::Utf16Be => UTF_16BE,
+            Self::Windows1252 => WINDOWS_1252,
+            Self::ShiftJis => SHIFT_JIS,
+            Self::Gbk => GBK,
+            Self::Other(encoding) => encoding,
+        };
+        match self {
+            Self::Utf8 | Self::Utf8Bom | Self::Utf16Le | Self::Utf16Be => {
+                encoding.decode_with_bom_removal(bytes)
+            }
+            _ => encoding.decode_without_bom_handling(bytes),
+        }
+    }
+}
+
+impl fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// Line terminators found in a preview.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Cr,
+    Mixed,
+}
+
+impl LineEnding {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Lf => "LF",
+            Self::Crlf => "CRLF",
+            Self::Cr => "CR",
+            Self::Mixed => "mixed",
+        }
+    }
+}
+
+/// Encoding and line endings shown in the preview metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextFormat {
+    pub encoding: TextEncoding,
+    pub line_ending: Option<LineEnding>,
+    /// Set when the user chose the encoding instead of it being detected.
+    pub forced: bool,
+}
+
+impl TextFormat {
+    pub fn label(&self) -> String {
+        let mut label = self.encoding.label().to_string();
+        if self.forced {
+            label.push_str(" (forced)");
+        }
+        if let Some(ending) = self.line_ending {
+            label.push(' ');
+            label.push_str(ending.label());
+        }
+        label
+    }
+}
+
+/// Encoding announced by a byte order mark at the start of `bytes`.
+pub fn encoding_from_bom(bytes: &[u8]) -> Option<TextEncoding> {
+    match Encoding::for_bom(bytes)?.0 {
+        encoding if encoding == UTF_8 => Some(TextEncoding::Utf8Bom),
+        encoding if encoding == UTF_16LE => Some(TextEncoding::Utf16Le),
+        encoding if encoding == UTF_16BE => Some(TextEncoding::Utf16Be),
+        _ => None,
+    }
+}
+
+/// Picks the encoding of `bytes`: a byte order mark wins, then UTF-8 if the
+/// bytes are valid (allowing a sequence cut off at the end when `truncated`),
+/// then a guess among legacy encodings.
+pub fn detect_encoding(bytes: &[u8], truncated: bool) -> TextEncoding {
+    if let Some(encoding) = encoding_from_bom(bytes) {
+        return encoding;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return TextEncoding::Utf8,
+        Err(err) if truncated && err.error_len().is_none() => return TextEncoding::Utf8,
+        Err(_) => {}
+    }
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, !truncated);
+    match detector.guess(None, false) {
+        // Windows-1252 only differs from Latin-1 in 0x80..=0x9F.
+        encoding if encoding == WINDOWS_1252 => {
+            if bytes.iter().any(|b| (0x80..=0x9f).contains(b)) {
+                TextEncoding::Windows1252
+            } else {
+                TextEncoding::Latin1
+            }
+        }
+        encoding if encoding == SHIFT_JIS => TextEncoding::ShiftJis,
+        encoding if encoding == GBK || encoding == GB18030 => TextEncoding::Gbk,
+        encoding if encoding == UTF_8 => TextEncoding::Utf8,
+        encoding => TextEncoding::Other(encoding),
+    }
+}
+
+/// Line terminator style of `text`, or `None` if it has no line breaks.
+pub fn detect_line_ending(text: &str) -> Option<LineEnding> {
+    let bytes = text.as_bytes();
+    let (mut lf, mut crlf, mut cr) = (false, false, false);
+    for (at, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'\n' if at > 0 && bytes[at - 1] == b'\r' => crlf = true,
+            b'\n' => lf = true,
+            b'\r' if bytes.get(at + 1) != Some(&b'\n') => cr = true,
+            _ => {}
+        }
+    }
+    match (lf, crlf, cr) {
+        (false, false, false) => None,
+        (true, false, false) => Some(LineEnding::Lf),
+        (false, true, false) => Some(LineEnding::Crlf),
+        (false, false, true) => Some(LineEnding::Cr),
+        _ => Some(LineEnding::Mixed),
+    }
+}
+
+/// `bytes` without a character cut off by truncating the read, so it is
+/// not reported as malformed.
+pub fn trim_partial_char(bytes: &[u8], encoding: TextEncoding) -> &[u8] {
+    match encoding {
+        TextEncoding::Utf8 | TextEncoding::Utf8Bom => match std::str::from_utf8(bytes) {
+            Err(err) if err.error_len().is_none() => &bytes[..err.valid_up_to()],
+            _ => bytes,
+        },
+        TextEncoding::Utf16Le | TextEncoding::Utf16Be => &bytes[..bytes.len() & !1],
+        _ => bytes,
+    }
+}
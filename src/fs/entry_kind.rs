@@ -0,0 +1,136 @@
+use crate::app::state::NodeType;
+use std::fs;
+use std::path::Path;
+
+/// File type and mode bits of an entry, as `ls` tells them apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Directory {
+        sticky: bool,
+        other_writable: bool,
+    },
+    Symlink {
+        orphan: bool,
+    },
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+    File {
+        setuid: bool,
+        setgid: bool,
+        executable: bool,
+        multi_link: bool,
+    },
+    Missing,
+}
+
+impl EntryKind {
+    /// Kind described by `meta`, which must not be a symlink's own metadata.
+    #[cfg(unix)]
+    pub fn from_metadata(meta: &fs::Metadata) -> Self {
+        use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+        let ft = meta.file_type();
+        let mode = meta.permissions().mode();
+        if ft.is_dir() {
+            Self::Directory {
+                sticky: mode & 0o1000 != 0,
+                other_writable: mode & 0o002 != 0,
+            }
+        } else if ft.is_fifo() {
+            Self::Fifo
+        } else if ft.is_socket() {
+            Self::Socket
+        } else if ft.is_block_device() {
+            Self::BlockDevice
+        } else if ft.is_char_device() {
+            Self::CharDevice
+        } else {
+            Self::File {
+                setuid: mode & 0o4000 != 0,
+                setgid: mode & 0o2000 != 0,
+                executable: mode & 0o111 != 0,
+                multi_link: meta.nlink() > 1,
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn from_metadata(meta: &fs::Metadata) -> Self {
+        if meta.is_dir() {
+            Self::Directory {
+                sticky: false,
+                other_writable: false,
+            }
+        } else {
+            Self::File {
+                setuid: false,
+                setgid: false,
+                executable: false,
+                multi_link: false,
+            }
+        }
+    }
+}
+
+/// An entry's kind and, for a symlink, the kind of its target. Read once
+/// when the entry's directory is listed so drawing the tree never touches
+/// the file system.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntryClass {
+    pub kind: EntryKind,
+    /// What a symlink points to; `Missing` when it dangles.
+    pub target: Option<EntryKind>,
+}
+
+impl EntryClass {
+    /// Classifies `path` from its `symlink_metadata`, following symlinks.
+    pub fn from_symlink_metadata(path: &Path, meta: Option<&fs::Metadata>) -> Self {
+        let Some(meta) = meta else {
+            return Self {
+                kind: EntryKind::Missing,
+                target: None,
+            };
+        };
+        if meta.file_type().is_symlink() {
+            let target = fs::metadata(path)
+                .map(|meta| EntryKind::from_metadata(&meta))
+                .unwrap_or(EntryKind::Missing);
+            return Self {
+                kind: EntryKind::Symlink {
+                    orphan: target == EntryKind::Missing,
+                },
+                target: Some(target),
+            };
+        }
+        Self {
+            kind: EntryKind::from_metadata(meta),
+            target: None,
+        }
+    }
+
+    /// Classifies the entry at `path`.
+    pub fn of(path: &Path) -> Self {
+        Self::from_symlink_metadata(path, fs::symlink_metadata(path).ok().as_ref())
+    }
+
+    /// Class of an entry known only by its node type, such as a file inside
+    /// an archive: no special mode bits.
+    pub fn for_node_type(node_type: &NodeType) -> Self {
+        let kind = match node_type {
+            NodeType::Directory => EntryKind::Directory {
+                sticky: false,
+                other_writable: false,
+            },
+            NodeType::File => EntryKind::File {
+                setuid: false,
+                setgid: false,
+                executable: false,
+                multi_link: false,
+            },
+            NodeType::Symlink => EntryKind::Symlink { orphan: false },
+            NodeType::Unknown => EntryKind::Missing,
+        };
+        Self { kind, target: None }
+    }
+}
@@ -0,0 +1,436 @@
+use crate::app::state::{ContentType, LoadState, PreviewDocument};
+use crate::highlight::ansi::parse_ansi_lines;
+use crate::highlight::detect::FileGlob;
+use indexmap::IndexMap;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Time a previewer command gets before it is killed, unless
+/// `previewer_timeout_ms` says otherwise.
+pub const DEFAULT_PREVIEWER_TIMEOUT: Duration = Duration::from_secs(5);
+/// Top-level MIME types; `[previewers]` keys starting with one of them are
+/// MIME types rather than globs.
+const MIME_TOP_LEVEL_TYPES: [&str; 9] = [
+    "application",
+    "audio",
+    "font",
+    "image",
+    "message",
+    "model",
+    "multipart",
+    "text",
+    "video",
+];
+/// How often a running previewer is checked on.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+/// Bytes of stderr kept to explain a failed command.
+const MAX_STDERR_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Clone)]
+enum PreviewerPattern {
+    Glob(FileGlob),
+    /// Lowercased `type/subtype`, or `type/*`.
+    Mime(String),
+}
+
+/// A `[previewers]` entry: a command whose output previews matching files.
+#[derive(Debug, Clone)]
+pub struct ExternalPreviewer {
+    pattern: PreviewerPattern,
+    pub command: String,
+    pub timeout: Duration,
+}
+
+fn is_mime_pattern(key: &str) -> bool {
+    key.split_once('/').is_some_and(|(top, subtype)| {
+        MIME_TOP_LEVEL_TYPES.contains(&top.to_ascii_lowercase().as_str())
+            && (subtype == "*"
+                || (!subtype.is_empty() && !subtype.contains(['/', '*', '?', '[', '{'])))
+    })
+}
+
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+impl ExternalPreviewer {
+    /// Whether the file at `path` is previewed by this command. MIME types
+    /// are guessed from the file extension.
+    pub fn matches(&self, path: &Path) -> bool {
+        match &self.pattern {
+            PreviewerPattern::Glob(glob) => glob.matches(path),
+            PreviewerPattern::Mime(pattern) => {
+                mime_guess::from_path(path)
+                    .iter()
+                    .any(|mime| match pattern.strip_suffix("/*") {
+                        Some(top) => mime.type_().as_str() == top,
+                        None => mime.essence_str() == pattern,
+                    })
+            }
+        }
+    }
+
+    /// The shell command run for `path`: each `{}` becomes the quoted path,
+    /// which is appended when there is no `{}`.
+    pub fn command_line(&self, path: &Path) -> String {
+        let quoted = shell_quote(&path.to_string_lossy());
+        if self.command.contains("{}") {
+            self.command.replace("{}", &quoted)
+        } else {
+            format!("{} {quoted}", self.command)
+        }
+    }
+}
+
+/// Compiles `[previewers]` in order, skipping invalid entries with a warning.
+pub fn compile_previewers(
+    previewers: &IndexMap<String, String>,
+    timeout: Duration,
+) -> (Vec<ExternalPreviewer>, Vec<String>) {
+    let mut compiled = Vec::with_capacity(previewers.len());
+    let mut warnings = Vec::new();
+    for (key, command) in previewers {
+        if command.trim().is_empty() {
+            warnings.push(format!("previewer '{key}' ignored: empty command"));
+            continue;
+        }
+        let pattern = if is_mime_pattern(key) {
+            PreviewerPattern::Mime(key.to_ascii_lowercase())
+        } else {
+            match FileGlob::new(key) {
+                Ok(glob) => PreviewerPattern::Glob(glob),
+                Err(err) => {
+                    warnings.push(format!("previewer '{key}' ignored: {err}"));
+                    continue;
+                }
+            }
+        };
+        compiled.push(ExternalPreviewer {
+            pattern,
+            command: command.clone(),
+            timeout,
+        });
+    }
+    (compiled, warnings)
+}
+
+/// First previewer for the file at `path`. Files inside archives are not
+/// on disk for a command to read.
+pub fn previewer_for<'a>(
+    previewers: &'a [ExternalPreviewer],
+    path: &Path,
+) -> Option<&'a ExternalPreviewer> {
+    if previewers.is_empty() || !path.is_file() {
+        return None;
+    }
+    previewers.iter().find(|previewer| previewer.matches(path))
+}
+
+#[derive(Debug)]
+struct RunOutput {
+    stdout: Vec<u8>,
+    truncated: bool,
+    /// Why the command did not succeed although it wrote output: its exit
+    /// status and first stderr line, or the timeout.
+    failure: Option<String>,
+}
+
+/// A previewer command running in a background thread. Dropping the last
+/// handle kills the command if it is still running.
+#[derive(Debug)]
+pub struct ExternalRun {
+    pub command: String,
+    result: Arc<Mutex<Option<Result<RunOutput, String>>>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl ExternalRun {
+    fn spawn(command: String, input: Option<String>, timeout: Duration, max_bytes: usize) -> Self {
+        let result = Arc::new(Mutex::new(None));
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (runner_result, runner_cancel) = (Arc::clone(&result), Arc::clone(&cancel));
+        let runner_command = command.clone();
+        thread::spawn(move || {
+            let output = run_command(&runner_command, input, timeout, max_bytes, &runner_cancel);
+            *runner_result
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(output);
+        });
+        Self {
+            command,
+            result,
+            cancel,
+        }
+    }
+
+    /// Runs `command` in the background with `input` on its stdin, keeping
+    /// at most `max_bytes` of its stdout.
+    pub fn with_input(command: String, input: String, timeout: Duration, max_bytes: usize) -> Self {
+        Self::spawn(command, Some(input), timeout, max_bytes)
+    }
+
+    fn take_result(&self) -> Option<Result<RunOutput, String>> {
+        self.result
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take()
+    }
+
+    /// Stdout of a command that exited successfully, or why it did not,
+    /// even if it wrote output; `None` while it runs and once the result was
+    /// taken.
+    pub fn take_output(&self) -> Option<Result<String, String>> {
+        self.take_result().map(|result| {
+            result.and_then(|output| match output.failure {
+                Some(failure) => Err(failure),
+                None => Ok(String::from_utf8_lossy(&output.stdout).into_owned()),
+            })
+        })
+    }
+}
+
+impl Drop for ExternalRun {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+fn first_line(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes)
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Reads `reader` into a shared buffer until it ends or `limit` bytes are
+/// kept, then drains the rest. The buffer is readable while the thread runs,
+/// since a command's children can hold the pipe open after it exits.
+fn spawn_reader(
+    mut reader: impl Read + Send + 'static,
+    limit: usize,
+) -> (Arc<Mutex<Vec<u8>>>, thread::JoinHandle<()>) {
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let shared = Arc::clone(&buffer);
+    let handle = thread::spawn(move || {
+        let mut chunk = [0_u8; 8192];
+        loop {
+            let read = match reader.read(&mut chunk) {
+                Ok(0) | Err(_) => return,
+                Ok(read) => read,
+            };
+            let mut data = shared
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let room = limit.saturating_sub(data.len());
+            data.extend_from_slice(&chunk[..read.min(room)]);
+            if data.len() >= limit {
+                break;
+            }
+        }
+        let _ = io::copy(&mut reader, &mut io::sink());
+    });
+    (buffer, handle)
+}
+
+fn take_buffer(buffer: &Mutex<Vec<u8>>) -> Vec<u8> {
+    std::mem::take(
+        &mut *buffer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+    )
+}
+
+/// Kills `child` and everything it started, which share its process group,
+/// then reaps it.
+fn kill_process_group(child: &mut Child) {
+    #[cfg(unix)]
+    if let Ok(pid) = libc::pid_t::try_from(child.id()) {
+        // SAFETY: `kill` takes no pointers; a negative pid names the group
+        // `child` leads, which outlives it until it is reaped below.
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Runs `command` through `sh -c`, keeping at most `max_bytes` of its
+/// stdout. Output written before a timeout is kept and marked truncated.
+/// `input` is written to its stdin, which is empty otherwise.
+fn run_command(
+    command: &str,
+    input: Option<String>,
+    timeout: Duration,
+    max_bytes: usize,
+    cancel: &AtomicBool,
+) -> Result<RunOutput, String> {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    // Its own process group, so a timeout also stops whatever it started.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut shell, 0);
+    let mut child = shell
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| format!("cannot run `{command}`: {err}"))?;
+    // Fed from its own thread so a command that writes before it has read
+    // everything cannot stall on a full stdout pipe.
+    if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+        thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+    let stdout = child
+        .stdout
+        .take()
+        .map(|stdout| spawn_reader(stdout, max_bytes + 1));
+    let stderr = child
+        .stderr
+        .take()
+        .map(|stderr| spawn_reader(stderr, MAX_STDERR_BYTES));
+
+    let started = Instant::now();
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) => {}
+            Err(err) => return Err(format!("`{command}` failed: {err}")),
+        }
+        if cancel.load(Ordering::Relaxed) {
+            kill_process_group(&mut child);
+            return Err(format!("`{command}` was cancelled"));
+        }
+        if started.elapsed() >= timeout {
+            kill_process_group(&mut child);
+            break None;
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+    // Let the readers catch up with the last output, within the deadline.
+    let readers = [&stdout, &stderr];
+    while readers.iter().any(|reader| {
+        reader
+            .as_ref()
+            .is_some_and(|(_, handle)| !handle.is_finished())
+    }) && started.elapsed() < timeout
+    {
+        thread::sleep(POLL_INTERVAL);
+    }
+    let mut stdout = stdout
+        .map(|(buffer, _)| take_buffer(&buffer))
+        .unwrap_or_default();
+    let stderr = stderr
+        .map(|(buffer, _)| take_buffer(&buffer))
+        .unwrap_or_default();
+
+    let Some(status) = status else {
+        let failure = format!("`{command}` timed out after {:.1}s", timeout.as_secs_f64());
+        if stdout.is_empty() {
+            return Err(failure);
+        }
+        stdout.truncate(max_bytes);
+        return Ok(RunOutput {
+            stdout,
+            truncated: true,
+            failure: Some(failure),
+        });
+    };
+    let failure = (!status.success()).then(|| {
+        let reason = first_line(&stderr);
+        if reason.is_empty() {
+            format!("`{command}` failed ({status})")
+        } else {
+            format!("`{command}` failed ({status}): {reason}")
+        }
+    });
+    if let Some(failure) = failure.as_ref().filter(|_| stdout.is_empty()) {
+        return Err(failure.clone());
+    }
+    let truncated = stdout.len() > max_bytes;
+    stdout.truncate(max_bytes);
+    Ok(RunOutput {
+        stdout,
+        truncated,
+        failure,
+    })
+}
+
+/// Preview of `path` by `previewer`, loading until the command finishes;
+/// [`poll_external_preview`] fills it in.
+pub fn start_external_preview(
+    path: &Path,
+    previewer: &ExternalPreviewer,
+    max_bytes: usize,
+) -> PreviewDocument {
+    let command = previewer.command_line(path);
+    PreviewDocument {
+        source_path: path.to_path_buf(),
+        load_state: LoadState::Loading,
+        content_excerpt: format!("Running `{command}`…"),
+        external: Some(Arc::new(ExternalRun::spawn(
+            command,
+            None,
+            previewer.timeout,
+            max_bytes,
+        ))),
+        ..PreviewDocument::default()
+    }
+}
+
+/// Shows the output of a finished previewer command, colored by its SGR
+/// sequences. Returns whether `doc` changed.
+pub fn poll_external_preview(doc: &mut PreviewDocument) -> bool {
+    if doc.load_state != LoadState::Loading {
+        return false;
+    }
+    let Some(result) = doc.external.as_ref().and_then(|run| run.take_result()) else {
+        return false;
+    };
+    match result {
+        Ok(output) => {
+            let lines = parse_ansi_lines(&String::from_utf8_lossy(&output.stdout));
+            let text = lines
+                .iter()
+                .map(|line| {
+                    line.iter()
+                        .map(|segment| segment.text.as_str())
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            doc.load_state = LoadState::Ready;
+            doc.content_type = if lines.is_empty() {
+                ContentType::PlainText
+            } else {
+                ContentType::Highlighted
+            };
+            doc.styled_lines = lines;
+            doc.truncated = output.truncated;
+            doc.content_excerpt = if output.truncated {
+                format!("{text}\n\n[truncated]")
+            } else {
+                text
+            };
+        }
+        Err(message) => {
+            doc.load_state = LoadState::Error;
+            doc.error_message = Some(message);
+        }
+    }
+    doc.wrapped_layout = None;
+    true
+}
@@ -103,13 +103,13 @@ fn parse_porcelain_status(text: &str, repo_root: PathBuf) -> Option<GitRepoStatu
             continue;
         }
 
-        if line.starts_with("?? ") {
-            let rel = PathBuf::from(line[3..].trim());
+        if let Some(rest) = line.strip_prefix("?? ") {
+            let rel = PathBuf::from(rest.trim());
             file_statuses.insert(rel, GitFileStatus::Untracked);
             continue;
         }
-        if line.starts_with("!! ") {
-            let rel = PathBuf::from(line[3..].trim());
+        if let Some(rest) = line.strip_prefix("!! ") {
+            let rel = PathBuf::from(rest.trim());
             file_statuses.insert(rel, GitFileStatus::Ignored);
             continue;
         }
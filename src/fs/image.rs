@@ -0,0 +1,112 @@
+use image::imageops::{self, FilterType};
+use image::io::{Limits, Reader};
+use image::{ImageFormat, RgbaImage};
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Largest image file read for a preview.
+pub const MAX_IMAGE_BYTES: usize = 64 * 1024 * 1024;
+/// Widest or tallest image decoded, in pixels.
+const MAX_IMAGE_SIDE: u32 = 16_384;
+/// Decoded images are shrunk to fit this many pixels a side: enough to fill a
+/// large terminal, small enough to rescale on every resize.
+const MAX_PREVIEW_SIDE: u32 = 2048;
+
+/// A decoded image, kept at up to [`MAX_PREVIEW_SIDE`] pixels a side.
+#[derive(Debug, Clone)]
+pub struct ImagePreview {
+    pub format: &'static str,
+    /// Size of the image file's picture, before any shrinking.
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Arc<RgbaImage>,
+    /// The last size `pixels` was scaled to, kept since every frame asks
+    /// for it again.
+    scaled: Arc<Mutex<Option<Arc<RgbaImage>>>>,
+}
+
+impl ImagePreview {
+    /// `pixels` resized to `width`×`height`.
+    pub fn scaled(&self, width: u32, height: u32) -> Arc<RgbaImage> {
+        let mut scaled = self
+            .scaled
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match scaled.as_ref() {
+            Some(image) if image.dimensions() == (width, height) => Arc::clone(image),
+            _ => {
+                let image = Arc::new(imageops::resize(
+                    self.pixels.as_ref(),
+                    width,
+                    height,
+                    FilterType::Triangle,
+                ));
+                *scaled = Some(Arc::clone(&image));
+                image
+            }
+        }
+    }
+
+    /// "640×480 px", for the preview metadata.
+    pub fn size_label(&self) -> String {
+        format!("{}×{} px", self.width, self.height)
+    }
+}
+
+fn format_for_extension(path: &Path) -> Option<ImageFormat> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => Some(ImageFormat::Png),
+        "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+        "gif" => Some(ImageFormat::Gif),
+        _ => None,
+    }
+}
+
+fn format_label(format: ImageFormat) -> &'static str {
+    match format {
+        ImageFormat::Png => "PNG",
+        ImageFormat::Jpeg => "JPEG",
+        ImageFormat::Gif => "GIF",
+        _ => "image",
+    }
+}
+
+/// Whether `path` names a PNG, JPEG or GIF file.
+pub fn is_image_path(path: &Path) -> bool {
+    format_for_extension(path).is_some()
+}
+
+/// Decodes `data` read from `path`; the file contents decide the format
+/// when they disagree with the extension. Animated GIFs show their first
+/// frame.
+pub fn decode_image(path: &Path, data: &[u8]) -> Result<ImagePreview, String> {
+    let mut reader = Reader::new(Cursor::new(data));
+    if let Some(format) = format_for_extension(path) {
+        reader.set_format(format);
+    }
+    let mut reader = reader
+        .with_guessed_format()
+        .map_err(|err| err.to_string())?;
+    let format = reader.format().ok_or("unknown image format")?;
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_IMAGE_SIDE);
+    limits.max_image_height = Some(MAX_IMAGE_SIDE);
+    reader.limits(limits);
+
+    let decoded = reader.decode().map_err(|err| err.to_string())?;
+    let (width, height) = (decoded.width(), decoded.height());
+    let decoded = if width.max(height) > MAX_PREVIEW_SIDE {
+        decoded.resize(MAX_PREVIEW_SIDE, MAX_PREVIEW_SIDE, FilterType::Triangle)
+    } else {
+        decoded
+    };
+    Ok(ImagePreview {
+        format: format_label(format),
+        width,
+        height,
+        pixels: Arc::new(decoded.into_rgba8()),
+        scaled: Arc::default(),
+    })
+}
@@ -1,4 +1,12 @@
+pub mod archive;
+pub mod background;
 pub mod current_dir;
+pub mod encoding;
+pub mod entry_kind;
+pub mod external;
 pub mod git;
+pub mod image;
+pub mod paged;
 pub mod preview;
+pub mod table;
 pub mod tree;
@@ -0,0 +1,266 @@
+use crate::fs::encoding::TextEncoding;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+/// Lines between two entries of the sparse line index.
+const LINES_PER_CHECKPOINT: usize = 1024;
+const INDEX_CHUNK_BYTES: usize = 1024 * 1024;
+/// Bytes of a line kept when it is read; the rest of a longer line is
+/// skipped and the line ends with `…`.
+pub const MAX_PAGED_LINE_BYTES: usize = 64 * 1024;
+
+/// How far the background line index of a [`PagedFile`] got.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexStatus {
+    Indexing,
+    /// Every line was counted.
+    Complete,
+    /// Reading failed or the file was dropped before the end; the lines
+    /// found so far are a lower bound.
+    Stopped(String),
+}
+
+#[derive(Debug)]
+struct LineIndex {
+    /// Byte offset of every `LINES_PER_CHECKPOINT`th line; entry 0 is line 0.
+    checkpoints: Vec<u64>,
+    newlines: usize,
+    status: IndexStatus,
+}
+
+#[derive(Debug)]
+struct SharedIndex {
+    index: Mutex<LineIndex>,
+    done: Condvar,
+}
+
+impl SharedIndex {
+    fn lock(&self) -> std::sync::MutexGuard<'_, LineIndex> {
+        self.index
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// A file too large to load at once. Lines are read a page at a time while
+/// a background thread indexes where they start; dropping the last handle
+/// stops the indexer.
+#[derive(Debug)]
+pub struct PagedFile {
+    path: PathBuf,
+    len: u64,
+    encoding: TextEncoding,
+    shared: Arc<SharedIndex>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl PagedFile {
+    /// Opens `path` for paging, decoding lines as `encoding`, which must
+    /// keep `\n` a single byte (anything but UTF-16).
+    pub fn open(path: &Path, encoding: TextEncoding) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let shared = Arc::new(SharedIndex {
+            index: Mutex::new(LineIndex {
+                checkpoints: vec![0],
+                newlines: 0,
+                status: IndexStatus::Indexing,
+            }),
+            done: Condvar::new(),
+        });
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (indexer_shared, indexer_cancel) = (Arc::clone(&shared), Arc::clone(&cancel));
+        thread::spawn(move || build_index(file, &indexer_shared, &indexer_cancel));
+        Ok(Self {
+            path: path.to_path_buf(),
+            len,
+            encoding,
+            shared,
+            cancel,
+        })
+    }
+
+    pub fn len_bytes(&self) -> u64 {
+        self.len
+    }
+
+    /// Lines found so far; the file's true line count once [`Self::is_indexed`].
+    /// Like `str::split('\n')`, a trailing newline ends with an empty line.
+    pub fn line_count(&self) -> usize {
+        self.shared.lock().newlines + 1
+    }
+
+    pub fn index_status(&self) -> IndexStatus {
+        self.shared.lock().status.clone()
+    }
+
+    /// Whether every line of the file was counted.
+    pub fn is_indexed(&self) -> bool {
+        self.shared.lock().status == IndexStatus::Complete
+    }
+
+    /// "120000 lines", or a lower bound while the index is built or after
+    /// it stopped early, for the preview metadata.
+    pub fn line_count_label(&self) -> String {
+        let index = self.shared.lock();
+        let lines = index.newlines + 1;
+        match &index.status {
+            IndexStatus::Complete if lines == 1 => "1 line".to_string(),
+            IndexStatus::Complete => format!("{lines} lines"),
+            IndexStatus::Indexing => format!("{lines}+ lines (indexing)"),
+            IndexStatus::Stopped(reason) => format!("{lines}+ lines (index stopped: {reason})"),
+        }
+    }
+
+    /// Blocks until the background indexer has reached the end of the file
+    /// or stopped early.
+    pub fn wait_until_indexed(&self) {
+        let mut index = self.shared.lock();
+        while index.status == IndexStatus::Indexing {
+            index = self
+                .shared
+                .done
+                .wait(index)
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+        }
+    }
+
+    /// Reads `lines` from disk, starting at the nearest indexed checkpoint.
+    /// Lines past the end of the file are left out; `\r\n` endings are
+    /// trimmed and malformed sequences are replaced.
+    pub fn read_lines(&self, lines: Range<usize>) -> io::Result<Vec<String>> {
+        let (mut line_no, offset) = {
+            let index = self.shared.lock();
+            let checkpoint = (lines.start / LINES_PER_CHECKPOINT).min(index.checkpoints.len() - 1);
+            (
+                checkpoint * LINES_PER_CHECKPOINT,
+                index.checkpoints[checkpoint],
+            )
+        };
+        let mut reader = BufReader::new(File::open(&self.path)?);
+        reader.seek(SeekFrom::Start(offset))?;
+
+        let mut buf = Vec::new();
+        // Whether the reader sits at the start of a line that exists even
+        // if no bytes follow (the empty line after a trailing newline).
+        let mut at_line_start = true;
+        while line_no < lines.start {
+            let Some(line) = read_capped_line(&mut reader, &mut buf, 0)? else {
+                return Ok(Vec::new());
+            };
+            at_line_start = line.ended;
+            line_no += 1;
+        }
+
+        let mut out = Vec::with_capacity(lines.len().min(LINES_PER_CHECKPOINT));
+        for _ in lines {
+            let Some(line) = read_capped_line(&mut reader, &mut buf, MAX_PAGED_LINE_BYTES)? else {
+                if at_line_start {
+                    out.push(String::new());
+                }
+                break;
+            };
+            at_line_start = line.ended;
+            let text = if line.truncated {
+                &buf[..]
+            } else {
+                buf.strip_suffix(b"\r").unwrap_or(&buf)
+            };
+            let mut text = self.encoding.decode(text).0.into_owned();
+            if line.truncated {
+                text.push('…');
+            }
+            out.push(text);
+        }
+        Ok(out)
+    }
+}
+
+/// How [`read_capped_line`] left a line.
+struct CappedLine {
+    /// The line ended with `\n` rather than at the end of the file.
+    ended: bool,
+    /// Bytes past the cap were skipped.
+    truncated: bool,
+}
+
+/// Reads the next line into `buf` without its `\n`, keeping at most
+/// `max_bytes` of it and skipping the rest, so a file without newlines is
+/// never held in memory. Returns `None` at the end of the file.
+fn read_capped_line(
+    reader: &mut impl BufRead,
+    buf: &mut Vec<u8>,
+    max_bytes: usize,
+) -> io::Result<Option<CappedLine>> {
+    buf.clear();
+    let mut line = CappedLine {
+        ended: false,
+        truncated: false,
+    };
+    let mut read_any = false;
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        if available.is_empty() {
+            return Ok(read_any.then_some(line));
+        }
+        read_any = true;
+        let (chunk, ended) = match available.iter().position(|byte| *byte == b'\n') {
+            Some(at) => (&available[..at], true),
+            None => (available, false),
+        };
+        let room = max_bytes.saturating_sub(buf.len());
+        buf.extend_from_slice(&chunk[..chunk.len().min(room)]);
+        line.truncated |= chunk.len() > room;
+        let used = chunk.len() + usize::from(ended);
+        reader.consume(used);
+        if ended {
+            line.ended = true;
+            return Ok(Some(line));
+        }
+    }
+}
+
+impl Drop for PagedFile {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+fn build_index(mut file: File, shared: &SharedIndex, cancel: &AtomicBool) {
+    let mut buf = vec![0; INDEX_CHUNK_BYTES];
+    let mut offset = 0u64;
+    let mut newlines = 0usize;
+    let status = loop {
+        if cancel.load(Ordering::Relaxed) {
+            break IndexStatus::Stopped("cancelled".to_string());
+        }
+        let read = match file.read(&mut buf) {
+            Ok(0) => break IndexStatus::Complete,
+            Ok(read) => read,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => break IndexStatus::Stopped(err.to_string()),
+        };
+        let mut checkpoints = Vec::new();
+        for (at, _) in buf[..read].iter().enumerate().filter(|(_, b)| **b == b'\n') {
+            newlines += 1;
+            if newlines % LINES_PER_CHECKPOINT == 0 {
+                checkpoints.push(offset + at as u64 + 1);
+            }
+        }
+        offset += read as u64;
+        let mut index = shared.lock();
+        index.checkpoints.extend(checkpoints);
+        index.newlines = newlines;
+    };
+    shared.lock().status = status;
+    shared.done.notify_all();
+}
@@ -1,13 +1,41 @@
-use crate::app::state::{ContentType, LoadState, PreviewDocument, PreviewFallbackReason};
+use crate::app::state::{
+    ContentType, LoadState, PagedSource, PreviewDocument, PreviewFallbackReason,
+};
+use crate::fs::archive::{read_archive_member_with_cancel, split_archive_path};
+use crate::fs::background::BackgroundLoad;
+use crate::fs::encoding::{
+    detect_encoding, detect_line_ending, encoding_from_bom, trim_partial_char, TextEncoding,
+    TextFormat,
+};
+use crate::fs::image::{decode_image, is_image_path, MAX_IMAGE_BYTES};
+use crate::fs::paged::PagedFile;
+use crate::fs::table::parse_table;
 use crate::highlight::render::{render_with_highlight, HighlightRenderResult};
 use crate::highlight::syntax::HighlightContext;
-use std::fs;
+use crate::highlight::viewport::{highlight_viewport, render_for_viewport, unstyled_line};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 
 const BINARY_SAMPLE: usize = 1024;
+/// Bytes read from the start of a paged file to detect its encoding.
+const ENCODING_SAMPLE: usize = 64 * 1024;
+/// Lines held on each side of the viewport in a paged preview.
+const PAGE_MARGIN_LINES: usize = 500;
+/// Larger files are highlighted one viewport at a time instead of up front.
 const HIGHLIGHT_MAX_BYTES: usize = 256 * 1024;
 
-fn is_probably_text(bytes: &[u8]) -> bool {
+fn is_probably_text(bytes: &[u8], forced: Option<TextEncoding>) -> bool {
+    // UTF-16 text is full of NUL bytes.
+    if forced
+        .or_else(|| encoding_from_bom(bytes))
+        .is_some_and(TextEncoding::is_utf16)
+    {
+        return true;
+    }
     !bytes.iter().take(BINARY_SAMPLE).any(|b| *b == 0)
 }
 
@@ -18,7 +46,7 @@ fn normalize_line_endings(text: &str) -> String {
     text.replace("\r\n", "\n").replace('\r', "\n")
 }
 
-fn sanitize_terminal_control_chars(text: &str) -> String {
+pub(crate) fn sanitize_terminal_control_chars(text: &str) -> String {
     let mut out = String::with_capacity(text.len());
     for ch in text.chars() {
         match ch {
@@ -31,56 +59,224 @@ fn sanitize_terminal_control_chars(text: &str) -> String {
     out
 }
 
-pub fn load_preview(path: &Path, max_bytes: usize, ctx: &HighlightContext) -> PreviewDocument {
-    let mut doc = PreviewDocument {
+fn empty_document(path: &Path) -> PreviewDocument {
+    PreviewDocument {
         source_path: PathBuf::from(path),
         load_state: LoadState::Loading,
         content_type: ContentType::PlainText,
         language_id: None,
+        language_reason: None,
         content_excerpt: String::new(),
         styled_lines: Vec::new(),
         fallback_reason: None,
         truncated: false,
         error_message: None,
+        viewport_highlight: None,
+        viewport_tree: None,
+        paged: None,
+        text_format: None,
+        rendered_markdown: None,
+        structured: None,
+        table: None,
+        image: None,
+        external: None,
+        wrapped_rows: None,
+        wrapped_layout: None,
+        pending: None,
+    }
+}
+
+/// Preview work left to a background thread, which
+/// [`poll_pending_preview`] turns into the document once it finishes.
+#[derive(Debug)]
+pub enum PendingPreview {
+    /// A document built whole in the background, such as an archive listing.
+    Document(BackgroundLoad<PreviewDocument>),
+    /// The head of a file inside an archive, highlighted once read.
+    ArchiveMember {
+        head: BackgroundLoad<io::Result<Vec<u8>>>,
+        max_bytes: usize,
+        forced: Option<TextEncoding>,
+    },
+}
+
+fn mark_unreadable(doc: &mut PreviewDocument) {
+    doc.load_state = LoadState::Error;
+    doc.error_message = Some("Cannot read file (permission denied or missing).".to_string());
+}
+
+fn mark_binary(doc: &mut PreviewDocument) {
+    doc.load_state = LoadState::Binary;
+    doc.content_type = ContentType::Unsupported;
+    doc.error_message = Some("Binary file preview is not supported.".to_string());
+}
+
+/// Reads at most `max_bytes` of `path`, which may be a file inside an
+/// archive, giving up once `cancel` is set.
+fn read_head(path: &Path, max_bytes: usize, cancel: &AtomicBool) -> io::Result<Vec<u8>> {
+    if let Some((archive, member)) = split_archive_path(path).filter(|(_, m)| !m.is_empty()) {
+        return read_archive_member_with_cancel(&archive, &member, max_bytes, cancel);
+    }
+    let mut data = Vec::new();
+    File::open(path)?
+        .take(max_bytes as u64)
+        .read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// Preview for the TUI: files over `max_bytes` are paged from disk instead
+/// of being loaded, so every line stays reachable.
+pub fn load_preview(path: &Path, max_bytes: usize, ctx: &HighlightContext) -> PreviewDocument {
+    load_preview_with_encoding(path, max_bytes, ctx, None)
+}
+
+/// [`load_preview`], decoding the file as `forced` instead of detecting its
+/// encoding when one is given.
+pub fn load_preview_with_encoding(
+    path: &Path,
+    max_bytes: usize,
+    ctx: &HighlightContext,
+    forced: Option<TextEncoding>,
+) -> PreviewDocument {
+    if is_image_path(path) {
+        return load_image_preview(path, &AtomicBool::new(false));
+    }
+    match fs::metadata(path) {
+        Ok(meta) if meta.is_file() && meta.len() > max_bytes as u64 => {
+            load_paged_preview(path, max_bytes, ctx, forced)
+        }
+        _ => load_preview_head(path, max_bytes, ctx, forced),
+    }
+}
+
+/// [`load_preview_with_encoding`] for the TUI, which decodes images and
+/// reads files inside archives in the background: the document is loading
+/// until [`poll_pending_preview`] fills it in.
+pub fn start_preview(
+    path: &Path,
+    max_bytes: usize,
+    ctx: &HighlightContext,
+    forced: Option<TextEncoding>,
+) -> PreviewDocument {
+    let name = path
+        .file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy();
+    let source = path.to_path_buf();
+    if is_image_path(path) {
+        return PreviewDocument {
+            content_excerpt: format!("Decoding {name}…"),
+            pending: Some(Arc::new(PendingPreview::Document(BackgroundLoad::spawn(
+                move |cancel| load_image_preview(&source, cancel),
+            )))),
+            ..empty_document(path)
+        };
+    }
+    if split_archive_path(path).is_none_or(|(_, member)| member.is_empty()) {
+        return load_preview_with_encoding(path, max_bytes, ctx, forced);
+    }
+    let head = BackgroundLoad::spawn(move |cancel| {
+        read_head(&source, max_bytes.saturating_add(1), cancel)
+    });
+    PreviewDocument {
+        content_excerpt: format!("Reading {name} from the archive…"),
+        pending: Some(Arc::new(PendingPreview::ArchiveMember {
+            head,
+            max_bytes,
+            forced,
+        })),
+        ..empty_document(path)
+    }
+}
+
+/// Shows a preview finished in the background. Returns whether `doc`
+/// changed.
+pub fn poll_pending_preview(doc: &mut PreviewDocument, ctx: &HighlightContext) -> bool {
+    if doc.load_state != LoadState::Loading {
+        return false;
+    }
+    let Some(pending) = doc.pending.clone() else {
+        return false;
+    };
+    let loaded = match &*pending {
+        PendingPreview::Document(load) => load.take(),
+        PendingPreview::ArchiveMember {
+            head,
+            max_bytes,
+            forced,
+        } => head
+            .take()
+            .map(|data| preview_from_head(&doc.source_path, data, *max_bytes, ctx, *forced)),
     };
+    let Some(loaded) = loaded else {
+        return false;
+    };
+    *doc = loaded;
+    true
+}
 
-    let Ok(data) = fs::read(path) else {
-        doc.load_state = LoadState::Error;
-        doc.error_message = Some("Cannot read file (permission denied or missing).".to_string());
+/// Preview of the first `max_bytes` of `path`, marked truncated when the
+/// file is longer. Used where the whole document must be in memory, such as
+/// `--print` and `--export-html`.
+pub fn load_preview_head(
+    path: &Path,
+    max_bytes: usize,
+    ctx: &HighlightContext,
+    forced: Option<TextEncoding>,
+) -> PreviewDocument {
+    // One byte past the limit tells whether the file was cut short.
+    let data = read_head(path, max_bytes.saturating_add(1), &AtomicBool::new(false));
+    preview_from_head(path, data, max_bytes, ctx, forced)
+}
+
+/// [`load_preview_head`] once `data`, read up to one byte past `max_bytes`,
+/// is in memory.
+fn preview_from_head(
+    path: &Path,
+    data: io::Result<Vec<u8>>,
+    max_bytes: usize,
+    ctx: &HighlightContext,
+    forced: Option<TextEncoding>,
+) -> PreviewDocument {
+    let mut doc = empty_document(path);
+    let Ok(mut data) = data else {
+        mark_unreadable(&mut doc);
         return doc;
     };
 
-    if !is_probably_text(&data) {
-        doc.load_state = LoadState::Binary;
-        doc.content_type = ContentType::Unsupported;
-        doc.error_message = Some("Binary file preview is not supported.".to_string());
+    if !is_probably_text(&data, forced) {
+        mark_binary(&mut doc);
         return doc;
     }
 
     let truncated = data.len() > max_bytes;
-    let clip = if truncated { &data[..max_bytes] } else { &data };
-    let (content, decode_uncertain) = match std::str::from_utf8(clip) {
-        Ok(s) => (s.to_string(), false),
-        Err(_) => (String::from_utf8_lossy(clip).into_owned(), true),
+    data.truncate(max_bytes);
+    let encoding = forced.unwrap_or_else(|| detect_encoding(&data, truncated));
+    let clip = if truncated {
+        trim_partial_char(&data, encoding)
+    } else {
+        &data
     };
+    let (content, decode_uncertain) = encoding.decode(clip);
+    doc.text_format = Some(TextFormat {
+        encoding,
+        line_ending: detect_line_ending(&content),
+        forced: forced.is_some(),
+    });
     let normalized_content = normalize_line_endings(&content);
     let safe_content = sanitize_terminal_control_chars(&normalized_content);
+    let per_viewport = !decode_uncertain && clip.len() > HIGHLIGHT_MAX_BYTES;
     let rendered = if decode_uncertain {
         HighlightRenderResult {
             rendered_text: safe_content.clone(),
             content_type: ContentType::PlainText,
             language_id: None,
+            language_reason: None,
             styled_lines: Vec::new(),
             fallback_reason: Some(PreviewFallbackReason::DecodeUncertain),
         }
-    } else if clip.len() > HIGHLIGHT_MAX_BYTES {
-        HighlightRenderResult {
-            rendered_text: safe_content.clone(),
-            content_type: ContentType::PlainText,
-            language_id: None,
-            styled_lines: Vec::new(),
-            fallback_reason: Some(PreviewFallbackReason::TooLarge),
-        }
+    } else if per_viewport {
+        render_for_viewport(ctx, path, &safe_content)
     } else {
         render_with_highlight(ctx, path, &safe_content)
     };
@@ -88,6 +284,7 @@ pub fn load_preview(path: &Path, max_bytes: usize, ctx: &HighlightContext) -> Pr
     doc.load_state = LoadState::Ready;
     doc.content_type = rendered.content_type;
     doc.language_id = rendered.language_id;
+    doc.language_reason = rendered.language_reason;
     doc.styled_lines = rendered.styled_lines;
     doc.fallback_reason = rendered.fallback_reason;
     doc.content_excerpt = if truncated {
@@ -96,9 +293,125 @@ pub fn load_preview(path: &Path, max_bytes: usize, ctx: &HighlightContext) -> Pr
         rendered.rendered_text
     };
     doc.truncated = truncated;
+    doc.table = parse_table(
+        path,
+        doc.language_id.as_deref(),
+        &normalized_content,
+        truncated,
+    );
+    if per_viewport && doc.content_type == ContentType::Highlighted {
+        doc.viewport_highlight = Some(0..0);
+        highlight_viewport(&mut doc, ctx, 0..0);
+    }
+    doc
+}
+
+/// Preview of a PNG, JPEG or GIF file, decoded whole whatever the preview
+/// size limit. `cancel` stops the read, not the decoding.
+fn load_image_preview(path: &Path, cancel: &AtomicBool) -> PreviewDocument {
+    let mut doc = empty_document(path);
+    let Ok(data) = read_head(path, MAX_IMAGE_BYTES, cancel) else {
+        mark_unreadable(&mut doc);
+        return doc;
+    };
+    match decode_image(path, &data) {
+        Ok(image) => {
+            doc.load_state = LoadState::Ready;
+            doc.content_type = ContentType::Unsupported;
+            doc.image = Some(image);
+        }
+        Err(err) => {
+            doc.load_state = LoadState::Error;
+            doc.content_type = ContentType::Unsupported;
+            doc.error_message = Some(format!("Cannot decode image: {err}"));
+        }
+    }
+    doc
+}
+
+fn load_paged_preview(
+    path: &Path,
+    max_bytes: usize,
+    ctx: &HighlightContext,
+    forced: Option<TextEncoding>,
+) -> PreviewDocument {
+    let mut doc = empty_document(path);
+    let Ok(head) = read_head(path, ENCODING_SAMPLE, &AtomicBool::new(false)) else {
+        mark_unreadable(&mut doc);
+        return doc;
+    };
+    let encoding = forced.unwrap_or_else(|| detect_encoding(&head, true));
+    // Paging splits lines on `\n` bytes, which UTF-16 does not keep whole.
+    if encoding.is_utf16() {
+        return load_preview_head(path, max_bytes, ctx, forced);
+    }
+    if !is_probably_text(&head, forced) {
+        mark_binary(&mut doc);
+        return doc;
+    }
+    let Ok(file) = PagedFile::open(path, encoding) else {
+        mark_unreadable(&mut doc);
+        return doc;
+    };
+    let (sample, _) = encoding.decode(trim_partial_char(&head, encoding));
+    doc.text_format = Some(TextFormat {
+        encoding,
+        // The sample may end between the `\r` and `\n` of a line break.
+        line_ending: detect_line_ending(sample.strip_suffix('\r').unwrap_or(&sample)),
+        forced: forced.is_some(),
+    });
+
+    doc.load_state = LoadState::Ready;
+    doc.truncated = true;
+    doc.paged = Some(PagedSource {
+        file: Arc::new(file),
+        first_line: 0,
+    });
+    page_viewport(&mut doc, 0..0);
+    if let Some(target) = ctx.target_for_file(path, &doc.content_excerpt) {
+        doc.content_type = ContentType::Highlighted;
+        doc.language_id = Some(target.language_id.to_string());
+        doc.language_reason = Some(target.reason);
+        doc.viewport_highlight = Some(0..0);
+        highlight_viewport(&mut doc, ctx, 0..0);
+    }
     doc
 }
 
+/// Loads the lines around `rows` into a paged preview unless they are
+/// already held. Does nothing for documents loaded whole.
+pub fn page_viewport(doc: &mut PreviewDocument, rows: Range<usize>) {
+    let Some(paged) = &doc.paged else {
+        return;
+    };
+    let held = paged.first_line..paged.first_line + doc.styled_lines.len();
+    let total = paged.file.line_count();
+    let wanted = rows.start.min(total)..rows.end.min(total);
+    if !held.is_empty() && held.start <= wanted.start && wanted.end <= held.end {
+        return;
+    }
+    let first_line = wanted.start.saturating_sub(PAGE_MARGIN_LINES);
+    let Ok(lines) = paged
+        .file
+        .read_lines(first_line..wanted.end + PAGE_MARGIN_LINES)
+    else {
+        return;
+    };
+    let lines = lines
+        .iter()
+        .map(|line| sanitize_terminal_control_chars(line))
+        .collect::<Vec<_>>();
+    doc.styled_lines = lines.iter().map(|line| unstyled_line(line)).collect();
+    doc.content_excerpt = lines.join("\n");
+    doc.viewport_tree = None;
+    if doc.viewport_highlight.is_some() {
+        doc.viewport_highlight = Some(0..0);
+    }
+    if let Some(paged) = &mut doc.paged {
+        paged.first_line = first_line;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::{normalize_line_endings, sanitize_terminal_control_chars};
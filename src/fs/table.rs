@@ -0,0 +1,193 @@
+use csv::ReaderBuilder;
+use std::path::Path;
+use unicode_width::UnicodeWidthStr;
+
+/// Delimiters tried when sniffing, in order of preference on a tie.
+const SNIFF_DELIMITERS: [u8; 4] = [b',', b'\t', b';', b'|'];
+/// Lines looked at to sniff a delimiter.
+const SNIFF_LINES: usize = 20;
+/// Lines a file without a `.csv`/`.tsv` extension needs to be sniffed as a table.
+const MIN_SNIFFED_LINES: usize = 3;
+/// Widest a column is shown; longer cells are cut with `…`.
+pub const MAX_COLUMN_WIDTH: usize = 40;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnAlignment {
+    Left,
+    Right,
+}
+
+/// A delimited file parsed into cells. The first record is the header.
+#[derive(Debug, Clone)]
+pub struct TablePreview {
+    pub delimiter: u8,
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// Display width of each column, capped at [`MAX_COLUMN_WIDTH`].
+    pub widths: Vec<usize>,
+    /// Numeric columns are right-aligned, everything else left-aligned.
+    pub alignments: Vec<ColumnAlignment>,
+}
+
+impl TablePreview {
+    pub fn column_count(&self) -> usize {
+        self.widths.len()
+    }
+
+    /// Body rows, not counting the header.
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// "120 rows × 5 columns", for the preview metadata.
+    pub fn size_label(&self) -> String {
+        let plural = |count: usize, noun: &str| {
+            if count == 1 {
+                format!("1 {noun}")
+            } else {
+                format!("{count} {noun}s")
+            }
+        };
+        format!(
+            "{} × {}",
+            plural(self.row_count(), "row"),
+            plural(self.column_count(), "column")
+        )
+    }
+}
+
+/// Counts `delimiter` in `line` outside double-quoted fields.
+fn count_delimiters(line: &str, delimiter: u8) -> usize {
+    let mut quoted = false;
+    line.bytes()
+        .filter(|byte| {
+            if *byte == b'"' {
+                quoted = !quoted;
+            }
+            !quoted && *byte == delimiter
+        })
+        .count()
+}
+
+/// Delimiter splitting every one of the first lines of `text` into the same
+/// number (more than one) of fields; the one giving most fields wins.
+pub fn sniff_delimiter(text: &str, min_lines: usize) -> Option<u8> {
+    let lines = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .take(SNIFF_LINES)
+        .collect::<Vec<_>>();
+    if lines.len() < min_lines.max(1) {
+        return None;
+    }
+    SNIFF_DELIMITERS
+        .iter()
+        .filter_map(|delimiter| {
+            let count = count_delimiters(lines[0], *delimiter);
+            let consistent = count > 0
+                && lines
+                    .iter()
+                    .all(|line| count_delimiters(line, *delimiter) == count);
+            consistent.then_some((*delimiter, count))
+        })
+        .fold(None, |best: Option<(u8, usize)>, candidate| match best {
+            Some(best) if best.1 >= candidate.1 => Some(best),
+            _ => Some(candidate),
+        })
+        .map(|(delimiter, _)| delimiter)
+}
+
+fn is_numeric(cell: &str) -> bool {
+    let cell = cell.trim();
+    let cell = cell.strip_suffix('%').unwrap_or(cell);
+    !cell.is_empty() && cell.parse::<f64>().is_ok()
+}
+
+/// Cell text made safe for the terminal: line breaks and other control
+/// characters inside quoted fields become spaces.
+fn clean_cell(cell: &str) -> String {
+    cell.chars()
+        .map(|ch| if ch.is_control() { ' ' } else { ch })
+        .collect()
+}
+
+/// Parses `text` as a table when `path` is a `.csv`/`.tsv` file, or when a
+/// file without a highlight language has consistently delimited lines. A
+/// `truncated` text drops its last record, which may be cut short.
+pub fn parse_table(
+    path: &Path,
+    language_id: Option<&str>,
+    text: &str,
+    truncated: bool,
+) -> Option<TablePreview> {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    let delimiter = match extension.as_deref() {
+        Some("csv") => sniff_delimiter(text, 1).unwrap_or(b','),
+        Some("tsv") => b'\t',
+        _ if language_id.is_none() => sniff_delimiter(text, MIN_SNIFFED_LINES)?,
+        _ => return None,
+    };
+
+    let mut records = ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes())
+        .into_records()
+        .map_while(Result::ok)
+        .map(|record| record.iter().map(clean_cell).collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+    if truncated {
+        records.pop();
+    }
+    if records.is_empty() {
+        return None;
+    }
+    let header = records.remove(0);
+    let columns = records
+        .iter()
+        .map(Vec::len)
+        .chain(std::iter::once(header.len()))
+        .max()
+        .unwrap_or(0);
+
+    let column = |index: usize| {
+        records
+            .iter()
+            .filter_map(move |row| row.get(index))
+            .map(String::as_str)
+    };
+    let widths = (0..columns)
+        .map(|index| {
+            column(index)
+                .chain(header.get(index).map(String::as_str))
+                .map(UnicodeWidthStr::width)
+                .max()
+                .unwrap_or(0)
+                .min(MAX_COLUMN_WIDTH)
+        })
+        .collect();
+    let alignments = (0..columns)
+        .map(|index| {
+            let mut cells = column(index)
+                .filter(|cell| !cell.trim().is_empty())
+                .peekable();
+            if cells.peek().is_some() && cells.all(is_numeric) {
+                ColumnAlignment::Right
+            } else {
+                ColumnAlignment::Left
+            }
+        })
+        .collect();
+
+    Some(TablePreview {
+        delimiter,
+        header,
+        rows: records,
+        widths,
+        alignments,
+    })
+}
@@ -1,4 +1,5 @@
 use crate::app::state::{NodeType, TreeNode};
+use crate::fs::entry_kind::EntryClass;
 use anyhow::Result;
 use ignore::WalkBuilder;
 use std::fs;
@@ -36,6 +37,7 @@ pub fn build_tree(root: &Path, max_entries: usize) -> Result<Vec<TreeNode>> {
         };
 
         let readable = fs::metadata(&path).is_ok();
+        let entry_class = EntryClass::from_symlink_metadata(&path, metadata.as_ref());
         nodes.push(TreeNode {
             path,
             name,
@@ -44,6 +46,7 @@ pub fn build_tree(root: &Path, max_entries: usize) -> Result<Vec<TreeNode>> {
             expanded: depth <= 1,
             readable,
             children_loaded: depth <= 1,
+            entry_class: Some(entry_class),
         });
     }
 
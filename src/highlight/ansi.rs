@@ -0,0 +1,233 @@
+use crate::app::state::{StyledPreviewLine, StyledPreviewSegment};
+use crate::fs::preview::sanitize_terminal_control_chars;
+use ratatui::style::{Color, Modifier, Style};
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Colors of SGR 30–37 and 90–97, in code order.
+const ANSI_COLORS: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// Color of an extended `38`/`48` SGR whose parameters follow `codes[0]`,
+/// with the number of parameters it used.
+fn extended_color(codes: &[u16]) -> (Option<Color>, usize) {
+    match codes {
+        [5, index, ..] => (u8::try_from(*index).ok().map(Color::Indexed), 2),
+        [2, r, g, b, ..] => {
+            let channel = |value: u16| u8::try_from(value).unwrap_or(u8::MAX);
+            (Some(Color::Rgb(channel(*r), channel(*g), channel(*b))), 4)
+        }
+        _ => (None, codes.len()),
+    }
+}
+
+/// Color of a colon-separated `38`/`48` SGR group after its code:
+/// `5:n`, `2:r:g:b`, or `2:id:r:g:b` with a (usually empty) color space id.
+fn colon_color(params: &[u16]) -> Option<Color> {
+    match params {
+        [2, _, r, g, b, ..] => extended_color(&[2, *r, *g, *b]).0,
+        _ => extended_color(params).0,
+    }
+}
+
+/// Applies the SGR parameters `params` (the text between `ESC [` and `m`)
+/// to `style`. Parameters are separated by `;`; a parameter may carry
+/// colon-separated sub-parameters (`4:3`, `38:2::r:g:b`), which belong to
+/// it alone.
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let groups = params
+        .split(';')
+        .map(|group| {
+            group
+                .split(':')
+                .map(|code| code.parse::<u16>().unwrap_or(0))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let mut index = 0;
+    while index < groups.len() {
+        let group = &groups[index];
+        let code = group[0];
+        index += 1;
+        match code {
+            0 => style = Style::default(),
+            1 => style = style.add_modifier(Modifier::BOLD),
+            2 => style = style.add_modifier(Modifier::DIM),
+            3 => style = style.add_modifier(Modifier::ITALIC),
+            // `4:0` turns underline off; `4:1`–`4:5` pick a style of it.
+            4 if group.get(1) == Some(&0) => style = style.remove_modifier(Modifier::UNDERLINED),
+            4 => style = style.add_modifier(Modifier::UNDERLINED),
+            5 => style = style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style = style.add_modifier(Modifier::REVERSED),
+            8 => style = style.add_modifier(Modifier::HIDDEN),
+            9 => style = style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style = style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style = style.remove_modifier(Modifier::ITALIC),
+            24 => style = style.remove_modifier(Modifier::UNDERLINED),
+            25 => style = style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style = style.remove_modifier(Modifier::REVERSED),
+            28 => style = style.remove_modifier(Modifier::HIDDEN),
+            29 => style = style.remove_modifier(Modifier::CROSSED_OUT),
+            30..=37 => style.fg = Some(ANSI_COLORS[usize::from(code - 30)]),
+            90..=97 => style.fg = Some(ANSI_COLORS[usize::from(code - 82)]),
+            40..=47 => style.bg = Some(ANSI_COLORS[usize::from(code - 40)]),
+            100..=107 => style.bg = Some(ANSI_COLORS[usize::from(code - 92)]),
+            // 58 is the underline color, which is parsed only to skip it.
+            38 | 48 | 58 => {
+                let color = if group.len() > 1 {
+                    colon_color(&group[1..])
+                } else {
+                    let following = groups[index..]
+                        .iter()
+                        .map(|group| group[0])
+                        .collect::<Vec<_>>();
+                    let (color, used) = extended_color(&following);
+                    index += used;
+                    color
+                };
+                match code {
+                    38 => style.fg = color.or(style.fg),
+                    48 => style.bg = color.or(style.bg),
+                    _ => {}
+                }
+            }
+            39 => style.fg = None,
+            49 => style.bg = None,
+            _ => {}
+        }
+    }
+    style
+}
+
+/// Skips an escape sequence after its `ESC`, returning the parameters of
+/// a CSI sequence ending in `m` (SGR); every other sequence is dropped.
+fn read_escape(chars: &mut Peekable<Chars<'_>>) -> Option<String> {
+    match chars.next()? {
+        '[' => {
+            let mut params = String::new();
+            for ch in chars.by_ref() {
+                if ('\u{40}'..='\u{7e}').contains(&ch) {
+                    return (ch == 'm').then_some(params);
+                }
+                params.push(ch);
+            }
+            None
+        }
+        // OSC strings run to BEL or ST (`ESC \`).
+        ']' | 'P' | '_' | '^' => {
+            while let Some(ch) = chars.next() {
+                if ch == '\u{7}' || (ch == '\u{1b}' && chars.next_if_eq(&'\\').is_some()) {
+                    break;
+                }
+            }
+            None
+        }
+        // Intermediate bytes, then one final byte.
+        ch if ('\u{20}'..='\u{2f}').contains(&ch) => {
+            while chars
+                .next_if(|ch| ('\u{20}'..='\u{2f}').contains(ch))
+                .is_some()
+            {}
+            chars.next();
+            None
+        }
+        _ => None,
+    }
+}
+
+fn push_segment(line: &mut StyledPreviewLine, text: &mut String, style: Style) {
+    if text.is_empty() {
+        return;
+    }
+    let text = sanitize_terminal_control_chars(&std::mem::take(text));
+    match line.last_mut() {
+        Some(last) if last.style == style => last.text.push_str(&text),
+        _ => line.push(StyledPreviewSegment { text, style }),
+    }
+}
+
+/// `over` written from the start of `under`, as a terminal does after a
+/// carriage return: the characters of `under` past the end of `over` stay.
+fn overlay(under: StyledPreviewLine, mut over: StyledPreviewLine) -> StyledPreviewLine {
+    let mut covered = over
+        .iter()
+        .map(|segment| segment.text.chars().count())
+        .sum::<usize>();
+    for segment in under {
+        let len = segment.text.chars().count();
+        if covered >= len {
+            covered -= len;
+            continue;
+        }
+        let text = segment.text.chars().skip(covered).collect::<String>();
+        covered = 0;
+        match over.last_mut() {
+            Some(last) if last.style == segment.style => last.text.push_str(&text),
+            _ => over.push(StyledPreviewSegment {
+                text,
+                style: segment.style,
+            }),
+        }
+    }
+    over
+}
+
+/// Splits command output into lines of styled segments. SGR sequences set
+/// colors and attributes, which carry over from line to line as in a
+/// terminal; a carriage return not ending a line goes back to its start, so
+/// progress output shows its last state. Every other escape sequence and
+/// control character is dropped or made safe to draw.
+pub fn parse_ansi_lines(text: &str) -> Vec<StyledPreviewLine> {
+    let mut lines = Vec::new();
+    // Text of the line written before its last carriage return.
+    let mut under = StyledPreviewLine::new();
+    let mut line = StyledPreviewLine::new();
+    let mut pending = String::new();
+    let mut style = Style::default();
+    let mut chars = text.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\u{1b}' => {
+                if let Some(params) = read_escape(&mut chars) {
+                    push_segment(&mut line, &mut pending, style);
+                    style = apply_sgr(style, &params);
+                }
+            }
+            '\n' => {
+                push_segment(&mut line, &mut pending, style);
+                lines.push(overlay(
+                    std::mem::take(&mut under),
+                    std::mem::take(&mut line),
+                ));
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\r' => {
+                push_segment(&mut line, &mut pending, style);
+                under = overlay(std::mem::take(&mut under), std::mem::take(&mut line));
+            }
+            _ => pending.push(ch),
+        }
+    }
+    push_segment(&mut line, &mut pending, style);
+    let line = overlay(under, line);
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
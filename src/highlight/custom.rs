@@ -0,0 +1,177 @@
+use crate::config::load::resolve_config_path;
+use crate::highlight::detect::FileGlob;
+use crate::highlight::syntax::{new_highlight_config, normalize_captures};
+use anyhow::{bail, Context, Result};
+use libloading::Library;
+use serde::Deserialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use tree_sitter::{Language, LANGUAGE_VERSION, MIN_COMPATIBLE_LANGUAGE_VERSION};
+use tree_sitter_highlight::HighlightConfiguration;
+
+/// First line marking a user query that is appended to the built-in one
+/// instead of replacing it, as in Neovim.
+const EXTENDS_MARKER: &str = "; extends";
+
+/// One `[[grammars]]` entry: a compiled tree-sitter grammar loaded at startup.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct UserGrammarConfig {
+    pub name: String,
+    pub library: String,
+    /// Exported constructor; defaults to `tree_sitter_<name>`.
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub files: Vec<String>,
+}
+
+/// `queries/` beside the config file, holding one directory per language.
+pub fn queries_dir(config_path: &Path) -> PathBuf {
+    config_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join("queries")
+}
+
+/// Query files read from one `queries/<lang>/` directory.
+#[derive(Debug, Default)]
+pub(crate) struct UserQueries {
+    pub(crate) highlights: Option<String>,
+    pub(crate) injections: Option<String>,
+    pub(crate) locals: Option<String>,
+}
+
+impl UserQueries {
+    pub(crate) fn read(dir: &Path) -> io::Result<Self> {
+        Ok(Self {
+            highlights: read_query(dir, "highlights.scm")?,
+            injections: read_query(dir, "injections.scm")?,
+            locals: read_query(dir, "locals.scm")?,
+        })
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.highlights.is_none() && self.injections.is_none() && self.locals.is_none()
+    }
+}
+
+fn read_query(dir: &Path, file: &str) -> io::Result<Option<String>> {
+    match fs::read_to_string(dir.join(file)) {
+        Ok(query) => Ok(Some(query)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// The query to compile for one kind: the built-in one, the user's
+/// replacement, or both when the user file starts with `; extends`.
+pub(crate) fn merge_query(builtin: &str, user: Option<&str>) -> String {
+    let Some(user) = user else {
+        return builtin.to_string();
+    };
+    let user = normalize_captures(user);
+    let extends = user
+        .lines()
+        .next()
+        .is_some_and(|line| line.trim() == EXTENDS_MARKER);
+    if extends {
+        format!("{builtin}\n{user}")
+    } else {
+        user
+    }
+}
+
+/// A grammar loaded from a shared library named in `[[grammars]]`.
+pub(crate) struct CustomGrammar {
+    pub(crate) name: String,
+    pub(crate) config: HighlightConfiguration,
+    files: Vec<FileGlob>,
+    // Declared last so it is dropped after `config`, whose grammar tables
+    // live inside the library.
+    _library: Library,
+}
+
+impl CustomGrammar {
+    pub(crate) fn matches(&self, path: &Path) -> bool {
+        self.files.iter().any(|glob| glob.matches(path))
+    }
+}
+
+fn load_language(library: &Library, symbol: &str) -> Result<Language> {
+    // SAFETY: tree-sitter grammars export `const TSLanguage *<symbol>(void)`,
+    // which `Language` wraps transparently.
+    let language = unsafe {
+        let constructor = library
+            .get::<unsafe extern "C" fn() -> Language>(symbol.as_bytes())
+            .with_context(|| format!("missing symbol {symbol}"))?;
+        constructor()
+    };
+    let version = language.version();
+    if !(MIN_COMPATIBLE_LANGUAGE_VERSION..=LANGUAGE_VERSION).contains(&version) {
+        bail!(
+            "ABI version {version} is not supported (expected \
+             {MIN_COMPATIBLE_LANGUAGE_VERSION} to {LANGUAGE_VERSION})"
+        );
+    }
+    Ok(language)
+}
+
+/// Loads one `[[grammars]]` entry with its queries from `queries/<name>/`.
+/// Problems that leave the grammar usable come back as warnings.
+pub(crate) fn load_custom_grammar(
+    user: &UserGrammarConfig,
+    config_path: &Path,
+) -> Result<(CustomGrammar, Vec<String>)> {
+    let mut warnings = Vec::new();
+    if user.name.trim().is_empty() {
+        bail!("missing name");
+    }
+    let path = resolve_config_path(&user.library, config_path);
+    // SAFETY: loading a library runs its initialisers; grammar libraries
+    // named in the user's own config are trusted like the config itself.
+    let library = unsafe { Library::new(&path) }
+        .with_context(|| format!("cannot load {}", path.display()))?;
+    let symbol = user
+        .symbol
+        .clone()
+        .unwrap_or_else(|| format!("tree_sitter_{}", user.name.replace('-', "_")));
+    let language = load_language(&library, &symbol)?;
+
+    let dir = queries_dir(config_path).join(&user.name);
+    let queries = UserQueries::read(&dir)
+        .with_context(|| format!("cannot read queries in {}", dir.display()))?;
+    if queries.highlights.is_none() {
+        warnings.push(format!(
+            "grammar '{}' has no {}",
+            user.name,
+            dir.join("highlights.scm").display()
+        ));
+    }
+    let config = new_highlight_config(
+        language,
+        &merge_query("", queries.highlights.as_deref()),
+        &merge_query("", queries.injections.as_deref()),
+        &merge_query("", queries.locals.as_deref()),
+    )
+    .with_context(|| format!("invalid queries in {}", dir.display()))?;
+
+    let mut files = Vec::with_capacity(user.files.len());
+    for pattern in &user.files {
+        match FileGlob::new(pattern) {
+            Ok(glob) => files.push(glob),
+            Err(err) => warnings.push(format!(
+                "grammar '{}' file pattern '{pattern}' ignored: {err}",
+                user.name
+            )),
+        }
+    }
+    Ok((
+        CustomGrammar {
+            name: user.name.clone(),
+            config,
+            files,
+            _library: library,
+        },
+        warnings,
+    ))
+}
@@ -0,0 +1,227 @@
+use crate::app::state::DetectionReason;
+use crate::highlight::syntax::HighlightLanguage;
+use globset::{GlobBuilder, GlobMatcher};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Lines at the start and end of a file searched for Vim modelines.
+const MODELINE_LINES: usize = 5;
+
+/// One `[[language_mappings]]` entry from the config.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct UserLanguageMapping {
+    pub glob: String,
+    pub language: String,
+}
+
+/// A config file pattern: globs containing `/` match the whole path,
+/// others only the file name.
+#[derive(Debug, Clone)]
+pub(crate) struct FileGlob {
+    glob: GlobMatcher,
+    matches_path: bool,
+}
+
+impl FileGlob {
+    pub(crate) fn new(pattern: &str) -> Result<Self, globset::Error> {
+        let glob = GlobBuilder::new(pattern).literal_separator(true).build()?;
+        Ok(Self {
+            glob: glob.compile_matcher(),
+            matches_path: pattern.contains('/'),
+        })
+    }
+
+    pub(crate) fn matches(&self, path: &Path) -> bool {
+        if self.matches_path {
+            self.glob.is_match(path)
+        } else {
+            path.file_name()
+                .is_some_and(|name| self.glob.is_match(Path::new(name)))
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LanguageMapping {
+    glob: FileGlob,
+    language: HighlightLanguage,
+}
+
+/// Compiles `[[language_mappings]]` in order, skipping invalid entries with a warning.
+pub fn compile_language_mappings(
+    mappings: &[UserLanguageMapping],
+) -> (Vec<LanguageMapping>, Vec<String>) {
+    let mut compiled = Vec::with_capacity(mappings.len());
+    let mut warnings = Vec::new();
+    for mapping in mappings {
+        let Some(language) = HighlightLanguage::from_name(&mapping.language) else {
+            warnings.push(format!(
+                "language mapping '{}' ignored: unknown language '{}'",
+                mapping.glob, mapping.language
+            ));
+            continue;
+        };
+        match FileGlob::new(&mapping.glob) {
+            Ok(glob) => compiled.push(LanguageMapping { glob, language }),
+            Err(err) => warnings.push(format!(
+                "language mapping '{}' ignored: {err}",
+                mapping.glob
+            )),
+        }
+    }
+    (compiled, warnings)
+}
+
+pub(crate) fn is_dockerfile_name(path: &Path) -> bool {
+    let Some(name) = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_ascii_lowercase())
+    else {
+        return false;
+    };
+    name == "dockerfile"
+        || name == "containerfile"
+        || name.starts_with("dockerfile.")
+        || name.ends_with(".dockerfile")
+}
+
+fn is_makefile_name(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| {
+            matches!(name, "Makefile" | "makefile" | "GNUmakefile") || name.ends_with(".mk")
+        })
+}
+
+fn language_for_file_name(path: &Path) -> Option<HighlightLanguage> {
+    if is_dockerfile_name(path) {
+        return HighlightLanguage::from_name("dockerfile");
+    }
+    // No Make grammar is bundled; recipes are shell, so that is the closest.
+    if is_makefile_name(path) {
+        return HighlightLanguage::from_name("bash");
+    }
+    let name = path.file_name()?.to_str()?;
+    let language = match name {
+        ".bashrc" | ".bash_profile" | ".bash_login" | ".bash_logout" | ".bash_aliases"
+        | ".zshrc" | ".zshenv" | ".zprofile" | ".zlogin" | ".profile" | ".kshrc" | "PKGBUILD"
+        | "APKBUILD" => "bash",
+        "Gemfile" | "Rakefile" | "Vagrantfile" | "Podfile" | "Brewfile" | "Guardfile"
+        | "Fastfile" | "Appfile" | "Berksfile" => "ruby",
+        "BUILD" | "BUILD.bazel" | "WORKSPACE" | "BUCK" | "Tiltfile" | "SConstruct"
+        | "SConscript" => "python",
+        "Cargo.lock" | "Pipfile" | "poetry.lock" | "uv.lock" => "toml",
+        ".babelrc" | ".eslintrc" | ".prettierrc" | ".jshintrc" | "flake.lock" | "composer.lock" => {
+            "json"
+        }
+        ".clang-format" | ".clang-tidy" | ".clangd" => "yaml",
+        // Groovy is not bundled; Java covers its strings, comments and calls.
+        "Jenkinsfile" => "java",
+        _ => return None,
+    };
+    HighlightLanguage::from_name(language)
+}
+
+/// Interpreter named by a `#!` line, e.g. `python` for `#!/usr/bin/env python3`.
+fn shebang_interpreter(first_line: &str) -> Option<String> {
+    let rest = first_line.strip_prefix("#!")?;
+    let mut words = rest.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+    let trimmed = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    Some(trimmed.to_ascii_lowercase())
+}
+
+fn language_for_shebang(content: &str) -> Option<HighlightLanguage> {
+    let interpreter = shebang_interpreter(content.lines().next()?)?;
+    let language = match interpreter.as_str() {
+        "sh" | "bash" | "zsh" | "ksh" | "dash" | "ash" | "mksh" => "bash",
+        "python" | "pypy" => "python",
+        "node" | "nodejs" | "deno" | "bun" => "javascript",
+        "ts-node" | "tsx" => "typescript",
+        "ruby" | "jruby" => "ruby",
+        "lua" | "luajit" => "lua",
+        "php" => "php",
+        "runhaskell" | "runghc" => "haskell",
+        "scala" => "scala",
+        "kotlin" | "kotlinc" => "kotlin",
+        "swift" => "swift",
+        "rust-script" => "rust",
+        _ => return None,
+    };
+    HighlightLanguage::from_name(language)
+}
+
+/// Mode from an Emacs `-*- mode: python -*-` or `-*- python -*-` line.
+fn emacs_mode(line: &str) -> Option<&str> {
+    let start = line.find("-*-")? + 3;
+    let end = start + line[start..].find("-*-")?;
+    let vars = line[start..end].trim();
+    if !vars.contains(':') {
+        return Some(vars);
+    }
+    vars.split(';').find_map(|var| {
+        let (key, value) = var.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case("mode")
+            .then(|| value.trim())
+    })
+}
+
+/// Filetype from a Vim modeline such as `vim: set ft=python:` or `vi: syntax=sh`.
+fn vim_filetype(line: &str) -> Option<&str> {
+    let start = ["vim:", "vi:", "ex:"]
+        .iter()
+        .filter_map(|marker| line.find(marker).map(|at| at + marker.len()))
+        .min()?;
+    line[start..]
+        .split(|c: char| c.is_whitespace() || c == ':')
+        .find_map(|option| {
+            let (key, value) = option.split_once('=')?;
+            matches!(key, "ft" | "filetype" | "syn" | "syntax").then_some(value)
+        })
+}
+
+fn language_for_modeline(content: &str) -> Option<HighlightLanguage> {
+    let lines: Vec<&str> = content.lines().collect();
+    // Emacs only reads the first line, or the second after a shebang.
+    let emacs = lines
+        .iter()
+        .take(2)
+        .find_map(|line| emacs_mode(line))
+        .and_then(HighlightLanguage::from_name);
+    if emacs.is_some() {
+        return emacs;
+    }
+    let head = lines.iter().take(MODELINE_LINES);
+    let tail = lines.iter().skip(MODELINE_LINES).rev().take(MODELINE_LINES);
+    head.chain(tail)
+        .filter_map(|line| vim_filetype(line))
+        .find_map(HighlightLanguage::from_name)
+}
+
+/// Picks the highlight language for `path`. Config mappings win, then a
+/// modeline, the file name, the extension, and finally the shebang line.
+pub(crate) fn detect_language(
+    path: &Path,
+    content: Option<&str>,
+    mappings: &[LanguageMapping],
+) -> Option<(HighlightLanguage, DetectionReason)> {
+    if let Some(mapping) = mappings.iter().find(|mapping| mapping.glob.matches(path)) {
+        return Some((mapping.language, DetectionReason::ConfigMapping));
+    }
+    if let Some(language) = content.and_then(language_for_modeline) {
+        return Some((language, DetectionReason::Modeline));
+    }
+    if let Some(language) = language_for_file_name(path) {
+        return Some((language, DetectionReason::FileName));
+    }
+    if let Some(language) = HighlightLanguage::for_extension(path) {
+        return Some((language, DetectionReason::Extension));
+    }
+    content
+        .and_then(language_for_shebang)
+        .map(|language| (language, DetectionReason::Shebang))
+}
@@ -0,0 +1,639 @@
+use crate::app::state::{
+    ContentType, PreviewDocument, RenderedMarkdown, StyledPreviewLine, StyledPreviewSegment,
+};
+use crate::highlight::render::highlight_lines;
+use crate::highlight::syntax::HighlightContext;
+use crate::highlight::theme::SyntaxTheme;
+use crate::highlight::viewport::unstyled_line;
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use ratatui::style::{Modifier, Style};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Narrowest width paragraphs are wrapped to, however small the pane.
+const MIN_WRAP_WIDTH: usize = 20;
+const CODE_INDENT: &str = "  ";
+
+enum Container {
+    Quote,
+    /// A list and the number of its next item, if it is ordered.
+    List(Option<u64>),
+    /// A list item; `marker` is shown on its first line only.
+    Item {
+        marker: Option<String>,
+        width: usize,
+    },
+}
+
+struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<Vec<StyledPreviewSegment>>>,
+    header_rows: usize,
+}
+
+struct CodeBlock {
+    language: Option<String>,
+    text: String,
+}
+
+struct MarkdownRenderer<'a> {
+    ctx: &'a HighlightContext,
+    width: usize,
+    lines: Vec<StyledPreviewLine>,
+    /// Inline text of the paragraph, heading or tight list item being read.
+    inline: Vec<StyledPreviewSegment>,
+    styles: Vec<Style>,
+    containers: Vec<Container>,
+    heading: Option<HeadingLevel>,
+    links: Vec<String>,
+    code: Option<CodeBlock>,
+    table: Option<Table>,
+    /// Whether a blank line is due before the next block.
+    gap: bool,
+}
+
+impl<'a> MarkdownRenderer<'a> {
+    fn new(ctx: &'a HighlightContext, width: usize) -> Self {
+        Self {
+            ctx,
+            width: width.max(MIN_WRAP_WIDTH),
+            lines: Vec::new(),
+            inline: Vec::new(),
+            styles: Vec::new(),
+            containers: Vec::new(),
+            heading: None,
+            links: Vec::new(),
+            code: None,
+            table: None,
+            gap: false,
+        }
+    }
+
+    fn theme(&self) -> &SyntaxTheme {
+        self.ctx.syntax_theme()
+    }
+
+    fn style(&self) -> Style {
+        self.styles.last().copied().unwrap_or_default()
+    }
+
+    fn push_style(&mut self, patch: Style) {
+        let style = self.style().patch(patch);
+        self.styles.push(style);
+    }
+
+    fn push_inline(&mut self, text: &str, style: Style) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(table) = &mut self.table {
+            if let Some(cell) = table.rows.last_mut().and_then(|row| row.last_mut()) {
+                cell.push(segment(text, style));
+            }
+            return;
+        }
+        self.inline.push(segment(text, style));
+    }
+
+    /// Quote bars and list indentation for the next line. The first line of
+    /// a list item takes its marker.
+    fn prefix(&mut self, take_marker: bool) -> Vec<StyledPreviewSegment> {
+        let quote_style = self.theme().style_for_capture("comment");
+        let mut prefix = Vec::new();
+        for container in &mut self.containers {
+            match container {
+                Container::Quote => prefix.push(segment("│ ", quote_style)),
+                Container::List(_) => {}
+                Container::Item { marker, width } => {
+                    let marker = if take_marker { marker.take() } else { None };
+                    match marker {
+                        Some(marker) => prefix.push(segment(&marker, Style::default())),
+                        None => prefix.push(segment(&" ".repeat(*width), Style::default())),
+                    }
+                }
+            }
+        }
+        prefix
+    }
+
+    fn prefix_width(&self) -> usize {
+        self.containers
+            .iter()
+            .map(|container| match container {
+                Container::Quote => 2,
+                Container::List(_) => 0,
+                Container::Item { width, .. } => *width,
+            })
+            .sum()
+    }
+
+    fn push_line(&mut self, content: StyledPreviewLine) {
+        let mut line = self.prefix(true);
+        line.extend(content);
+        self.lines.push(line);
+    }
+
+    fn start_block(&mut self) {
+        self.flush_inline();
+        if self.gap && !self.lines.is_empty() {
+            let mut blank = self.prefix(false);
+            while blank
+                .last()
+                .is_some_and(|segment| segment.text.trim().is_empty())
+            {
+                blank.pop();
+            }
+            for bar in &mut blank {
+                bar.text = bar.text.trim_end().to_string();
+            }
+            self.lines.push(blank);
+        }
+        self.gap = false;
+    }
+
+    fn end_block(&mut self) {
+        self.flush_inline();
+        self.gap = true;
+    }
+
+    /// Wraps the pending inline text to the width left by the prefix.
+    fn flush_inline(&mut self) {
+        if self.inline.is_empty() {
+            return;
+        }
+        let inline = std::mem::take(&mut self.inline);
+        let width = self.width.saturating_sub(self.prefix_width()).max(1);
+        let wrapped = wrap_words(&inline, width);
+        let underline = match self.heading {
+            Some(HeadingLevel::H1) => Some('═'),
+            Some(HeadingLevel::H2) => Some('─'),
+            _ => None,
+        };
+        let rule_width = wrapped.iter().map(|line| line_width(line)).max();
+        for line in wrapped {
+            self.push_line(line);
+        }
+        if let (Some(rule), Some(rule_width)) = (underline, rule_width) {
+            let style = self.theme().style_for_capture("text.title");
+            self.push_line(vec![segment(&rule.to_string().repeat(rule_width), style)]);
+        }
+    }
+
+    fn heading_style(&self, level: HeadingLevel) -> Style {
+        let title = self.theme().style_for_capture("text.title");
+        match level {
+            HeadingLevel::H1 | HeadingLevel::H2 | HeadingLevel::H3 => {
+                title.add_modifier(Modifier::BOLD)
+            }
+            HeadingLevel::H4 => title,
+            HeadingLevel::H5 | HeadingLevel::H6 => title.add_modifier(Modifier::ITALIC),
+        }
+    }
+
+    fn start(&mut self, tag: Tag<'_>) {
+        match tag {
+            Tag::Paragraph => self.start_block(),
+            Tag::Heading { level, .. } => {
+                self.start_block();
+                self.heading = Some(level);
+                let style = self.heading_style(level);
+                self.styles.push(style);
+                if level > HeadingLevel::H2 {
+                    let marker = "#".repeat(level as usize);
+                    self.push_inline(&format!("{marker} "), style);
+                }
+            }
+            Tag::BlockQuote(_) => {
+                self.start_block();
+                self.containers.push(Container::Quote);
+            }
+            Tag::CodeBlock(kind) => {
+                self.start_block();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split(|ch: char| ch.is_whitespace() || ch == ',' || ch == '{')
+                        .next()
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_string),
+                    CodeBlockKind::Indented => None,
+                };
+                self.code = Some(CodeBlock {
+                    language,
+                    text: String::new(),
+                });
+            }
+            Tag::HtmlBlock => self.start_block(),
+            Tag::List(first) => {
+                // Nested lists follow their item's text without a gap.
+                if self.containers.is_empty() {
+                    self.start_block();
+                } else {
+                    self.flush_inline();
+                }
+                self.containers.push(Container::List(first));
+            }
+            Tag::Item => {
+                self.flush_inline();
+                let marker = match self.containers.last_mut() {
+                    Some(Container::List(Some(number))) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "• ".to_string(),
+                };
+                let width = marker.width();
+                self.containers.push(Container::Item {
+                    marker: Some(marker),
+                    width,
+                });
+            }
+            Tag::Table(alignments) => {
+                self.start_block();
+                self.table = Some(Table {
+                    alignments,
+                    rows: Vec::new(),
+                    header_rows: 0,
+                });
+            }
+            Tag::TableHead | Tag::TableRow => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(Vec::new());
+                }
+            }
+            Tag::TableCell => {
+                if let Some(row) = self.table.as_mut().and_then(|table| table.rows.last_mut()) {
+                    row.push(Vec::new());
+                }
+            }
+            Tag::Emphasis => self.push_style(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.push_style(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => {
+                self.push_style(Style::default().add_modifier(Modifier::CROSSED_OUT))
+            }
+            Tag::Link { dest_url, .. } => {
+                let style = self.theme().style_for_capture("text.reference");
+                self.push_style(style);
+                self.links.push(dest_url.to_string());
+            }
+            Tag::Image { dest_url, .. } => {
+                let style = self.theme().style_for_capture("text.reference");
+                self.push_style(style);
+                self.push_inline("[image: ", style);
+                self.links.push(dest_url.to_string());
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::HtmlBlock => self.end_block(),
+            TagEnd::Heading(_) => {
+                self.end_block();
+                self.heading = None;
+                self.styles.pop();
+            }
+            TagEnd::BlockQuote(_) => {
+                self.end_block();
+                self.containers.pop();
+            }
+            TagEnd::CodeBlock => {
+                if let Some(code) = self.code.take() {
+                    self.push_code_block(code);
+                }
+                self.gap = true;
+            }
+            TagEnd::List(_) => {
+                self.flush_inline();
+                self.containers.pop();
+                self.gap = true;
+            }
+            TagEnd::Item => {
+                self.flush_inline();
+                // An item with no text still shows its marker.
+                if let Some(Container::Item {
+                    marker: Some(_), ..
+                }) = self.containers.last()
+                {
+                    self.push_line(Vec::new());
+                }
+                self.containers.pop();
+            }
+            TagEnd::Table => {
+                if let Some(table) = self.table.take() {
+                    self.push_table(table);
+                }
+                self.gap = true;
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.header_rows = table.rows.len();
+                }
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => {
+                self.styles.pop();
+            }
+            TagEnd::Link => {
+                self.styles.pop();
+                if let Some(url) = self.links.pop() {
+                    let shown = self.inline.last().map(|last| last.text.as_str());
+                    if !url.is_empty() && shown != Some(url.as_str()) {
+                        let style = self.theme().style_for_capture("text.uri");
+                        self.push_inline(&format!(" ({url})"), style);
+                    }
+                }
+            }
+            TagEnd::Image => {
+                let style = self.style();
+                self.styles.pop();
+                let url = self.links.pop().unwrap_or_default();
+                self.push_inline(&format!("]({url})"), style);
+            }
+            _ => {}
+        }
+    }
+
+    fn event(&mut self, event: Event<'_>) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => {
+                if let Some(code) = &mut self.code {
+                    code.text.push_str(&text);
+                } else {
+                    self.push_inline(&text, self.style());
+                }
+            }
+            Event::Code(text) => {
+                let style = self
+                    .style()
+                    .patch(self.theme().style_for_capture("text.literal"));
+                self.push_inline(&text, style);
+            }
+            Event::Html(html) => {
+                let style = self.theme().style_for_capture("comment");
+                for line in html.lines().filter(|line| !line.trim().is_empty()) {
+                    self.push_line(vec![segment(line, style)]);
+                }
+            }
+            Event::InlineHtml(html) => {
+                let style = self.theme().style_for_capture("comment");
+                self.push_inline(&html, style);
+            }
+            Event::FootnoteReference(name) => {
+                let style = self.theme().style_for_capture("text.reference");
+                self.push_inline(&format!("[^{name}]"), style);
+            }
+            Event::SoftBreak => self.push_inline(" ", self.style()),
+            Event::HardBreak => self.push_inline("\n", self.style()),
+            Event::Rule => {
+                self.start_block();
+                let style = self.theme().style_for_capture("comment");
+                let width = self.width.saturating_sub(self.prefix_width());
+                self.push_line(vec![segment(&"─".repeat(width), style)]);
+                self.gap = true;
+            }
+            Event::TaskListMarker(done) => {
+                self.push_inline(if done { "[x] " } else { "[ ] " }, self.style());
+            }
+            _ => {}
+        }
+    }
+
+    /// Code blocks keep their lines as written, highlighted when the fence
+    /// names a known language.
+    fn push_code_block(&mut self, code: CodeBlock) {
+        let text = code.text.strip_suffix('\n').unwrap_or(&code.text);
+        let highlighted = code
+            .language
+            .as_deref()
+            .and_then(|language| self.ctx.injection_config(language))
+            .and_then(|config| highlight_lines(self.ctx, config, text));
+        let literal = self.theme().style_for_capture("text.literal");
+        let lines = highlighted.unwrap_or_else(|| {
+            text.split('\n')
+                .map(|line| {
+                    let mut styled = unstyled_line(line);
+                    for segment in &mut styled {
+                        segment.style = literal;
+                    }
+                    styled
+                })
+                .collect()
+        });
+        for line in lines {
+            let mut content = vec![segment(CODE_INDENT, Style::default())];
+            content.extend(line);
+            self.push_line(content);
+        }
+    }
+
+    fn push_table(&mut self, table: Table) {
+        let columns = table.rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for row in &table.rows {
+            for (column, cell) in row.iter().enumerate() {
+                widths[column] = widths[column].max(line_width(cell));
+            }
+        }
+        let border = self.theme().style_for_capture("comment");
+        for (index, row) in table.rows.iter().enumerate() {
+            let mut line = Vec::new();
+            for (column, width) in widths.iter().enumerate() {
+                if column > 0 {
+                    line.push(segment(" │ ", border));
+                }
+                let cell = row.get(column).cloned().unwrap_or_default();
+                let padding = width - line_width(&cell);
+                let (before, after) = match table.alignments.get(column) {
+                    Some(Alignment::Right) => (padding, 0),
+                    Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+                    _ => (0, padding),
+                };
+                line.push(segment(&" ".repeat(before), Style::default()));
+                let bold = index < table.header_rows;
+                line.extend(cell.into_iter().map(|mut segment| {
+                    if bold {
+                        segment.style = segment.style.add_modifier(Modifier::BOLD);
+                    }
+                    segment
+                }));
+                line.push(segment(&" ".repeat(after), Style::default()));
+            }
+            while line
+                .last()
+                .is_some_and(|segment| segment.text.trim().is_empty())
+            {
+                line.pop();
+            }
+            line.retain(|segment| !segment.text.is_empty());
+            self.push_line(line);
+            if index + 1 == table.header_rows {
+                let rule = widths
+                    .iter()
+                    .map(|width| "─".repeat(*width))
+                    .collect::<Vec<_>>()
+                    .join("─┼─");
+                self.push_line(vec![segment(&rule, border)]);
+            }
+        }
+    }
+}
+
+fn segment(text: &str, style: Style) -> StyledPreviewSegment {
+    StyledPreviewSegment {
+        text: text.to_string(),
+        style,
+    }
+}
+
+fn line_width(line: &[StyledPreviewSegment]) -> usize {
+    line.iter().map(|segment| segment.text.width()).sum()
+}
+
+/// Appends `text` to `line`, merging it into the last segment when the
+/// styles match.
+fn append(line: &mut StyledPreviewLine, text: &str, style: Style) {
+    match line.last_mut() {
+        Some(last) if last.style == style => last.text.push_str(text),
+        _ => line.push(segment(text, style)),
+    }
+}
+
+/// Greedy word wrap of styled inline text into lines of `width` columns.
+struct WordWrap {
+    width: usize,
+    lines: Vec<StyledPreviewLine>,
+    line: StyledPreviewLine,
+    line_cols: usize,
+    /// The word being read, which may span several styles.
+    word: Vec<(String, Style)>,
+    word_cols: usize,
+    /// Style of the space seen before the pending word.
+    space: Option<Style>,
+}
+
+impl WordWrap {
+    fn break_line(&mut self) {
+        self.lines.push(std::mem::take(&mut self.line));
+        self.line_cols = 0;
+    }
+
+    fn push_word(&mut self) {
+        if self.word.is_empty() {
+            return;
+        }
+        let space = self.space.take().filter(|_| self.line_cols > 0);
+        let space_cols = usize::from(space.is_some());
+        if self.line_cols > 0 && self.line_cols + space_cols + self.word_cols > self.width {
+            self.break_line();
+        } else if let Some(style) = space {
+            append(&mut self.line, " ", style);
+            self.line_cols += 1;
+        }
+        // Words wider than the line are split wherever they overflow.
+        for (text, style) in std::mem::take(&mut self.word) {
+            for ch in text.chars() {
+                let cols = UnicodeWidthChar::width(ch).unwrap_or(0);
+                if self.line_cols > 0 && self.line_cols + cols > self.width {
+                    self.break_line();
+                }
+                append(&mut self.line, ch.encode_utf8(&mut [0; 4]), style);
+                self.line_cols += cols;
+            }
+        }
+        self.word_cols = 0;
+    }
+}
+
+/// Wraps styled inline text to `width` columns at spaces; `\n` forces a
+/// line break.
+fn wrap_words(inline: &[StyledPreviewSegment], width: usize) -> Vec<StyledPreviewLine> {
+    let mut wrap = WordWrap {
+        width,
+        lines: Vec::new(),
+        line: Vec::new(),
+        line_cols: 0,
+        word: Vec::new(),
+        word_cols: 0,
+        space: None,
+    };
+    for part in inline {
+        for (index, piece) in part.text.split('\n').enumerate() {
+            if index > 0 {
+                wrap.push_word();
+                wrap.break_line();
+                wrap.space = None;
+            }
+            for (index, word) in piece.split(' ').enumerate() {
+                if index > 0 {
+                    wrap.push_word();
+                    wrap.space = Some(part.style);
+                }
+                if !word.is_empty() {
+                    wrap.word.push((word.to_string(), part.style));
+                    wrap.word_cols += word.width();
+                }
+            }
+        }
+    }
+    wrap.push_word();
+    if !wrap.line.is_empty() || wrap.lines.is_empty() {
+        wrap.break_line();
+    }
+    wrap.lines
+}
+
+/// Renders Markdown `source` for reading: headings styled by level,
+/// paragraphs wrapped to `width`, lists, block quotes, tables laid out as
+/// columns, fenced code highlighted by its language and links followed by
+/// their URL.
+pub fn render_markdown(
+    ctx: &HighlightContext,
+    source: &str,
+    width: usize,
+) -> Vec<StyledPreviewLine> {
+    let options = Options::ENABLE_TABLES
+        | Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_FOOTNOTES;
+    let mut renderer = MarkdownRenderer::new(ctx, width);
+    for event in Parser::new_ext(source, options) {
+        renderer.event(event);
+    }
+    renderer.flush_inline();
+    if renderer.lines.is_empty() {
+        renderer.lines.push(Vec::new());
+    }
+    renderer.lines
+}
+
+/// Replaces the source of a Markdown preview with its rendering at
+/// `width`, re-rendering when the width changes. Other previews, and
+/// Markdown files too large to load whole, are left alone.
+pub fn render_markdown_view(doc: &mut PreviewDocument, ctx: &HighlightContext, width: usize) {
+    if doc.language_id.as_deref() != Some("Markdown") || doc.paged.is_some() {
+        return;
+    }
+    if doc
+        .rendered_markdown
+        .as_ref()
+        .is_some_and(|rendered| rendered.width == width)
+    {
+        return;
+    }
+    let source = match doc.rendered_markdown.take() {
+        Some(rendered) => rendered.source,
+        None => doc.plain_source().to_string(),
+    };
+    doc.styled_lines = render_markdown(ctx, &source, width);
+    doc.content_excerpt = doc
+        .styled_lines
+        .iter()
+        .map(|line| line.iter().map(|segment| segment.text.as_str()).collect())
+        .collect::<Vec<String>>()
+        .join("\n");
+    doc.content_type = ContentType::Highlighted;
+    doc.viewport_highlight = None;
+    doc.wrapped_layout = None;
+    doc.rendered_markdown = Some(RenderedMarkdown { source, width });
+}
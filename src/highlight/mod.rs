@@ -1,2 +1,9 @@
+pub mod ansi;
+pub mod custom;
+pub mod detect;
+pub mod markdown;
 pub mod render;
+pub mod structured;
 pub mod syntax;
+pub mod theme;
+pub mod viewport;
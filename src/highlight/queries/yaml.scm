@@ -0,0 +1,34 @@
+(comment) @comment
+
+(block_mapping_pair
+  key: (flow_node (plain_scalar (string_scalar) @property)))
+(block_mapping_pair
+  key: (flow_node [(double_quote_scalar) (single_quote_scalar)] @property))
+(flow_pair
+  key: (flow_node (plain_scalar (string_scalar) @property)))
+(flow_pair
+  key: (flow_node [(double_quote_scalar) (single_quote_scalar)] @property))
+
+(string_scalar) @string
+(double_quote_scalar) @string
+(single_quote_scalar) @string
+(block_scalar) @string
+(escape_sequence) @escape
+
+(integer_scalar) @number
+(float_scalar) @number
+(boolean_scalar) @constant.builtin
+(null_scalar) @constant.builtin
+
+(anchor_name) @type
+(alias_name) @type
+(tag) @type
+
+(yaml_directive) @keyword
+(tag_directive) @keyword
+(reserved_directive) @keyword
+
+["&" "*"] @punctuation.special
+["-" "?" ":" ","] @punctuation.delimiter
+["[" "]" "{" "}"] @punctuation.bracket
+["---" "..." "|" ">"] @punctuation.special
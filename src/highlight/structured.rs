@@ -0,0 +1,426 @@
+use crate::app::state::{ContentType, PreviewDocument, StyledPreviewLine, StyledPreviewSegment};
+use crate::highlight::syntax::HighlightContext;
+use crate::highlight::theme::SyntaxTheme;
+use ratatui::style::Style;
+use serde::Deserialize;
+use std::collections::BTreeSet;
+
+const INDENT: &str = "  ";
+
+/// Data formats the structured view can parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructuredFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl StructuredFormat {
+    /// Format of a preview, from its highlight language or else its extension.
+    pub fn for_document(doc: &PreviewDocument) -> Option<Self> {
+        match doc.language_id.as_deref() {
+            Some("JSON") => return Some(Self::Json),
+            Some("TOML") => return Some(Self::Toml),
+            Some("YAML") => return Some(Self::Yaml),
+            _ => {}
+        }
+        let extension = doc.source_path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(Self::Json),
+            "toml" => Some(Self::Toml),
+            "yaml" | "yml" => Some(Self::Yaml),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Toml => "TOML",
+            Self::Yaml => "YAML",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScalarKind {
+    Null,
+    Bool,
+    Number,
+    String,
+    /// Dates and other typed literals shown as written.
+    Other,
+}
+
+/// A parsed document, independent of the format it came from. Objects keep
+/// their keys in source order.
+#[derive(Debug, Clone)]
+enum Node {
+    Scalar(ScalarKind, String),
+    Array(Vec<Node>),
+    Object(Vec<(String, Node)>),
+}
+
+impl From<serde_json::Value> for Node {
+    fn from(value: serde_json::Value) -> Self {
+        use serde_json::Value;
+        match value {
+            Value::Null => Node::Scalar(ScalarKind::Null, "null".to_string()),
+            Value::Bool(flag) => Node::Scalar(ScalarKind::Bool, flag.to_string()),
+            Value::Number(number) => Node::Scalar(ScalarKind::Number, number.to_string()),
+            Value::String(text) => Node::Scalar(ScalarKind::String, text),
+            Value::Array(items) => Node::Array(items.into_iter().map(Node::from).collect()),
+            Value::Object(map) => Node::Object(
+                map.into_iter()
+                    .map(|(key, value)| (key, Node::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<toml::Value> for Node {
+    fn from(value: toml::Value) -> Self {
+        use toml::Value;
+        match value {
+            Value::String(text) => Node::Scalar(ScalarKind::String, text),
+            Value::Integer(number) => Node::Scalar(ScalarKind::Number, number.to_string()),
+            Value::Float(number) => Node::Scalar(ScalarKind::Number, number.to_string()),
+            Value::Boolean(flag) => Node::Scalar(ScalarKind::Bool, flag.to_string()),
+            Value::Datetime(datetime) => Node::Scalar(ScalarKind::Other, datetime.to_string()),
+            Value::Array(items) => Node::Array(items.into_iter().map(Node::from).collect()),
+            Value::Table(table) => Node::Object(
+                table
+                    .into_iter()
+                    .map(|(key, value)| (key, Node::from(value)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl From<serde_yaml::Value> for Node {
+    fn from(value: serde_yaml::Value) -> Self {
+        use serde_yaml::Value;
+        match value {
+            Value::Null => Node::Scalar(ScalarKind::Null, "null".to_string()),
+            Value::Bool(flag) => Node::Scalar(ScalarKind::Bool, flag.to_string()),
+            Value::Number(number) => Node::Scalar(ScalarKind::Number, number.to_string()),
+            Value::String(text) => Node::Scalar(ScalarKind::String, text),
+            Value::Sequence(items) => Node::Array(items.into_iter().map(Node::from).collect()),
+            Value::Mapping(mapping) => Node::Object(
+                mapping
+                    .into_iter()
+                    .map(|(key, value)| (yaml_key(key), Node::from(value)))
+                    .collect(),
+            ),
+            Value::Tagged(tagged) => Node::from(tagged.value),
+        }
+    }
+}
+
+fn yaml_key(key: serde_yaml::Value) -> String {
+    match Node::from(key) {
+        Node::Scalar(_, text) => text,
+        other => serde_json::to_string(&other.to_json()).unwrap_or_default(),
+    }
+}
+
+impl Node {
+    /// Complex YAML keys are shown as compact JSON.
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            Node::Scalar(_, text) => serde_json::Value::String(text.clone()),
+            Node::Array(items) => items.iter().map(Node::to_json).collect(),
+            Node::Object(entries) => entries
+                .iter()
+                .map(|(key, value)| (key.clone(), value.to_json()))
+                .collect(),
+        }
+    }
+}
+
+fn first_line(message: String) -> String {
+    message.lines().next().unwrap_or_default().to_string()
+}
+
+fn parse(format: StructuredFormat, source: &str) -> Result<Node, String> {
+    match format {
+        StructuredFormat::Json => serde_json::from_str::<serde_json::Value>(source)
+            .map(Node::from)
+            .map_err(|err| err.to_string()),
+        StructuredFormat::Toml => source
+            .parse::<toml::Table>()
+            .map(|table| Node::from(toml::Value::Table(table)))
+            .map_err(|err| first_line(err.message().to_string())),
+        StructuredFormat::Yaml => {
+            // Several `---` documents are shown as an array of documents.
+            let mut documents = serde_yaml::Deserializer::from_str(source)
+                .map(|document| serde_yaml::Value::deserialize(document).map(Node::from))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| first_line(err.to_string()))?;
+            Ok(match documents.len() {
+                0 => Node::Scalar(ScalarKind::Null, "null".to_string()),
+                1 => documents.remove(0),
+                _ => Node::Array(documents),
+            })
+        }
+    }
+}
+
+/// One row of the structured view: the node it shows and its fold state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuredRow {
+    /// jq-style path of the node, e.g. `.servers[2].port`.
+    pub path: String,
+    pub depth: usize,
+    /// Whether the node is a non-empty object or array.
+    pub foldable: bool,
+    pub collapsed: bool,
+}
+
+/// A JSON, TOML or YAML preview laid out as a tree, and the folds it was
+/// laid out with.
+#[derive(Debug, Clone)]
+pub struct StructuredView {
+    pub format: StructuredFormat,
+    root: Node,
+    collapsed: BTreeSet<String>,
+    pub rows: Vec<StructuredRow>,
+}
+
+impl StructuredView {
+    /// Row of the closest enclosing node of the row at `index`.
+    pub fn parent_row(&self, index: usize) -> Option<usize> {
+        let depth = self.rows.get(index)?.depth;
+        self.rows[..index]
+            .iter()
+            .rposition(|row| row.depth + 1 == depth)
+    }
+}
+
+enum Label<'a> {
+    Root,
+    Key(&'a str),
+    Index(usize),
+}
+
+/// jq-style path of a child of the node at `parent`; subscripts of the root
+/// keep jq's leading dot (`.[0]`).
+fn child_path(parent: &str, label: &Label<'_>) -> String {
+    let parent = match parent {
+        "" => return ".".to_string(),
+        "." => ".",
+        parent => parent,
+    };
+    let subscript = match label {
+        Label::Root => return parent.to_string(),
+        Label::Key(key) if is_identifier(key) => {
+            let parent = parent.strip_suffix('.').unwrap_or(parent);
+            return format!("{parent}.{key}");
+        }
+        Label::Key(key) => format!("[{}]", serde_json::to_string(key).unwrap_or_default()),
+        Label::Index(index) => format!("[{index}]"),
+    };
+    format!("{parent}{subscript}")
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_')
+        && chars.all(|ch| ch.is_alphanumeric() || ch == '_')
+}
+
+fn count_label(count: usize, singular: &str) -> String {
+    if count == 1 {
+        format!("1 {singular}")
+    } else {
+        format!("{count} {singular}s")
+    }
+}
+
+struct Layout<'a> {
+    theme: &'a SyntaxTheme,
+    collapsed: &'a BTreeSet<String>,
+    rows: Vec<StructuredRow>,
+    lines: Vec<StyledPreviewLine>,
+}
+
+impl Layout<'_> {
+    fn segment(&self, text: impl Into<String>, capture: &str) -> StyledPreviewSegment {
+        StyledPreviewSegment {
+            text: text.into(),
+            style: self.theme.style_for_capture(capture),
+        }
+    }
+
+    fn node(&mut self, node: &Node, label: Label<'_>, parent: &str, depth: usize) {
+        let path = child_path(parent, &label);
+        let children = match node {
+            Node::Array(items) => items.len(),
+            Node::Object(entries) => entries.len(),
+            Node::Scalar(..) => 0,
+        };
+        let foldable = children > 0;
+        let collapsed = foldable && self.collapsed.contains(&path);
+
+        let mut line = vec![StyledPreviewSegment {
+            text: INDENT.repeat(depth),
+            style: Style::default(),
+        }];
+        if foldable {
+            line.push(self.segment(if collapsed { "▸ " } else { "▾ " }, "punctuation"));
+        } else if depth > 0 {
+            line.push(self.segment(INDENT, "punctuation"));
+        }
+        match label {
+            Label::Root => {}
+            Label::Key(key) => {
+                line.push(self.segment(key, "property"));
+                line.push(self.segment(": ", "punctuation"));
+            }
+            Label::Index(index) => {
+                line.push(self.segment(format!("[{index}]"), "comment"));
+                line.push(self.segment(": ", "punctuation"));
+            }
+        }
+        line.push(match node {
+            Node::Scalar(ScalarKind::String, text) => {
+                self.segment(serde_json::to_string(text).unwrap_or_default(), "string")
+            }
+            Node::Scalar(ScalarKind::Number, text) => self.segment(text.clone(), "number"),
+            Node::Scalar(ScalarKind::Null | ScalarKind::Bool, text) => {
+                self.segment(text.clone(), "constant")
+            }
+            Node::Scalar(ScalarKind::Other, text) => self.segment(text.clone(), "string.special"),
+            Node::Array(_) if !foldable => self.segment("[]", "punctuation"),
+            Node::Object(_) if !foldable => self.segment("{}", "punctuation"),
+            Node::Array(_) => {
+                self.segment(format!("[{}]", count_label(children, "item")), "comment")
+            }
+            Node::Object(_) => {
+                self.segment(format!("{{{}}}", count_label(children, "key")), "comment")
+            }
+        });
+        self.lines.push(line);
+        self.rows.push(StructuredRow {
+            path: path.clone(),
+            depth,
+            foldable,
+            collapsed,
+        });
+
+        if collapsed {
+            return;
+        }
+        match node {
+            Node::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    self.node(item, Label::Index(index), &path, depth + 1);
+                }
+            }
+            Node::Object(entries) => {
+                for (key, value) in entries {
+                    self.node(value, Label::Key(key), &path, depth + 1);
+                }
+            }
+            Node::Scalar(..) => {}
+        }
+    }
+}
+
+/// Parses `source` and lays it out with the nodes at `collapsed` paths folded.
+pub fn structured_lines(
+    ctx: &HighlightContext,
+    format: StructuredFormat,
+    source: &str,
+    collapsed: &BTreeSet<String>,
+) -> Result<(StructuredView, Vec<StyledPreviewLine>), String> {
+    let root = parse(format, source)?;
+    let mut view = StructuredView {
+        format,
+        root,
+        collapsed: collapsed.clone(),
+        rows: Vec::new(),
+    };
+    let lines = layout(ctx, &mut view);
+    Ok((view, lines))
+}
+
+fn layout(ctx: &HighlightContext, view: &mut StructuredView) -> Vec<StyledPreviewLine> {
+    let mut layout = Layout {
+        theme: ctx.syntax_theme(),
+        collapsed: &view.collapsed,
+        rows: Vec::new(),
+        lines: Vec::new(),
+    };
+    layout.node(&view.root, Label::Root, "", 0);
+    view.rows = layout.rows;
+    layout.lines
+}
+
+/// Replaces a JSON, TOML or YAML preview with its structured view, folded at
+/// the `collapsed` paths. The source is parsed once; after that only a fold
+/// change lays the rows out again. Returns the parse error the first time
+/// the source turns out not to be valid, leaving the preview as it was.
+pub fn structured_view(
+    doc: &mut PreviewDocument,
+    ctx: &HighlightContext,
+    collapsed: &BTreeSet<String>,
+) -> Option<String> {
+    match doc.structured.take() {
+        Some(Ok(mut view)) => {
+            if view.collapsed != *collapsed {
+                view.collapsed = collapsed.clone();
+                let lines = layout(ctx, &mut view);
+                set_lines(doc, lines);
+            }
+            doc.structured = Some(Ok(view));
+            return None;
+        }
+        Some(Err(err)) => {
+            doc.structured = Some(Err(err));
+            return None;
+        }
+        None => {}
+    }
+    // Output of a `[previewers]` command is shown as the command wrote it.
+    if doc.external.is_some() {
+        return None;
+    }
+    let format = StructuredFormat::for_document(doc)?;
+    if !doc.has_source_text() {
+        return None;
+    }
+    let parsed = if doc.paged.is_some() || doc.truncated {
+        Err("file is larger than the preview size limit".to_string())
+    } else {
+        structured_lines(ctx, format, doc.plain_source(), collapsed)
+    };
+    match parsed {
+        Ok((view, lines)) => {
+            set_lines(doc, lines);
+            doc.structured = Some(Ok(view));
+            None
+        }
+        Err(err) => {
+            let message = format!("{} structure unavailable: {err}", format.label());
+            doc.structured = Some(Err(message.clone()));
+            Some(message)
+        }
+    }
+}
+
+fn set_lines(doc: &mut PreviewDocument, lines: Vec<StyledPreviewLine>) {
+    doc.content_excerpt = lines
+        .iter()
+        .map(|line| line.iter().map(|segment| segment.text.as_str()).collect())
+        .collect::<Vec<String>>()
+        .join("\n");
+    doc.styled_lines = lines;
+    doc.content_type = ContentType::Highlighted;
+    doc.viewport_highlight = None;
+    doc.wrapped_layout = None;
+}
@@ -1,5 +1,13 @@
+use crate::app::state::DetectionReason;
+use crate::highlight::custom::{
+    load_custom_grammar, merge_query, queries_dir, CustomGrammar, UserGrammarConfig, UserQueries,
+};
+use crate::highlight::detect::{detect_language, is_dockerfile_name, LanguageMapping};
+use crate::highlight::theme::SyntaxTheme;
+use std::cell::OnceCell;
+use std::fs;
 use std::path::Path;
-use tree_sitter::Language;
+use tree_sitter::{Language, QueryError};
 use tree_sitter_highlight::HighlightConfiguration;
 
 const EMPTY_QUERY: &str = "";
@@ -41,7 +49,7 @@ pub const HIGHLIGHT_NAMES: &[&str] = &[
 ];
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum HighlightLanguage {
+pub(crate) enum HighlightLanguage {
     Bash,
     C,
     Cpp,
@@ -58,10 +66,35 @@ enum HighlightLanguage {
     Toml,
     TypeScript,
     Tsx,
+    #[cfg(feature = "lang-csharp")]
+    CSharp,
+    #[cfg(feature = "lang-dockerfile")]
+    Dockerfile,
+    #[cfg(feature = "lang-haskell")]
+    Haskell,
+    #[cfg(feature = "lang-kotlin")]
+    Kotlin,
+    #[cfg(feature = "lang-lua")]
+    Lua,
+    #[cfg(feature = "lang-nix")]
+    Nix,
+    #[cfg(feature = "lang-php")]
+    Php,
+    #[cfg(feature = "lang-ruby")]
+    Ruby,
+    #[cfg(feature = "lang-scala")]
+    Scala,
+    #[cfg(feature = "lang-sql")]
+    Sql,
+    #[cfg(feature = "lang-swift")]
+    Swift,
+    #[cfg(feature = "lang-xml")]
+    Xml,
+    #[cfg(feature = "lang-yaml")]
+    Yaml,
 }
 
 struct LanguageSpec {
-    language_id: &'static str,
     language: Language,
     highlights_query: String,
     injections_query: &'static str,
@@ -69,32 +102,68 @@ struct LanguageSpec {
 }
 
 pub struct HighlightTarget<'a> {
-    pub language_id: &'static str,
+    pub language_id: &'a str,
     pub config: &'a HighlightConfiguration,
+    pub reason: DetectionReason,
 }
 
+/// A grammar's highlight configuration, built the first time it is needed.
+/// `None` inside the cell records a grammar whose queries failed to load.
+type LazyConfig = OnceCell<Option<HighlightConfiguration>>;
+
+/// Highlight configurations for every grammar, each initialised on first use.
+#[derive(Default)]
 pub struct HighlightContext {
-    bash_config: Option<HighlightConfiguration>,
-    c_config: Option<HighlightConfiguration>,
-    cpp_config: Option<HighlightConfiguration>,
-    css_config: Option<HighlightConfiguration>,
-    go_config: Option<HighlightConfiguration>,
-    html_config: Option<HighlightConfiguration>,
-    java_config: Option<HighlightConfiguration>,
-    js_config: Option<HighlightConfiguration>,
-    jsx_config: Option<HighlightConfiguration>,
-    json_config: Option<HighlightConfiguration>,
-    markdown_config: Option<HighlightConfiguration>,
-    markdown_inline_config: Option<HighlightConfiguration>,
-    py_config: Option<HighlightConfiguration>,
-    rs_config: Option<HighlightConfiguration>,
-    toml_config: Option<HighlightConfiguration>,
-    ts_config: Option<HighlightConfiguration>,
-    tsx_config: Option<HighlightConfiguration>,
+    bash_config: LazyConfig,
+    c_config: LazyConfig,
+    cpp_config: LazyConfig,
+    css_config: LazyConfig,
+    go_config: LazyConfig,
+    html_config: LazyConfig,
+    java_config: LazyConfig,
+    js_config: LazyConfig,
+    jsx_config: LazyConfig,
+    json_config: LazyConfig,
+    markdown_config: LazyConfig,
+    markdown_inline_config: LazyConfig,
+    py_config: LazyConfig,
+    rs_config: LazyConfig,
+    toml_config: LazyConfig,
+    ts_config: LazyConfig,
+    tsx_config: LazyConfig,
+    #[cfg(feature = "lang-csharp")]
+    csharp_config: LazyConfig,
+    #[cfg(feature = "lang-dockerfile")]
+    dockerfile_config: LazyConfig,
+    #[cfg(feature = "lang-haskell")]
+    haskell_config: LazyConfig,
+    #[cfg(feature = "lang-kotlin")]
+    kotlin_config: LazyConfig,
+    #[cfg(feature = "lang-lua")]
+    lua_config: LazyConfig,
+    #[cfg(feature = "lang-nix")]
+    nix_config: LazyConfig,
+    #[cfg(feature = "lang-php")]
+    php_config: LazyConfig,
+    #[cfg(feature = "lang-ruby")]
+    ruby_config: LazyConfig,
+    #[cfg(feature = "lang-scala")]
+    scala_config: LazyConfig,
+    #[cfg(feature = "lang-sql")]
+    sql_config: LazyConfig,
+    #[cfg(feature = "lang-swift")]
+    swift_config: LazyConfig,
+    #[cfg(feature = "lang-xml")]
+    xml_config: LazyConfig,
+    #[cfg(feature = "lang-yaml")]
+    yaml_config: LazyConfig,
+    syntax_theme: SyntaxTheme,
+    language_mappings: Vec<LanguageMapping>,
+    custom_grammars: Vec<CustomGrammar>,
 }
 
 impl HighlightLanguage {
-    fn language_id(self) -> &'static str {
+    pub(crate) fn language_id(self) -> &'static str {
         match self {
             Self::Bash => "Shell",
             Self::C => "C",
@@ -110,69 +179,86 @@ impl HighlightLanguage {
             Self::Rust => "Rust",
             Self::Toml => "TOML",
             Self::TypeScript | Self::Tsx => "TypeScript",
+            #[cfg(feature = "lang-csharp")]
+            Self::CSharp => "C#",
+            #[cfg(feature = "lang-dockerfile")]
+            Self::Dockerfile => "Dockerfile",
+            #[cfg(feature = "lang-haskell")]
+            Self::Haskell => "Haskell",
+            #[cfg(feature = "lang-kotlin")]
+            Self::Kotlin => "Kotlin",
+            #[cfg(feature = "lang-lua")]
+            Self::Lua => "Lua",
+            #[cfg(feature = "lang-nix")]
+            Self::Nix => "Nix",
+            #[cfg(feature = "lang-php")]
+            Self::Php => "PHP",
+            #[cfg(feature = "lang-ruby")]
+            Self::Ruby => "Ruby",
+            #[cfg(feature = "lang-scala")]
+            Self::Scala => "Scala",
+            #[cfg(feature = "lang-sql")]
+            Self::Sql => "SQL",
+            #[cfg(feature = "lang-swift")]
+            Self::Swift => "Swift",
+            #[cfg(feature = "lang-xml")]
+            Self::Xml => "XML",
+            #[cfg(feature = "lang-yaml")]
+            Self::Yaml => "YAML",
         }
     }
 
     fn spec(self) -> LanguageSpec {
         match self {
             Self::Bash => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_bash::language(),
                 highlights_query: tree_sitter_bash::HIGHLIGHT_QUERY.to_string(),
                 injections_query: EMPTY_QUERY,
                 locals_query: EMPTY_QUERY,
             },
             Self::C => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_c::language(),
                 highlights_query: tree_sitter_c::HIGHLIGHT_QUERY.to_string(),
                 injections_query: EMPTY_QUERY,
                 locals_query: EMPTY_QUERY,
             },
             Self::Cpp => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_cpp::language(),
                 highlights_query: tree_sitter_cpp::HIGHLIGHT_QUERY.to_string(),
                 injections_query: EMPTY_QUERY,
                 locals_query: EMPTY_QUERY,
             },
             Self::Css => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_css::language(),
                 highlights_query: tree_sitter_css::HIGHLIGHTS_QUERY.to_string(),
                 injections_query: EMPTY_QUERY,
                 locals_query: EMPTY_QUERY,
             },
             Self::Go => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_go::language(),
                 highlights_query: tree_sitter_go::HIGHLIGHT_QUERY.to_string(),
                 injections_query: EMPTY_QUERY,
                 locals_query: EMPTY_QUERY,
             },
             Self::Html => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_html::language(),
                 highlights_query: tree_sitter_html::HIGHLIGHTS_QUERY.to_string(),
                 injections_query: tree_sitter_html::INJECTIONS_QUERY,
                 locals_query: EMPTY_QUERY,
             },
             Self::Java => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_java::language(),
                 highlights_query: tree_sitter_java::HIGHLIGHT_QUERY.to_string(),
                 injections_query: EMPTY_QUERY,
                 locals_query: EMPTY_QUERY,
             },
             Self::JavaScript => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_javascript::language(),
                 highlights_query: tree_sitter_javascript::HIGHLIGHT_QUERY.to_string(),
                 injections_query: tree_sitter_javascript::INJECTION_QUERY,
                 locals_query: tree_sitter_javascript::LOCALS_QUERY,
             },
             Self::Jsx => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_javascript::language(),
                 highlights_query: format!(
                     "{}\n{}",
@@ -183,58 +269,193 @@ impl HighlightLanguage {
                 locals_query: tree_sitter_javascript::LOCALS_QUERY,
             },
             Self::Json => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_json::language(),
                 highlights_query: tree_sitter_json::HIGHLIGHT_QUERY.to_string(),
                 injections_query: EMPTY_QUERY,
                 locals_query: EMPTY_QUERY,
             },
             Self::Markdown => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_md::language(),
                 highlights_query: tree_sitter_md::HIGHLIGHT_QUERY_BLOCK.to_string(),
                 injections_query: tree_sitter_md::INJECTION_QUERY_BLOCK,
                 locals_query: EMPTY_QUERY,
             },
             Self::Python => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_python::language(),
                 highlights_query: tree_sitter_python::HIGHLIGHT_QUERY.to_string(),
                 injections_query: EMPTY_QUERY,
                 locals_query: EMPTY_QUERY,
             },
             Self::Rust => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_rust::language(),
                 highlights_query: tree_sitter_rust::HIGHLIGHT_QUERY.to_string(),
                 injections_query: tree_sitter_rust::INJECTIONS_QUERY,
                 locals_query: EMPTY_QUERY,
             },
             Self::Toml => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_toml::language(),
                 highlights_query: tree_sitter_toml::HIGHLIGHT_QUERY.to_string(),
                 injections_query: EMPTY_QUERY,
                 locals_query: EMPTY_QUERY,
             },
             Self::TypeScript => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_typescript::language_typescript(),
                 highlights_query: tree_sitter_typescript::HIGHLIGHT_QUERY.to_string(),
                 injections_query: EMPTY_QUERY,
                 locals_query: tree_sitter_typescript::LOCALS_QUERY,
             },
             Self::Tsx => LanguageSpec {
-                language_id: self.language_id(),
                 language: tree_sitter_typescript::language_tsx(),
                 highlights_query: tree_sitter_typescript::HIGHLIGHT_QUERY.to_string(),
                 injections_query: EMPTY_QUERY,
                 locals_query: tree_sitter_typescript::LOCALS_QUERY,
             },
+            #[cfg(feature = "lang-csharp")]
+            Self::CSharp => LanguageSpec {
+                language: tree_sitter_c_sharp::language(),
+                highlights_query: normalize_captures(tree_sitter_c_sharp::HIGHLIGHT_QUERY),
+                injections_query: EMPTY_QUERY,
+                locals_query: EMPTY_QUERY,
+            },
+            #[cfg(feature = "lang-dockerfile")]
+            Self::Dockerfile => LanguageSpec {
+                language: tree_sitter_dockerfile::language(),
+                highlights_query: normalize_captures(include_str!("queries/dockerfile.scm")),
+                injections_query: EMPTY_QUERY,
+                locals_query: EMPTY_QUERY,
+            },
+            #[cfg(feature = "lang-haskell")]
+            Self::Haskell => LanguageSpec {
+                language: tree_sitter_haskell::language(),
+                highlights_query: normalize_captures(tree_sitter_haskell::HIGHLIGHTS_QUERY),
+                injections_query: EMPTY_QUERY,
+                locals_query: tree_sitter_haskell::LOCALS_QUERY,
+            },
+            #[cfg(feature = "lang-kotlin")]
+            Self::Kotlin => LanguageSpec {
+                language: tree_sitter_kotlin::language(),
+                highlights_query: normalize_captures(include_str!("queries/kotlin.scm")),
+                injections_query: EMPTY_QUERY,
+                locals_query: EMPTY_QUERY,
+            },
+            #[cfg(feature = "lang-lua")]
+            Self::Lua => LanguageSpec {
+                language: tree_sitter_lua::language(),
+                highlights_query: normalize_captures(tree_sitter_lua::HIGHLIGHTS_QUERY),
+                injections_query: tree_sitter_lua::INJECTIONS_QUERY,
+                locals_query: tree_sitter_lua::LOCALS_QUERY,
+            },
+            #[cfg(feature = "lang-nix")]
+            Self::Nix => LanguageSpec {
+                language: tree_sitter_nix::language(),
+                highlights_query: normalize_captures(tree_sitter_nix::HIGHLIGHTS_QUERY),
+                injections_query: EMPTY_QUERY,
+                locals_query: EMPTY_QUERY,
+            },
+            #[cfg(feature = "lang-php")]
+            Self::Php => LanguageSpec {
+                language: tree_sitter_php::language_php(),
+                highlights_query: normalize_captures(tree_sitter_php::HIGHLIGHT_QUERY),
+                injections_query: tree_sitter_php::INJECTIONS_QUERY,
+                locals_query: EMPTY_QUERY,
+            },
+            #[cfg(feature = "lang-ruby")]
+            Self::Ruby => LanguageSpec {
+                language: tree_sitter_ruby::language(),
+                highlights_query: normalize_captures(tree_sitter_ruby::HIGHLIGHT_QUERY),
+                injections_query: EMPTY_QUERY,
+                locals_query: tree_sitter_ruby::LOCALS_QUERY,
+            },
+            #[cfg(feature = "lang-scala")]
+            Self::Scala => LanguageSpec {
+                language: tree_sitter_scala::language(),
+                highlights_query: normalize_captures(tree_sitter_scala::HIGHLIGHTS_QUERY),
+                injections_query: EMPTY_QUERY,
+                locals_query: tree_sitter_scala::LOCALS_QUERY,
+            },
+            #[cfg(feature = "lang-sql")]
+            Self::Sql => LanguageSpec {
+                language: tree_sitter_sql_bigquery::language(),
+                highlights_query: normalize_captures(include_str!("queries/sql.scm")),
+                injections_query: EMPTY_QUERY,
+                locals_query: EMPTY_QUERY,
+            },
+            #[cfg(feature = "lang-swift")]
+            Self::Swift => LanguageSpec {
+                language: tree_sitter_swift::language(),
+                highlights_query: normalize_captures(tree_sitter_swift::HIGHLIGHTS_QUERY),
+                injections_query: tree_sitter_swift::INJECTIONS_QUERY,
+                locals_query: tree_sitter_swift::LOCALS_QUERY,
+            },
+            #[cfg(feature = "lang-xml")]
+            Self::Xml => LanguageSpec {
+                language: tree_sitter_xml::language_xml(),
+                highlights_query: normalize_captures(tree_sitter_xml::XML_HIGHLIGHT_QUERY),
+                injections_query: EMPTY_QUERY,
+                locals_query: EMPTY_QUERY,
+            },
+            #[cfg(feature = "lang-yaml")]
+            Self::Yaml => LanguageSpec {
+                language: tree_sitter_yaml::language(),
+                highlights_query: include_str!("queries/yaml.scm").to_string(),
+                injections_query: EMPTY_QUERY,
+                locals_query: EMPTY_QUERY,
+            },
         }
     }
 
-    fn for_path(path: &Path) -> Option<Self> {
+    /// Resolves a language name or alias as used by injections, modelines
+    /// and config mappings.
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        let language = match name.trim().to_ascii_lowercase().as_str() {
+            "bash" | "sh" | "shell" | "shell-script" | "zsh" | "ksh" | "dash" => Self::Bash,
+            "c" => Self::C,
+            "cc" | "cp" | "cpp" | "cxx" | "c++" | "hpp" | "hh" | "hxx" => Self::Cpp,
+            "css" => Self::Css,
+            "go" | "golang" => Self::Go,
+            "html" | "htm" => Self::Html,
+            "java" => Self::Java,
+            "javascript" | "js" | "mjs" | "cjs" | "node" => Self::JavaScript,
+            "jsx" => Self::Jsx,
+            "json" => Self::Json,
+            "markdown" | "md" => Self::Markdown,
+            "python" | "py" => Self::Python,
+            "rust" | "rs" => Self::Rust,
+            "toml" | "conf-toml" => Self::Toml,
+            "typescript" | "ts" => Self::TypeScript,
+            "tsx" => Self::Tsx,
+            #[cfg(feature = "lang-csharp")]
+            "c#" | "csharp" | "cs" => Self::CSharp,
+            #[cfg(feature = "lang-dockerfile")]
+            "dockerfile" | "docker" => Self::Dockerfile,
+            #[cfg(feature = "lang-haskell")]
+            "haskell" | "hs" => Self::Haskell,
+            #[cfg(feature = "lang-kotlin")]
+            "kotlin" | "kt" | "kts" => Self::Kotlin,
+            #[cfg(feature = "lang-lua")]
+            "lua" => Self::Lua,
+            #[cfg(feature = "lang-nix")]
+            "nix" => Self::Nix,
+            #[cfg(feature = "lang-php")]
+            "php" => Self::Php,
+            #[cfg(feature = "lang-ruby")]
+            "ruby" | "rb" => Self::Ruby,
+            #[cfg(feature = "lang-scala")]
+            "scala" => Self::Scala,
+            #[cfg(feature = "lang-sql")]
+            "sql" => Self::Sql,
+            #[cfg(feature = "lang-swift")]
+            "swift" => Self::Swift,
+            #[cfg(feature = "lang-xml")]
+            "xml" | "svg" => Self::Xml,
+            #[cfg(feature = "lang-yaml")]
+            "yaml" | "yml" => Self::Yaml,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    pub(crate) fn for_extension(path: &Path) -> Option<Self> {
         let ext = path.extension()?.to_string_lossy().to_ascii_lowercase();
         match ext.as_str() {
             "bash" | "sh" | "zsh" | "ksh" => Some(Self::Bash),
@@ -253,116 +474,338 @@ impl HighlightLanguage {
             "toml" => Some(Self::Toml),
             "ts" => Some(Self::TypeScript),
             "tsx" => Some(Self::Tsx),
+            #[cfg(feature = "lang-csharp")]
+            "cs" | "csx" => Some(Self::CSharp),
+            #[cfg(feature = "lang-haskell")]
+            "hs" | "lhs" => Some(Self::Haskell),
+            #[cfg(feature = "lang-kotlin")]
+            "kt" | "kts" => Some(Self::Kotlin),
+            #[cfg(feature = "lang-lua")]
+            "lua" => Some(Self::Lua),
+            #[cfg(feature = "lang-nix")]
+            "nix" => Some(Self::Nix),
+            #[cfg(feature = "lang-php")]
+            "php" | "phtml" => Some(Self::Php),
+            #[cfg(feature = "lang-ruby")]
+            "rb" | "rake" | "gemspec" => Some(Self::Ruby),
+            #[cfg(feature = "lang-scala")]
+            "scala" | "sc" | "sbt" => Some(Self::Scala),
+            #[cfg(feature = "lang-sql")]
+            "sql" => Some(Self::Sql),
+            #[cfg(feature = "lang-swift")]
+            "swift" => Some(Self::Swift),
+            #[cfg(feature = "lang-xml")]
+            "xml" | "xsd" | "xsl" | "xslt" | "svg" | "plist" => Some(Self::Xml),
+            #[cfg(feature = "lang-yaml")]
+            "yaml" | "yml" => Some(Self::Yaml),
             _ => None,
         }
     }
 }
 
+/// Maps the nvim-style capture names used by many upstream queries onto
+/// [`HIGHLIGHT_NAMES`], so `@conditional` styles like `@keyword`.
+pub(crate) fn normalize_captures(query: &str) -> String {
+    const RENAMES: &[(&str, &str)] = &[
+        ("boolean", "constant.builtin"),
+        ("character", "string"),
+        ("conditional", "keyword"),
+        ("constant.macro", "constant"),
+        ("exception", "keyword"),
+        ("field", "property"),
+        ("float", "number"),
+        ("function.call", "function"),
+        ("function.macro", "function"),
+        ("include", "keyword"),
+        ("markup.heading", "text.title"),
+        ("markup.link", "text.uri"),
+        ("markup.raw", "text.literal"),
+        ("method", "function.method"),
+        ("method.call", "function.method"),
+        ("namespace", "module"),
+        ("parameter", "variable.parameter"),
+        ("preproc", "keyword"),
+        ("repeat", "keyword"),
+        ("storageclass", "keyword"),
+        ("symbol", "constant"),
+        ("type.qualifier", "keyword"),
+    ];
+    let mut out = String::with_capacity(query.len());
+    let mut rest = query;
+    while let Some(at) = rest.find('@') {
+        out.push_str(&rest[..=at]);
+        rest = &rest[at + 1..];
+        let end = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '.' || c == '_' || c == '-'))
+            .unwrap_or(rest.len());
+        let name = &rest[..end];
+        match RENAMES.iter().find(|(from, _)| *from == name) {
+            Some((_, to)) => out.push_str(to),
+            None => out.push_str(name),
+        }
+        rest = &rest[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+pub(crate) fn new_highlight_config(
+    language: Language,
+    highlights_query: &str,
+    injections_query: &str,
+    locals_query: &str,
+) -> Result<HighlightConfiguration, QueryError> {
+    let mut config =
+        HighlightConfiguration::new(language, highlights_query, injections_query, locals_query)?;
+    config.configure(HIGHLIGHT_NAMES);
+    Ok(config)
+}
+
 fn build_config(language: HighlightLanguage) -> Option<HighlightConfiguration> {
     let spec = language.spec();
-    let mut config = HighlightConfiguration::new(
+    new_highlight_config(
         spec.language,
         &spec.highlights_query,
         spec.injections_query,
         spec.locals_query,
     )
-    .ok()?;
-    config.configure(HIGHLIGHT_NAMES);
-    Some(config)
+    .ok()
+}
+
+/// Builds `language` with the user's queries merged over the built-in ones.
+fn build_config_with(
+    language: HighlightLanguage,
+    queries: &UserQueries,
+) -> Result<HighlightConfiguration, QueryError> {
+    let spec = language.spec();
+    new_highlight_config(
+        spec.language,
+        &merge_query(&spec.highlights_query, queries.highlights.as_deref()),
+        &merge_query(spec.injections_query, queries.injections.as_deref()),
+        &merge_query(spec.locals_query, queries.locals.as_deref()),
+    )
 }
 
 fn build_markdown_inline_config() -> Option<HighlightConfiguration> {
-    let mut config = HighlightConfiguration::new(
+    new_highlight_config(
         tree_sitter_md::inline_language(),
         tree_sitter_md::HIGHLIGHT_QUERY_INLINE,
         tree_sitter_md::INJECTION_QUERY_INLINE,
         EMPTY_QUERY,
     )
-    .ok()?;
-    config.configure(HIGHLIGHT_NAMES);
-    Some(config)
+    .ok()
 }
 
 impl HighlightContext {
     pub fn new() -> Self {
-        Self {
-            bash_config: build_config(HighlightLanguage::Bash),
-            c_config: build_config(HighlightLanguage::C),
-            cpp_config: build_config(HighlightLanguage::Cpp),
-            css_config: build_config(HighlightLanguage::Css),
-            go_config: build_config(HighlightLanguage::Go),
-            html_config: build_config(HighlightLanguage::Html),
-            java_config: build_config(HighlightLanguage::Java),
-            js_config: build_config(HighlightLanguage::JavaScript),
-            jsx_config: build_config(HighlightLanguage::Jsx),
-            json_config: build_config(HighlightLanguage::Json),
-            markdown_config: build_config(HighlightLanguage::Markdown),
-            markdown_inline_config: build_markdown_inline_config(),
-            py_config: build_config(HighlightLanguage::Python),
-            rs_config: build_config(HighlightLanguage::Rust),
-            toml_config: build_config(HighlightLanguage::Toml),
-            ts_config: build_config(HighlightLanguage::TypeScript),
-            tsx_config: build_config(HighlightLanguage::Tsx),
+        Self::default()
+    }
+
+    pub fn with_syntax_theme(mut self, theme: SyntaxTheme) -> Self {
+        self.syntax_theme = theme;
+        self
+    }
+
+    pub fn syntax_theme(&self) -> &SyntaxTheme {
+        &self.syntax_theme
+    }
+
+    pub fn with_language_mappings(mut self, mappings: Vec<LanguageMapping>) -> Self {
+        self.language_mappings = mappings;
+        self
+    }
+
+    /// Loads the `[[grammars]]` shared libraries, returning a warning for
+    /// each grammar that could not be used.
+    pub fn load_custom_grammars(
+        &mut self,
+        grammars: &[UserGrammarConfig],
+        config_path: &Path,
+    ) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for user in grammars {
+            match load_custom_grammar(user, config_path) {
+                Ok((grammar, grammar_warnings)) => {
+                    self.custom_grammars.push(grammar);
+                    warnings.extend(grammar_warnings);
+                }
+                Err(err) => warnings.push(format!("grammar '{}' ignored: {err:#}", user.name)),
+            }
         }
+        warnings
     }
 
-    pub fn target_for_path(&self, path: &Path) -> Option<HighlightTarget<'_>> {
-        let language = HighlightLanguage::for_path(path)?;
-        let spec = language.spec();
-        let config = match language {
-            HighlightLanguage::Bash => self.bash_config.as_ref()?,
-            HighlightLanguage::C => self.c_config.as_ref()?,
-            HighlightLanguage::Cpp => self.cpp_config.as_ref()?,
-            HighlightLanguage::Css => self.css_config.as_ref()?,
-            HighlightLanguage::Go => self.go_config.as_ref()?,
-            HighlightLanguage::Html => self.html_config.as_ref()?,
-            HighlightLanguage::Java => self.java_config.as_ref()?,
-            HighlightLanguage::JavaScript => self.js_config.as_ref()?,
-            HighlightLanguage::Jsx => self.jsx_config.as_ref()?,
-            HighlightLanguage::Json => self.json_config.as_ref()?,
-            HighlightLanguage::Markdown => self.markdown_config.as_ref()?,
-            HighlightLanguage::Python => self.py_config.as_ref()?,
-            HighlightLanguage::Rust => self.rs_config.as_ref()?,
-            HighlightLanguage::Toml => self.toml_config.as_ref()?,
-            HighlightLanguage::TypeScript => self.ts_config.as_ref()?,
-            HighlightLanguage::Tsx => self.tsx_config.as_ref()?,
+    /// Applies `queries/<lang>/*.scm` beside the config file to the built-in
+    /// grammars. Queries that fail to compile are reported and the built-in
+    /// ones kept. Must run before anything is highlighted.
+    pub fn load_query_overrides(&mut self, config_path: &Path) -> Vec<String> {
+        let dir = queries_dir(config_path);
+        let Ok(entries) = fs::read_dir(&dir) else {
+            return Vec::new();
         };
+        let mut dirs = entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        dirs.sort();
+
+        let mut warnings = Vec::new();
+        for name in dirs {
+            if self.custom_grammar(&name).is_some() {
+                continue;
+            }
+            let Some(language) = HighlightLanguage::from_name(&name) else {
+                warnings.push(format!("queries for unknown language '{name}' ignored"));
+                continue;
+            };
+            let queries = match UserQueries::read(&dir.join(&name)) {
+                Ok(queries) if queries.is_empty() => continue,
+                Ok(queries) => queries,
+                Err(err) => {
+                    warnings.push(format!("queries for '{name}' ignored: {err}"));
+                    continue;
+                }
+            };
+            match build_config_with(language, &queries) {
+                Ok(config) => {
+                    let _ = self.cell_for(language).set(Some(config));
+                }
+                Err(err) => warnings.push(format!("queries for '{name}' ignored: {err}")),
+            }
+        }
+        warnings
+    }
+
+    fn custom_grammar(&self, name: &str) -> Option<&CustomGrammar> {
+        self.custom_grammars
+            .iter()
+            .find(|grammar| grammar.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Whether the highlight configuration of the grammar called `name`
+    /// has been built yet.
+    pub fn is_grammar_built(&self, name: &str) -> bool {
+        HighlightLanguage::from_name(name)
+            .is_some_and(|language| self.cell_for(language).get().is_some())
+    }
+
+    fn config_for(&self, language: HighlightLanguage) -> Option<&HighlightConfiguration> {
+        self.cell_for(language)
+            .get_or_init(|| build_config(language))
+            .as_ref()
+    }
+
+    fn cell_for(&self, language: HighlightLanguage) -> &LazyConfig {
+        match language {
+            HighlightLanguage::Bash => &self.bash_config,
+            HighlightLanguage::C => &self.c_config,
+            HighlightLanguage::Cpp => &self.cpp_config,
+            HighlightLanguage::Css => &self.css_config,
+            HighlightLanguage::Go => &self.go_config,
+            HighlightLanguage::Html => &self.html_config,
+            HighlightLanguage::Java => &self.java_config,
+            HighlightLanguage::JavaScript => &self.js_config,
+            HighlightLanguage::Jsx => &self.jsx_config,
+            HighlightLanguage::Json => &self.json_config,
+            HighlightLanguage::Markdown => &self.markdown_config,
+            HighlightLanguage::Python => &self.py_config,
+            HighlightLanguage::Rust => &self.rs_config,
+            HighlightLanguage::Toml => &self.toml_config,
+            HighlightLanguage::TypeScript => &self.ts_config,
+            HighlightLanguage::Tsx => &self.tsx_config,
+            #[cfg(feature = "lang-csharp")]
+            HighlightLanguage::CSharp => &self.csharp_config,
+            #[cfg(feature = "lang-dockerfile")]
+            HighlightLanguage::Dockerfile => &self.dockerfile_config,
+            #[cfg(feature = "lang-haskell")]
+            HighlightLanguage::Haskell => &self.haskell_config,
+            #[cfg(feature = "lang-kotlin")]
+            HighlightLanguage::Kotlin => &self.kotlin_config,
+            #[cfg(feature = "lang-lua")]
+            HighlightLanguage::Lua => &self.lua_config,
+            #[cfg(feature = "lang-nix")]
+            HighlightLanguage::Nix => &self.nix_config,
+            #[cfg(feature = "lang-php")]
+            HighlightLanguage::Php => &self.php_config,
+            #[cfg(feature = "lang-ruby")]
+            HighlightLanguage::Ruby => &self.ruby_config,
+            #[cfg(feature = "lang-scala")]
+            HighlightLanguage::Scala => &self.scala_config,
+            #[cfg(feature = "lang-sql")]
+            HighlightLanguage::Sql => &self.sql_config,
+            #[cfg(feature = "lang-swift")]
+            HighlightLanguage::Swift => &self.swift_config,
+            #[cfg(feature = "lang-xml")]
+            HighlightLanguage::Xml => &self.xml_config,
+            #[cfg(feature = "lang-yaml")]
+            HighlightLanguage::Yaml => &self.yaml_config,
+        }
+    }
+
+    fn custom_target(&self, path: &Path) -> Option<HighlightTarget<'_>> {
+        let grammar = self
+            .custom_grammars
+            .iter()
+            .find(|grammar| grammar.matches(path))?;
         Some(HighlightTarget {
-            language_id: spec.language_id,
-            config,
+            language_id: &grammar.name,
+            config: &grammar.config,
+            reason: DetectionReason::ConfigMapping,
         })
     }
 
-    pub fn injection_config(&self, language_name: &str) -> Option<&HighlightConfiguration> {
-        match language_name.to_ascii_lowercase().as_str() {
-            "markdown_inline" => self.markdown_inline_config.as_ref(),
-            "bash" | "sh" | "shell" | "zsh" | "ksh" => self.bash_config.as_ref(),
-            "c" => self.c_config.as_ref(),
-            "cc" | "cp" | "cpp" | "cxx" | "c++" | "hpp" | "hh" | "hxx" => self.cpp_config.as_ref(),
-            "css" => self.css_config.as_ref(),
-            "go" | "golang" => self.go_config.as_ref(),
-            "html" | "htm" => self.html_config.as_ref(),
-            "java" => self.java_config.as_ref(),
-            "javascript" | "js" | "mjs" | "cjs" => self.js_config.as_ref(),
-            "jsx" => self.jsx_config.as_ref(),
-            "json" => self.json_config.as_ref(),
-            "python" | "py" => self.py_config.as_ref(),
-            "rust" | "rs" => self.rs_config.as_ref(),
-            "toml" => self.toml_config.as_ref(),
-            "typescript" | "ts" => self.ts_config.as_ref(),
-            "tsx" => self.tsx_config.as_ref(),
-            _ => None,
+    fn target(
+        &self,
+        (language, reason): (HighlightLanguage, DetectionReason),
+    ) -> Option<HighlightTarget<'_>> {
+        Some(HighlightTarget {
+            language_id: language.language_id(),
+            config: self.config_for(language)?,
+            reason,
+        })
+    }
+
+    /// Language chosen from the path alone: custom grammar patterns, config
+    /// mappings, well-known file names, then the extension.
+    pub fn target_for_path(&self, path: &Path) -> Option<HighlightTarget<'_>> {
+        if let Some(target) = self.custom_target(path) {
+            return Some(target);
         }
+        self.target(detect_language(path, None, &self.language_mappings)?)
+    }
+
+    /// Like [`Self::target_for_path`], but also reads modelines and the
+    /// shebang line from `content`.
+    pub fn target_for_file(&self, path: &Path, content: &str) -> Option<HighlightTarget<'_>> {
+        if let Some(target) = self.custom_target(path) {
+            return Some(target);
+        }
+        self.target(detect_language(
+            path,
+            Some(content),
+            &self.language_mappings,
+        )?)
     }
-}
 
-impl Default for HighlightContext {
-    fn default() -> Self {
-        Self::new()
+    pub fn injection_config(&self, language_name: &str) -> Option<&HighlightConfiguration> {
+        if language_name.eq_ignore_ascii_case("markdown_inline") {
+            return self
+                .markdown_inline_config
+                .get_or_init(build_markdown_inline_config)
+                .as_ref();
+        }
+        if let Some(grammar) = self.custom_grammar(language_name) {
+            return Some(&grammar.config);
+        }
+        self.config_for(HighlightLanguage::from_name(language_name)?)
     }
 }
 
 pub fn language_for_path(path: &Path) -> Option<String> {
+    if is_dockerfile_name(path) {
+        return Some("Dockerfile".to_string());
+    }
     let ext = path.extension()?.to_string_lossy().to_ascii_lowercase();
     let lang = match ext.as_str() {
         "bash" | "sh" | "zsh" | "ksh" => "Shell",
@@ -380,7 +823,17 @@ pub fn language_for_path(path: &Path) -> Option<String> {
         "yaml" | "yml" => "YAML",
         "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
         "tsx" | "ts" => "TypeScript",
-        "xml" => "XML",
+        "xml" | "xsd" | "xsl" | "xslt" | "svg" | "plist" => "XML",
+        "sql" => "SQL",
+        "rb" | "rake" | "gemspec" => "Ruby",
+        "lua" => "Lua",
+        "php" | "phtml" => "PHP",
+        "kt" | "kts" => "Kotlin",
+        "swift" => "Swift",
+        "cs" | "csx" => "C#",
+        "scala" | "sc" | "sbt" => "Scala",
+        "hs" | "lhs" => "Haskell",
+        "nix" => "Nix",
         _ => return None,
     };
     Some(lang.to_string())
@@ -0,0 +1,266 @@
+use crate::highlight::syntax::HIGHLIGHT_NAMES;
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyntaxPalette {
+    #[default]
+    Dark,
+    Light,
+    HighContrast,
+}
+
+/// Background and text colors a syntax theme is meant to be read on, used
+/// for the page of an HTML export.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageColors {
+    pub background: Color,
+    pub foreground: Color,
+}
+
+impl Default for PageColors {
+    fn default() -> Self {
+        SyntaxPalette::default().page_colors()
+    }
+}
+
+impl SyntaxPalette {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().replace('_', "-").as_str() {
+            "dark" => Some(Self::Dark),
+            "light" => Some(Self::Light),
+            "high-contrast" => Some(Self::HighContrast),
+            _ => None,
+        }
+    }
+
+    pub fn page_colors(self) -> PageColors {
+        let (background, foreground) = match self {
+            Self::Dark => (Color::Rgb(30, 31, 34), Color::Rgb(212, 212, 212)),
+            Self::Light => (Color::Rgb(255, 255, 255), Color::Rgb(36, 41, 46)),
+            Self::HighContrast => (Color::Rgb(0, 0, 0), Color::Rgb(255, 255, 255)),
+        };
+        PageColors {
+            background,
+            foreground,
+        }
+    }
+
+    fn entries(self) -> Vec<(&'static str, Style)> {
+        let fg = |r, g, b| Style::default().fg(Color::Rgb(r, g, b));
+        match self {
+            Self::Dark => vec![
+                ("comment", fg(120, 150, 120).add_modifier(Modifier::ITALIC)),
+                ("keyword", fg(220, 150, 80).add_modifier(Modifier::BOLD)),
+                ("string", fg(140, 200, 130)),
+                ("escape", fg(140, 200, 130)),
+                ("number", fg(120, 190, 210)),
+                ("constant", fg(120, 190, 210)),
+                ("type", fg(120, 170, 230)),
+                ("tag", fg(120, 170, 230)),
+                ("attribute", fg(120, 170, 230)),
+                ("function", fg(220, 200, 120)),
+                ("constructor", fg(220, 200, 120)),
+                ("variable.parameter", fg(210, 170, 230)),
+                ("property", fg(210, 170, 230)),
+                ("text.title", fg(110, 170, 240).add_modifier(Modifier::BOLD)),
+                ("text.literal", fg(140, 200, 130)),
+                (
+                    "text.emphasis",
+                    Style::default().add_modifier(Modifier::ITALIC),
+                ),
+                ("text.strong", Style::default().add_modifier(Modifier::BOLD)),
+                (
+                    "text.reference",
+                    fg(130, 180, 230).add_modifier(Modifier::UNDERLINED),
+                ),
+                (
+                    "text.uri",
+                    fg(130, 180, 230).add_modifier(Modifier::UNDERLINED),
+                ),
+            ],
+            Self::Light => vec![
+                ("comment", fg(106, 115, 125).add_modifier(Modifier::ITALIC)),
+                ("keyword", fg(175, 30, 45).add_modifier(Modifier::BOLD)),
+                ("string", fg(10, 90, 40)),
+                ("escape", fg(10, 90, 40)),
+                ("number", fg(5, 80, 174)),
+                ("constant", fg(5, 80, 174)),
+                ("type", fg(111, 66, 193)),
+                ("tag", fg(34, 134, 58)),
+                ("attribute", fg(111, 66, 193)),
+                ("function", fg(130, 60, 10)),
+                ("constructor", fg(130, 60, 10)),
+                ("variable.parameter", fg(149, 56, 0)),
+                ("property", fg(0, 92, 197)),
+                ("text.title", fg(5, 80, 174).add_modifier(Modifier::BOLD)),
+                ("text.literal", fg(10, 90, 40)),
+                (
+                    "text.emphasis",
+                    Style::default().add_modifier(Modifier::ITALIC),
+                ),
+                ("text.strong", Style::default().add_modifier(Modifier::BOLD)),
+                (
+                    "text.reference",
+                    fg(3, 102, 214).add_modifier(Modifier::UNDERLINED),
+                ),
+                (
+                    "text.uri",
+                    fg(3, 102, 214).add_modifier(Modifier::UNDERLINED),
+                ),
+            ],
+            Self::HighContrast => vec![
+                ("comment", fg(190, 190, 190).add_modifier(Modifier::ITALIC)),
+                ("keyword", fg(255, 215, 0).add_modifier(Modifier::BOLD)),
+                ("string", fg(0, 255, 135)),
+                ("escape", fg(0, 255, 135).add_modifier(Modifier::BOLD)),
+                ("number", fg(0, 215, 255)),
+                ("constant", fg(0, 215, 255)),
+                ("type", fg(95, 175, 255).add_modifier(Modifier::BOLD)),
+                ("tag", fg(95, 175, 255).add_modifier(Modifier::BOLD)),
+                ("attribute", fg(95, 175, 255)),
+                ("function", fg(255, 255, 95)),
+                ("constructor", fg(255, 255, 95)),
+                ("variable.parameter", fg(255, 135, 255)),
+                ("property", fg(255, 135, 255)),
+                (
+                    "text.title",
+                    fg(255, 255, 255).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                ),
+                ("text.literal", fg(0, 255, 135)),
+                (
+                    "text.emphasis",
+                    Style::default().add_modifier(Modifier::ITALIC),
+                ),
+                ("text.strong", Style::default().add_modifier(Modifier::BOLD)),
+                (
+                    "text.reference",
+                    fg(95, 215, 255).add_modifier(Modifier::UNDERLINED),
+                ),
+                (
+                    "text.uri",
+                    fg(95, 215, 255).add_modifier(Modifier::UNDERLINED),
+                ),
+            ],
+        }
+    }
+}
+
+/// One `[theme.syntax]` entry, e.g. `comment = { fg = "#6a737d", modifiers = ["italic"] }`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct SyntaxStyleConfig {
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+    #[serde(default)]
+    pub modifiers: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxTheme {
+    styles: HashMap<String, Style>,
+    page: PageColors,
+}
+
+impl Default for SyntaxTheme {
+    fn default() -> Self {
+        Self::builtin(SyntaxPalette::Dark)
+    }
+}
+
+impl SyntaxTheme {
+    pub fn builtin(palette: SyntaxPalette) -> Self {
+        let styles = palette
+            .entries()
+            .into_iter()
+            .map(|(name, style)| (name.to_string(), style))
+            .collect();
+        Self {
+            styles,
+            page: palette.page_colors(),
+        }
+    }
+
+    pub fn set_style(&mut self, capture: &str, style: Style) {
+        self.styles.insert(capture.to_string(), style);
+    }
+
+    pub fn page_colors(&self) -> PageColors {
+        self.page
+    }
+
+    pub fn set_page_colors(&mut self, page: PageColors) {
+        self.page = page;
+    }
+
+    /// Style for a capture name, falling back to its dotted parents
+    /// (`function.builtin` -> `function`) and then to the terminal default.
+    pub fn style_for_capture(&self, name: &str) -> Style {
+        let mut current = name;
+        loop {
+            if let Some(style) = self.styles.get(current) {
+                return *style;
+            }
+            match current.rsplit_once('.') {
+                Some((parent, _)) => current = parent,
+                None => return Style::default(),
+            }
+        }
+    }
+}
+
+pub fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim().to_ascii_lowercase();
+    if value.is_empty() {
+        return None;
+    }
+    Color::from_str(&value).ok()
+}
+
+pub fn parse_modifier(value: &str) -> Option<Modifier> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "bold" => Some(Modifier::BOLD),
+        "dim" => Some(Modifier::DIM),
+        "italic" => Some(Modifier::ITALIC),
+        "underline" | "underlined" => Some(Modifier::UNDERLINED),
+        "reverse" | "reversed" => Some(Modifier::REVERSED),
+        "strikethrough" | "crossed_out" => Some(Modifier::CROSSED_OUT),
+        _ => None,
+    }
+}
+
+/// Builds a style from a config entry. Unknown colors and modifiers are
+/// skipped and reported through `warnings`.
+pub fn style_from_config(
+    capture: &str,
+    entry: &SyntaxStyleConfig,
+    warnings: &mut Vec<String>,
+) -> Style {
+    let mut style = Style::default();
+    for (value, is_fg) in [(&entry.fg, true), (&entry.bg, false)] {
+        let Some(value) = value else {
+            continue;
+        };
+        match parse_color(value) {
+            Some(color) if is_fg => style = style.fg(color),
+            Some(color) => style = style.bg(color),
+            None => warnings.push(format!(
+                "invalid color '{value}' for syntax capture '{capture}' ignored"
+            )),
+        }
+    }
+    for name in &entry.modifiers {
+        match parse_modifier(name) {
+            Some(modifier) => style = style.add_modifier(modifier),
+            None => warnings.push(format!(
+                "unknown modifier '{name}' for syntax capture '{capture}' ignored"
+            )),
+        }
+    }
+    style
+}
+
+pub fn is_known_capture(name: &str) -> bool {
+    HIGHLIGHT_NAMES.contains(&name)
+}
@@ -0,0 +1,219 @@
+use crate::app::state::{
+    ContentType, PreviewDocument, PreviewFallbackReason, StyledPreviewLine, StyledPreviewSegment,
+};
+use crate::highlight::render::{highlight_lines, HighlightRenderResult};
+use crate::highlight::syntax::HighlightContext;
+use ratatui::style::Style;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::thread;
+use tree_sitter::{Language, Node, Parser, Point, Tree};
+
+/// Lines styled above and below the visible rows, so short scrolls land on
+/// text that is already highlighted.
+pub const VIEWPORT_MARGIN_LINES: usize = 200;
+/// Lines past its margins a highlight window may reach to start and end on
+/// syntax node boundaries.
+const MAX_LOOKBACK_LINES: usize = 2000;
+
+/// Syntax tree of a document's text, parsed in a background thread since a
+/// large file takes a while. Dropping the last handle stops the parse.
+#[derive(Debug)]
+pub struct BackgroundParse {
+    tree: Arc<OnceLock<Tree>>,
+    cancel: Arc<AtomicBool>,
+    /// Whether a highlight window was laid out on the finished tree.
+    used: AtomicBool,
+}
+
+impl BackgroundParse {
+    fn spawn(language: Language, text: String) -> Self {
+        let tree = Arc::new(OnceLock::new());
+        let cancel = Arc::new(AtomicBool::new(false));
+        let (parsed, parse_cancel) = (Arc::clone(&tree), Arc::clone(&cancel));
+        thread::spawn(move || {
+            let mut parser = Parser::new();
+            if parser.set_language(language).is_err() {
+                return;
+            }
+            // Ending the input early is how a parse is cut short.
+            let bytes = text.as_bytes();
+            let tree = parser.parse_with(
+                &mut |offset, _| {
+                    if parse_cancel.load(Ordering::Relaxed) {
+                        &[][..]
+                    } else {
+                        &bytes[offset.min(bytes.len())..]
+                    }
+                },
+                None,
+            );
+            if let Some(tree) = tree.filter(|_| !parse_cancel.load(Ordering::Relaxed)) {
+                let _ = parsed.set(tree);
+            }
+        });
+        Self {
+            tree,
+            cancel,
+            used: AtomicBool::new(false),
+        }
+    }
+
+    pub fn is_parsed(&self) -> bool {
+        self.tree.get().is_some()
+    }
+}
+
+impl Drop for BackgroundParse {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+pub(crate) fn unstyled_line(text: &str) -> StyledPreviewLine {
+    if text.is_empty() {
+        Vec::new()
+    } else {
+        vec![StyledPreviewSegment {
+            text: text.to_string(),
+            style: Style::default(),
+        }]
+    }
+}
+
+fn line_text(line: &StyledPreviewLine) -> String {
+    line.iter().map(|segment| segment.text.as_str()).collect()
+}
+
+/// The outermost syntax node around `point` that `fits`, or the token (a
+/// comment, say) around it however long; `None` when `point` lies between
+/// nodes. Highlighting a window that starts and ends outside of it parses
+/// the same way as the whole text.
+fn node_around(tree: &Tree, point: Point, fits: impl Fn(&Node<'_>) -> bool) -> Option<Node<'_>> {
+    let mut node = tree.root_node();
+    loop {
+        let mut cursor = node.walk();
+        let child = node
+            .children(&mut cursor)
+            .find(|child| child.start_position() <= point && point < child.end_position())?;
+        if fits(&child) || child.child_count() == 0 || child.is_extra() {
+            return Some(child);
+        }
+        node = child;
+    }
+}
+
+/// `window` widened to syntax node boundaries, reaching at most
+/// [`MAX_LOOKBACK_LINES`] further unless a token is longer.
+fn widen_to_nodes(tree: &Tree, window: Range<usize>, total: usize) -> Range<usize> {
+    let start = node_around(tree, Point::new(window.start, 0), |node| {
+        node.start_position().row + MAX_LOOKBACK_LINES >= window.start
+    })
+    .map_or(window.start, |node| node.start_position().row);
+    let Some(last) = window.end.checked_sub(1) else {
+        return start..window.end;
+    };
+    let end = node_around(tree, Point::new(last, usize::MAX), |node| {
+        node.end_position().row <= last + MAX_LOOKBACK_LINES
+    })
+    .map_or(window.end, |node| node.end_position().row + 1);
+    start.min(window.start)..end.clamp(window.end, total)
+}
+
+/// Detects the language of a file too large to highlight at once and lays
+/// out its lines unstyled, for [`highlight_viewport`] to fill in.
+pub fn render_for_viewport(
+    ctx: &HighlightContext,
+    path: &Path,
+    content: &str,
+) -> HighlightRenderResult {
+    let Some(target) = ctx.target_for_file(path, content) else {
+        return HighlightRenderResult {
+            rendered_text: content.to_string(),
+            content_type: ContentType::PlainText,
+            language_id: None,
+            language_reason: None,
+            styled_lines: Vec::new(),
+            fallback_reason: Some(PreviewFallbackReason::UnsupportedExtension),
+        };
+    };
+    HighlightRenderResult {
+        rendered_text: content.to_string(),
+        content_type: ContentType::Highlighted,
+        language_id: Some(target.language_id.to_string()),
+        language_reason: Some(target.reason),
+        styled_lines: content.split('\n').map(unstyled_line).collect(),
+        fallback_reason: None,
+    }
+}
+
+/// Styles the source lines in `rows`, plus [`VIEWPORT_MARGIN_LINES`] on
+/// either side, of a document highlighted per viewport. Once the syntax
+/// tree of the document is parsed, the window is widened to node boundaries
+/// so it never starts or ends inside a construct (a long block comment,
+/// say), and the window styled before that is styled again. Does nothing
+/// when the rows are already styled or the document was highlighted up
+/// front.
+pub fn highlight_viewport(doc: &mut PreviewDocument, ctx: &HighlightContext, rows: Range<usize>) {
+    let Some(styled) = doc.viewport_highlight.clone() else {
+        return;
+    };
+    // Paged previews hold a window of the file; `rows` count from its start.
+    let offset = doc.line_offset();
+    let total = doc.styled_lines.len();
+    let wanted =
+        rows.start.saturating_sub(offset).min(total)..rows.end.saturating_sub(offset).min(total);
+    let tree_unused = doc
+        .viewport_tree
+        .as_ref()
+        .is_some_and(|parse| parse.is_parsed() && !parse.used.load(Ordering::Relaxed));
+    if !styled.is_empty()
+        && styled.start <= wanted.start
+        && wanted.end <= styled.end
+        && !tree_unused
+    {
+        return;
+    }
+    let Some(target) = ctx.target_for_file(&doc.source_path, doc.plain_source()) else {
+        return;
+    };
+    let window = wanted.start.saturating_sub(VIEWPORT_MARGIN_LINES)
+        ..(wanted.end + VIEWPORT_MARGIN_LINES).min(total);
+    // A window over the whole text needs no boundaries.
+    let window = if window == (0..total) {
+        window
+    } else {
+        let parse = match &doc.viewport_tree {
+            Some(parse) => Arc::clone(parse),
+            None => {
+                let parse = Arc::new(BackgroundParse::spawn(
+                    target.config.language,
+                    doc.plain_source().to_string(),
+                ));
+                doc.viewport_tree = Some(Arc::clone(&parse));
+                parse
+            }
+        };
+        match parse.tree.get() {
+            Some(tree) => {
+                parse.used.store(true, Ordering::Relaxed);
+                widen_to_nodes(tree, window, total)
+            }
+            None => window,
+        }
+    };
+    let source = doc.styled_lines[window.clone()]
+        .iter()
+        .map(line_text)
+        .collect::<Vec<_>>()
+        .join("\n");
+    let Some(lines) = highlight_lines(ctx, target.config, &source) else {
+        return;
+    };
+    for (slot, line) in doc.styled_lines[window.clone()].iter_mut().zip(lines) {
+        *slot = line;
+    }
+    doc.viewport_highlight = Some(window);
+}
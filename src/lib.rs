@@ -1,5 +1,6 @@
 pub mod app;
 pub mod config;
+pub mod export;
 pub mod fs;
 pub mod highlight;
 pub mod tui;
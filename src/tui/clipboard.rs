@@ -0,0 +1,35 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalMultiplexer {
+    None,
+    Tmux,
+    Screen,
+}
+
+pub fn detect_multiplexer() -> TerminalMultiplexer {
+    if std::env::var_os("TMUX").is_some() {
+        return TerminalMultiplexer::Tmux;
+    }
+    if std::env::var("TERM").is_ok_and(|term| term.starts_with("screen")) {
+        return TerminalMultiplexer::Screen;
+    }
+    TerminalMultiplexer::None
+}
+
+pub fn osc52_sequence(text: &str, multiplexer: TerminalMultiplexer) -> String {
+    let osc = format!("\x1b]52;c;{}\x07", STANDARD.encode(text));
+    match multiplexer {
+        TerminalMultiplexer::None => osc,
+        TerminalMultiplexer::Tmux => format!("\x1bPtmux;{}\x1b\\", osc.replace('\x1b', "\x1b\x1b")),
+        TerminalMultiplexer::Screen => format!("\x1bP{osc}\x1b\\"),
+    }
+}
+
+pub fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    stdout.write_all(osc52_sequence(text, detect_multiplexer()).as_bytes())?;
+    stdout.flush()
+}
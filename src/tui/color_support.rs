@@ -0,0 +1,243 @@
+use crate::config::load::ColorMode;
+use ratatui::buffer::Buffer;
+use ratatui::style::{Color, Modifier, Style};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorSupport {
+    Monochrome,
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColorEnvironment {
+    pub colorterm: Option<String>,
+    pub term: Option<String>,
+    pub no_color: bool,
+}
+
+impl ColorEnvironment {
+    pub fn from_env() -> Self {
+        Self {
+            colorterm: std::env::var("COLORTERM").ok(),
+            term: std::env::var("TERM").ok(),
+            // https://no-color.org: any non-empty value disables color.
+            no_color: std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()),
+        }
+    }
+}
+
+/// Color depth advertised by `COLORTERM`/`TERM`, ignoring `NO_COLOR`.
+pub fn detect_color_support(env: &ColorEnvironment) -> ColorSupport {
+    let colorterm = env.colorterm.as_deref().unwrap_or_default();
+    if matches!(
+        colorterm.to_ascii_lowercase().as_str(),
+        "truecolor" | "24bit"
+    ) {
+        return ColorSupport::TrueColor;
+    }
+    let term = env.term.as_deref().unwrap_or_default().to_ascii_lowercase();
+    if term.is_empty() || term == "dumb" {
+        return ColorSupport::Monochrome;
+    }
+    if term.contains("truecolor") || term.contains("24bit") || term.ends_with("-direct") {
+        return ColorSupport::TrueColor;
+    }
+    if term.contains("256color") {
+        return ColorSupport::Ansi256;
+    }
+    ColorSupport::Ansi16
+}
+
+/// Resolves the `color` setting against the environment. `auto` honors
+/// `NO_COLOR` and non-terminal output; `always` forces at least 16 colors.
+pub fn resolve_color_support(
+    mode: ColorMode,
+    is_terminal: bool,
+    env: &ColorEnvironment,
+) -> ColorSupport {
+    match mode {
+        ColorMode::Never => ColorSupport::Monochrome,
+        ColorMode::Auto if !is_terminal || env.no_color => ColorSupport::Monochrome,
+        ColorMode::Auto => detect_color_support(env),
+        ColorMode::Always => detect_color_support(env).max(ColorSupport::Ansi16),
+    }
+}
+
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+const ANSI16: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).unsigned_abs().pow(2);
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// RGB value of an xterm 256-color palette entry.
+pub fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => {
+            const BASE: [(u8, u8, u8); 16] = [
+                (0, 0, 0),
+                (205, 0, 0),
+                (0, 205, 0),
+                (205, 205, 0),
+                (0, 0, 238),
+                (205, 0, 205),
+                (0, 205, 205),
+                (229, 229, 229),
+                (127, 127, 127),
+                (255, 0, 0),
+                (0, 255, 0),
+                (255, 255, 0),
+                (92, 92, 255),
+                (255, 0, 255),
+                (0, 255, 255),
+                (255, 255, 255),
+            ];
+            BASE[index as usize]
+        }
+        16..=231 => {
+            let value = index - 16;
+            (
+                CUBE_LEVELS[(value / 36) as usize],
+                CUBE_LEVELS[((value / 6) % 6) as usize],
+                CUBE_LEVELS[(value % 6) as usize],
+            )
+        }
+        _ => {
+            let gray = 8 + (index - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// Nearest xterm 256-color index, picking between the 6x6x6 cube and the
+/// grayscale ramp.
+pub fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    let nearest_level = |v: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - v as i32).abs())
+            .unwrap_or(0) as u8
+    };
+    let (ri, gi, bi) = (nearest_level(r), nearest_level(g), nearest_level(b));
+    let cube = 16 + 36 * ri + 6 * gi + bi;
+
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray = 232 + gray_step;
+
+    let target = (r, g, b);
+    if distance(indexed_rgb(gray), target) < distance(indexed_rgb(cube), target) {
+        gray
+    } else {
+        cube
+    }
+}
+
+/// Nearest of the 16 basic colors. Plain RGB distance sends most muted
+/// syntax colors to gray, so this buckets by hue and brightness instead.
+pub fn rgb_to_ansi16(r: u8, g: u8, b: u8) -> Color {
+    let max = r.max(g).max(b) as f32;
+    let min = r.min(g).min(b) as f32;
+    let value = max / 255.0;
+    let saturation = if max == 0.0 { 0.0 } else { (max - min) / max };
+    if saturation < 0.25 {
+        return match value {
+            v if v < 0.25 => Color::Black,
+            v if v < 0.6 => Color::DarkGray,
+            v if v < 0.85 => Color::Gray,
+            _ => Color::White,
+        };
+    }
+
+    let (rf, gf, bf) = (r as f32, g as f32, b as f32);
+    let delta = max - min;
+    let hue = if max == rf {
+        60.0 * ((gf - bf) / delta).rem_euclid(6.0)
+    } else if max == gf {
+        60.0 * ((bf - rf) / delta + 2.0)
+    } else {
+        60.0 * ((rf - gf) / delta + 4.0)
+    };
+    let bright = value > 0.85;
+    let sector = (((hue + 30.0) / 60.0) as usize) % 6;
+    let (normal, light) = [
+        (Color::Red, Color::LightRed),
+        (Color::Yellow, Color::LightYellow),
+        (Color::Green, Color::LightGreen),
+        (Color::Cyan, Color::LightCyan),
+        (Color::Blue, Color::LightBlue),
+        (Color::Magenta, Color::LightMagenta),
+    ][sector];
+    if bright {
+        light
+    } else {
+        normal
+    }
+}
+
+/// Maps a color into what `support` can display; `None` means "no color".
+pub fn adapt_color(color: Color, support: ColorSupport) -> Option<Color> {
+    match (support, color) {
+        (ColorSupport::Monochrome, _) => None,
+        (ColorSupport::TrueColor, _) => Some(color),
+        (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => {
+            Some(Color::Indexed(rgb_to_ansi256(r, g, b)))
+        }
+        (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => Some(rgb_to_ansi16(r, g, b)),
+        (ColorSupport::Ansi16, Color::Indexed(index)) if index < 16 => Some(ANSI16[index as usize]),
+        (ColorSupport::Ansi16, Color::Indexed(index)) => {
+            let (r, g, b) = indexed_rgb(index);
+            Some(rgb_to_ansi16(r, g, b))
+        }
+        _ => Some(color),
+    }
+}
+
+/// Adapts a style's colors to `support`. Without color, a background is
+/// replaced by reverse video so selections and bars stay visible.
+pub fn adapt_style(style: Style, support: ColorSupport) -> Style {
+    if support == ColorSupport::TrueColor {
+        return style;
+    }
+    let mut adapted = style;
+    adapted.fg = style.fg.and_then(|c| adapt_color(c, support));
+    adapted.bg = style.bg.and_then(|c| adapt_color(c, support));
+    if support == ColorSupport::Monochrome && style.bg.is_some_and(|bg| bg != Color::Reset) {
+        adapted = adapted.add_modifier(Modifier::REVERSED);
+    }
+    adapted
+}
+
+/// Applies [`adapt_style`] to every cell of a rendered frame, so tree,
+/// preview and status bar all degrade the same way.
+pub fn adapt_buffer(buffer: &mut Buffer, support: ColorSupport) {
+    if support == ColorSupport::TrueColor {
+        return;
+    }
+    for cell in buffer.content.iter_mut() {
+        let style = adapt_style(cell.style(), support);
+        cell.fg = style.fg.unwrap_or(Color::Reset);
+        cell.bg = style.bg.unwrap_or(Color::Reset);
+        cell.modifier = style.add_modifier;
+    }
+}
@@ -1,15 +1,26 @@
+use crate::app::cli::LineRange;
+use crate::app::clipboard::{
+    copy_confirmation, copy_text_for_target, preview_selection_text, CopyTarget,
+};
 use crate::app::focus::switch_focus;
 use crate::app::navigation::{
-    enter_selected_directory, format_status_with_path, go_to_parent_directory, move_down, move_up,
-    toggle_hidden_visibility,
+    enter_selected_directory, format_status_with_path, go_to_parent_directory, is_enterable,
+    move_down, move_up, toggle_hidden_visibility,
 };
-use crate::app::state::{FocusPane, NodeType, SessionState, TreeNode};
+use crate::app::preview_controller::PAGED_WRAP_NOTICE;
+use crate::app::print::{default_export_dir, export_html_file};
+use crate::app::selection_pipe::{start_pipe_selection, SELECTION_PIPE_TIMEOUT};
+use crate::app::state::{FocusPane, NodeType, PreviewDocument, SessionState, TreeNode};
 use crate::app::status::navigation_status_message;
 use crate::config::keymap::Action;
 use crate::fs::current_dir::is_filesystem_root;
+use crate::fs::table::TablePreview;
+use crate::highlight::structured::StructuredView;
+use crate::tui::clipboard::copy_to_clipboard;
 use crate::tui::input::map_key_to_action;
+use crate::tui::preview_pane::preview_header_rows;
 use anyhow::Result;
-use crossterm::event::{self, Event, MouseButton, MouseEvent, MouseEventKind};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
 use ratatui::layout::Rect;
 use std::collections::HashMap;
 use std::time::Duration;
@@ -85,6 +96,203 @@ fn tree_index_for_click(
     }
 }
 
+fn copy_target_for_action(action: Action) -> Option<CopyTarget> {
+    match action {
+        Action::CopyAbsolutePath => Some(CopyTarget::AbsolutePath),
+        Action::CopyRelativePath => Some(CopyTarget::RelativePath),
+        Action::CopyFileName => Some(CopyTarget::FileName),
+        Action::CopyPreviewSelection => Some(CopyTarget::PreviewSelection),
+        Action::CopySelectionReference => Some(CopyTarget::SelectionReference),
+        _ => None,
+    }
+}
+
+fn apply_copy_action(state: &mut SessionState, preview: &PreviewDocument, target: CopyTarget) {
+    let Some(text) = copy_text_for_target(state, preview, target) else {
+        state.status_notice = Some(format!("Nothing to copy for {}.", target.label()));
+        return;
+    };
+    state.status_notice = Some(match copy_to_clipboard(&text) {
+        Ok(()) => copy_confirmation(target, &text),
+        Err(err) => format!("Clipboard write failed: {err}"),
+    });
+}
+
+fn apply_pipe_selection(state: &mut SessionState, preview: &PreviewDocument) {
+    let Some(command) = state.selection_pipe_command.clone() else {
+        state.status_notice = Some("No selection_pipe_command configured.".to_string());
+        return;
+    };
+    let Some(text) = preview_selection_text(preview, state.preview_selection_range()) else {
+        state.status_notice = Some("Nothing to pipe.".to_string());
+        return;
+    };
+    if let Some(running) = &state.selection_pipe {
+        state.status_notice = Some(format!("`{}` is still running.", running.command));
+        return;
+    }
+    let line_count = text.split('\n').count();
+    let suffix = if line_count == 1 { "" } else { "s" };
+    state.status_notice = Some(format!("Piping {line_count} line{suffix} to `{command}`…"));
+    state.selection_pipe = Some(start_pipe_selection(
+        &command,
+        &text,
+        SELECTION_PIPE_TIMEOUT,
+    ));
+}
+
+fn apply_export_html(state: &mut SessionState, preview: &PreviewDocument) {
+    if !preview.source_path.is_file() {
+        state.status_notice = Some("Select a file to export.".to_string());
+        return;
+    }
+    let range = state
+        .preview_selection_range()
+        .map(|(start, end)| LineRange {
+            start: start + 1,
+            end: Some(end + 1),
+        });
+    state.status_notice = Some(
+        match default_export_dir().and_then(|dir| {
+            export_html_file(
+                preview,
+                range,
+                state.preview_show_line_numbers,
+                state.page_colors,
+                &dir,
+            )
+        }) {
+            Ok(path) => format!("Exported HTML: {}", path.display()),
+            Err(err) => format!("HTML export failed: {err}"),
+        },
+    );
+}
+
+/// Forces the next encoding for the previewed file. Returns whether the
+/// preview must be reloaded.
+fn apply_cycle_encoding(state: &mut SessionState, preview: &PreviewDocument) -> bool {
+    let Some(format) = preview.text_format else {
+        state.status_notice = Some("No text encoding to change.".to_string());
+        return false;
+    };
+    let path = preview.source_path.clone();
+    state.status_notice = Some(
+        match state.cycle_forced_encoding(&path, Some(format.encoding)) {
+            Some(encoding) => format!("Encoding: {encoding} (forced)."),
+            None => "Encoding: detected.".to_string(),
+        },
+    );
+    true
+}
+
+fn preview_has_focus(state: &SessionState) -> bool {
+    state.preview_fullscreen || state.focus_pane == FocusPane::Preview
+}
+
+fn move_visual_cursor(
+    state: &mut SessionState,
+    preview: &PreviewDocument,
+    delta: isize,
+    preview_viewport_rows: usize,
+) {
+    state.move_preview_cursor(delta, preview.source_line_count());
+    state.ensure_preview_cursor_visible_with_rows(
+        preview_header_rows(preview),
+        preview_viewport_rows,
+        preview.wrapped_rows.as_ref(),
+    );
+}
+
+/// The structured view of the preview while it has focus; the cursor keys
+/// then move between its rows and fold them.
+fn focused_structured_view<'a>(
+    state: &SessionState,
+    preview: &'a PreviewDocument,
+) -> Option<&'a StructuredView> {
+    if !state.preview_structured || !preview_has_focus(state) {
+        return None;
+    }
+    preview.structured.as_ref()?.as_ref().ok()
+}
+
+/// Unfolds (`expand`) or folds the node under the cursor. Expanding an open
+/// node steps into its first child; collapsing a folded node or a leaf steps
+/// out to its parent.
+fn apply_structured_fold(
+    state: &mut SessionState,
+    preview: &PreviewDocument,
+    view: &StructuredView,
+    expand: bool,
+    preview_viewport_rows: usize,
+) {
+    let cursor = state.preview_cursor_line;
+    let Some(row) = view.rows.get(cursor) else {
+        return;
+    };
+    if row.foldable && row.collapsed == expand {
+        state.set_structured_fold(&preview.source_path, &row.path, !expand);
+    } else if expand {
+        if row.foldable {
+            move_visual_cursor(state, preview, 1, preview_viewport_rows);
+        }
+    } else if let Some(parent) = view.parent_row(cursor) {
+        state.preview_cursor_line = parent;
+        state.ensure_preview_cursor_visible(preview_header_rows(preview), preview_viewport_rows);
+    }
+}
+
+/// The table in the preview while it has focus; Expand/Collapse then scroll
+/// it a column at a time.
+fn focused_table<'a>(
+    state: &SessionState,
+    preview: &'a PreviewDocument,
+) -> Option<&'a TablePreview> {
+    preview.table.as_ref().filter(|_| preview_has_focus(state))
+}
+
+fn apply_go_to_line(state: &mut SessionState, preview: &PreviewDocument, input: &str) {
+    let Ok(line) = input.trim().parse::<usize>() else {
+        state.status_notice = Some(format!("Invalid line number: {input}"));
+        return;
+    };
+    let total = preview.source_line_count();
+    state.status_notice = Some(
+        match state.go_to_preview_line_with_rows(
+            line,
+            total,
+            preview_header_rows(preview),
+            preview.wrapped_rows.as_ref(),
+        ) {
+            Some(target) if target == line.max(1) => format!("Line {target}."),
+            Some(target) => format!("Line {target} (last line of {total})."),
+            None => "Nothing to jump to in this preview.".to_string(),
+        },
+    );
+}
+
+/// Consumes key input while the go-to-line prompt is open.
+fn handle_line_prompt_key(state: &mut SessionState, preview: &PreviewDocument, key: KeyEvent) {
+    let Some(input) = state.line_prompt.as_mut() else {
+        return;
+    };
+    match key.code {
+        KeyCode::Char(ch) if ch.is_ascii_digit() => input.push(ch),
+        KeyCode::Backspace => {
+            input.pop();
+        }
+        KeyCode::Enter => {
+            let input = state.line_prompt.take().unwrap_or_default();
+            if !input.is_empty() {
+                apply_go_to_line(state, preview, &input);
+            }
+        }
+        KeyCode::Esc => {
+            state.line_prompt = None;
+        }
+        _ => {}
+    }
+}
+
 fn can_enter_fullscreen_preview(state: &SessionState, nodes: &[TreeNode]) -> bool {
     nodes
         .get(state.selected_index)
@@ -96,16 +304,43 @@ pub fn process_once(
     state: &mut SessionState,
     nodes: &mut Vec<TreeNode>,
     bindings: &HashMap<Action, crossterm::event::KeyEvent>,
+    preview: &PreviewDocument,
     preview_total_lines: usize,
     preview_viewport_rows: usize,
 ) -> Result<(bool, bool)> {
     if !event::poll(Duration::from_millis(50))? {
         return Ok((false, false));
     }
+    handle_event(
+        state,
+        nodes,
+        bindings,
+        preview,
+        preview_total_lines,
+        preview_viewport_rows,
+        event::read()?,
+    )
+}
 
+/// Applies one terminal event; returns whether to quit and whether the
+/// preview must be reloaded.
+pub fn handle_event(
+    state: &mut SessionState,
+    nodes: &mut Vec<TreeNode>,
+    bindings: &HashMap<Action, crossterm::event::KeyEvent>,
+    preview: &PreviewDocument,
+    preview_total_lines: usize,
+    preview_viewport_rows: usize,
+    event: Event,
+) -> Result<(bool, bool)> {
     let mut should_refresh_preview = false;
-    match event::read()? {
+    match event {
         Event::Key(key) => {
+            state.status_notice = None;
+            if state.line_prompt.is_some() {
+                handle_line_prompt_key(state, preview, key);
+                return Ok((false, false));
+            }
             if let Some(action) = map_key_to_action(key, bindings) {
                 match action {
                     Action::ToggleHelp => {
@@ -115,7 +350,11 @@ pub fn process_once(
                         if state.help_overlay_visible {
                             return Ok((false, false));
                         }
-                        if state.preview_fullscreen {
+                        if state.visual_selection_active()
+                            || focused_structured_view(state, preview).is_some()
+                        {
+                            move_visual_cursor(state, preview, -1, preview_viewport_rows);
+                        } else if state.preview_fullscreen {
                             state.scroll_preview_lines(
                                 -1,
                                 preview_total_lines,
@@ -132,7 +371,11 @@ pub fn process_once(
                         if state.help_overlay_visible {
                             return Ok((false, false));
                         }
-                        if state.preview_fullscreen {
+                        if state.visual_selection_active()
+                            || focused_structured_view(state, preview).is_some()
+                        {
+                            move_visual_cursor(state, preview, 1, preview_viewport_rows);
+                        } else if state.preview_fullscreen {
                             state.scroll_preview_lines(
                                 1,
                                 preview_total_lines,
@@ -149,7 +392,18 @@ pub fn process_once(
                         if state.help_overlay_visible {
                             return Ok((false, false));
                         }
-                        if !state.preview_fullscreen {
+                        if let Some(view) = focused_structured_view(state, preview) {
+                            apply_structured_fold(
+                                state,
+                                preview,
+                                view,
+                                true,
+                                preview_viewport_rows,
+                            );
+                        } else if let Some(table) = focused_table(state, preview) {
+                            state.preview_table_column = (state.preview_table_column + 1)
+                                .min(table.column_count().saturating_sub(1));
+                        } else if !state.preview_fullscreen {
                             let result = enter_selected_directory(state, nodes)?;
                             state.status_message = format_status_with_path(
                                 &navigation_status_message(&result),
@@ -163,7 +417,20 @@ pub fn process_once(
                         if state.help_overlay_visible {
                             return Ok((false, false));
                         }
-                        if !state.preview_fullscreen && !is_filesystem_root(&state.current_path) {
+                        if let Some(view) = focused_structured_view(state, preview) {
+                            apply_structured_fold(
+                                state,
+                                preview,
+                                view,
+                                false,
+                                preview_viewport_rows,
+                            );
+                        } else if focused_table(state, preview).is_some() {
+                            state.preview_table_column =
+                                state.preview_table_column.saturating_sub(1);
+                        } else if !state.preview_fullscreen
+                            && !is_filesystem_root(&state.current_path)
+                        {
                             let result = go_to_parent_directory(state, nodes)?;
                             state.status_message = format_status_with_path(
                                 &navigation_status_message(&result),
@@ -179,9 +446,7 @@ pub fn process_once(
                         }
                         if !state.preview_fullscreen {
                             if let Some(node) = nodes.get(state.selected_index) {
-                                if state.focus_pane == FocusPane::Tree
-                                    && node.node_type == NodeType::Directory
-                                {
+                                if state.focus_pane == FocusPane::Tree && is_enterable(node) {
                                     let result = enter_selected_directory(state, nodes)?;
                                     state.status_message = format_status_with_path(
                                         &navigation_status_message(&result),
@@ -201,6 +466,8 @@ pub fn process_once(
                     Action::ExitFullscreenPreview => {
                         if state.help_overlay_visible {
                             state.help_overlay_visible = false;
+                        } else if state.visual_selection_active() {
+                            state.cancel_visual_selection();
                         } else if state.preview_fullscreen {
                             state.preview_fullscreen = false;
                         }
@@ -211,13 +478,19 @@ pub fn process_once(
                         }
                         if !state.preview_fullscreen {
                             switch_focus(state);
+                            state.cancel_visual_selection();
                         }
                     }
                     Action::PageUp => {
                         if state.help_overlay_visible {
                             return Ok((false, false));
                         }
-                        if state.preview_fullscreen || state.focus_pane == FocusPane::Preview {
+                        if state.visual_selection_active()
+                            || focused_structured_view(state, preview).is_some()
+                        {
+                            let page = preview_viewport_rows.max(1) as isize;
+                            move_visual_cursor(state, preview, -page, preview_viewport_rows);
+                        } else if preview_has_focus(state) {
                             state
                                 .page_scroll_preview_up(preview_total_lines, preview_viewport_rows);
                         }
@@ -226,7 +499,12 @@ pub fn process_once(
                         if state.help_overlay_visible {
                             return Ok((false, false));
                         }
-                        if state.preview_fullscreen || state.focus_pane == FocusPane::Preview {
+                        if state.visual_selection_active()
+                            || focused_structured_view(state, preview).is_some()
+                        {
+                            let page = preview_viewport_rows.max(1) as isize;
+                            move_visual_cursor(state, preview, page, preview_viewport_rows);
+                        } else if preview_has_focus(state) {
                             state.page_scroll_preview_down(
                                 preview_total_lines,
                                 preview_viewport_rows,
@@ -237,7 +515,7 @@ pub fn process_once(
                         if state.help_overlay_visible {
                             return Ok((false, false));
                         }
-                        if state.preview_fullscreen || state.focus_pane == FocusPane::Preview {
+                        if preview_has_focus(state) {
                             state.scroll_preview_lines(
                                 -3,
                                 preview_total_lines,
@@ -249,7 +527,7 @@ pub fn process_once(
                         if state.help_overlay_visible {
                             return Ok((false, false));
                         }
-                        if state.preview_fullscreen || state.focus_pane == FocusPane::Preview {
+                        if preview_has_focus(state) {
                             state.scroll_preview_lines(
                                 3,
                                 preview_total_lines,
@@ -268,6 +546,34 @@ pub fn process_once(
                             return Ok((false, false));
                         }
                         state.preview_wrap_enabled = !state.preview_wrap_enabled;
+                        if state.preview_wrap_enabled && preview.paged.is_some() {
+                            state.status_notice = Some(PAGED_WRAP_NOTICE.to_string());
+                        }
+                    }
+                    Action::ToggleMarkdownRender => {
+                        if state.help_overlay_visible {
+                            return Ok((false, false));
+                        }
+                        state.preview_render_markdown = !state.preview_render_markdown;
+                        // Rendered and source lines differ, so start from the top.
+                        state.reset_preview_scroll();
+                        should_refresh_preview = true;
+                    }
+                    Action::ToggleStructuredView => {
+                        if state.help_overlay_visible {
+                            return Ok((false, false));
+                        }
+                        state.preview_structured = !state.preview_structured;
+                        state.reset_preview_scroll();
+                        should_refresh_preview = true;
+                    }
+                    Action::ToggleTableView => {
+                        if state.help_overlay_visible {
+                            return Ok((false, false));
+                        }
+                        state.preview_table_view = !state.preview_table_view;
+                        state.reset_preview_scroll();
+                        should_refresh_preview = true;
                     }
                     Action::ToggleHidden => {
                         if state.help_overlay_visible {
@@ -303,6 +609,58 @@ pub fn process_once(
                             should_refresh_preview = true;
                         }
                     }
+                    Action::ToggleVisualSelection => {
+                        if state.help_overlay_visible {
+                            return Ok((false, false));
+                        }
+                        if state.visual_selection_active() {
+                            state.cancel_visual_selection();
+                        } else if preview_has_focus(state) {
+                            state.start_visual_selection_with_rows(
+                                preview.source_line_count(),
+                                preview_header_rows(preview),
+                                preview.wrapped_rows.as_ref(),
+                            );
+                        }
+                    }
+                    Action::GoToLine => {
+                        if state.help_overlay_visible {
+                            return Ok((false, false));
+                        }
+                        if preview.source_line_count() > 0 {
+                            state.line_prompt = Some(String::new());
+                        }
+                    }
+                    Action::ExportHtml => {
+                        if state.help_overlay_visible {
+                            return Ok((false, false));
+                        }
+                        apply_export_html(state, preview);
+                    }
+                    Action::CycleEncoding => {
+                        if state.help_overlay_visible {
+                            return Ok((false, false));
+                        }
+                        should_refresh_preview = apply_cycle_encoding(state, preview);
+                    }
+                    Action::PipeSelection => {
+                        if state.help_overlay_visible {
+                            return Ok((false, false));
+                        }
+                        apply_pipe_selection(state, preview);
+                    }
+                    Action::CopyAbsolutePath
+                    | Action::CopyRelativePath
+                    | Action::CopyFileName
+                    | Action::CopyPreviewSelection
+                    | Action::CopySelectionReference => {
+                        if state.help_overlay_visible {
+                            return Ok((false, false));
+                        }
+                        if let Some(target) = copy_target_for_action(action) {
+                            apply_copy_action(state, preview, target);
+                        }
+                    }
                     Action::Quit => return Ok((true, false)),
                 }
             }
@@ -322,10 +680,7 @@ pub fn process_once(
                     state.reset_preview_scroll();
                     should_refresh_preview = true;
 
-                    if nodes
-                        .get(index)
-                        .is_some_and(|node| node.node_type == NodeType::Directory)
-                    {
+                    if nodes.get(index).is_some_and(is_enterable) {
                         let result = enter_selected_directory(state, nodes)?;
                         state.status_message = format_status_with_path(
                             &navigation_status_message(&result),
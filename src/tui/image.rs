@@ -0,0 +1,382 @@
+use crate::config::load::ImageProtocolMode;
+use crate::fs::image::ImagePreview;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use color_quant::NeuQuant;
+use crossterm::cursor::MoveTo;
+use crossterm::queue;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use image::{Rgba, RgbaImage};
+use ratatui::backend::CrosstermBackend;
+use ratatui::buffer::{Buffer, Cell};
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use ratatui::Terminal;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Cell size in pixels assumed when the terminal does not report one.
+const DEFAULT_CELL_SIZE: (u16, u16) = (8, 16);
+/// Base64 bytes sent per kitty graphics escape.
+const KITTY_CHUNK: usize = 4096;
+/// Pixels with less alpha are left transparent.
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// How images are drawn in the preview pane.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImageProtocol {
+    /// `▀` cells with truecolor foreground and background, two pixels a
+    /// cell; works in any color terminal.
+    #[default]
+    HalfBlocks,
+    Kitty,
+    Sixel,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImageEnvironment {
+    pub term: Option<String>,
+    pub term_program: Option<String>,
+    pub kitty_window_id: bool,
+    pub tmux: bool,
+}
+
+impl ImageEnvironment {
+    pub fn from_env() -> Self {
+        Self {
+            term: std::env::var("TERM").ok(),
+            term_program: std::env::var("TERM_PROGRAM").ok(),
+            kitty_window_id: std::env::var_os("KITTY_WINDOW_ID").is_some(),
+            tmux: std::env::var_os("TMUX").is_some(),
+        }
+    }
+}
+
+/// Graphics protocol advertised by `TERM`/`TERM_PROGRAM`, half blocks when
+/// there is none. tmux passes neither protocol through by default.
+pub fn detect_image_protocol(env: &ImageEnvironment) -> ImageProtocol {
+    if env.tmux {
+        return ImageProtocol::HalfBlocks;
+    }
+    let term = env.term.as_deref().unwrap_or_default().to_ascii_lowercase();
+    let program = env
+        .term_program
+        .as_deref()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if env.kitty_window_id
+        || matches!(term.as_str(), "xterm-kitty" | "xterm-ghostty")
+        || matches!(program.as_str(), "ghostty" | "wezterm")
+    {
+        ImageProtocol::Kitty
+    } else if term.contains("sixel")
+        || term.starts_with("foot")
+        || term.starts_with("mlterm")
+        || program == "mlterm"
+    {
+        ImageProtocol::Sixel
+    } else {
+        ImageProtocol::HalfBlocks
+    }
+}
+
+/// Resolves the `image_protocol` setting against the environment.
+pub fn resolve_image_protocol(mode: ImageProtocolMode, env: &ImageEnvironment) -> ImageProtocol {
+    match mode {
+        ImageProtocolMode::Auto => detect_image_protocol(env),
+        ImageProtocolMode::Halfblocks => ImageProtocol::HalfBlocks,
+        ImageProtocolMode::Kitty => ImageProtocol::Kitty,
+        ImageProtocolMode::Sixel => ImageProtocol::Sixel,
+    }
+}
+
+/// `width`×`height` scaled to fit `max_width`×`max_height` with its aspect
+/// ratio kept; `(0, 0)` when either is empty.
+pub fn fit_size(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    if width == 0 || height == 0 || max_width == 0 || max_height == 0 {
+        return (0, 0);
+    }
+    let scale =
+        (f64::from(max_width) / f64::from(width)).min(f64::from(max_height) / f64::from(height));
+    let fit = |side: u32, max: u32| ((f64::from(side) * scale).round() as u32).clamp(1, max);
+    (fit(width, max_width), fit(height, max_height))
+}
+
+fn opaque_color(pixel: &Rgba<u8>) -> Option<Color> {
+    (pixel[3] >= ALPHA_THRESHOLD).then(|| Color::Rgb(pixel[0], pixel[1], pixel[2]))
+}
+
+/// Rows of `▀`/`▄` cells drawing `image` scaled to fit `cols`×`rows`
+/// cells. Each cell holds two pixels, so cells twice as tall as wide keep
+/// the picture's proportions.
+pub fn half_block_lines(image: &ImagePreview, cols: u16, rows: u16) -> Vec<Line<'static>> {
+    let (width, height) = fit_size(
+        image.pixels.width(),
+        image.pixels.height(),
+        cols.into(),
+        u32::from(rows) * 2,
+    );
+    if width == 0 {
+        return Vec::new();
+    }
+    let scaled = image.scaled(width, height);
+    (0..height.div_ceil(2))
+        .map(|row| {
+            let spans = (0..width)
+                .map(|x| {
+                    let top = opaque_color(scaled.get_pixel(x, row * 2));
+                    let bottom = (row * 2 + 1 < height)
+                        .then(|| opaque_color(scaled.get_pixel(x, row * 2 + 1)))
+                        .flatten();
+                    match (top, bottom) {
+                        (Some(top), Some(bottom)) => {
+                            Span::styled("▀", Style::default().fg(top).bg(bottom))
+                        }
+                        (Some(top), None) => Span::styled("▀", Style::default().fg(top)),
+                        (None, Some(bottom)) => Span::styled("▄", Style::default().fg(bottom)),
+                        (None, None) => Span::raw(" "),
+                    }
+                })
+                .collect::<Vec<_>>();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Pixel size of `image` fitted to `cols`×`rows` cells of `cell` pixels.
+fn fit_to_cells(image: &ImagePreview, cols: u16, rows: u16, cell: (u16, u16)) -> (u32, u32) {
+    fit_size(
+        image.pixels.width(),
+        image.pixels.height(),
+        u32::from(cols) * u32::from(cell.0),
+        u32::from(rows) * u32::from(cell.1),
+    )
+}
+
+/// Kitty graphics escapes drawing `image` at the cursor, fitted to
+/// `cols`×`rows` cells. Pixels are sent zlib-compressed, and the terminal is
+/// asked not to reply.
+pub fn kitty_sequence(image: &ImagePreview, cols: u16, rows: u16, cell: (u16, u16)) -> String {
+    let (width, height) = fit_to_cells(image, cols, rows, cell);
+    if width == 0 {
+        return String::new();
+    }
+    let scaled = image.scaled(width, height);
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+    // Writing to a Vec cannot fail.
+    let _ = encoder.write_all(scaled.as_raw());
+    let payload = STANDARD.encode(encoder.finish().unwrap_or_default());
+    let chunks = payload.as_bytes().chunks(KITTY_CHUNK).collect::<Vec<_>>();
+    let mut out = String::new();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(index + 1 < chunks.len());
+        let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+        if index == 0 {
+            let _ = write!(
+                out,
+                "\x1b_Ga=T,f=32,o=z,s={width},v={height},C=1,q=2,m={more};{chunk}\x1b\\"
+            );
+        } else {
+            let _ = write!(out, "\x1b_Gm={more};{chunk}\x1b\\");
+        }
+    }
+    out
+}
+
+/// Kitty graphics escape deleting every image on screen.
+pub fn kitty_delete_sequence() -> &'static str {
+    "\x1b_Ga=d,d=A,q=2\x1b\\"
+}
+
+fn sixel_run(out: &mut String, bits: u8, count: usize) {
+    let ch = char::from(63 + bits);
+    if count > 3 {
+        let _ = write!(out, "!{count}{ch}");
+    } else {
+        out.extend(std::iter::repeat_n(ch, count));
+    }
+}
+
+/// Sixel escape drawing `image` at the cursor, fitted to `cols`×`rows`
+/// cells and reduced to a 256-color palette. Its height is cut to a
+/// multiple of six pixels so it never spills below the area.
+pub fn sixel_sequence(image: &ImagePreview, cols: u16, rows: u16, cell: (u16, u16)) -> String {
+    let rows_px = u32::from(rows) * u32::from(cell.1) / 6 * 6;
+    let (width, height) = fit_size(
+        image.pixels.width(),
+        image.pixels.height(),
+        u32::from(cols) * u32::from(cell.0),
+        rows_px,
+    );
+    if width == 0 || height < 6 {
+        return String::new();
+    }
+    let height = height / 6 * 6;
+    let scaled = image.scaled(width, height);
+    sixel_encode(&scaled)
+}
+
+fn sixel_encode(image: &RgbaImage) -> String {
+    let (width, height) = image.dimensions();
+    let quant = NeuQuant::new(10, 256, image.as_raw());
+    let indices = image
+        .pixels()
+        .map(|pixel| (pixel[3] >= ALPHA_THRESHOLD).then(|| quant.index_of(&pixel.0) as u8))
+        .collect::<Vec<_>>();
+
+    // Transparent pixels keep the background (P2 = 1).
+    let mut out = format!("\x1bP0;1;0q\"1;1;{width};{height}");
+    for (index, rgb) in quant.color_map_rgb().chunks(3).enumerate() {
+        let percent = |value: u8| u32::from(value) * 100 / 255;
+        let _ = write!(
+            out,
+            "#{index};2;{};{};{}",
+            percent(rgb[0]),
+            percent(rgb[1]),
+            percent(rgb[2])
+        );
+    }
+    let width = width as usize;
+    for band in (0..height as usize).step_by(6) {
+        let band_rows = 6.min(height as usize - band);
+        let mut used = [false; 256];
+        for row in band..band + band_rows {
+            for index in indices[row * width..(row + 1) * width].iter().flatten() {
+                used[usize::from(*index)] = true;
+            }
+        }
+        for color in (0..256).filter(|color| used[*color]) {
+            let _ = write!(out, "#{color}");
+            let mut run = (0_u8, 0_usize);
+            for x in 0..width {
+                let bits = (0..band_rows)
+                    .filter(|dy| indices[(band + dy) * width + x] == Some(color as u8))
+                    .fold(0_u8, |bits, dy| bits | 1 << dy);
+                if bits == run.0 {
+                    run.1 += 1;
+                } else {
+                    sixel_run(&mut out, run.0, run.1);
+                    run = (bits, 1);
+                }
+            }
+            sixel_run(&mut out, run.0, run.1);
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+/// Draws preview images with a graphics protocol. The images sit over
+/// cells ratatui leaves blank, so they are drawn once after a frame and
+/// removed before the frame that no longer wants them.
+pub struct ImageGraphics {
+    protocol: ImageProtocol,
+    cell_size: (u16, u16),
+    shown: Option<(PathBuf, Rect)>,
+    /// Area of a removed sixel image whose cells are written again after
+    /// the next frame.
+    stale: Option<Rect>,
+}
+
+impl ImageGraphics {
+    pub fn new(protocol: ImageProtocol) -> Self {
+        let cell_size = crossterm::terminal::window_size()
+            .ok()
+            .filter(|size| size.columns > 0 && size.rows > 0)
+            .map(|size| (size.width / size.columns, size.height / size.rows))
+            .filter(|(width, height)| *width > 0 && *height > 0)
+            .unwrap_or(DEFAULT_CELL_SIZE);
+        Self {
+            protocol,
+            cell_size,
+            shown: None,
+            stale: None,
+        }
+    }
+
+    /// Removes the image on screen unless it is `wanted` at the same place.
+    /// Sixel pixels stay until text is written over them, which
+    /// [`ImageGraphics::take_stale_cells`] finds for the next frame.
+    pub fn clear_stale<W: Write>(
+        &mut self,
+        terminal: &mut Terminal<CrosstermBackend<W>>,
+        wanted: Option<(&Path, Rect)>,
+    ) -> io::Result<()> {
+        let Some((path, area)) = &self.shown else {
+            return Ok(());
+        };
+        if wanted == Some((path.as_path(), *area)) {
+            return Ok(());
+        }
+        match self.protocol {
+            ImageProtocol::Kitty => {
+                let backend = terminal.backend_mut();
+                backend.write_all(kitty_delete_sequence().as_bytes())?;
+                backend.flush()?;
+            }
+            ImageProtocol::Sixel => self.stale = Some(*area),
+            ImageProtocol::HalfBlocks => {}
+        }
+        self.shown = None;
+        Ok(())
+    }
+
+    /// Cells of `buffer`, the frame just drawn, under a removed sixel image;
+    /// empty when none was removed. ratatui skips cells it believes
+    /// unchanged, so [`repaint_cells`] must write them over its pixels.
+    pub fn take_stale_cells(&mut self, buffer: &Buffer) -> Vec<(u16, u16, Cell)> {
+        let Some(area) = self.stale.take() else {
+            return Vec::new();
+        };
+        let area = area.intersection(buffer.area);
+        (area.top()..area.bottom())
+            .flat_map(|y| (area.left()..area.right()).map(move |x| (x, y)))
+            .map(|(x, y)| (x, y, buffer.get(x, y).clone()))
+            .collect()
+    }
+
+    /// Draws `image` of `path` into `area` unless it is already there.
+    pub fn show<W: Write>(
+        &mut self,
+        backend: &mut CrosstermBackend<W>,
+        path: &Path,
+        image: &ImagePreview,
+        area: Rect,
+    ) -> io::Result<()> {
+        if self.protocol == ImageProtocol::HalfBlocks
+            || self
+                .shown
+                .as_ref()
+                .is_some_and(|(shown, at)| shown == path && *at == area)
+        {
+            return Ok(());
+        }
+        let sequence = match self.protocol {
+            ImageProtocol::Kitty => kitty_sequence(image, area.width, area.height, self.cell_size),
+            ImageProtocol::Sixel => sixel_sequence(image, area.width, area.height, self.cell_size),
+            ImageProtocol::HalfBlocks => String::new(),
+        };
+        queue!(backend, MoveTo(area.x, area.y))?;
+        backend.write_all(sequence.as_bytes())?;
+        backend.flush()?;
+        self.shown = Some((path.to_path_buf(), area));
+        Ok(())
+    }
+}
+
+/// Writes `cells` to the terminal whatever ratatui last drew there.
+pub fn repaint_cells<W: Write>(
+    backend: &mut CrosstermBackend<W>,
+    cells: &[(u16, u16, Cell)],
+) -> io::Result<()> {
+    if cells.is_empty() {
+        return Ok(());
+    }
+    ratatui::backend::Backend::draw(backend, cells.iter().map(|(x, y, cell)| (*x, *y, cell)))?;
+    backend.flush()
+}
@@ -1,5 +1,8 @@
+pub mod clipboard;
+pub mod color_support;
 pub mod config_warnings;
 pub mod event_loop;
+pub mod image;
 pub mod input;
 pub mod preview_pane;
 pub mod status_bar;
@@ -1,14 +1,22 @@
 use crate::app::state::{
-    ContentType, LoadState, PreviewDocument, PreviewFallbackReason, SessionState,
+    ContentType, FocusPane, LoadState, PreviewDocument, PreviewFallbackReason, SessionState,
+    WrapLayout, WrappedRows,
 };
 use crate::config::load::ThemeProfile;
-use crate::tui::status_bar::compose_preview_metadata_line;
+use crate::fs::table::{ColumnAlignment, TablePreview};
+use crate::highlight::structured::StructuredView;
+use crate::tui::image::{half_block_lines, ImageProtocol};
+use crate::tui::status_bar::{
+    compose_preview_metadata_line, compose_preview_metadata_line_with_lines,
+};
 use ratatui::layout::Alignment;
-use ratatui::style::{Color, Style};
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span, Text};
-use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 use ratatui::Frame;
-use unicode_width::UnicodeWidthChar;
+use std::ops::Range;
+use std::time::Instant;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 pub fn preview_title_for_state(state: &SessionState) -> String {
     if state.selected_metadata.filename.trim().is_empty() || state.selected_metadata.filename == "-"
@@ -23,6 +31,35 @@ pub fn preview_border_metadata_for_state(state: &SessionState, width: usize) ->
     compose_preview_metadata_line(&state.selected_metadata, width)
 }
 
+/// Metadata line for `doc`, labelled with its detected highlight language,
+/// encoding, line endings, for tables and images their size and for paged
+/// files how many lines were counted.
+pub fn preview_border_metadata_for_doc(
+    state: &SessionState,
+    doc: &PreviewDocument,
+    width: usize,
+) -> String {
+    let language = doc.language_id.as_deref().zip(doc.language_reason);
+    let lines = doc
+        .paged
+        .as_ref()
+        .map(|paged| paged.file.line_count_label());
+    compose_preview_metadata_line_with_lines(
+        &state.selected_metadata,
+        language,
+        doc.text_format.as_ref(),
+        doc.table.as_ref(),
+        doc.image.as_ref(),
+        lines.as_deref(),
+        width,
+    )
+}
+
+/// Cells an image preview is drawn in: the pane inside its border.
+pub fn preview_image_area(area: ratatui::layout::Rect) -> ratatui::layout::Rect {
+    Block::default().borders(Borders::ALL).inner(area)
+}
+
 fn line_count(text: &str) -> usize {
     text.split('\n').count().max(1)
 }
@@ -40,7 +77,6 @@ fn plain_text_for_doc(doc: &PreviewDocument) -> String {
                     let reason_text = match reason {
                         PreviewFallbackReason::UnsupportedExtension => "unsupported-extension",
                         PreviewFallbackReason::EngineFailure => "highlight-failed",
-                        PreviewFallbackReason::TooLarge => "large-file-guard",
                         PreviewFallbackReason::DecodeUncertain => "decode-uncertain",
                     };
                     content = format!("[plain-text fallback: {reason_text}]\n{content}");
@@ -51,6 +87,85 @@ fn plain_text_for_doc(doc: &PreviewDocument) -> String {
     }
 }
 
+/// Rows rendered above the first source line (the plain-text fallback notice).
+pub fn preview_header_rows(doc: &PreviewDocument) -> usize {
+    let has_header = !matches!(doc.load_state, LoadState::Error | LoadState::Binary)
+        && matches!(doc.content_type, ContentType::PlainText)
+        && doc.fallback_reason.is_some();
+    usize::from(has_header)
+}
+
+fn selection_style() -> Style {
+    Style::default().bg(Color::Rgb(58, 64, 90))
+}
+
+fn selection_cursor_style() -> Style {
+    Style::default()
+        .bg(Color::Rgb(82, 92, 128))
+        .add_modifier(Modifier::BOLD)
+}
+
+fn line_flash_style() -> Style {
+    Style::default()
+        .bg(Color::Rgb(120, 96, 40))
+        .add_modifier(Modifier::BOLD)
+}
+
+fn structured_view_for(doc: &PreviewDocument) -> Option<&StructuredView> {
+    doc.structured.as_ref()?.as_ref().ok()
+}
+
+/// Path of the structured view node under the cursor, e.g. `.servers[2].port`.
+pub fn structured_cursor_path<'a>(
+    doc: &'a PreviewDocument,
+    state: &SessionState,
+) -> Option<&'a str> {
+    let view = structured_view_for(doc)?;
+    let row = view.rows.get(state.preview_cursor_line)?;
+    Some(row.path.as_str())
+}
+
+fn row_highlight_style(
+    state: &SessionState,
+    doc: &PreviewDocument,
+    display_index: usize,
+) -> Option<Style> {
+    let source_index = display_index.checked_sub(preview_header_rows(doc))?;
+    if state.flashed_preview_line(Instant::now()) == Some(source_index) {
+        return Some(line_flash_style());
+    }
+    if structured_view_for(doc).is_some()
+        && !state.visual_selection_active()
+        && (state.preview_fullscreen || state.focus_pane == FocusPane::Preview)
+    {
+        return (source_index == state.preview_cursor_line).then(selection_cursor_style);
+    }
+    let (start, end) = state.preview_selection_range()?;
+    if source_index == state.preview_cursor_line {
+        Some(selection_cursor_style())
+    } else if (start..=end).contains(&source_index) {
+        Some(selection_style())
+    } else {
+        None
+    }
+}
+
+fn patch_spans(spans: Vec<Span<'static>>, patch: Option<Style>) -> Vec<Span<'static>> {
+    let Some(patch) = patch else {
+        return spans;
+    };
+    if spans.iter().all(|span| span.content.is_empty()) {
+        return vec![Span::styled(" ", patch)];
+    }
+    spans
+        .into_iter()
+        .map(|span| {
+            let style = span.style.patch(patch);
+            span.style(style)
+        })
+        .collect()
+}
+
 fn line_number_width(total_lines: usize) -> usize {
     total_lines.max(1).to_string().len().max(2)
 }
@@ -73,6 +188,15 @@ fn line_number_blank_prefix(total_lines: usize) -> Span<'static> {
 }
 
 pub fn preview_total_lines(doc: &PreviewDocument) -> usize {
+    if let Some(rows) = &doc.wrapped_rows {
+        return rows.total_rows();
+    }
+    if let Some(table) = &doc.table {
+        return table.row_count() + TABLE_HEADER_ROWS;
+    }
+    if doc.paged.is_some() {
+        return doc.source_line_count();
+    }
     if matches!(doc.content_type, ContentType::Highlighted) && !doc.styled_lines.is_empty() {
         return doc.styled_lines.len();
     }
@@ -110,11 +234,10 @@ fn render_scroll_indicator(
         .min(indicator_height);
     let max_scroll = total_lines.saturating_sub(viewport_rows);
     let max_thumb_top = indicator_height.saturating_sub(thumb_height);
-    let thumb_top = if max_scroll == 0 {
-        0
-    } else {
-        scroll_row.saturating_mul(max_thumb_top) / max_scroll
-    };
+    let thumb_top = scroll_row
+        .saturating_mul(max_thumb_top)
+        .checked_div(max_scroll)
+        .unwrap_or(0);
 
     let track_style = Style::default().fg(Color::DarkGray);
     let thumb_style = Style::default().fg(Color::Gray);
@@ -138,7 +261,7 @@ fn render_scroll_indicator(
     frame.render_widget(Paragraph::new(Text::from(lines)), indicator_area);
 }
 
-fn wrap_styled_spans(spans: Vec<Span<'_>>, width: usize) -> Vec<Vec<Span<'static>>> {
+pub fn wrap_styled_spans(spans: Vec<Span<'_>>, width: usize) -> Vec<Vec<Span<'static>>> {
     let wrap_width = width.max(1);
     let mut wrapped: Vec<Vec<Span<'static>>> = Vec::new();
     let mut current: Vec<Span<'static>> = Vec::new();
@@ -193,6 +316,76 @@ fn wrap_styled_spans(spans: Vec<Span<'_>>, width: usize) -> Vec<Vec<Span<'static
     wrapped
 }
 
+/// Rows each text line of `doc` wraps to in a pane `inner_width` columns
+/// wide, as [`draw_preview`] lays them out; `None` when the preview does not
+/// wrap.
+fn wraps_text(doc: &PreviewDocument, state: &SessionState) -> bool {
+    let unwrapped_view = doc.paged.is_some()
+        || doc.rendered_markdown.is_some()
+        || structured_view_for(doc).is_some();
+    state.preview_wrap_enabled
+        && doc.image.is_none()
+        && doc.table.is_none()
+        && !(unwrapped_view && doc.has_source_text())
+}
+
+/// Recomputes `doc.wrapped_rows` when the width or a setting it depends on
+/// changed since it was last computed, or when the text was replaced.
+pub fn refresh_wrapped_rows(doc: &mut PreviewDocument, state: &SessionState, inner_width: u16) {
+    let layout = WrapLayout {
+        width: inner_width,
+        wraps: wraps_text(doc, state),
+        line_numbers: line_numbers_enabled(state, doc),
+    };
+    if doc.wrapped_layout == Some(layout) {
+        return;
+    }
+    doc.wrapped_rows = wrapped_preview_rows(doc, state, inner_width);
+    doc.wrapped_layout = Some(layout);
+}
+
+pub fn wrapped_preview_rows(
+    doc: &PreviewDocument,
+    state: &SessionState,
+    inner_width: u16,
+) -> Option<WrappedRows> {
+    if !wraps_text(doc, state) {
+        return None;
+    }
+    let width = usize::from(inner_width);
+    let row_count = |spans: Vec<Span<'_>>, width: usize| wrap_styled_spans(spans, width).len();
+    if !line_numbers_enabled(state, doc) {
+        let text = plain_text_for_doc(doc);
+        return Some(WrappedRows::from_row_counts(
+            text.split('\n')
+                .map(|row| row_count(vec![Span::raw(row)], width.max(1))),
+        ));
+    }
+    if matches!(doc.content_type, ContentType::Highlighted) && !doc.styled_lines.is_empty() {
+        let content_width = width
+            .saturating_sub(line_number_width(doc.styled_lines.len()) + 1)
+            .max(1);
+        return Some(WrappedRows::from_row_counts(doc.styled_lines.iter().map(
+            |line| {
+                let spans = line
+                    .iter()
+                    .map(|segment| Span::raw(segment.text.as_str()))
+                    .collect();
+                row_count(spans, content_width)
+            },
+        )));
+    }
+    let text = plain_text_for_doc(doc);
+    let rows = text.split('\n').collect::<Vec<_>>();
+    let content_width = width
+        .saturating_sub(line_number_width(rows.len().max(1)) + 1)
+        .max(1);
+    Some(WrappedRows::from_row_counts(
+        rows.iter()
+            .map(|row| row_count(vec![Span::raw(*row)], content_width)),
+    ))
+}
+
 fn numbered_lines_with_wrapped_content(
     line_number: usize,
     total_lines: usize,
@@ -217,6 +410,191 @@ fn numbered_lines_with_wrapped_content(
     lines
 }
 
+fn plain_lines_with_highlights(
+    text: &str,
+    state: &SessionState,
+    doc: &PreviewDocument,
+) -> Text<'static> {
+    if !state.visual_selection_active() && state.flashed_preview_line(Instant::now()).is_none() {
+        return Text::from(text.to_string());
+    }
+    let lines = text
+        .split('\n')
+        .enumerate()
+        .map(|(index, row)| {
+            Line::from(patch_spans(
+                vec![Span::raw(row.to_string())],
+                row_highlight_style(state, doc, index),
+            ))
+        })
+        .collect::<Vec<_>>();
+    Text::from(lines)
+}
+
+/// Visible rows of a paged preview, numbered from the start of the file, or
+/// of rendered Markdown or a structured view, which are never numbered.
+/// None of them wrap, so rows and document lines coincide.
+fn unwrapped_lines(
+    doc: &PreviewDocument,
+    state: &SessionState,
+    rows: usize,
+    total_lines: usize,
+) -> Vec<Line<'static>> {
+    let numbered = state.preview_show_line_numbers
+        && doc.rendered_markdown.is_none()
+        && structured_view_for(doc).is_none();
+    let offset = doc.line_offset();
+    (state.preview_scroll_row..state.preview_scroll_row + rows)
+        .map_while(|index| {
+            let styled_line = doc.styled_lines.get(index.checked_sub(offset)?)?;
+            let content_spans = patch_spans(
+                styled_line
+                    .iter()
+                    .map(|segment| Span::styled(segment.text.clone(), segment.style))
+                    .collect::<Vec<_>>(),
+                row_highlight_style(state, doc, index),
+            );
+            if !numbered {
+                return Some(Line::from(content_spans));
+            }
+            Some(Line::from(
+                std::iter::once(line_number_prefix(index + 1, total_lines))
+                    .chain(std::iter::once(Span::raw(" ")))
+                    .chain(content_spans)
+                    .collect::<Vec<_>>(),
+            ))
+        })
+        .collect()
+}
+
+/// The header row and the rule under it stay put while a table scrolls.
+const TABLE_HEADER_ROWS: usize = 2;
+const TABLE_SEPARATOR: &str = " │ ";
+
+/// `cell` cut with `…` or padded to `width` columns on the side `alignment`
+/// leaves empty.
+fn fit_cell(cell: &str, width: usize, alignment: ColumnAlignment) -> String {
+    let mut fitted = String::new();
+    let mut used = 0;
+    for ch in cell.chars() {
+        let ch_width = UnicodeWidthChar::width(ch).unwrap_or(0);
+        if used + ch_width > width {
+            while used + 1 > width {
+                let Some(last) = fitted.pop() else {
+                    break;
+                };
+                used -= UnicodeWidthChar::width(last).unwrap_or(0);
+            }
+            if used < width {
+                fitted.push('…');
+                used += 1;
+            }
+            break;
+        }
+        fitted.push(ch);
+        used += ch_width;
+    }
+    let padding = " ".repeat(width.saturating_sub(used));
+    match alignment {
+        ColumnAlignment::Left => fitted + &padding,
+        ColumnAlignment::Right => padding + &fitted,
+    }
+}
+
+/// Columns of `table` that fit in `width` starting at `first`; at least one
+/// is always shown.
+fn visible_table_columns(table: &TablePreview, first: usize, width: usize) -> Range<usize> {
+    let first = first.min(table.column_count().saturating_sub(1));
+    let mut used = 0;
+    let mut end = first;
+    while end < table.column_count() {
+        let separator = if end > first {
+            TABLE_SEPARATOR.width()
+        } else {
+            0
+        };
+        let needed = separator + table.widths[end];
+        if end > first && used + needed > width {
+            break;
+        }
+        used += needed;
+        end += 1;
+    }
+    first..end
+}
+
+fn table_row_spans(
+    table: &TablePreview,
+    cells: &[String],
+    columns: Range<usize>,
+    cell_style: Style,
+) -> Vec<Span<'static>> {
+    let separator_style = Style::default().fg(Color::DarkGray);
+    let mut spans = Vec::new();
+    for column in columns.clone() {
+        if column > columns.start {
+            spans.push(Span::styled(TABLE_SEPARATOR, separator_style));
+        }
+        let cell = cells.get(column).map_or("", String::as_str);
+        spans.push(Span::styled(
+            fit_cell(cell, table.widths[column], table.alignments[column]),
+            cell_style,
+        ));
+    }
+    spans
+}
+
+/// The frozen header and rule of a table preview, then the body rows from
+/// the scroll position, plus a title naming the columns shown when they
+/// do not all fit.
+fn table_lines(
+    table: &TablePreview,
+    doc: &PreviewDocument,
+    state: &SessionState,
+    inner: ratatui::layout::Rect,
+) -> (Vec<Line<'static>>, Option<String>) {
+    // The scroll indicator takes the last column.
+    let width = inner.width.saturating_sub(1) as usize;
+    let columns = visible_table_columns(table, state.preview_table_column, width);
+    let rule = columns
+        .clone()
+        .map(|column| "─".repeat(table.widths[column]))
+        .collect::<Vec<_>>()
+        .join("─┼─");
+    let mut lines = vec![
+        Line::from(table_row_spans(
+            table,
+            &table.header,
+            columns.clone(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(Span::styled(rule, Style::default().fg(Color::DarkGray))),
+    ];
+    let body_rows = (inner.height as usize).saturating_sub(TABLE_HEADER_ROWS);
+    for (index, row) in table
+        .rows
+        .iter()
+        .enumerate()
+        .skip(state.preview_scroll_row)
+        .take(body_rows)
+    {
+        // Body row `index` is line `index + 1` of the file, after the header.
+        lines.push(Line::from(patch_spans(
+            table_row_spans(table, row, columns.clone(), Style::default()),
+            row_highlight_style(state, doc, index + 1 + preview_header_rows(doc)),
+        )));
+    }
+    let title = (columns.len() < table.column_count()).then(|| {
+        format!(
+            " columns {}–{} of {} ",
+            columns.start + 1,
+            columns.end,
+            table.column_count()
+        )
+    });
+    (lines, title)
+}
+
 pub fn draw_preview(
     frame: &mut Frame<'_>,
     area: ratatui::layout::Rect,
@@ -227,17 +605,59 @@ pub fn draw_preview(
     frame.render_widget(Clear, area);
     let title = preview_title_for_state(state);
     let metadata_line =
-        preview_border_metadata_for_state(state, area.width.saturating_sub(2) as usize);
-    let block = Block::default()
+        preview_border_metadata_for_doc(state, doc, area.width.saturating_sub(2) as usize);
+    let mut block = Block::default()
         .title(
             Line::from(vec![Span::raw(" "), Span::raw(title), Span::raw(" ")])
                 .alignment(Alignment::Right),
         )
         .title_bottom(Line::from(metadata_line).alignment(Alignment::Right))
         .borders(Borders::ALL);
+    if let Some(path) = structured_cursor_path(doc, state) {
+        block = block.title(Line::from(format!(" {path} ")).alignment(Alignment::Left));
+    }
     let inner = block.inner(area);
+
+    if let Some(image) = &doc.image {
+        frame.render_widget(block, area);
+        // Graphics protocols draw over the blank cells after the frame.
+        if state.image_protocol == ImageProtocol::HalfBlocks {
+            let lines = half_block_lines(image, inner.width, inner.height);
+            frame.render_widget(Paragraph::new(Text::from(lines)), inner);
+        }
+        return;
+    }
+
+    if let Some(table) = &doc.table {
+        let (lines, columns_title) = table_lines(table, doc, state, inner);
+        if let Some(columns_title) = columns_title {
+            block = block.title(Line::from(columns_title).alignment(Alignment::Left));
+        }
+        frame.render_widget(block, area);
+        frame.render_widget(Paragraph::new(Text::from(lines)), inner);
+        render_scroll_indicator(
+            frame,
+            inner,
+            preview_total_lines(doc),
+            state.preview_scroll_row,
+        );
+        return;
+    }
     frame.render_widget(block, area);
 
+    if (doc.paged.is_some()
+        || doc.rendered_markdown.is_some()
+        || structured_view_for(doc).is_some())
+        && doc.has_source_text()
+    {
+        let total_lines = preview_total_lines(doc);
+        let lines = unwrapped_lines(doc, state, inner.height as usize, total_lines);
+        frame.render_widget(Clear, inner);
+        frame.render_widget(Paragraph::new(Text::from(lines)), inner);
+        render_scroll_indicator(frame, inner, total_lines, state.preview_scroll_row);
+        return;
+    }
+
     let text = plain_text_for_doc(doc);
     let scroll_row_usize = state.preview_scroll_row;
     let scroll_row = scroll_row_usize.min(u16::MAX as usize) as u16;
@@ -249,14 +669,30 @@ pub fn draw_preview(
             let total_lines = doc.styled_lines.len();
             let line_number_cols = line_number_width(total_lines) + 1;
             let content_width = inner.width.saturating_sub(line_number_cols as u16).max(1) as usize;
+            // Without wrapping only the visible rows are built, which keeps
+            // frames cheap for previews with hundreds of thousands of lines.
+            let (first_row, row_count) = if use_wrap {
+                (0, total_lines)
+            } else {
+                (scroll_row_usize, inner.height as usize)
+            };
             let mut lines = Vec::new();
 
-            for (index, styled_line) in doc.styled_lines.iter().enumerate() {
+            for (index, styled_line) in doc
+                .styled_lines
+                .iter()
+                .enumerate()
+                .skip(first_row)
+                .take(row_count)
+            {
                 let line_number = index + 1;
-                let content_spans = styled_line
-                    .iter()
-                    .map(|segment| Span::styled(segment.text.clone(), segment.style))
-                    .collect::<Vec<_>>();
+                let content_spans = patch_spans(
+                    styled_line
+                        .iter()
+                        .map(|segment| Span::styled(segment.text.clone(), segment.style))
+                        .collect::<Vec<_>>(),
+                    row_highlight_style(state, doc, index),
+                );
                 if use_wrap {
                     lines.extend(numbered_lines_with_wrapped_content(
                         line_number,
@@ -273,11 +709,15 @@ pub fn draw_preview(
                     ));
                 }
             }
-            let rendered_total = lines.len();
-            (
-                Paragraph::new(Text::from(lines)).scroll((scroll_row, 0)),
-                rendered_total,
-            )
+            if use_wrap {
+                let rendered_total = lines.len();
+                (
+                    Paragraph::new(Text::from(lines)).scroll((scroll_row, 0)),
+                    rendered_total,
+                )
+            } else {
+                (Paragraph::new(Text::from(lines)), total_lines)
+            }
         } else {
             let rows = text.split('\n').collect::<Vec<_>>();
             let total_lines = rows.len().max(1);
@@ -287,7 +727,10 @@ pub fn draw_preview(
 
             for (index, row) in rows.iter().enumerate() {
                 let line_number = index + 1;
-                let content_spans = vec![Span::raw((*row).to_string())];
+                let content_spans = patch_spans(
+                    vec![Span::raw((*row).to_string())],
+                    row_highlight_style(state, doc, index),
+                );
                 if use_wrap {
                     lines.extend(numbered_lines_with_wrapped_content(
                         line_number,
@@ -296,11 +739,12 @@ pub fn draw_preview(
                         content_width,
                     ));
                 } else {
-                    lines.push(Line::from(vec![
-                        line_number_prefix(line_number, total_lines),
-                        Span::raw(" "),
-                        Span::raw((*row).to_string()),
-                    ]));
+                    lines.push(Line::from(
+                        std::iter::once(line_number_prefix(line_number, total_lines))
+                            .chain(std::iter::once(Span::raw(" ")))
+                            .chain(content_spans)
+                            .collect::<Vec<_>>(),
+                    ));
                 }
             }
             let rendered_total = lines.len();
@@ -310,25 +754,29 @@ pub fn draw_preview(
             )
         }
     } else if use_wrap {
-        let mut rendered_total = 0usize;
-        for row in text.split('\n') {
-            rendered_total = rendered_total.saturating_add(
+        let width = inner.width.max(1) as usize;
+        let lines = text
+            .split('\n')
+            .enumerate()
+            .flat_map(|(index, row)| {
                 wrap_styled_spans(
-                    vec![Span::raw(row.to_string())],
-                    inner.width.max(1) as usize,
+                    patch_spans(
+                        vec![Span::raw(row.to_string())],
+                        row_highlight_style(state, doc, index),
+                    ),
+                    width,
                 )
-                .len(),
-            );
-        }
+            })
+            .map(Line::from)
+            .collect::<Vec<_>>();
+        let rendered_total = lines.len();
         (
-            Paragraph::new(text)
-                .wrap(Wrap { trim: false })
-                .scroll((scroll_row, 0)),
+            Paragraph::new(Text::from(lines)).scroll((scroll_row, 0)),
             rendered_total.max(1),
         )
     } else {
         (
-            Paragraph::new(text).scroll((scroll_row, 0)),
+            Paragraph::new(plain_lines_with_highlights(&text, state, doc)).scroll((scroll_row, 0)),
             preview_total_lines(doc),
         )
     };
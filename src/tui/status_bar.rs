@@ -1,10 +1,14 @@
 use crate::app::current_dir_state::truncate_for_status;
-use crate::app::state::SelectedEntryMetadata;
 use crate::app::state::SessionState;
+use crate::app::state::{DetectionReason, SelectedEntryMetadata};
 use crate::config::keymap::Action;
-use crate::config::load::StatusDisplayMode;
+use crate::config::load::{StatusDisplayMode, ThemeProfile};
+use crate::fs::encoding::TextFormat;
+use crate::fs::image::ImagePreview;
+use crate::fs::table::TablePreview;
+use crate::tui::tree_pane::color_from_name;
 use crossterm::event::KeyEvent;
-use ratatui::style::{Color, Style};
+use ratatui::style::Style;
 use ratatui::widgets::Paragraph;
 use ratatui::Frame;
 use std::collections::HashMap;
@@ -52,11 +56,24 @@ pub fn compose_shortcut_help_text(bindings: &HashMap<Action, KeyEvent>) -> Strin
     let scroll_down = key_display(bindings, Action::PreviewScrollDown, "/");
     let toggle_lines = key_display(bindings, Action::TogglePreviewLineNumbers, "l");
     let toggle_wrap = key_display(bindings, Action::TogglePreviewWrap, "w");
+    let toggle_markdown = key_display(bindings, Action::ToggleMarkdownRender, "m");
+    let toggle_structured = key_display(bindings, Action::ToggleStructuredView, "t");
+    let toggle_table = key_display(bindings, Action::ToggleTableView, "g");
     let esc = key_display(bindings, Action::ExitFullscreenPreview, "Esc");
     let help = key_display(bindings, Action::ToggleHelp, "?");
+    let copy_abs = key_display(bindings, Action::CopyAbsolutePath, "y");
+    let copy_rel = key_display(bindings, Action::CopyRelativePath, "r");
+    let copy_name = key_display(bindings, Action::CopyFileName, "n");
+    let copy_selection = key_display(bindings, Action::CopyPreviewSelection, "c");
+    let visual = key_display(bindings, Action::ToggleVisualSelection, "v");
+    let copy_reference = key_display(bindings, Action::CopySelectionReference, "x");
+    let pipe = key_display(bindings, Action::PipeSelection, "|");
+    let go_to_line = key_display(bindings, Action::GoToLine, ":");
+    let export_html = key_display(bindings, Action::ExportHtml, "e");
+    let encoding = key_display(bindings, Action::CycleEncoding, "u");
 
     format!(
-        "Shortcuts\n\nNavigation\n  {up}/{down}: move selection\n  {expand}: enter directory\n  {collapse}: parent directory\n  {open}: open (directory/fullscreen)\n\nPanels\n  {focus}: switch tree/preview focus\n  {narrower}/{wider}: resize preview panel\n\nPreview\n  {scroll_up}/{scroll_down}: scroll 3 lines\n  {page_up}/{page_down}: page up/down\n  {toggle_lines}: toggle line numbers\n  {toggle_wrap}: toggle wrap\n  {esc}: exit fullscreen\n\nOther\n  {hidden}: show/hide hidden files\n  {help}: close help\n  {quit}: quit fpv"
+        "Shortcuts\n\nNavigation\n  {up}/{down}: move selection\n  {expand}: enter directory\n  {collapse}: parent directory\n  {open}: open (directory/fullscreen)\n\nPanels\n  {focus}: switch tree/preview focus\n  {narrower}/{wider}: resize preview panel\n\nPreview\n  {scroll_up}/{scroll_down}: scroll 3 lines\n  {page_up}/{page_down}: page up/down\n  {toggle_lines}: toggle line numbers\n  {toggle_wrap}: toggle wrap\n  {toggle_markdown}: toggle rendered Markdown\n  {toggle_structured}: toggle JSON/TOML/YAML tree ({expand}/{collapse} fold)\n  {toggle_table}: toggle CSV/TSV table ({expand}/{collapse} scroll columns)\n  {go_to_line}: go to line\n  {encoding}: cycle forced encoding\n  {esc}: exit fullscreen\n\nSelection (preview focused)\n  {visual}: start/stop visual line selection\n  {up}/{down}, {page_up}/{page_down}: extend selection\n  {copy_reference}: copy path:start-end reference\n  {pipe}: pipe selection to selection_pipe_command\n  {export_html}: export file or selection as HTML\n\nClipboard\n  {copy_abs}: copy absolute path\n  {copy_rel}: copy relative path\n  {copy_name}: copy file name\n  {copy_selection}: copy preview selection\n\nOther\n  {hidden}: show/hide hidden files\n  {help}: close help\n  {quit}: quit fpv"
     )
 }
 
@@ -66,15 +83,20 @@ fn type_name_for_extension(ext: &str) -> &'static str {
         "c" | "h" => "C",
         "cc" | "cp" | "cpp" | "cxx" | "c++" | "hpp" | "hh" | "hxx" => "C++",
         "css" => "CSS",
+        "csv" => "CSV",
+        "gif" => "GIF",
         "go" => "Go",
         "html" | "htm" => "HTML",
         "java" => "Java",
+        "jpg" | "jpeg" => "JPEG",
         "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
         "json" => "JSON",
         "md" | "markdown" => "Markdown",
+        "png" => "PNG",
         "py" => "Python",
         "rs" => "Rust",
         "toml" => "TOML",
+        "tsv" => "TSV",
         "tsx" | "ts" => "TypeScript",
         "xml" => "XML",
         "yaml" | "yml" => "YAML",
@@ -82,7 +104,7 @@ fn type_name_for_extension(ext: &str) -> &'static str {
     }
 }
 
-fn file_type_label(filename: &str) -> String {
+fn file_type_label(filename: &str, language: Option<(&str, DetectionReason)>) -> String {
     let trimmed = filename.trim();
     if trimmed.is_empty() || trimmed == "-" {
         return "Unknown(none)".to_string();
@@ -92,23 +114,95 @@ fn file_type_label(filename: &str) -> String {
         .file_name()
         .and_then(|part| part.to_str())
         .unwrap_or(trimmed);
-    if let Some((_, ext)) = name.rsplit_once('.') {
-        if !ext.is_empty() {
-            let normalized = ext.to_ascii_lowercase();
-            return format!("{}(.{})", type_name_for_extension(&normalized), normalized);
-        }
+    let ext = name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_ascii_lowercase())
+        .filter(|ext| !ext.is_empty());
+    match (language, ext) {
+        (Some((language, DetectionReason::Extension)), Some(ext)) => format!("{language}(.{ext})"),
+        (Some((language, reason)), _) => format!("{language}({})", reason.label()),
+        (None, Some(ext)) => format!("{}(.{})", type_name_for_extension(&ext), ext),
+        (None, None) => "Unknown(none)".to_string(),
     }
-
-    "Unknown(none)".to_string()
 }
 
 pub fn compose_preview_metadata_line(metadata: &SelectedEntryMetadata, width: usize) -> String {
+    compose_preview_metadata_line_with_language(metadata, None, width)
+}
+
+/// Metadata line whose type label names the highlight language of the
+/// preview and, unless it came from the extension, how it was detected.
+pub fn compose_preview_metadata_line_with_language(
+    metadata: &SelectedEntryMetadata,
+    language: Option<(&str, DetectionReason)>,
+    width: usize,
+) -> String {
+    compose_preview_metadata_line_with_format(metadata, language, None, width)
+}
+
+/// [`compose_preview_metadata_line_with_language`] followed by the encoding
+/// and line endings of a text preview.
+pub fn compose_preview_metadata_line_with_format(
+    metadata: &SelectedEntryMetadata,
+    language: Option<(&str, DetectionReason)>,
+    format: Option<&TextFormat>,
+    width: usize,
+) -> String {
+    compose_preview_metadata_line_with_table(metadata, language, format, None, width)
+}
+
+/// [`compose_preview_metadata_line_with_format`] followed by the row and
+/// column count of a table preview.
+pub fn compose_preview_metadata_line_with_table(
+    metadata: &SelectedEntryMetadata,
+    language: Option<(&str, DetectionReason)>,
+    format: Option<&TextFormat>,
+    table: Option<&TablePreview>,
+    width: usize,
+) -> String {
+    compose_preview_metadata_line_with_image(metadata, language, format, table, None, width)
+}
+
+/// [`compose_preview_metadata_line_with_table`] followed by the pixel size
+/// of an image preview.
+pub fn compose_preview_metadata_line_with_image(
+    metadata: &SelectedEntryMetadata,
+    language: Option<(&str, DetectionReason)>,
+    format: Option<&TextFormat>,
+    table: Option<&TablePreview>,
+    image: Option<&ImagePreview>,
+    width: usize,
+) -> String {
+    compose_preview_metadata_line_with_lines(metadata, language, format, table, image, None, width)
+}
+
+/// [`compose_preview_metadata_line_with_image`] followed by the line count
+/// of a paged preview, e.g. `PagedFile::line_count_label`.
+pub fn compose_preview_metadata_line_with_lines(
+    metadata: &SelectedEntryMetadata,
+    language: Option<(&str, DetectionReason)>,
+    format: Option<&TextFormat>,
+    table: Option<&TablePreview>,
+    image: Option<&ImagePreview>,
+    lines: Option<&str>,
+    width: usize,
+) -> String {
+    let mut type_label = file_type_label(&metadata.filename, language);
+    if let Some(format) = format {
+        type_label = format!("{type_label} | {}", format.label());
+    }
+    if let Some(table) = table {
+        type_label = format!("{type_label} | {}", table.size_label());
+    }
+    if let Some(image) = image {
+        type_label = format!("{type_label} | {}", image.size_label());
+    }
+    if let Some(lines) = lines {
+        type_label = format!("{type_label} | {lines}");
+    }
     let raw = format!(
         "{} | {} | {} | {}",
-        file_type_label(&metadata.filename),
-        metadata.size_text,
-        metadata.permission_text,
-        metadata.modified_text
+        type_label, metadata.size_text, metadata.permission_text, metadata.modified_text
     );
     truncate_for_status(&raw, width)
 }
@@ -118,8 +212,20 @@ pub fn compose_bottom_status_line(
     bindings: &HashMap<Action, KeyEvent>,
     width: usize,
 ) -> String {
+    if let Some(input) = &state.line_prompt {
+        let padded = format!(" Go to line: {input}_ (Enter to jump, Esc to cancel) ");
+        return truncate_for_status(&padded, width);
+    }
+    if let Some(notice) = &state.status_notice {
+        let padded = format!(" fpv {APP_VERSION} | {notice} | {} ", help_line(bindings));
+        return truncate_for_status(&padded, width);
+    }
+    let visual = state
+        .preview_selection_range()
+        .map(|(start, end)| format!(" Visual={}-{}", start + 1, end + 1))
+        .unwrap_or_default();
     let raw = format!(
-        "fpv {} | Show hidden={} Word wrap={} Preview latency={}ms | {}",
+        "fpv {} | Show hidden={} Word wrap={}{} Preview latency={}ms | {}",
         APP_VERSION,
         if state.show_hidden { "on" } else { "off" },
         if state.preview_wrap_enabled {
@@ -127,6 +233,7 @@ pub fn compose_bottom_status_line(
         } else {
             "off"
         },
+        visual,
         state.last_preview_latency_ms,
         help_line(bindings)
     );
@@ -142,8 +249,10 @@ pub fn compose_status_title_line(
     compose_bottom_status_line(state, bindings, width)
 }
 
-fn status_bar_style() -> Style {
-    Style::default().fg(Color::White).bg(Color::DarkGray)
+fn status_bar_style(theme: &ThemeProfile) -> Style {
+    Style::default()
+        .fg(color_from_name(&theme.status_foreground_color))
+        .bg(color_from_name(&theme.status_background_color))
 }
 
 pub fn draw_status(
@@ -151,10 +260,11 @@ pub fn draw_status(
     area: ratatui::layout::Rect,
     state: &SessionState,
     bindings: &HashMap<Action, KeyEvent>,
+    theme: &ThemeProfile,
 ) {
     let content = match state.status_display_mode {
         StatusDisplayMode::Bar => compose_bottom_status_line(state, bindings, area.width as usize),
         StatusDisplayMode::Title => compose_status_title_line(state, bindings, area.width as usize),
     };
-    frame.render_widget(Paragraph::new(content).style(status_bar_style()), area);
+    frame.render_widget(Paragraph::new(content).style(status_bar_style(theme)), area);
 }
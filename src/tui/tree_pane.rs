@@ -1,7 +1,9 @@
 use crate::app::current_dir_state::truncate_for_status;
 use crate::app::state::{NodeType, SessionState, TreeNode};
 use crate::config::load::ThemeProfile;
+use crate::config::style_rules::{matching_rule, StyleRule};
 use crate::fs::git::GitFileStatus;
+use crate::highlight::theme::parse_color;
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
@@ -18,6 +20,33 @@ pub fn entry_prefix(node_type: &NodeType) -> &'static str {
     }
 }
 
+/// Name shown for `node` in the tree, led by a rule icon, the icon set's
+/// glyph, or the `entry_prefix` marker when icons are off.
+pub fn tree_entry_label(
+    node: &TreeNode,
+    theme: &ThemeProfile,
+    git_status: Option<GitFileStatus>,
+) -> String {
+    tree_entry_label_with_rule(node, theme, matching_rule(&theme.rules, node, git_status))
+}
+
+/// [`tree_entry_label`] for a node whose theme rule was already matched.
+pub fn tree_entry_label_with_rule(
+    node: &TreeNode,
+    theme: &ThemeProfile,
+    rule: Option<&StyleRule>,
+) -> String {
+    let icon = rule
+        .and_then(|rule| rule.icon.as_deref())
+        .or_else(|| theme.icons.icon_for(node))
+        .unwrap_or_else(|| entry_prefix(&node.node_type));
+    if icon.is_empty() {
+        node.name.clone()
+    } else {
+        format!("{icon} {}", node.name)
+    }
+}
+
 pub fn current_directory_header_line(state: &SessionState, width: usize) -> String {
     truncate_for_status(&raw_current_directory_header_line(state), width)
 }
@@ -96,16 +125,12 @@ pub fn color_from_name(name: &str) -> Color {
         "magenta" => Color::Magenta,
         "cyan" => Color::Cyan,
         "gray" | "white" => Color::White,
-        _ => Color::White,
+        _ => parse_color(name).unwrap_or(Color::White),
     }
 }
 
-pub fn node_style(
-    node: &TreeNode,
-    theme: &ThemeProfile,
-    git_status: Option<GitFileStatus>,
-) -> Style {
-    let mut style = match node.node_type {
+fn theme_style(node: &TreeNode, theme: &ThemeProfile) -> Style {
+    match node.node_type {
         NodeType::Directory => Style::default().fg(color_from_name(&theme.directory_color)),
         NodeType::File => {
             let ext = node
@@ -122,7 +147,34 @@ pub fn node_style(
             Style::default().fg(color_from_name(color_name))
         }
         _ => Style::default().fg(color_from_name(&theme.fallback_file_color)),
-    };
+    }
+}
+
+pub fn node_style(
+    node: &TreeNode,
+    theme: &ThemeProfile,
+    git_status: Option<GitFileStatus>,
+) -> Style {
+    node_style_with_rule(
+        node,
+        theme,
+        git_status,
+        matching_rule(&theme.rules, node, git_status),
+    )
+}
+
+/// [`node_style`] for a node whose theme rule was already matched.
+pub fn node_style_with_rule(
+    node: &TreeNode,
+    theme: &ThemeProfile,
+    git_status: Option<GitFileStatus>,
+    rule: Option<&StyleRule>,
+) -> Style {
+    let mut style = theme
+        .ls_colors
+        .as_ref()
+        .and_then(|ls_colors| ls_colors.style_for_class(node.class(), &node.name))
+        .unwrap_or_else(|| theme_style(node, theme));
     if theme.hidden_dim_enabled && node.name.starts_with('.') {
         style = style.add_modifier(Modifier::DIM);
     }
@@ -131,6 +183,12 @@ pub fn node_style(
             .fg(Color::DarkGray)
             .add_modifier(Modifier::DIM);
     }
+    if let Some(rule) = rule {
+        if let Some(color) = &rule.color {
+            style = style.fg(color_from_name(color));
+        }
+        style = style.add_modifier(rule.modifiers);
+    }
     style
 }
 
@@ -188,19 +246,18 @@ pub fn draw_tree(
     let items: Vec<ListItem<'_>> = nodes
         .iter()
         .map(|n| {
-            let icon = entry_prefix(&n.node_type);
             let status = git_status_label_for_node(state, n);
-            let left = if icon.is_empty() {
-                n.name.clone()
-            } else {
-                format!("{icon} {}", n.name)
-            };
+            let rule = matching_rule(&theme.rules, n, status);
+            let left = tree_entry_label_with_rule(n, theme, rule);
             let right_indicator = right_indicator_for_node(state, n, status);
             let (left_text, padding, right_label) =
                 compose_tree_entry_segments(&left, right_indicator, content_width);
 
             let mut spans = Vec::with_capacity(3);
-            spans.push(Span::styled(left_text, node_style(n, theme, status)));
+            spans.push(Span::styled(
+                left_text,
+                node_style_with_rule(n, theme, status, rule),
+            ));
             if padding > 0 {
                 spans.push(Span::raw(" ".repeat(padding)));
             }
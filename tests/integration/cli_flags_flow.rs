@@ -0,0 +1,120 @@
+use assert_cmd::Command;
+use predicates::prelude::PredicateBooleanExt;
+use predicates::str::contains;
+
+#[test]
+fn help_lists_options_without_starting_tui() {
+    Command::cargo_bin("fpv")
+        .expect("binary")
+        .arg("--help")
+        .assert()
+        .success()
+        .stdout(contains("--show-hidden"))
+        .stdout(contains("--max-preview-bytes"))
+        .stdout(contains("--no-mouse"));
+}
+
+#[test]
+fn version_prints_package_version() {
+    Command::cargo_bin("fpv")
+        .expect("binary")
+        .arg("--version")
+        .assert()
+        .success()
+        .stdout(contains(env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn unknown_flag_is_an_error() {
+    Command::cargo_bin("fpv")
+        .expect("binary")
+        .arg("--bogus")
+        .assert()
+        .failure()
+        .stderr(contains("--bogus"));
+}
+
+#[test]
+fn missing_directory_is_an_error() {
+    Command::cargo_bin("fpv")
+        .expect("binary")
+        .arg("definitely-missing-directory")
+        .assert()
+        .failure()
+        .stderr(contains("not a directory or file"));
+}
+
+#[test]
+fn print_mode_writes_plain_text_when_piped() {
+    let d = tempfile::tempdir().expect("create tempdir");
+    let file = d.path().join("main.rs");
+    std::fs::write(&file, "fn main() {}\n").expect("write");
+
+    Command::cargo_bin("fpv")
+        .expect("binary")
+        .arg("--print")
+        .arg(&file)
+        .arg("--line-numbers")
+        .assert()
+        .success()
+        .stdout(" 1 fn main() {}\n");
+}
+
+#[test]
+fn print_mode_emits_truecolor_when_forced() {
+    let d = tempfile::tempdir().expect("create tempdir");
+    let file = d.path().join("main.rs");
+    std::fs::write(&file, "fn main() {}\n").expect("write");
+
+    Command::cargo_bin("fpv")
+        .expect("binary")
+        .arg("--print")
+        .arg(&file)
+        .args(["--color", "always"])
+        .env("COLORTERM", "truecolor")
+        .assert()
+        .success()
+        .stdout(contains("\x1b[1;38;2;"));
+}
+
+#[test]
+fn print_mode_fails_for_binary_files() {
+    let d = tempfile::tempdir().expect("create tempdir");
+    let file = d.path().join("blob.bin");
+    std::fs::write(&file, [0_u8, 1, 2]).expect("write");
+
+    Command::cargo_bin("fpv")
+        .expect("binary")
+        .arg("--print")
+        .arg(&file)
+        .assert()
+        .failure()
+        .stderr(contains("Binary file"));
+}
+
+#[test]
+fn export_html_writes_document_for_line_range() {
+    let d = tempfile::tempdir().expect("create tempdir");
+    let file = d.path().join("main.rs");
+    std::fs::write(&file, "fn a() {}\nfn b() {}\nfn c() {}\n").expect("write");
+
+    Command::cargo_bin("fpv")
+        .expect("binary")
+        .arg("--export-html")
+        .arg(&file)
+        .args(["--lines", "2-2"])
+        .assert()
+        .success()
+        .stdout(contains("<title>main.rs:2-2</title>"))
+        .stdout(contains(">b</span>"))
+        .stdout(contains(">a</span>").not());
+}
+
+#[test]
+fn lines_requires_export_html() {
+    Command::cargo_bin("fpv")
+        .expect("binary")
+        .args(["--lines", "1-2"])
+        .assert()
+        .failure();
+}
@@ -36,6 +36,7 @@ fn metadata_refresh_stays_within_budget() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     }];
     let mut state = SessionState::new(PathBuf::from("."));
     let ctx = HighlightContext::new();
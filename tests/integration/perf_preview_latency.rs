@@ -1,7 +1,8 @@
 use fpv::app::preview_controller::refresh_preview;
 use fpv::app::state::{ContentType, NodeType, SessionState, TreeNode};
-use fpv::fs::preview::load_preview;
+use fpv::fs::preview::{load_preview, page_viewport};
 use fpv::highlight::syntax::HighlightContext;
+use fpv::highlight::viewport::highlight_viewport;
 use std::fs;
 use std::path::PathBuf;
 use std::time::Instant;
@@ -25,6 +26,64 @@ fn preview_under_one_second_for_1mb_file() {
     ));
 }
 
+#[test]
+fn multi_megabyte_source_highlights_only_the_viewport() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("generated.rs");
+    let body = "pub fn generated(value: u32) -> u32 { value * 2 } // item\n".repeat(80_000);
+    fs::write(&p, &body).expect("write file");
+    let ctx = HighlightContext::new();
+
+    let started = Instant::now();
+    let mut doc = load_preview(&p, body.len(), &ctx);
+    let load_ms = started.elapsed().as_millis();
+    assert_eq!(doc.content_type, ContentType::Highlighted);
+    assert!(load_ms < 1000, "large preview took {load_ms}ms");
+
+    let started = Instant::now();
+    highlight_viewport(&mut doc, &ctx, 40_000..40_050);
+    let scroll_ms = started.elapsed().as_millis();
+    assert!(scroll_ms < 200, "viewport highlight took {scroll_ms}ms");
+}
+
+#[test]
+fn huge_log_opens_without_reading_the_whole_file() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("huge.log");
+    let line = "2024-01-01T00:00:00Z INFO request handled in 12ms path=/api/items\n";
+    fs::write(&p, line.repeat(500_000)).expect("write file");
+    let ctx = HighlightContext::new();
+
+    let started = Instant::now();
+    let mut doc = load_preview(&p, 1024 * 1024, &ctx);
+    let open_ms = started.elapsed().as_millis();
+    assert!(open_ms < 200, "paged preview took {open_ms}ms");
+    assert!(doc.paged.is_some());
+
+    doc.paged.clone().expect("paged").file.wait_until_indexed();
+    assert_eq!(doc.source_line_count(), 500_001);
+    let started = Instant::now();
+    page_viewport(&mut doc, 499_950..500_001);
+    let jump_ms = started.elapsed().as_millis();
+    assert!(jump_ms < 200, "jump to end took {jump_ms}ms");
+    assert_eq!(doc.source_lines().last().map(String::as_str), Some(""));
+}
+
+#[test]
+fn highlight_context_defers_grammar_setup_until_first_use() {
+    let ctx = HighlightContext::new();
+    assert!(!ctx.is_grammar_built("rust"));
+    assert!(!ctx.is_grammar_built("python"));
+
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("main.rs");
+    fs::write(&p, "fn main() { let x = 1; }\n").expect("write file");
+    let doc = load_preview(&p, 1024, &ctx);
+    assert_eq!(doc.content_type, ContentType::Highlighted);
+    assert!(ctx.is_grammar_built("rust"));
+    assert!(!ctx.is_grammar_built("python"));
+}
+
 #[test]
 fn selection_driven_preview_refresh_stays_under_200ms() {
     let d = tempdir().expect("create tempdir");
@@ -40,6 +99,7 @@ fn selection_driven_preview_refresh_stays_under_200ms() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         });
     }
 
@@ -36,6 +36,7 @@ fn permission_text_remains_stable_across_selection_changes() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         },
         TreeNode {
             path: b,
@@ -45,6 +46,7 @@ fn permission_text_remains_stable_across_selection_changes() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         },
     ];
 
@@ -24,6 +24,7 @@ fn selection_change_updates_preview_source() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         },
         TreeNode {
             path: p2.clone(),
@@ -33,6 +34,7 @@ fn selection_change_updates_preview_source() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         },
     ];
 
@@ -64,6 +66,7 @@ fn highlighted_preview_remains_consistent_across_file_switches() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         },
         TreeNode {
             path: b,
@@ -73,6 +76,7 @@ fn highlighted_preview_remains_consistent_across_file_switches() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         },
     ];
 
@@ -103,6 +107,7 @@ fn directory_selection_shows_neutral_preview_while_file_errors_remain() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         },
         TreeNode {
             path: missing.clone(),
@@ -112,6 +117,7 @@ fn directory_selection_shows_neutral_preview_while_file_errors_remain() {
             expanded: false,
             readable: false,
             children_loaded: false,
+            entry_class: None,
         },
     ];
 
@@ -144,6 +150,7 @@ fn header_and_preview_metadata_strings_follow_layout_contract() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     }];
     let mut state = SessionState::new(d.path().to_path_buf());
     let ctx = HighlightContext::new();
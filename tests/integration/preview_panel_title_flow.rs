@@ -23,6 +23,7 @@ fn preview_title_tracks_selection_changes() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         },
         TreeNode {
             path: b,
@@ -32,6 +33,7 @@ fn preview_title_tracks_selection_changes() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         },
     ];
 
@@ -61,6 +63,7 @@ fn border_metadata_uses_compact_contract_without_filename_prefix() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     }];
 
     let mut state = SessionState::new(PathBuf::from("."));
@@ -39,6 +39,7 @@ fn theme_file_type_mapping_resolves_expected_color() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     };
     let style = node_style(&node, &theme, None);
     assert_eq!(style.fg, Some(Color::Green));
@@ -44,6 +44,7 @@ fn directory_preview_does_not_emit_error_message() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     }];
 
     let ctx = HighlightContext::new();
@@ -65,6 +66,7 @@ fn hidden_dimming_applies_without_removing_base_color() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     };
     let style = node_style(&hidden, &theme, None);
     assert!(style.fg.is_some());
@@ -88,6 +90,7 @@ fn preview_title_and_footer_remain_in_sync_after_multiple_selection_changes() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         },
         TreeNode {
             path: p2,
@@ -97,6 +100,7 @@ fn preview_title_and_footer_remain_in_sync_after_multiple_selection_changes() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         },
     ];
 
@@ -132,6 +136,7 @@ fn mixed_supported_and_unsupported_navigation_keeps_preview_readable() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         },
         TreeNode {
             path: unsupported,
@@ -141,6 +146,7 @@ fn mixed_supported_and_unsupported_navigation_keeps_preview_readable() {
             expanded: false,
             readable: true,
             children_loaded: false,
+            entry_class: None,
         },
     ];
 
@@ -1,3 +1,5 @@
+#[path = "integration/cli_flags_flow.rs"]
+mod cli_flags_flow;
 #[path = "integration/config_override_flow.rs"]
 mod config_override_flow;
 #[path = "integration/empty_directory_state_tests.rs"]
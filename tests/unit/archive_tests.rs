@@ -0,0 +1,277 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use fpv::app::navigation::{enter_selected_directory, go_to_parent_directory};
+use fpv::app::preview_controller::refresh_preview;
+use fpv::app::state::{ContentType, LoadState, NodeType, PreviewDocument, SessionState};
+use fpv::fs::archive::{
+    archive_entries, list_archive_directory, read_archive_member, split_archive_path,
+};
+use fpv::fs::current_dir::{list_current_directory, selected_entry_metadata};
+use fpv::fs::preview::poll_pending_preview;
+use fpv::highlight::syntax::HighlightContext;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const MAIN_RS: &str = "fn main() {\n    println!(\"hi\");\n}\n";
+
+fn write_zip(path: &Path) {
+    let mut zip = ZipWriter::new(File::create(path).expect("create"));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    zip.start_file("README.md", options).expect("start");
+    zip.write_all(b"# demo\n").expect("write");
+    // No entry for `src/` itself: the directory is implied.
+    zip.start_file("src/main.rs", options).expect("start");
+    zip.write_all(MAIN_RS.as_bytes()).expect("write");
+    zip.add_directory("docs/", options).expect("dir");
+    zip.start_file(".hidden", options).expect("start");
+    zip.finish().expect("finish");
+}
+
+/// Polls `doc` until its background load finishes.
+fn finish(doc: &mut PreviewDocument, ctx: &HighlightContext) {
+    let started = Instant::now();
+    while !poll_pending_preview(doc, ctx) {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "preview never finished"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+fn tar_bytes() -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, data) in [
+        ("./pkg/lib.rs", "pub fn f() {}\n"),
+        ("pkg/notes.txt", "n\n"),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(1_700_000_000);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, data.as_bytes())
+            .expect("append");
+    }
+    builder.into_inner().expect("tar")
+}
+
+#[test]
+fn zip_listing_includes_implied_directories() {
+    let d = tempdir().expect("tempdir");
+    let archive = d.path().join("demo.zip");
+    write_zip(&archive);
+
+    let paths = archive_entries(&archive)
+        .expect("entries")
+        .entries
+        .iter()
+        .map(|entry| (entry.path.clone(), entry.is_dir))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![
+            (".hidden".to_string(), false),
+            ("README.md".to_string(), false),
+            ("docs".to_string(), true),
+            ("src".to_string(), true),
+            ("src/main.rs".to_string(), false),
+        ]
+    );
+
+    let top = list_archive_directory(&archive, "", 100, false).expect("list");
+    assert_eq!(top.len(), 3);
+    let nested = list_archive_directory(&archive, "src", 100, true).expect("list");
+    assert_eq!(nested[0].path, archive.join("src/main.rs"));
+    assert_eq!(nested[0].node_type, NodeType::File);
+}
+
+#[test]
+fn archive_paths_split_into_archive_and_member() {
+    let d = tempdir().expect("tempdir");
+    let archive = d.path().join("demo.zip");
+    write_zip(&archive);
+
+    assert_eq!(
+        split_archive_path(&archive.join("src/main.rs")),
+        Some((archive.clone(), "src/main.rs".to_string()))
+    );
+    assert_eq!(
+        split_archive_path(&archive),
+        Some((archive.clone(), String::new()))
+    );
+    assert_eq!(split_archive_path(d.path()), None);
+    // A directory merely named like an archive is not one.
+    let fake = d.path().join("fake.zip");
+    fs::create_dir(&fake).expect("mkdir");
+    assert_eq!(split_archive_path(&fake.join("x")), None);
+}
+
+#[test]
+fn tarballs_are_read_without_extracting() {
+    let d = tempdir().expect("tempdir");
+    let plain = d.path().join("pkg.tar");
+    fs::write(&plain, tar_bytes()).expect("write");
+    let gz = d.path().join("pkg.tgz");
+    let mut encoder = GzEncoder::new(File::create(&gz).expect("create"), Compression::fast());
+    encoder.write_all(&tar_bytes()).expect("write");
+    encoder.finish().expect("finish");
+    let xz = d.path().join("pkg.tar.xz");
+    let mut compressed = Vec::new();
+    lzma_rs::xz_compress(&mut tar_bytes().as_slice(), &mut compressed).expect("xz");
+    fs::write(&xz, compressed).expect("write");
+
+    for archive in [plain, gz, xz] {
+        let listing = archive_entries(&archive).expect("entries");
+        assert!(!listing.partial);
+        let entries = &listing.entries;
+        assert_eq!(entries.len(), 3, "{}", archive.display());
+        assert_eq!(entries[1].path, "pkg/lib.rs");
+        assert_eq!(entries[1].modified, Some(1_700_000_000));
+        assert_eq!(
+            read_archive_member(&archive, "pkg/lib.rs", 6).expect("read"),
+            b"pub fn"
+        );
+        assert!(read_archive_member(&archive, "pkg/missing.rs", 6).is_err());
+    }
+    assert_eq!(fs::read_dir(d.path()).expect("dir").count(), 3);
+}
+
+#[test]
+fn compressed_members_are_read_in_any_order_until_the_archive_changes() {
+    let d = tempdir().expect("tempdir");
+    let gz = d.path().join("pkg.tar.gz");
+    let write_gz = |data: &[u8]| {
+        let mut encoder = GzEncoder::new(File::create(&gz).expect("create"), Compression::fast());
+        encoder.write_all(data).expect("write");
+        encoder.finish().expect("finish");
+    };
+    write_gz(&tar_bytes());
+
+    // Forward reads continue one stream; going back starts it again, and a
+    // longer read is not served from a shorter cached head.
+    assert_eq!(
+        read_archive_member(&gz, "pkg/lib.rs", 3).expect("read"),
+        b"pub"
+    );
+    assert_eq!(
+        read_archive_member(&gz, "pkg/notes.txt", 64).expect("read"),
+        b"n\n"
+    );
+    assert_eq!(
+        read_archive_member(&gz, "pkg/lib.rs", 64).expect("read"),
+        b"pub fn f() {}\n"
+    );
+    assert_eq!(
+        read_archive_member(&gz, "pkg/lib.rs", 6).expect("read"),
+        b"pub fn"
+    );
+
+    let mut builder = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(9);
+    header.set_mode(0o644);
+    header.set_cksum();
+    builder
+        .append_data(&mut header, "pkg/lib.rs", "changed!\n".as_bytes())
+        .expect("append");
+    write_gz(&builder.into_inner().expect("tar"));
+    assert_eq!(
+        read_archive_member(&gz, "pkg/lib.rs", 64).expect("read"),
+        b"changed!\n"
+    );
+}
+
+#[test]
+fn large_xz_members_are_streamed_across_chunks() {
+    // Members well past the decoder's chunk size, so reads span chunks.
+    let big = "0123456789abcdef".repeat(40_000);
+    let mut builder = tar::Builder::new(Vec::new());
+    for (name, data) in [("big.txt", big.as_str()), ("after.txt", "tail\n")] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, data.as_bytes())
+            .expect("append");
+    }
+    let mut compressed = Vec::new();
+    lzma_rs::xz_compress(
+        &mut builder.into_inner().expect("tar").as_slice(),
+        &mut compressed,
+    )
+    .expect("xz");
+    let d = tempdir().expect("tempdir");
+    let archive = d.path().join("big.txz");
+    fs::write(&archive, compressed).expect("write");
+
+    assert_eq!(
+        read_archive_member(&archive, "after.txt", 4096).expect("read"),
+        b"tail\n"
+    );
+    let head = read_archive_member(&archive, "big.txt", 100_000).expect("read");
+    assert_eq!(head.len(), 100_000);
+    assert_eq!(&head[99_984..], b"0123456789abcdef");
+}
+
+#[test]
+fn archives_are_entered_and_left_like_directories() {
+    let d = tempdir().expect("tempdir");
+    let archive = d.path().join("demo.zip");
+    write_zip(&archive);
+
+    let mut state = SessionState::new(d.path().to_path_buf());
+    let mut nodes = list_current_directory(d.path(), 100).expect("list");
+    enter_selected_directory(&mut state, &mut nodes).expect("enter");
+    assert_eq!(state.current_path, archive);
+    assert_eq!(nodes[0].name, "docs");
+
+    state.selected_index = 1;
+    enter_selected_directory(&mut state, &mut nodes).expect("enter");
+    assert_eq!(state.current_path, archive.join("src"));
+    assert_eq!(selected_entry_metadata(&nodes[0]).size_text, "34 B");
+
+    go_to_parent_directory(&mut state, &mut nodes).expect("parent");
+    go_to_parent_directory(&mut state, &mut nodes).expect("parent");
+    assert_eq!(state.current_path, d.path());
+    assert_eq!(nodes[state.selected_index].path, archive);
+}
+
+#[test]
+fn archive_and_member_previews() {
+    let d = tempdir().expect("tempdir");
+    let archive = d.path().join("demo.zip");
+    write_zip(&archive);
+    let ctx = HighlightContext::new();
+
+    let mut state = SessionState::new(d.path().to_path_buf());
+    let nodes = list_current_directory(d.path(), 100).expect("list");
+    let mut doc = refresh_preview(&mut state, &nodes, &ctx, 4096);
+    assert_eq!(doc.load_state, LoadState::Loading);
+    assert_eq!(doc.content_excerpt, "Reading archive…");
+    finish(&mut doc, &ctx);
+    assert_eq!(doc.load_state, LoadState::Ready);
+    let lines = doc.content_excerpt.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "3 files, 2 directories, 41 B uncompressed");
+    assert!(lines[4].ends_with("  docs/"), "{}", lines[4]);
+    assert!(lines[6].trim_start().starts_with("34 B  "), "{}", lines[6]);
+    assert!(lines[6].ends_with("  src/main.rs"), "{}", lines[6]);
+
+    let nodes = list_archive_directory(&archive, "src", 100, true).expect("list");
+    let mut doc = refresh_preview(&mut state, &nodes, &ctx, 4096);
+    assert_eq!(doc.load_state, LoadState::Loading);
+    assert_eq!(doc.content_excerpt, "Reading main.rs from the archive…");
+    finish(&mut doc, &ctx);
+    assert_eq!(doc.load_state, LoadState::Ready);
+    assert_eq!(doc.content_type, ContentType::Highlighted);
+    assert_eq!(doc.language_id.as_deref(), Some("Rust"));
+    assert_eq!(doc.source_lines()[1], "    println!(\"hi\");");
+}
@@ -0,0 +1,86 @@
+use clap::Parser;
+use fpv::app::cli::{parse_byte_size, CliArgs, DEFAULT_MAX_PREVIEW_BYTES};
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+#[test]
+fn defaults_match_previous_behavior() {
+    let args = CliArgs::try_parse_from(["fpv"]).expect("parse");
+    assert!(args.target.is_none());
+    assert!(!args.show_hidden);
+    assert!(!args.fullscreen);
+    assert!(!args.no_git);
+    assert!(!args.no_mouse);
+    assert_eq!(args.max_preview_bytes, DEFAULT_MAX_PREVIEW_BYTES);
+    assert_eq!(args.launch_target().root, PathBuf::from("."));
+}
+
+#[test]
+fn all_options_are_parsed() {
+    let args = CliArgs::try_parse_from([
+        "fpv",
+        "project",
+        "--show-hidden",
+        "--fullscreen",
+        "--select",
+        "src/main.rs",
+        "--max-preview-bytes",
+        "512K",
+        "--no-git",
+        "--no-mouse",
+        "--config",
+        "custom.toml",
+    ])
+    .expect("parse");
+    assert_eq!(args.target.as_deref(), Some("project"));
+    assert!(args.show_hidden && args.fullscreen && args.no_git && args.no_mouse);
+    assert_eq!(args.select.as_deref(), Some("src/main.rs"));
+    assert_eq!(args.max_preview_bytes, 512 * 1024);
+    assert_eq!(args.config, Some(PathBuf::from("custom.toml")));
+}
+
+#[test]
+fn unknown_flags_and_bad_values_are_rejected() {
+    assert!(CliArgs::try_parse_from(["fpv", "--bogus"]).is_err());
+    assert!(CliArgs::try_parse_from(["fpv", "--max-preview-bytes", "lots"]).is_err());
+    assert!(CliArgs::try_parse_from(["fpv", "--config"]).is_err());
+}
+
+#[test]
+fn output_options_require_print_or_html_export() {
+    let parse = |args: &[&str]| CliArgs::try_parse_from(args.iter().copied());
+    assert!(parse(&["fpv", "-n"]).is_err());
+    assert!(parse(&["fpv", "-n", "--print", "a.rs"]).is_ok());
+    assert!(parse(&["fpv", "-n", "--export-html", "a.rs"]).is_ok());
+    assert!(parse(&["fpv", "--wrap-width", "80"]).is_err());
+    assert!(parse(&["fpv", "--wrap-width", "80", "--print", "a.rs"]).is_ok());
+    assert!(parse(&["fpv", "--wrap-width", "80", "--export-html", "a.rs"]).is_err());
+    assert!(parse(&["fpv", "--lines", "1-2", "--print", "a.rs"]).is_err());
+}
+
+#[test]
+fn byte_sizes_accept_unit_suffixes() {
+    assert_eq!(parse_byte_size("2048"), Ok(2048));
+    assert_eq!(parse_byte_size("4k"), Ok(4096));
+    assert_eq!(parse_byte_size("2MiB"), Ok(2 * 1024 * 1024));
+    assert!(parse_byte_size("0").is_err());
+    assert!(parse_byte_size("3X").is_err());
+}
+
+#[test]
+fn select_keeps_root_and_starts_in_file_directory() {
+    let d = tempdir().expect("create tempdir");
+    let nested = d.path().join("src/app");
+    fs::create_dir_all(&nested).expect("mkdir");
+    fs::write(nested.join("state.rs"), "a\nb\n").expect("write");
+
+    let root = d.path().to_string_lossy().to_string();
+    let args = CliArgs::try_parse_from(["fpv", root.as_str(), "--select", "src/app/state.rs:2"])
+        .expect("parse");
+    let target = args.launch_target();
+    assert_eq!(target.root, d.path().to_path_buf());
+    assert_eq!(target.start_dir, nested);
+    assert_eq!(target.selected_file, Some(nested.join("state.rs")));
+    assert_eq!(target.line, Some(2));
+}
@@ -0,0 +1,82 @@
+use fpv::app::clipboard::{
+    copy_confirmation, copy_text_for_target, path_relative_to_root, CopyTarget,
+};
+use fpv::app::state::{ContentType, LoadState, PreviewDocument, SessionState};
+use fpv::tui::clipboard::{osc52_sequence, TerminalMultiplexer};
+use std::path::{Path, PathBuf};
+
+#[test]
+fn osc52_sequence_wraps_for_multiplexers() {
+    let plain = osc52_sequence("hi", TerminalMultiplexer::None);
+    assert_eq!(plain, "\x1b]52;c;aGk=\x07");
+
+    let tmux = osc52_sequence("hi", TerminalMultiplexer::Tmux);
+    assert_eq!(tmux, "\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\");
+
+    let screen = osc52_sequence("hi", TerminalMultiplexer::Screen);
+    assert_eq!(screen, "\x1bP\x1b]52;c;aGk=\x07\x1b\\");
+}
+
+#[test]
+fn relative_path_is_computed_from_root() {
+    let rel = path_relative_to_root(Path::new("/work/fpv"), Path::new("/work/fpv/src/lib.rs"));
+    assert_eq!(rel, PathBuf::from("src/lib.rs"));
+
+    let same = path_relative_to_root(Path::new("/work/fpv"), Path::new("/work/fpv"));
+    assert_eq!(same, PathBuf::from("."));
+
+    let outside = path_relative_to_root(Path::new("/work/fpv"), Path::new("/etc/hosts"));
+    assert_eq!(outside, PathBuf::from("/etc/hosts"));
+}
+
+#[test]
+fn copy_text_uses_selected_path_and_preview() {
+    let mut state = SessionState::new(PathBuf::from("/work/fpv"));
+    state.selected_path = PathBuf::from("/work/fpv/./src/main.rs");
+    let doc = PreviewDocument {
+        load_state: LoadState::Ready,
+        content_type: ContentType::PlainText,
+        content_excerpt: "fn main() {}\n\n[truncated]".to_string(),
+        ..PreviewDocument::default()
+    };
+
+    assert_eq!(
+        copy_text_for_target(&state, &doc, CopyTarget::AbsolutePath).as_deref(),
+        Some("/work/fpv/src/main.rs")
+    );
+    assert_eq!(
+        copy_text_for_target(&state, &doc, CopyTarget::RelativePath).as_deref(),
+        Some("src/main.rs")
+    );
+    assert_eq!(
+        copy_text_for_target(&state, &doc, CopyTarget::FileName).as_deref(),
+        Some("main.rs")
+    );
+    assert_eq!(
+        copy_text_for_target(&state, &doc, CopyTarget::PreviewSelection).as_deref(),
+        Some("fn main() {}")
+    );
+}
+
+#[test]
+fn copy_preview_selection_is_empty_for_binary_preview() {
+    let state = SessionState::new(PathBuf::from("."));
+    let doc = PreviewDocument {
+        load_state: LoadState::Binary,
+        error_message: Some("Binary file preview is not supported.".to_string()),
+        ..PreviewDocument::default()
+    };
+    assert!(copy_text_for_target(&state, &doc, CopyTarget::PreviewSelection).is_none());
+}
+
+#[test]
+fn copy_confirmation_mentions_target() {
+    assert_eq!(
+        copy_confirmation(CopyTarget::FileName, "main.rs"),
+        "Copied file name: main.rs"
+    );
+    assert_eq!(
+        copy_confirmation(CopyTarget::PreviewSelection, "a\nb"),
+        "Copied preview selection (2 lines)."
+    );
+}
@@ -0,0 +1,224 @@
+use fpv::config::load::{
+    load_theme_color_scheme, load_user_config, resolve_config_path, ThemeProfile, UserThemeConfig,
+};
+use fpv::config::merge::{merge_syntax_theme, merge_theme_profile};
+use fpv::config::scheme::{load_color_scheme, parse_base16_scheme, parse_tm_theme};
+use fpv::highlight::theme::PageColors;
+use ratatui::style::{Color, Modifier};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+const OCEAN: &str = r#"
+scheme: "Ocean"
+author: "Chris Kempson"
+base00: "2b303b"
+base01: "343d46"
+base02: "4f5b66"
+base03: "65737e"
+base04: "a7adba"
+base05: "c0c5ce"
+base06: "dfe1e8"
+base07: "eff1f5"
+base08: "bf616a"
+base09: "d08770"
+base0A: "ebcb8b"
+base0B: "a3be8c"
+base0C: "96b5b4"
+base0D: "8fa1b3"
+base0E: "b48ead"
+base0F: "ab7967"
+"#;
+
+const TM_THEME: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>name</key><string>Sample</string>
+  <key>settings</key>
+  <array>
+    <dict>
+      <key>settings</key>
+      <dict>
+        <key>background</key><string>#272822</string>
+        <key>foreground</key><string>#F8F8F2</string>
+        <key>lineHighlight</key><string>#3E3D32</string>
+      </dict>
+    </dict>
+    <dict>
+      <key>scope</key><string>comment</string>
+      <key>settings</key>
+      <dict>
+        <key>foreground</key><string>#75715E</string>
+        <key>fontStyle</key><string>italic</string>
+      </dict>
+    </dict>
+    <dict>
+      <key>scope</key><string>keyword, storage</string>
+      <key>settings</key>
+      <dict><key>foreground</key><string>#F92672</string></dict>
+    </dict>
+    <dict>
+      <key>scope</key><string>keyword.operator</string>
+      <key>settings</key>
+      <dict><key>foreground</key><string>#F8F8F2</string></dict>
+    </dict>
+    <dict>
+      <key>scope</key><string>string</string>
+      <key>settings</key>
+      <dict><key>foreground</key><string>#E6DB74FF</string></dict>
+    </dict>
+    <dict>
+      <key>scope</key><string>entity.name.function</string>
+      <key>settings</key>
+      <dict>
+        <key>foreground</key><string>#A6E22E</string>
+        <key>fontStyle</key><string>bold underline</string>
+      </dict>
+    </dict>
+  </array>
+</dict>
+</plist>
+"#;
+
+fn style_of<'a>(
+    syntax: &'a [(String, ratatui::style::Style)],
+    capture: &str,
+) -> Option<&'a ratatui::style::Style> {
+    syntax
+        .iter()
+        .find(|(name, _)| name == capture)
+        .map(|(_, style)| style)
+}
+
+#[test]
+fn base16_slots_map_to_captures_and_ui_colors() {
+    let scheme = parse_base16_scheme(OCEAN).expect("parse base16");
+    let keyword = style_of(&scheme.syntax, "keyword").expect("keyword");
+    assert_eq!(keyword.fg, Some(Color::Rgb(0xb4, 0x8e, 0xad)));
+    let comment = style_of(&scheme.syntax, "comment").expect("comment");
+    assert_eq!(comment.fg, Some(Color::Rgb(0x65, 0x73, 0x7e)));
+    assert!(comment.add_modifier.contains(Modifier::ITALIC));
+    assert_eq!(scheme.directory_color.as_deref(), Some("#8fa1b3"));
+    assert_eq!(scheme.fallback_file_color.as_deref(), Some("#c0c5ce"));
+    assert_eq!(scheme.status_foreground_color.as_deref(), Some("#a7adba"));
+    assert_eq!(scheme.status_background_color.as_deref(), Some("#343d46"));
+    assert_eq!(
+        scheme.page_colors,
+        Some(PageColors {
+            background: Color::Rgb(0x2b, 0x30, 0x3b),
+            foreground: Color::Rgb(0xc0, 0xc5, 0xce),
+        })
+    );
+}
+
+#[test]
+fn base16_accepts_nested_palette_layout() {
+    let nested = format!(
+        "system: base16\nname: Ocean\npalette:\n{}",
+        OCEAN
+            .lines()
+            .filter(|line| line.starts_with("base"))
+            .map(|line| format!("  {}\n", line.replace(": \"", ": \"#")))
+            .collect::<String>()
+    );
+    let scheme = parse_base16_scheme(&nested).expect("parse nested palette");
+    assert_eq!(scheme, parse_base16_scheme(OCEAN).expect("parse flat"));
+}
+
+#[test]
+fn base16_reports_missing_slots() {
+    let err = parse_base16_scheme("base00: \"000000\"\n").expect_err("incomplete scheme");
+    assert!(err.to_string().contains("missing base"));
+}
+
+#[test]
+fn tm_theme_scopes_resolve_by_specificity() {
+    let scheme = parse_tm_theme(TM_THEME.as_bytes()).expect("parse tmTheme");
+    assert_eq!(
+        style_of(&scheme.syntax, "keyword").and_then(|s| s.fg),
+        Some(Color::Rgb(0xf9, 0x26, 0x72))
+    );
+    assert_eq!(
+        style_of(&scheme.syntax, "operator").and_then(|s| s.fg),
+        Some(Color::Rgb(0xf8, 0xf8, 0xf2))
+    );
+    assert_eq!(
+        style_of(&scheme.syntax, "string").and_then(|s| s.fg),
+        Some(Color::Rgb(0xe6, 0xdb, 0x74))
+    );
+    let function = style_of(&scheme.syntax, "function").expect("function");
+    assert!(function
+        .add_modifier
+        .contains(Modifier::BOLD | Modifier::UNDERLINED));
+    assert!(style_of(&scheme.syntax, "tag").is_none());
+    assert_eq!(scheme.directory_color.as_deref(), Some("#a6e22e"));
+    assert_eq!(scheme.status_background_color.as_deref(), Some("#3e3d32"));
+    assert_eq!(
+        scheme.page_colors.map(|page| page.background),
+        Some(Color::Rgb(0x27, 0x28, 0x22))
+    );
+}
+
+#[test]
+fn scheme_paths_resolve_relative_to_config_dir() {
+    let config = Path::new("/etc/fpv/config");
+    assert_eq!(
+        resolve_config_path("schemes/ocean.yaml", config),
+        Path::new("/etc/fpv/schemes/ocean.yaml")
+    );
+    assert_eq!(
+        resolve_config_path("/opt/ocean.yaml", config),
+        Path::new("/opt/ocean.yaml")
+    );
+}
+
+#[test]
+fn unsupported_scheme_extension_is_an_error() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("ocean.json");
+    fs::write(&p, "{}").expect("write scheme");
+    assert!(load_color_scheme(&p).is_err());
+}
+
+#[test]
+fn config_color_scheme_layers_under_explicit_settings() {
+    let d = tempdir().expect("create tempdir");
+    fs::write(d.path().join("ocean.yaml"), OCEAN).expect("write scheme");
+    let config = d.path().join("config");
+    fs::write(
+        &config,
+        "[theme]\ncolor_scheme = 'ocean.yaml'\ndirectory_color = 'yellow'\n\n[theme.syntax]\nstring = { fg = 'red' }\n",
+    )
+    .expect("write config");
+    let user = load_user_config(&config).expect("load config");
+    let imported = load_theme_color_scheme(&user.theme, &config)
+        .expect("load scheme")
+        .expect("scheme configured");
+
+    let profile = merge_theme_profile(
+        imported.apply_to_profile(ThemeProfile::default()),
+        &user.theme,
+    );
+    assert_eq!(profile.directory_color, "yellow");
+    assert_eq!(profile.fallback_file_color, "#c0c5ce");
+
+    let (syntax, warnings) = merge_syntax_theme(&user.theme, Some(&imported));
+    assert!(warnings.is_empty(), "{warnings:?}");
+    assert_eq!(syntax.style_for_capture("string").fg, Some(Color::Red));
+    assert_eq!(
+        syntax.style_for_capture("function.method").fg,
+        Some(Color::Rgb(0x8f, 0xa1, 0xb3))
+    );
+}
+
+#[test]
+fn missing_color_scheme_is_reported() {
+    let user = UserThemeConfig {
+        color_scheme: Some("absent.tmTheme".to_string()),
+        ..UserThemeConfig::default()
+    };
+    let err =
+        load_theme_color_scheme(&user, Path::new("/nonexistent/config")).expect_err("missing file");
+    assert!(format!("{err:#}").contains("absent.tmTheme"));
+}
@@ -0,0 +1,193 @@
+use fpv::app::state::StyledPreviewSegment;
+use fpv::config::load::{load_user_config, ColorMode};
+use fpv::export::ansi::{render_ansi_lines, AnsiRenderOptions};
+use fpv::tui::color_support::{
+    adapt_buffer, adapt_color, adapt_style, detect_color_support, resolve_color_support,
+    rgb_to_ansi16, rgb_to_ansi256, ColorEnvironment, ColorSupport,
+};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use std::fs;
+use tempfile::tempdir;
+
+fn env(colorterm: Option<&str>, term: Option<&str>, no_color: bool) -> ColorEnvironment {
+    ColorEnvironment {
+        colorterm: colorterm.map(str::to_string),
+        term: term.map(str::to_string),
+        no_color,
+    }
+}
+
+#[test]
+fn detection_reads_colorterm_then_term() {
+    assert_eq!(
+        detect_color_support(&env(Some("truecolor"), Some("xterm"), false)),
+        ColorSupport::TrueColor
+    );
+    assert_eq!(
+        detect_color_support(&env(Some("24bit"), None, false)),
+        ColorSupport::TrueColor
+    );
+    assert_eq!(
+        detect_color_support(&env(None, Some("xterm-256color"), false)),
+        ColorSupport::Ansi256
+    );
+    assert_eq!(
+        detect_color_support(&env(None, Some("xterm-direct"), false)),
+        ColorSupport::TrueColor
+    );
+    assert_eq!(
+        detect_color_support(&env(None, Some("linux"), false)),
+        ColorSupport::Ansi16
+    );
+    assert_eq!(
+        detect_color_support(&env(None, Some("dumb"), false)),
+        ColorSupport::Monochrome
+    );
+    assert_eq!(
+        detect_color_support(&env(None, None, false)),
+        ColorSupport::Monochrome
+    );
+}
+
+#[test]
+fn color_mode_resolution_honors_no_color_and_terminals() {
+    let truecolor = env(Some("truecolor"), Some("xterm-256color"), false);
+    let no_color = env(Some("truecolor"), Some("xterm-256color"), true);
+    assert_eq!(
+        resolve_color_support(ColorMode::Auto, true, &truecolor),
+        ColorSupport::TrueColor
+    );
+    assert_eq!(
+        resolve_color_support(ColorMode::Auto, false, &truecolor),
+        ColorSupport::Monochrome
+    );
+    assert_eq!(
+        resolve_color_support(ColorMode::Auto, true, &no_color),
+        ColorSupport::Monochrome
+    );
+    assert_eq!(
+        resolve_color_support(ColorMode::Always, false, &no_color),
+        ColorSupport::TrueColor
+    );
+    assert_eq!(
+        resolve_color_support(ColorMode::Always, true, &env(None, Some("dumb"), false)),
+        ColorSupport::Ansi16
+    );
+    assert_eq!(
+        resolve_color_support(ColorMode::Never, true, &truecolor),
+        ColorSupport::Monochrome
+    );
+}
+
+#[test]
+fn rgb_quantizes_to_cube_and_gray_ramp() {
+    assert_eq!(rgb_to_ansi256(255, 0, 0), 196);
+    assert_eq!(rgb_to_ansi256(0, 0, 0), 16);
+    assert_eq!(rgb_to_ansi256(128, 128, 128), 244);
+    assert_eq!(rgb_to_ansi256(220, 150, 80), 173);
+}
+
+#[test]
+fn rgb_quantizes_to_basic_colors_by_hue() {
+    assert_eq!(rgb_to_ansi16(220, 150, 80), Color::LightYellow);
+    assert_eq!(rgb_to_ansi16(140, 200, 130), Color::Green);
+    assert_eq!(rgb_to_ansi16(120, 170, 230), Color::LightBlue);
+    assert_eq!(rgb_to_ansi16(120, 150, 120), Color::DarkGray);
+    assert_eq!(rgb_to_ansi16(160, 40, 40), Color::Red);
+    assert_eq!(rgb_to_ansi16(30, 30, 30), Color::Black);
+    assert_eq!(rgb_to_ansi16(255, 255, 255), Color::White);
+}
+
+#[test]
+fn adapt_color_keeps_representable_colors() {
+    assert_eq!(
+        adapt_color(Color::Rgb(1, 2, 3), ColorSupport::TrueColor),
+        Some(Color::Rgb(1, 2, 3))
+    );
+    assert_eq!(
+        adapt_color(Color::Indexed(200), ColorSupport::Ansi256),
+        Some(Color::Indexed(200))
+    );
+    assert_eq!(
+        adapt_color(Color::Indexed(9), ColorSupport::Ansi16),
+        Some(Color::LightRed)
+    );
+    assert_eq!(
+        adapt_color(Color::Cyan, ColorSupport::Ansi16),
+        Some(Color::Cyan)
+    );
+    assert_eq!(adapt_color(Color::Cyan, ColorSupport::Monochrome), None);
+}
+
+#[test]
+fn monochrome_styles_keep_modifiers_and_reverse_backgrounds() {
+    let selection = Style::default()
+        .bg(Color::Rgb(58, 64, 90))
+        .add_modifier(Modifier::BOLD);
+    let adapted = adapt_style(selection, ColorSupport::Monochrome);
+    assert_eq!(adapted.fg, None);
+    assert_eq!(adapted.bg, None);
+    assert!(adapted
+        .add_modifier
+        .contains(Modifier::BOLD | Modifier::REVERSED));
+
+    let comment = Style::default()
+        .fg(Color::Rgb(120, 150, 120))
+        .add_modifier(Modifier::ITALIC);
+    let adapted = adapt_style(comment, ColorSupport::Monochrome);
+    assert_eq!(adapted.fg, None);
+    assert_eq!(adapted.add_modifier, Modifier::ITALIC);
+}
+
+#[test]
+fn adapt_buffer_rewrites_every_cell() {
+    let mut buffer = Buffer::empty(Rect::new(0, 0, 2, 1));
+    buffer.set_string(
+        0,
+        0,
+        "ab",
+        Style::default()
+            .fg(Color::Rgb(255, 0, 0))
+            .bg(Color::DarkGray),
+    );
+    adapt_buffer(&mut buffer, ColorSupport::Ansi256);
+    assert_eq!(buffer.get(0, 0).fg, Color::Indexed(196));
+    assert_eq!(buffer.get(1, 0).bg, Color::DarkGray);
+
+    adapt_buffer(&mut buffer, ColorSupport::Monochrome);
+    let cell = buffer.get(0, 0);
+    assert_eq!((cell.fg, cell.bg), (Color::Reset, Color::Reset));
+    assert!(cell.modifier.contains(Modifier::REVERSED));
+}
+
+#[test]
+fn ansi_output_uses_quantized_sgr() {
+    let lines = vec![vec![StyledPreviewSegment {
+        text: "fn".to_string(),
+        style: Style::default().fg(Color::Rgb(255, 0, 0)),
+    }]];
+    let render = |color| {
+        render_ansi_lines(
+            &lines,
+            AnsiRenderOptions {
+                color,
+                line_numbers: false,
+                wrap_width: None,
+            },
+        )
+    };
+    assert_eq!(render(ColorSupport::Ansi256), "\x1b[38;5;196mfn\x1b[0m\n");
+    assert_eq!(render(ColorSupport::Ansi16), "\x1b[91mfn\x1b[0m\n");
+    assert_eq!(render(ColorSupport::Monochrome), "fn\n");
+}
+
+#[test]
+fn color_option_parses_from_config() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("config.toml");
+    fs::write(&p, "color = \"never\"\n").expect("write file");
+    let cfg = load_user_config(&p).expect("load config");
+    assert_eq!(cfg.color, Some(ColorMode::Never));
+}
@@ -33,6 +33,7 @@ fn theme_merge_overrides_selected_keys_only() {
         fallback_file_color: None,
         hidden_dim_enabled: Some(false),
         file_type_colors: HashMap::from([("md".to_string(), "magenta".to_string())]),
+        ..UserThemeConfig::default()
     };
     let merged = merge_theme_profile(defaults, &overrides);
     assert_eq!(merged.directory_color, "blue");
@@ -87,3 +87,26 @@ fn ensure_default_config_creates_file_on_first_run() {
     assert_eq!(cfg.mappings.get("toggle_help"), Some(&"?".to_string()));
     assert_eq!(cfg.mappings.get("toggle_hidden"), Some(&"h".to_string()));
 }
+
+#[test]
+fn parse_syntax_theme_section_from_config_file() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("config.toml");
+    fs::write(
+        &p,
+        "[theme]\nsyntax_palette = 'light'\n\n[theme.syntax]\ncomment = { fg = '#6a737d', modifiers = ['italic'] }\n\"function.builtin\" = { fg = 'cyan', bg = 'black' }\n",
+    )
+    .expect("write file");
+    let cfg = load_user_config(&p).expect("load config");
+    assert_eq!(cfg.theme.syntax_palette.as_deref(), Some("light"));
+    let comment = cfg.theme.syntax.get("comment").expect("comment entry");
+    assert_eq!(comment.fg.as_deref(), Some("#6a737d"));
+    assert_eq!(comment.modifiers, vec!["italic".to_string()]);
+    assert_eq!(
+        cfg.theme
+            .syntax
+            .get("function.builtin")
+            .and_then(|entry| entry.bg.as_deref()),
+        Some("black")
+    );
+}
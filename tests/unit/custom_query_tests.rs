@@ -0,0 +1,157 @@
+use fpv::config::load::load_user_config;
+use fpv::highlight::custom::UserGrammarConfig;
+use fpv::highlight::render::render_with_highlight;
+use fpv::highlight::syntax::HighlightContext;
+use ratatui::style::Style;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::{tempdir, TempDir};
+
+/// A config dir holding `queries/<lang>/<file>` for each entry.
+fn config_with_queries(queries: &[(&str, &str, &str)]) -> (TempDir, PathBuf) {
+    let dir = tempdir().expect("tempdir");
+    for (language, file, query) in queries {
+        let lang_dir = dir.path().join("queries").join(language);
+        fs::create_dir_all(&lang_dir).expect("mkdir");
+        fs::write(lang_dir.join(file), query).expect("write");
+    }
+    let config = dir.path().join("config");
+    (dir, config)
+}
+
+fn style_of(ctx: &HighlightContext, path: &str, content: &str, text: &str) -> Option<Style> {
+    render_with_highlight(ctx, Path::new(path), content)
+        .styled_lines
+        .iter()
+        .flatten()
+        .find(|segment| segment.text == text)
+        .map(|segment| segment.style)
+}
+
+#[test]
+fn query_override_replaces_builtin_highlights() {
+    let (_dir, config) =
+        config_with_queries(&[("rust", "highlights.scm", "(line_comment) @keyword\n")]);
+    let mut ctx = HighlightContext::new();
+    assert!(ctx.load_query_overrides(&config).is_empty());
+
+    let keyword = ctx.syntax_theme().style_for_capture("keyword");
+    let source = "fn main() {}\n// note\n";
+    assert_eq!(style_of(&ctx, "main.rs", source, "// note"), Some(keyword));
+    assert_eq!(
+        style_of(&ctx, "main.rs", source, "fn main() {}"),
+        Some(Style::default())
+    );
+}
+
+#[test]
+fn extends_query_keeps_builtin_highlights() {
+    let (_dir, config) = config_with_queries(&[(
+        "rs",
+        "highlights.scm",
+        "; extends\n(let_declaration pattern: (identifier) @conditional)\n",
+    )]);
+    let mut ctx = HighlightContext::new();
+    assert!(ctx.load_query_overrides(&config).is_empty());
+
+    let theme = ctx.syntax_theme();
+    let source = "fn main() { let total = 1; } // note\n";
+    assert_eq!(
+        style_of(&ctx, "main.rs", source, "total"),
+        Some(theme.style_for_capture("keyword"))
+    );
+    assert_eq!(
+        style_of(&ctx, "main.rs", source, "// note"),
+        Some(theme.style_for_capture("comment"))
+    );
+}
+
+#[test]
+fn invalid_query_is_reported_and_builtin_kept() {
+    let (_dir, config) = config_with_queries(&[
+        ("python", "highlights.scm", "(not_a_python_node) @keyword\n"),
+        ("klingon", "highlights.scm", "(comment) @comment\n"),
+    ]);
+    let mut ctx = HighlightContext::new();
+    let warnings = ctx.load_query_overrides(&config);
+    assert_eq!(warnings.len(), 2, "{warnings:?}");
+    assert!(warnings[0].contains("'klingon'"), "{warnings:?}");
+    assert!(warnings[1].starts_with("queries for 'python' ignored"));
+
+    assert_eq!(
+        style_of(&ctx, "app.py", "def run():\n    pass\n", "def"),
+        Some(ctx.syntax_theme().style_for_capture("keyword"))
+    );
+}
+
+#[test]
+fn missing_queries_dir_is_not_an_error() {
+    let dir = tempdir().expect("tempdir");
+    let mut ctx = HighlightContext::new();
+    assert!(ctx
+        .load_query_overrides(&dir.path().join("config"))
+        .is_empty());
+}
+
+#[test]
+fn grammars_parse_from_config() {
+    let dir = tempdir().expect("tempdir");
+    let path = dir.path().join("config");
+    fs::write(
+        &path,
+        r#"
+[[grammars]]
+name = "just"
+library = "grammars/just.so"
+files = ["justfile", "*.just"]
+
+[[grammars]]
+name = "kdl"
+library = "~/lib/kdl.so"
+symbol = "tree_sitter_kdl_v2"
+"#,
+    )
+    .expect("write config");
+    let config = load_user_config(&path).expect("parse config");
+    assert_eq!(
+        config.grammars,
+        vec![
+            UserGrammarConfig {
+                name: "just".to_string(),
+                library: "grammars/just.so".to_string(),
+                symbol: None,
+                files: vec!["justfile".to_string(), "*.just".to_string()],
+            },
+            UserGrammarConfig {
+                name: "kdl".to_string(),
+                library: "~/lib/kdl.so".to_string(),
+                symbol: Some("tree_sitter_kdl_v2".to_string()),
+                files: Vec::new(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn missing_grammar_library_is_a_warning() {
+    let (dir, config) = config_with_queries(&[("just", "highlights.scm", "")]);
+    let grammar = UserGrammarConfig {
+        name: "just".to_string(),
+        library: "grammars/just.so".to_string(),
+        symbol: None,
+        files: vec!["justfile".to_string()],
+    };
+    let mut ctx = HighlightContext::new();
+    let warnings = ctx.load_custom_grammars(&[grammar], &config);
+    assert_eq!(warnings.len(), 1, "{warnings:?}");
+    assert!(warnings[0].starts_with("grammar 'just' ignored: cannot load"));
+    assert!(warnings[0].contains(&dir.path().join("grammars/just.so").display().to_string()));
+    assert!(ctx.target_for_path(Path::new("justfile")).is_none());
+
+    // The unloaded grammar's query dir is reported as an unknown language.
+    let warnings = ctx.load_query_overrides(&config);
+    assert_eq!(
+        warnings,
+        vec!["queries for unknown language 'just' ignored"]
+    );
+}
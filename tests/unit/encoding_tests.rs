@@ -0,0 +1,174 @@
+use fpv::app::state::{ContentType, LoadState, SelectedEntryMetadata, SessionState};
+use fpv::config::keymap::{action_from_name, Action};
+use fpv::fs::encoding::{detect_line_ending, LineEnding, TextEncoding, TextFormat};
+use fpv::fs::preview::{load_preview, load_preview_with_encoding};
+use fpv::highlight::syntax::HighlightContext;
+use fpv::tui::status_bar::compose_preview_metadata_line_with_format;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::{tempdir, TempDir};
+
+fn write_bytes(name: &str, bytes: &[u8]) -> (TempDir, PathBuf) {
+    let dir = tempdir().expect("tempdir");
+    let path = dir.path().join(name);
+    fs::write(&path, bytes).expect("write file");
+    (dir, path)
+}
+
+fn utf16le_with_bom(text: &str) -> Vec<u8> {
+    let mut bytes = vec![0xff, 0xfe];
+    bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    bytes
+}
+
+#[test]
+fn utf16_file_with_bom_is_decoded_and_highlighted() {
+    let (_dir, path) = write_bytes("main.py", &utf16le_with_bom("print('héllo')\n"));
+    let doc = load_preview(&path, 1024, &HighlightContext::new());
+    assert_eq!(doc.load_state, LoadState::Ready);
+    assert_eq!(doc.content_type, ContentType::Highlighted);
+    assert_eq!(doc.source_lines()[0], "print('héllo')");
+    let format = doc.text_format.expect("text format");
+    assert_eq!(format.encoding, TextEncoding::Utf16Le);
+    assert_eq!(format.line_ending, Some(LineEnding::Lf));
+}
+
+#[test]
+fn utf8_bom_is_stripped_from_the_first_line() {
+    let (_dir, path) = write_bytes("notes.txt", b"\xef\xbb\xbfhello\r\nworld\r\n");
+    let doc = load_preview(&path, 1024, &HighlightContext::new());
+    assert_eq!(doc.source_lines()[0], "hello");
+    let format = doc.text_format.expect("text format");
+    assert_eq!(format.encoding, TextEncoding::Utf8Bom);
+    assert_eq!(format.line_ending, Some(LineEnding::Crlf));
+}
+
+#[test]
+fn legacy_single_byte_files_are_decoded_before_highlighting() {
+    let (_dir, path) = write_bytes("price.py", b"price = '\x80 5' # caf\xe9\n");
+    let doc = load_preview(&path, 1024, &HighlightContext::new());
+    assert_eq!(doc.content_type, ContentType::Highlighted);
+    assert_eq!(doc.fallback_reason, None);
+    assert_eq!(doc.source_lines()[0], "price = '€ 5' # café");
+    assert_eq!(
+        doc.text_format.map(|format| format.encoding),
+        Some(TextEncoding::Windows1252)
+    );
+
+    let (_dir, path) = write_bytes("cafe.txt", b"caf\xe9 cr\xe8me br\xfbl\xe9e\n");
+    let doc = load_preview(&path, 1024, &HighlightContext::new());
+    assert_eq!(doc.source_lines()[0], "café crème brûlée");
+    assert_eq!(
+        doc.text_format.map(|format| format.encoding),
+        Some(TextEncoding::Latin1)
+    );
+}
+
+#[test]
+fn cjk_legacy_encodings_are_guessed() {
+    let japanese = "これは日本語のテキストです。東京の天気は晴れです。\n".repeat(4);
+    let (bytes, _, _) = encoding_rs::SHIFT_JIS.encode(&japanese);
+    let (_dir, path) = write_bytes("jp.txt", &bytes);
+    let doc = load_preview(&path, 4096, &HighlightContext::new());
+    assert_eq!(
+        doc.text_format.map(|format| format.encoding),
+        Some(TextEncoding::ShiftJis)
+    );
+    assert!(doc.source_lines()[0].starts_with("これは日本語"));
+
+    let chinese = "这是一个中文文本文件。今天北京的天气很好，我们去公园散步。\n".repeat(4);
+    let (bytes, _, _) = encoding_rs::GBK.encode(&chinese);
+    let (_dir, path) = write_bytes("zh.txt", &bytes);
+    let doc = load_preview(&path, 4096, &HighlightContext::new());
+    assert_eq!(
+        doc.text_format.map(|format| format.encoding),
+        Some(TextEncoding::Gbk)
+    );
+    assert!(doc.source_lines()[0].starts_with("这是一个中文"));
+}
+
+#[test]
+fn truncation_inside_a_character_is_not_a_decode_error() {
+    let (_dir, path) = write_bytes("long.txt", "é".repeat(100).as_bytes());
+    let doc = load_preview(&path, 51, &HighlightContext::new());
+    assert_eq!(doc.fallback_reason, None);
+    assert_eq!(
+        doc.text_format.map(|format| format.encoding),
+        Some(TextEncoding::Utf8)
+    );
+}
+
+#[test]
+fn paged_files_decode_lines_with_the_detected_encoding() {
+    let (_dir, path) = write_bytes("big.txt", &b"caf\xe9 \x80\r\n".repeat(2_000));
+    let doc = load_preview(&path, 1024, &HighlightContext::new());
+    assert!(doc.paged.is_some());
+    assert_eq!(doc.source_lines()[0], "café €");
+    let format = doc.text_format.expect("text format");
+    assert_eq!(format.encoding, TextEncoding::Windows1252);
+    assert_eq!(format.line_ending, Some(LineEnding::Crlf));
+}
+
+#[test]
+fn forced_encoding_overrides_detection() {
+    let (_dir, path) = write_bytes("notes.txt", "café\n".as_bytes());
+    let ctx = HighlightContext::new();
+    let doc = load_preview_with_encoding(&path, 1024, &ctx, Some(TextEncoding::Latin1));
+    assert_eq!(doc.source_lines()[0], "cafÃ©");
+    let format = doc.text_format.expect("text format");
+    assert!(format.forced);
+    assert_eq!(format.label(), "Latin-1 (forced) LF");
+}
+
+#[test]
+fn cycling_the_forced_encoding_returns_to_detection() {
+    let mut state = SessionState::new(PathBuf::from("."));
+    let path = PathBuf::from("notes.txt");
+    assert_eq!(
+        state.cycle_forced_encoding(&path, Some(TextEncoding::Utf8)),
+        Some(TextEncoding::Utf16Le)
+    );
+    assert_eq!(
+        state.forced_encoding_for(&path),
+        Some(TextEncoding::Utf16Le)
+    );
+    assert_eq!(state.forced_encoding_for(&PathBuf::from("other.txt")), None);
+
+    let mut current = Some(TextEncoding::Utf16Le);
+    while let Some(encoding) = current {
+        current = state.cycle_forced_encoding(&path, Some(encoding));
+    }
+    assert_eq!(state.forced_encoding_for(&path), None);
+    assert_eq!(
+        action_from_name("cycle_encoding"),
+        Some(Action::CycleEncoding)
+    );
+}
+
+#[test]
+fn line_endings_are_classified() {
+    assert_eq!(detect_line_ending("one line"), None);
+    assert_eq!(detect_line_ending("a\nb\n"), Some(LineEnding::Lf));
+    assert_eq!(detect_line_ending("a\r\nb\r\n"), Some(LineEnding::Crlf));
+    assert_eq!(detect_line_ending("a\rb\r"), Some(LineEnding::Cr));
+    assert_eq!(detect_line_ending("a\r\nb\n"), Some(LineEnding::Mixed));
+}
+
+#[test]
+fn metadata_line_shows_encoding_and_line_endings() {
+    let metadata = SelectedEntryMetadata {
+        filename: "legacy.txt".to_string(),
+        size_text: "12 B".to_string(),
+        ..SelectedEntryMetadata::default()
+    };
+    let format = TextFormat {
+        encoding: TextEncoding::ShiftJis,
+        line_ending: Some(LineEnding::Crlf),
+        forced: false,
+    };
+    let line = compose_preview_metadata_line_with_format(&metadata, None, Some(&format), 200);
+    assert!(
+        line.starts_with("Unknown(.txt) | Shift_JIS CRLF | 12 B"),
+        "{line}"
+    );
+}
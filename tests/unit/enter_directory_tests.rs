@@ -19,6 +19,7 @@ fn enter_directory_updates_current_path() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     }];
 
     let result = enter_selected_directory(&mut state, &mut nodes).expect("enter");
@@ -37,6 +38,7 @@ fn entering_file_is_no_change() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     }];
     let result = enter_selected_directory(&mut state, &mut nodes).expect("result");
     assert_eq!(result.new_path, state.current_path);
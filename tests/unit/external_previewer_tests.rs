@@ -0,0 +1,270 @@
+use fpv::app::preview_controller::refresh_preview;
+use fpv::app::state::{
+    ContentType, LoadState, NodeType, PreviewDocument, SessionState, StyledPreviewLine, TreeNode,
+};
+use fpv::config::load::load_user_config;
+use fpv::fs::external::{
+    compile_previewers, poll_external_preview, previewer_for, start_external_preview,
+};
+use fpv::highlight::ansi::parse_ansi_lines;
+use fpv::highlight::syntax::HighlightContext;
+use indexmap::IndexMap;
+use ratatui::style::{Color, Modifier};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+fn previewers(entries: &[(&str, &str)]) -> IndexMap<String, String> {
+    entries
+        .iter()
+        .map(|(key, command)| (key.to_string(), command.to_string()))
+        .collect()
+}
+
+fn write_file(dir: &Path, name: &str) -> PathBuf {
+    let path = dir.join(name);
+    fs::write(&path, "contents\n").expect("write file");
+    path
+}
+
+/// Polls `doc` until its command finishes.
+fn finish(doc: &mut PreviewDocument) {
+    let started = Instant::now();
+    while !poll_external_preview(doc) {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "previewer never finished"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn sgr_sequences_become_styled_segments() {
+    let lines = parse_ansi_lines(
+        "plain \x1b[1;31mbold red\x1b[0m\n\x1b[38;5;208mindexed\x1b[39m \x1b[48;2;1;2;3mrgb\x1b[m\n",
+    );
+    assert_eq!(lines.len(), 2);
+    let first = &lines[0];
+    assert_eq!(first[0].text, "plain ");
+    assert_eq!(first[0].style.fg, None);
+    assert_eq!(first[1].text, "bold red");
+    assert_eq!(first[1].style.fg, Some(Color::Red));
+    assert!(first[1].style.add_modifier.contains(Modifier::BOLD));
+
+    let second = &lines[1];
+    assert_eq!(second[0].text, "indexed");
+    assert_eq!(second[0].style.fg, Some(Color::Indexed(208)));
+    assert_eq!(second[1].text, " ");
+    assert_eq!(second[1].style.fg, None);
+    assert_eq!(second[2].text, "rgb");
+    assert_eq!(second[2].style.bg, Some(Color::Rgb(1, 2, 3)));
+}
+
+#[test]
+fn colon_separated_sgr_parameters_stay_one_parameter() {
+    let lines = parse_ansi_lines(
+        "\x1b[4:3mcurly\x1b[4:0m \x1b[38:2::1:2:3mrgb\x1b[38:2:4:5:6mbare\x1b[38:5:9;1mindexed\x1b[58:2::7:8:9;32mgreen\n",
+    );
+    let line = &lines[0];
+    assert_eq!(line[0].text, "curly");
+    assert!(line[0].style.add_modifier.contains(Modifier::UNDERLINED));
+    assert_eq!(line[1].text, " ");
+    assert!(!line[1].style.add_modifier.contains(Modifier::UNDERLINED));
+    assert_eq!(line[2].text, "rgb");
+    assert_eq!(line[2].style.fg, Some(Color::Rgb(1, 2, 3)));
+    assert_eq!(line[3].text, "bare");
+    assert_eq!(line[3].style.fg, Some(Color::Rgb(4, 5, 6)));
+    assert_eq!(line[4].text, "indexed");
+    assert_eq!(line[4].style.fg, Some(Color::Indexed(9)));
+    assert!(line[4].style.add_modifier.contains(Modifier::BOLD));
+    assert_eq!(line[5].text, "green");
+    assert_eq!(line[5].style.fg, Some(Color::Green));
+}
+
+#[test]
+fn other_escape_sequences_and_control_characters_are_dropped() {
+    let lines =
+        parse_ansi_lines("\x1b]0;title\x07a\x1b[2Kb\x1b(Bc\x1b]8;;http://x\x1b\\link\r\n\tx\x07y");
+    let text = lines
+        .iter()
+        .map(|line| {
+            line.iter()
+                .map(|segment| segment.text.as_str())
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(text, ["abclink", "    x y"]);
+}
+
+#[test]
+fn carriage_returns_overwrite_the_line_like_a_terminal() {
+    let text = |lines: Vec<StyledPreviewLine>| {
+        lines
+            .iter()
+            .map(|line| {
+                line.iter()
+                    .map(|segment| segment.text.as_str())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(text(parse_ansi_lines("50%\r100%")), ["100%"]);
+    assert_eq!(
+        text(parse_ansi_lines("loading 50%\rdone\r\nnext\n")),
+        ["doneing 50%", "next"]
+    );
+
+    let lines = parse_ansi_lines("\x1b[31m50%\x1b[0m\r1");
+    assert_eq!(lines[0].len(), 2);
+    assert_eq!(lines[0][0].text, "1");
+    assert_eq!(lines[0][0].style.fg, None);
+    assert_eq!(lines[0][1].text, "0%");
+    assert_eq!(lines[0][1].style.fg, Some(Color::Red));
+}
+
+#[test]
+fn previewers_match_globs_and_mime_types_in_config_order() {
+    let (compiled, warnings) = compile_previewers(
+        &previewers(&[
+            ("*.pdf", "pdftotext {} -"),
+            ("application/pdf", "never used"),
+            ("text/*", "cat"),
+            ("*.md", "   "),
+            ("[", "broken"),
+        ]),
+        Duration::from_secs(1),
+    );
+    assert_eq!(compiled.len(), 3);
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].starts_with("previewer '*.md' ignored"));
+    assert!(warnings[1].starts_with("previewer '[' ignored"));
+
+    let d = tempdir().expect("tempdir");
+    let pdf = write_file(d.path(), "it's a.pdf");
+    let previewer = previewer_for(&compiled, &pdf).expect("pdf previewer");
+    assert_eq!(previewer.command, "pdftotext {} -");
+    assert_eq!(
+        previewer.command_line(&pdf),
+        format!("pdftotext '{}/it'\\''s a.pdf' -", d.path().display())
+    );
+
+    let notes = write_file(d.path(), "notes.txt");
+    let previewer = previewer_for(&compiled, &notes).expect("text previewer");
+    assert_eq!(
+        previewer.command_line(&notes),
+        format!("cat '{}'", notes.display())
+    );
+    assert!(previewer_for(&compiled, &write_file(d.path(), "model.onnx")).is_none());
+    assert!(previewer_for(&compiled, &d.path().join("missing.pdf")).is_none());
+}
+
+#[test]
+fn command_output_is_shown_with_its_colors() {
+    let d = tempdir().expect("tempdir");
+    let path = write_file(d.path(), "report.pdf");
+    let (compiled, _) = compile_previewers(
+        &previewers(&[("*.pdf", r"printf '\033[32mpage\033[0m 1\n'; true")]),
+        Duration::from_secs(5),
+    );
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.external_previewers = compiled;
+    let nodes = vec![TreeNode {
+        path,
+        name: "report.pdf".to_string(),
+        node_type: NodeType::File,
+        depth: 0,
+        expanded: false,
+        readable: true,
+        children_loaded: false,
+        entry_class: None,
+    }];
+
+    let mut doc = refresh_preview(&mut state, &nodes, &HighlightContext::new(), 4096);
+    assert_eq!(doc.load_state, LoadState::Loading);
+    assert!(doc.content_excerpt.starts_with("Running `printf"));
+    finish(&mut doc);
+    assert_eq!(doc.load_state, LoadState::Ready);
+    assert_eq!(doc.content_type, ContentType::Highlighted);
+    assert_eq!(doc.content_excerpt, "page 1");
+    assert_eq!(doc.styled_lines[0][0].text, "page");
+    assert_eq!(doc.styled_lines[0][0].style.fg, Some(Color::Green));
+    assert_eq!(doc.styled_lines[0][1].text, " 1");
+    assert!(!poll_external_preview(&mut doc));
+}
+
+#[test]
+fn failing_and_slow_commands_report_why() {
+    let d = tempdir().expect("tempdir");
+    let path = write_file(d.path(), "data.bin");
+    let (compiled, _) = compile_previewers(
+        &previewers(&[("*.bin", "echo 'no such format' >&2; exit 3")]),
+        Duration::from_secs(5),
+    );
+    let mut doc = start_external_preview(&path, &compiled[0], 4096);
+    finish(&mut doc);
+    assert_eq!(doc.load_state, LoadState::Error);
+    let message = doc.error_message.expect("message");
+    assert!(message.ends_with("no such format"), "{message}");
+
+    let (compiled, _) = compile_previewers(
+        &previewers(&[("*.bin", "test -f {} && sleep 5")]),
+        Duration::from_millis(100),
+    );
+    let mut doc = start_external_preview(&path, &compiled[0], 4096);
+    finish(&mut doc);
+    assert_eq!(doc.load_state, LoadState::Error);
+    let message = doc.error_message.expect("message");
+    assert!(message.ends_with("timed out after 0.1s"), "{message}");
+
+    let (compiled, _) = compile_previewers(
+        &previewers(&[("*.bin", "echo partial; sleep 5; cat {}")]),
+        Duration::from_millis(300),
+    );
+    let mut doc = start_external_preview(&path, &compiled[0], 4096);
+    finish(&mut doc);
+    assert_eq!(doc.load_state, LoadState::Ready);
+    assert!(doc.truncated);
+    assert_eq!(doc.content_excerpt, "partial\n\n[truncated]");
+}
+
+#[test]
+fn timeouts_kill_everything_the_command_started() {
+    let d = tempdir().expect("tempdir");
+    let path = write_file(d.path(), "data.bin");
+    let marker = d.path().join("marker");
+    let command = format!(
+        "(sleep 0.5; touch '{}') & test -f {{}} && sleep 5",
+        marker.display()
+    );
+    let (compiled, _) = compile_previewers(
+        &previewers(&[("*.bin", command.as_str())]),
+        Duration::from_millis(100),
+    );
+    let mut doc = start_external_preview(&path, &compiled[0], 4096);
+    finish(&mut doc);
+    assert_eq!(doc.load_state, LoadState::Error);
+    let message = doc.error_message.expect("message");
+    assert!(message.ends_with("timed out after 0.1s"), "{message}");
+    thread::sleep(Duration::from_secs(1));
+    assert!(!marker.exists(), "background job outlived the timeout");
+}
+
+#[test]
+fn previewers_are_read_from_the_config_in_order() {
+    let d = tempdir().expect("tempdir");
+    let config = d.path().join("config.toml");
+    fs::write(
+        &config,
+        "previewer_timeout_ms = 2500\n\n[previewers]\n\"*.pdf\" = \"pdftotext {} -\"\n\"image/*\" = \"chafa\"\n\"*.csv\" = \"xsv table\"\n",
+    )
+    .expect("write");
+    let cfg = load_user_config(&config).expect("load config");
+    assert_eq!(cfg.previewer_timeout_ms, Some(2500));
+    assert_eq!(
+        cfg.previewers.keys().collect::<Vec<_>>(),
+        ["*.pdf", "image/*", "*.csv"]
+    );
+}
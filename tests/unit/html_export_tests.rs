@@ -0,0 +1,172 @@
+use fpv::app::cli::{parse_line_range, LineRange};
+use fpv::app::print::{export_html_file, html_for_document, select_line_range};
+use fpv::app::state::StyledPreviewSegment;
+use fpv::export::html::{css_color, css_for_style, escape_html};
+use fpv::fs::preview::load_preview;
+use fpv::highlight::syntax::HighlightContext;
+use fpv::highlight::theme::{PageColors, SyntaxPalette, SyntaxTheme};
+use ratatui::style::{Color, Modifier, Style};
+use std::fs;
+use tempfile::tempdir;
+
+fn line(text: &str) -> Vec<StyledPreviewSegment> {
+    vec![StyledPreviewSegment {
+        text: text.to_string(),
+        style: Style::default(),
+    }]
+}
+
+#[test]
+fn line_ranges_parse_closed_open_and_single_forms() {
+    assert_eq!(
+        parse_line_range("3-7"),
+        Ok(LineRange {
+            start: 3,
+            end: Some(7)
+        })
+    );
+    assert_eq!(
+        parse_line_range("10-"),
+        Ok(LineRange {
+            start: 10,
+            end: None
+        })
+    );
+    assert_eq!(
+        parse_line_range("4"),
+        Ok(LineRange {
+            start: 4,
+            end: Some(4)
+        })
+    );
+    assert!(parse_line_range("0-2").is_err());
+    assert!(parse_line_range("9-2").is_err());
+    assert!(parse_line_range("a-b").is_err());
+}
+
+#[test]
+fn select_line_range_slices_and_reports_first_number() {
+    let lines = vec![line("a"), line("b"), line("c"), line("d")];
+    let (selected, first) = select_line_range(
+        lines.clone(),
+        Some(LineRange {
+            start: 2,
+            end: Some(3),
+        }),
+    )
+    .expect("range");
+    assert_eq!(first, 2);
+    assert_eq!(selected.len(), 2);
+    assert_eq!(selected[0][0].text, "b");
+
+    let (tail, _) = select_line_range(
+        lines.clone(),
+        Some(LineRange {
+            start: 3,
+            end: Some(99),
+        }),
+    )
+    .expect("clamped range");
+    assert_eq!(tail.len(), 2);
+
+    assert!(select_line_range(
+        lines,
+        Some(LineRange {
+            start: 9,
+            end: None
+        })
+    )
+    .is_err());
+}
+
+#[test]
+fn styles_convert_to_inline_css() {
+    assert_eq!(
+        css_color(Color::Rgb(220, 150, 80)).as_deref(),
+        Some("#dc9650")
+    );
+    assert_eq!(css_color(Color::Indexed(196)).as_deref(), Some("#ff0000"));
+    assert_eq!(css_color(Color::Reset), None);
+    let style = Style::default()
+        .fg(Color::Rgb(120, 150, 120))
+        .add_modifier(Modifier::ITALIC | Modifier::UNDERLINED);
+    assert_eq!(
+        css_for_style(style),
+        "color:#789678;font-style:italic;text-decoration:underline"
+    );
+}
+
+#[test]
+fn html_escapes_markup_characters() {
+    assert_eq!(
+        escape_html("<a href=\"x\">&</a>"),
+        "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+    );
+}
+
+#[test]
+fn html_document_contains_highlighted_spans_and_numbers() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("main.rs");
+    fs::write(&p, "fn main() {}\nlet x = \"<b>\";\n").expect("write");
+    let doc = load_preview(&p, 1024, &HighlightContext::new());
+
+    let html = html_for_document(&doc, None, true, PageColors::default()).expect("html");
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("background: #1e1f22; color: #d4d4d4;"));
+    assert!(html.contains("<title>main.rs</title>"));
+    assert!(html.contains("<span class=\"ln\">1</span>"));
+    assert!(html.contains("color:#dc9650;font-weight:bold\">fn</span>"));
+    assert!(html.contains("&lt;b&gt;"));
+}
+
+#[test]
+fn html_page_colors_follow_the_syntax_palette() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("main.rs");
+    fs::write(&p, "fn main() {}\n").expect("write");
+    let ctx = HighlightContext::new().with_syntax_theme(SyntaxTheme::builtin(SyntaxPalette::Light));
+    let doc = load_preview(&p, 1024, &ctx);
+
+    let html = html_for_document(&doc, None, true, ctx.syntax_theme().page_colors()).expect("html");
+    assert!(html.contains("background: #ffffff; color: #24292e;"));
+    assert!(html.contains("color:#af1e2d;font-weight:bold\">fn</span>"));
+
+    let terminal = PageColors {
+        background: Color::Reset,
+        foreground: Color::Reset,
+    };
+    let html = html_for_document(&doc, None, true, terminal).expect("html");
+    assert!(html.contains("background: Canvas; color: CanvasText;"));
+}
+
+#[test]
+fn export_html_file_names_ranges() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("main.rs");
+    fs::write(&p, "a\nb\nc\n").expect("write");
+    let doc = load_preview(&p, 1024, &HighlightContext::new());
+
+    let out_dir = d.path().join("out");
+    let path = export_html_file(
+        &doc,
+        Some(LineRange {
+            start: 2,
+            end: Some(3),
+        }),
+        false,
+        PageColors::default(),
+        &out_dir,
+    )
+    .expect("export");
+    assert_eq!(path, out_dir.join("main.rs.2-3.html"));
+    let html = fs::read_to_string(path).expect("read export");
+    assert!(html.contains("<title>main.rs:2-3</title>"));
+    assert!(!html.contains("class=\"ln\">"));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&out_dir).expect("stat").permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+}
@@ -0,0 +1,145 @@
+use fpv::app::state::{NodeType, TreeNode};
+use fpv::config::icons::{compile_icon_theme, IconSet, IconTheme, UserIconConfig};
+use fpv::config::load::{load_user_config, ThemeProfile};
+use fpv::config::style_rules::{compile_style_rules, UserStyleRule};
+use fpv::tui::tree_pane::tree_entry_label;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+fn node(path: &str, node_type: NodeType) -> TreeNode {
+    let path = PathBuf::from(path);
+    TreeNode {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path,
+        node_type,
+        depth: 0,
+        expanded: false,
+        readable: true,
+        children_loaded: false,
+        entry_class: None,
+    }
+}
+
+fn theme_with(icons: IconTheme) -> ThemeProfile {
+    ThemeProfile {
+        icons,
+        ..ThemeProfile::default()
+    }
+}
+
+#[test]
+fn icons_off_keeps_entry_prefix_markers() {
+    let theme = ThemeProfile::default();
+    assert!(!theme.icons.enabled());
+    let label = |path: &str, node_type| tree_entry_label(&node(path, node_type), &theme, None);
+    assert_eq!(label("src", NodeType::Directory), "/ src");
+    assert_eq!(label("main.rs", NodeType::File), "main.rs");
+    assert_eq!(label("latest", NodeType::Symlink), "@ latest");
+}
+
+#[test]
+fn nerd_icons_follow_names_then_extensions_then_type() {
+    let theme = theme_with(IconTheme::new(IconSet::Nerd));
+    let label = |path: &str, node_type| tree_entry_label(&node(path, node_type), &theme, None);
+    assert_eq!(label("main.rs", NodeType::File), "\u{e7a8} main.rs");
+    assert_eq!(label("Dockerfile", NodeType::File), "\u{f308} Dockerfile");
+    assert_eq!(label("Cargo.lock", NodeType::File), "\u{f023} Cargo.lock");
+    assert_eq!(
+        label("notes.unknownext", NodeType::File),
+        "\u{f15b} notes.unknownext"
+    );
+    assert_eq!(label("src", NodeType::Directory), "\u{f07b} src");
+    assert_eq!(label(".git", NodeType::Directory), "\u{e702} .git");
+    assert_eq!(label("license", NodeType::Directory), "\u{f07b} license");
+}
+
+#[test]
+fn unicode_and_ascii_sets_cover_the_same_entries() {
+    let unicode = theme_with(IconTheme::new(IconSet::Unicode));
+    let ascii = theme_with(IconTheme::new(IconSet::Ascii));
+    let rs = node("lib.rs", NodeType::File);
+    let dir = node("docs", NodeType::Directory);
+    let txt = node("README", NodeType::File);
+    assert_eq!(tree_entry_label(&rs, &unicode, None), "🦀 lib.rs");
+    assert_eq!(tree_entry_label(&dir, &unicode, None), "📁 docs");
+    assert_eq!(tree_entry_label(&txt, &unicode, None), "📄 README");
+    assert_eq!(tree_entry_label(&rs, &ascii, None), "* lib.rs");
+    assert_eq!(tree_entry_label(&dir, &ascii, None), "/ docs");
+    assert_eq!(tree_entry_label(&txt, &ascii, None), "- README");
+}
+
+#[test]
+fn user_overrides_win_over_the_builtin_table() {
+    let (icons, warnings) = compile_icon_theme(&UserIconConfig {
+        set: Some("ascii".to_string()),
+        types: HashMap::from([("directory".to_string(), "D".to_string())]),
+        extensions: HashMap::from([(".RS".to_string(), "R".to_string())]),
+        names: HashMap::from([("build.rs".to_string(), "B".to_string())]),
+    });
+    assert!(warnings.is_empty(), "{warnings:?}");
+    let theme = theme_with(icons);
+    let label = |path: &str, node_type| tree_entry_label(&node(path, node_type), &theme, None);
+    assert_eq!(label("build.rs", NodeType::File), "B build.rs");
+    assert_eq!(label("main.rs", NodeType::File), "R main.rs");
+    assert_eq!(label("src", NodeType::Directory), "D src");
+    assert_eq!(label(".git", NodeType::Directory), "= .git");
+}
+
+#[test]
+fn style_rule_icons_take_precedence() {
+    let (rules, _) = compile_style_rules(&[UserStyleRule {
+        name: Some("Cargo.toml".to_string()),
+        icon: Some("📦".to_string()),
+        ..UserStyleRule::default()
+    }]);
+    let theme = ThemeProfile {
+        rules,
+        icons: IconTheme::new(IconSet::Nerd),
+        ..ThemeProfile::default()
+    };
+    let label = tree_entry_label(&node("Cargo.toml", NodeType::File), &theme, None);
+    assert_eq!(label, "📦 Cargo.toml");
+}
+
+#[test]
+fn unknown_icon_set_and_types_are_reported() {
+    let (icons, warnings) = compile_icon_theme(&UserIconConfig {
+        set: Some("emoji".to_string()),
+        types: HashMap::from([("socket".to_string(), "S".to_string())]),
+        ..UserIconConfig::default()
+    });
+    assert_eq!(icons.set, IconSet::Off);
+    assert_eq!(
+        warnings,
+        vec![
+            "unknown icon set 'emoji' ignored".to_string(),
+            "unknown icon type 'socket' ignored".to_string(),
+        ]
+    );
+}
+
+#[test]
+fn icons_parse_from_config() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("config.toml");
+    fs::write(
+        &p,
+        "[theme.icons]\nset = 'nerd'\n\n[theme.icons.extensions]\nrs = 'R'\n\n[theme.icons.names]\nJustfile = 'J'\n",
+    )
+    .expect("write config");
+    let cfg = load_user_config(&p).expect("load config");
+    assert_eq!(cfg.theme.icons.set.as_deref(), Some("nerd"));
+    assert_eq!(
+        cfg.theme.icons.extensions.get("rs").map(String::as_str),
+        Some("R")
+    );
+    assert_eq!(
+        cfg.theme.icons.names.get("Justfile").map(String::as_str),
+        Some("J")
+    );
+}
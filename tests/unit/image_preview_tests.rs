@@ -0,0 +1,287 @@
+use fpv::app::state::{LoadState, SelectedEntryMetadata, SessionState};
+use fpv::config::load::{load_user_config, ImageProtocolMode};
+use fpv::fs::preview::{load_preview, poll_pending_preview, start_preview};
+use fpv::highlight::syntax::HighlightContext;
+use fpv::tui::image::{
+    detect_image_protocol, fit_size, kitty_sequence, repaint_cells, resolve_image_protocol,
+    sixel_sequence, ImageEnvironment, ImageGraphics, ImageProtocol,
+};
+use fpv::tui::preview_pane::draw_preview;
+use fpv::tui::status_bar::compose_preview_metadata_line_with_image;
+use image::{Rgba, RgbaImage};
+use ratatui::backend::{CrosstermBackend, TestBackend};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Color;
+use ratatui::widgets::Paragraph;
+use ratatui::{Terminal, TerminalOptions, Viewport};
+use std::cell::RefCell;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+
+/// A `width`×`height` PNG, red on top and blue below.
+fn write_png(path: &Path, width: u32, height: u32) {
+    RgbaImage::from_fn(
+        width,
+        height,
+        |_, y| if y < height / 2 { RED } else { BLUE },
+    )
+    .save(path)
+    .expect("save png");
+}
+
+fn env(term: Option<&str>, term_program: Option<&str>) -> ImageEnvironment {
+    ImageEnvironment {
+        term: term.map(str::to_string),
+        term_program: term_program.map(str::to_string),
+        ..ImageEnvironment::default()
+    }
+}
+
+#[test]
+fn images_are_decoded_with_their_size_in_the_metadata() {
+    let d = tempdir().expect("tempdir");
+    let path = d.path().join("logo.png");
+    write_png(&path, 640, 480);
+    let doc = load_preview(&path, 64, &HighlightContext::new());
+    assert_eq!(doc.load_state, LoadState::Ready);
+    let image = doc.image.as_ref().expect("image");
+    assert_eq!((image.format, image.width, image.height), ("PNG", 640, 480));
+
+    let metadata = SelectedEntryMetadata {
+        filename: "logo.png".to_string(),
+        size_text: "2.1 KiB".to_string(),
+        ..SelectedEntryMetadata::default()
+    };
+    let line =
+        compose_preview_metadata_line_with_image(&metadata, None, None, None, Some(image), 200);
+    assert!(
+        line.starts_with("PNG(.png) | 640×480 px | 2.1 KiB"),
+        "{line}"
+    );
+}
+
+#[test]
+fn images_are_decoded_in_the_background_for_the_tui() {
+    let d = tempdir().expect("tempdir");
+    let path = d.path().join("logo.png");
+    write_png(&path, 64, 48);
+    let ctx = HighlightContext::new();
+    let mut doc = start_preview(&path, 64, &ctx, None);
+    assert_eq!(doc.load_state, LoadState::Loading);
+    assert_eq!(doc.content_excerpt, "Decoding logo.png…");
+
+    let started = Instant::now();
+    while !poll_pending_preview(&mut doc, &ctx) {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "image never decoded"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(doc.load_state, LoadState::Ready);
+    let image = doc.image.as_ref().expect("image");
+    assert_eq!((image.width, image.height), (64, 48));
+    assert!(doc.pending.is_none());
+}
+
+#[test]
+fn undecodable_images_report_an_error() {
+    let d = tempdir().expect("tempdir");
+    let path = d.path().join("broken.jpg");
+    fs::write(&path, b"not a jpeg at all").expect("write");
+    let doc = load_preview(&path, 4096, &HighlightContext::new());
+    assert_eq!(doc.load_state, LoadState::Error);
+    assert!(doc.image.is_none());
+    let message = doc.error_message.expect("message");
+    assert!(message.starts_with("Cannot decode image:"), "{message}");
+}
+
+#[test]
+fn images_fit_the_pane_keeping_their_aspect_ratio() {
+    assert_eq!(fit_size(640, 480, 40, 40), (40, 30));
+    assert_eq!(fit_size(100, 400, 40, 40), (10, 40));
+    assert_eq!(fit_size(2, 2, 40, 20), (20, 20));
+    assert_eq!(fit_size(1000, 1, 10, 10), (10, 1));
+    assert_eq!(fit_size(10, 10, 0, 5), (0, 0));
+}
+
+#[test]
+fn half_blocks_draw_two_pixels_per_cell() {
+    let d = tempdir().expect("tempdir");
+    let path = d.path().join("flag.png");
+    write_png(&path, 8, 8);
+    let doc = load_preview(&path, 4096, &HighlightContext::new());
+    let state = SessionState::new(PathBuf::from("."));
+
+    let mut terminal = Terminal::new(TestBackend::new(10, 6)).expect("terminal");
+    terminal
+        .draw(|frame| {
+            draw_preview(
+                frame,
+                frame.size(),
+                &doc,
+                &state,
+                &fpv::config::load::ThemeProfile::default(),
+            )
+        })
+        .expect("draw");
+    let buffer = terminal.backend().buffer();
+    // An 8×8 picture in 8×4 cells: one red and one blue row of cells.
+    let top = buffer.get(1, 1);
+    assert_eq!(top.symbol(), "▀");
+    assert_eq!(
+        (top.fg, top.bg),
+        (Color::Rgb(255, 0, 0), Color::Rgb(255, 0, 0))
+    );
+    let bottom = buffer.get(8, 4);
+    assert_eq!(
+        (bottom.fg, bottom.bg),
+        (Color::Rgb(0, 0, 255), Color::Rgb(0, 0, 255))
+    );
+    assert_eq!(buffer.get(9, 2).symbol(), "│");
+
+    let mut state = state;
+    state.image_protocol = ImageProtocol::Kitty;
+    terminal
+        .draw(|frame| {
+            draw_preview(
+                frame,
+                frame.size(),
+                &doc,
+                &state,
+                &fpv::config::load::ThemeProfile::default(),
+            )
+        })
+        .expect("draw");
+    assert_eq!(terminal.backend().buffer().get(1, 1).symbol(), " ");
+}
+
+#[test]
+fn protocol_is_detected_from_the_terminal() {
+    assert_eq!(
+        detect_image_protocol(&env(Some("xterm-kitty"), None)),
+        ImageProtocol::Kitty
+    );
+    assert_eq!(
+        detect_image_protocol(&env(Some("xterm-256color"), Some("WezTerm"))),
+        ImageProtocol::Kitty
+    );
+    assert_eq!(
+        detect_image_protocol(&env(Some("foot"), None)),
+        ImageProtocol::Sixel
+    );
+    assert_eq!(
+        detect_image_protocol(&env(Some("xterm-256color"), None)),
+        ImageProtocol::HalfBlocks
+    );
+    let tmux = ImageEnvironment {
+        tmux: true,
+        ..env(Some("xterm-kitty"), None)
+    };
+    assert_eq!(detect_image_protocol(&tmux), ImageProtocol::HalfBlocks);
+    assert_eq!(
+        resolve_image_protocol(ImageProtocolMode::Sixel, &env(None, None)),
+        ImageProtocol::Sixel
+    );
+
+    let d = tempdir().expect("tempdir");
+    let config = d.path().join("config.toml");
+    fs::write(&config, "image_protocol = \"halfblocks\"\n").expect("write");
+    let cfg = load_user_config(&config).expect("load config");
+    assert_eq!(cfg.image_protocol, Some(ImageProtocolMode::Halfblocks));
+}
+
+#[test]
+fn graphics_protocols_encode_the_scaled_picture() {
+    let d = tempdir().expect("tempdir");
+    let path = d.path().join("photo.png");
+    write_png(&path, 400, 300);
+    let doc = load_preview(&path, 4096, &HighlightContext::new());
+    let image = doc.image.as_ref().expect("image");
+
+    let kitty = kitty_sequence(image, 10, 10, (8, 16));
+    assert!(
+        kitty.starts_with("\x1b_Ga=T,f=32,o=z,s=80,v=60,C=1,q=2,m="),
+        "{}",
+        &kitty[..40]
+    );
+    assert!(kitty.ends_with("\x1b\\"));
+
+    let sixel = sixel_sequence(image, 10, 2, (8, 16));
+    // 32 rows of pixels fit, cut to a multiple of six.
+    assert!(
+        sixel.starts_with("\x1bP0;1;0q\"1;1;40;30#"),
+        "{}",
+        &sixel[..30]
+    );
+    assert!(sixel.ends_with("\x1b\\"));
+    assert_eq!(sixel.matches('-').count(), 5);
+    assert_eq!(sixel_sequence(image, 10, 0, (8, 16)), "");
+}
+
+/// Terminal output kept for inspection after the backend took the writer.
+#[derive(Clone, Default)]
+struct SharedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for SharedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn a_removed_sixel_image_is_repainted_over_without_clearing_the_screen() {
+    let d = tempdir().expect("tempdir");
+    let path = d.path().join("photo.png");
+    write_png(&path, 40, 40);
+    let doc = load_preview(&path, 4096, &HighlightContext::new());
+    let image = doc.image.as_ref().expect("image");
+
+    let output = SharedOutput::default();
+    let mut terminal = Terminal::with_options(
+        CrosstermBackend::new(output.clone()),
+        TerminalOptions {
+            viewport: Viewport::Fixed(Rect::new(0, 0, 20, 10)),
+        },
+    )
+    .expect("terminal");
+    let area = Rect::new(10, 2, 6, 4);
+    let mut graphics = ImageGraphics::new(ImageProtocol::Sixel);
+    graphics
+        .show(terminal.backend_mut(), &path, image, area)
+        .expect("show");
+    graphics
+        .clear_stale(&mut terminal, Some((&path, area)))
+        .expect("keep");
+    output.0.borrow_mut().clear();
+    graphics.clear_stale(&mut terminal, None).expect("clear");
+
+    let frame = terminal
+        .draw(|f| f.render_widget(Paragraph::new("text"), f.size()))
+        .expect("draw");
+    let stale = graphics.take_stale_cells(frame.buffer);
+    repaint_cells(terminal.backend_mut(), &stale).expect("repaint");
+    assert_eq!(stale.len(), 24);
+    assert_eq!((stale[0].0, stale[0].1), (10, 2));
+    assert_eq!((stale[23].0, stale[23].1), (15, 5));
+    let written = String::from_utf8_lossy(&output.0.borrow()).into_owned();
+    // Erasing the display would redraw every cell on the next frame.
+    assert!(!written.contains("\x1b[2J"), "{written:?}");
+    assert!(graphics
+        .take_stale_cells(&Buffer::empty(Rect::new(0, 0, 20, 10)))
+        .is_empty());
+}
@@ -124,3 +124,46 @@ fn default_exit_fullscreen_mapping_exists() {
         Some(Action::ExitFullscreenPreview)
     );
 }
+
+#[test]
+fn clipboard_action_names_and_defaults_are_supported() {
+    assert_eq!(
+        action_from_name("copy_absolute_path"),
+        Some(Action::CopyAbsolutePath)
+    );
+    assert_eq!(
+        action_from_name("copy_relative_path"),
+        Some(Action::CopyRelativePath)
+    );
+    assert_eq!(
+        action_from_name("copy_file_name"),
+        Some(Action::CopyFileName)
+    );
+    assert_eq!(
+        action_from_name("copy_preview_selection"),
+        Some(Action::CopyPreviewSelection)
+    );
+
+    let map = default_keymap();
+    let yank = KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE);
+    assert_eq!(
+        map_key_to_action(yank, &map),
+        Some(Action::CopyAbsolutePath)
+    );
+}
+
+#[test]
+fn visual_selection_action_names_are_supported() {
+    assert_eq!(
+        action_from_name("toggle_visual_selection"),
+        Some(Action::ToggleVisualSelection)
+    );
+    assert_eq!(
+        action_from_name("copy_selection_reference"),
+        Some(Action::CopySelectionReference)
+    );
+    assert_eq!(
+        action_from_name("pipe_selection"),
+        Some(Action::PipeSelection)
+    );
+}
@@ -0,0 +1,195 @@
+use fpv::app::state::{ContentType, DetectionReason, SelectedEntryMetadata};
+use fpv::config::load::load_user_config;
+use fpv::fs::preview::load_preview;
+use fpv::highlight::detect::{compile_language_mappings, UserLanguageMapping};
+use fpv::highlight::syntax::HighlightContext;
+use fpv::tui::status_bar::compose_preview_metadata_line_with_language;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn detect<'a>(
+    ctx: &'a HighlightContext,
+    path: &str,
+    content: &str,
+) -> Option<(&'a str, DetectionReason)> {
+    ctx.target_for_file(Path::new(path), content)
+        .map(|target| (target.language_id, target.reason))
+}
+
+fn mapping(glob: &str, language: &str) -> UserLanguageMapping {
+    UserLanguageMapping {
+        glob: glob.to_string(),
+        language: language.to_string(),
+    }
+}
+
+#[test]
+fn well_known_file_names_are_detected() {
+    let ctx = HighlightContext::new();
+    assert_eq!(
+        detect(&ctx, ".bashrc", "alias ll='ls -l'\n"),
+        Some(("Shell", DetectionReason::FileName))
+    );
+    assert_eq!(
+        detect(&ctx, "Cargo.lock", "version = 3\n"),
+        Some(("TOML", DetectionReason::FileName))
+    );
+    assert_eq!(
+        detect(&ctx, "main.rs", "fn main() {}\n"),
+        Some(("Rust", DetectionReason::Extension))
+    );
+}
+
+#[test]
+fn makefiles_and_jenkinsfiles_fall_back_to_related_grammars() {
+    let ctx = HighlightContext::new();
+    for name in ["Makefile", "makefile", "GNUmakefile", "rules.mk"] {
+        assert_eq!(
+            detect(&ctx, name, "all:\n\tcc -o app main.c\n"),
+            Some(("Shell", DetectionReason::FileName)),
+            "{name}"
+        );
+    }
+    assert_eq!(
+        detect(&ctx, "Jenkinsfile", "pipeline {\n    agent any\n}\n"),
+        Some(("Java", DetectionReason::FileName))
+    );
+}
+
+#[cfg(feature = "lang-dockerfile")]
+#[test]
+fn dockerfile_variants_are_detected_by_name() {
+    let ctx = HighlightContext::new();
+    for name in [
+        "Dockerfile",
+        "Dockerfile.dev",
+        "app.dockerfile",
+        "Containerfile",
+    ] {
+        assert_eq!(
+            detect(&ctx, name, "FROM alpine\n"),
+            Some(("Dockerfile", DetectionReason::FileName)),
+            "{name}"
+        );
+    }
+}
+
+#[test]
+fn shebang_lines_pick_the_interpreter() {
+    let ctx = HighlightContext::new();
+    assert_eq!(
+        detect(&ctx, "bin/tool", "#!/usr/bin/env python3\nprint(1)\n"),
+        Some(("Python", DetectionReason::Shebang))
+    );
+    assert_eq!(
+        detect(&ctx, "run", "#!/bin/bash -e\necho hi\n"),
+        Some(("Shell", DetectionReason::Shebang))
+    );
+    assert_eq!(
+        detect(&ctx, "serve", "#!/usr/bin/env -S node --no-warnings\n"),
+        Some(("JavaScript", DetectionReason::Shebang))
+    );
+    assert_eq!(detect(&ctx, "notes", "#!/usr/bin/env unknown-tool\n"), None);
+    assert_eq!(detect(&ctx, "notes", "plain text\n"), None);
+}
+
+#[test]
+fn emacs_and_vim_modelines_override_the_extension() {
+    let ctx = HighlightContext::new();
+    assert_eq!(
+        detect(
+            &ctx,
+            "widget.h",
+            "// -*- mode: c++; indent-tabs-mode: nil -*-\nclass A {};\n"
+        ),
+        Some(("C++", DetectionReason::Modeline))
+    );
+    assert_eq!(
+        detect(&ctx, "setup", "#!/bin/sh\n# -*- python -*-\n"),
+        Some(("Python", DetectionReason::Modeline))
+    );
+    let vim_tail = format!("{}# vim: set ft=sh ts=2:\n", "x=1\n".repeat(20));
+    assert_eq!(
+        detect(&ctx, "env.conf", &vim_tail),
+        Some(("Shell", DetectionReason::Modeline))
+    );
+    let buried = format!("{}# vim: ft=sh\n{}", "x\n".repeat(10), "y\n".repeat(10));
+    assert_eq!(detect(&ctx, "env.conf", &buried), None);
+}
+
+#[test]
+fn config_mappings_win_and_report_invalid_entries() {
+    let (mappings, warnings) = compile_language_mappings(&[
+        mapping("*.conf", "bash"),
+        mapping("ci/**/*.txt", "python"),
+        mapping("*.x", "cobol"),
+        mapping("[oops", "rust"),
+    ]);
+    assert_eq!(mappings.len(), 2);
+    assert_eq!(warnings.len(), 2, "{warnings:?}");
+    assert!(warnings[0].contains("unknown language 'cobol'"));
+    assert!(warnings[1].starts_with("language mapping '[oops' ignored"));
+
+    let ctx = HighlightContext::new().with_language_mappings(mappings);
+    assert_eq!(
+        detect(&ctx, "/etc/app.conf", "# vim: ft=python\n"),
+        Some(("Shell", DetectionReason::ConfigMapping))
+    );
+    assert_eq!(
+        detect(&ctx, "ci/jobs/build.txt", "x = 1\n"),
+        Some(("Python", DetectionReason::ConfigMapping))
+    );
+    let target = ctx.target_for_path(Path::new("a.conf")).expect("mapped");
+    assert_eq!(target.language_id, "Shell");
+}
+
+#[test]
+fn language_mappings_parse_from_config() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("config.toml");
+    fs::write(
+        &p,
+        "[[language_mappings]]\nglob = 'Jenkinsfile'\nlanguage = 'java'\n\n[[language_mappings]]\nglob = '*.env'\nlanguage = 'sh'\n",
+    )
+    .expect("write config");
+    let cfg = load_user_config(&p).expect("load config");
+    assert_eq!(
+        cfg.language_mappings,
+        vec![mapping("Jenkinsfile", "java"), mapping("*.env", "sh")]
+    );
+}
+
+#[test]
+fn preview_reports_language_and_detection_reason() {
+    let d = tempdir().expect("create tempdir");
+    let script = d.path().join("deploy");
+    fs::write(&script, "#!/usr/bin/env python3\nimport os\n").expect("write script");
+    let ctx = HighlightContext::new();
+    let doc = load_preview(&script, 1024, &ctx);
+    assert_eq!(doc.content_type, ContentType::Highlighted);
+    assert_eq!(doc.language_id.as_deref(), Some("Python"));
+    assert_eq!(doc.language_reason, Some(DetectionReason::Shebang));
+
+    let metadata = SelectedEntryMetadata {
+        filename: "deploy".to_string(),
+        size_text: "40 B".to_string(),
+        ..SelectedEntryMetadata::default()
+    };
+    let line = compose_preview_metadata_line_with_language(
+        &metadata,
+        Some(("Python", DetectionReason::Shebang)),
+        200,
+    );
+    assert!(line.starts_with("Python(shebang) | 40 B"), "{line}");
+    let by_ext = SelectedEntryMetadata {
+        filename: "app.rb".to_string(),
+        ..SelectedEntryMetadata::default()
+    };
+    let line = compose_preview_metadata_line_with_language(
+        &by_ext,
+        Some(("Ruby", DetectionReason::Extension)),
+        200,
+    );
+    assert!(line.starts_with("Ruby(.rb) |"), "{line}");
+}
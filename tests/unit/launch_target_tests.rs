@@ -0,0 +1,113 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use fpv::app::launch::{resolve_launch_target, LaunchTarget};
+use fpv::app::state::{ContentType, LoadState, PreviewDocument, SessionState};
+use fpv::config::keymap::{default_keymap, Action};
+use fpv::tui::event_loop::handle_event;
+use fpv::tui::preview_pane::{preview_total_lines, wrapped_preview_rows};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tempfile::tempdir;
+
+#[test]
+fn directory_argument_opens_as_root() {
+    let d = tempdir().expect("create tempdir");
+    let arg = d.path().to_string_lossy().to_string();
+    assert_eq!(
+        resolve_launch_target(&arg),
+        LaunchTarget::directory(d.path().to_path_buf())
+    );
+}
+
+#[test]
+fn file_argument_opens_parent_with_file_selected() {
+    let d = tempdir().expect("create tempdir");
+    let file = d.path().join("state.rs");
+    fs::write(&file, "fn main() {}\n").expect("write file");
+
+    let target = resolve_launch_target(&file.to_string_lossy());
+    assert_eq!(target.root, d.path().to_path_buf());
+    assert_eq!(target.selected_file, Some(file));
+    assert_eq!(target.line, None);
+}
+
+#[test]
+fn file_line_argument_sets_line() {
+    let d = tempdir().expect("create tempdir");
+    let file = d.path().join("state.rs");
+    fs::write(&file, "a\nb\nc\n").expect("write file");
+
+    let target = resolve_launch_target(&format!("{}:120", file.display()));
+    assert_eq!(target.selected_file, Some(file.clone()));
+    assert_eq!(target.line, Some(120));
+
+    let with_column = resolve_launch_target(&format!("{}:2:7", file.display()));
+    assert_eq!(with_column.selected_file, Some(file));
+    assert_eq!(with_column.line, Some(2));
+}
+
+#[test]
+fn missing_path_with_line_suffix_is_kept_as_root() {
+    let target = resolve_launch_target("does-not-exist.rs:12");
+    assert_eq!(target.root, PathBuf::from("does-not-exist.rs:12"));
+    assert!(target.selected_file.is_none());
+}
+
+#[test]
+fn go_to_preview_line_scrolls_clamps_and_flashes() {
+    let mut state = SessionState::new(PathBuf::from("."));
+    assert_eq!(state.go_to_preview_line(5, 10, 0), Some(5));
+    assert_eq!(state.preview_scroll_row, 4);
+    assert_eq!(state.flashed_preview_line(Instant::now()), Some(4));
+    assert!(state
+        .flashed_preview_line(Instant::now() + Duration::from_secs(5))
+        .is_none());
+
+    assert_eq!(state.go_to_preview_line(500, 10, 1), Some(10));
+    assert_eq!(state.preview_scroll_row, 10);
+
+    assert_eq!(state.go_to_preview_line(3, 0, 0), None);
+}
+
+#[test]
+fn go_to_line_scrolls_to_the_first_wrapped_row_of_the_line() {
+    let mut doc = PreviewDocument {
+        load_state: LoadState::Ready,
+        content_type: ContentType::PlainText,
+        language_id: Some("Rust".to_string()),
+        content_excerpt: format!("{}\n{}\nthird\nfourth", "a".repeat(25), "b".repeat(8)),
+        ..PreviewDocument::default()
+    };
+    let bindings = default_keymap();
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.preview_show_line_numbers = false;
+    state.preview_wrap_enabled = true;
+    doc.wrapped_rows = wrapped_preview_rows(&doc, &state, 10);
+    assert_eq!(preview_total_lines(&doc), 6);
+
+    let press = |state: &mut SessionState, key: KeyEvent| {
+        handle_event(
+            state,
+            &mut Vec::new(),
+            &bindings,
+            &doc,
+            preview_total_lines(&doc),
+            3,
+            Event::Key(key),
+        )
+        .expect("handle key");
+    };
+    press(&mut state, bindings[&Action::GoToLine]);
+    press(
+        &mut state,
+        KeyEvent::new(KeyCode::Char('3'), KeyModifiers::NONE),
+    );
+    press(
+        &mut state,
+        KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE),
+    );
+    assert_eq!(state.status_notice.as_deref(), Some("Line 3."));
+    // Line 1 wraps to three rows and line 2 fits in one.
+    assert_eq!(state.preview_scroll_row, 4);
+    assert_eq!(state.flashed_preview_line(Instant::now()), Some(2));
+}
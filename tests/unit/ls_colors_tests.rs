@@ -0,0 +1,154 @@
+use fpv::app::state::{NodeType, TreeNode};
+use fpv::config::load::ThemeProfile;
+use fpv::config::ls_colors::{sgr_style, LsColors, DEFAULT_LS_COLORS};
+use fpv::fs::current_dir::list_current_directory;
+use fpv::tui::tree_pane::node_style;
+use ratatui::style::{Color, Modifier, Style};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn file_node(path: &Path) -> TreeNode {
+    TreeNode {
+        path: path.to_path_buf(),
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        node_type: NodeType::File,
+        depth: 0,
+        expanded: false,
+        readable: true,
+        children_loaded: false,
+        entry_class: None,
+    }
+}
+
+#[test]
+fn sgr_codes_map_to_styles() {
+    assert_eq!(
+        sgr_style("01;34"),
+        Style::default()
+            .fg(Color::Blue)
+            .add_modifier(Modifier::BOLD)
+    );
+    assert_eq!(
+        sgr_style("38;5;208;48;2;10;20;30"),
+        Style::default()
+            .fg(Color::Indexed(208))
+            .bg(Color::Rgb(10, 20, 30))
+    );
+    assert_eq!(
+        sgr_style("4;91;103"),
+        Style::default()
+            .fg(Color::LightRed)
+            .bg(Color::LightYellow)
+            .add_modifier(Modifier::UNDERLINED)
+    );
+    assert_eq!(sgr_style("00"), Style::default());
+}
+
+#[test]
+fn extensions_and_globs_match_file_names() {
+    let d = tempdir().expect("create tempdir");
+    let colors = LsColors::parse("fi=37:*.gz=31:*.tar.gz=01;35:*.JPG=33:*README*=04;32");
+    let touch = |name: &str| {
+        let path = d.path().join(name);
+        fs::write(&path, "x").expect("write file");
+        path
+    };
+
+    assert_eq!(
+        colors.style_for_path(&touch("a.tar.gz")),
+        Some(sgr_style("01;35"))
+    );
+    assert_eq!(colors.style_for_path(&touch("b.gz")), Some(sgr_style("31")));
+    assert_eq!(
+        colors.style_for_path(&touch("photo.jpg")),
+        Some(sgr_style("33"))
+    );
+    assert_eq!(
+        colors.style_for_path(&touch("README.md")),
+        Some(sgr_style("04;32"))
+    );
+    assert_eq!(
+        colors.style_for_path(&touch("notes.txt")),
+        Some(sgr_style("37"))
+    );
+}
+
+#[test]
+fn directories_and_missing_paths_use_type_keys() {
+    let d = tempdir().expect("create tempdir");
+    let colors = LsColors::parse(DEFAULT_LS_COLORS);
+    assert_eq!(colors.style_for_path(d.path()), Some(sgr_style("01;34")));
+    assert_eq!(colors.style_for_path(&d.path().join("absent")), None);
+    let plain = d.path().join("plain.txt");
+    fs::write(&plain, "x").expect("write file");
+    assert_eq!(colors.style_for_path(&plain), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn executables_symlinks_and_orphans_are_classified() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let d = tempdir().expect("create tempdir");
+    let colors = LsColors::parse(DEFAULT_LS_COLORS);
+
+    let script = d.path().join("run.sh");
+    fs::write(&script, "#!/bin/sh\n").expect("write script");
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).expect("chmod");
+    assert_eq!(colors.style_for_path(&script), Some(sgr_style("01;32")));
+
+    let link = d.path().join("link");
+    symlink(&script, &link).expect("symlink");
+    assert_eq!(colors.style_for_path(&link), Some(sgr_style("01;36")));
+
+    let orphan = d.path().join("orphan");
+    symlink(d.path().join("gone"), &orphan).expect("symlink");
+    assert_eq!(colors.style_for_path(&orphan), Some(sgr_style("40;31;01")));
+
+    let as_target = LsColors::parse("ln=target:ex=01;32");
+    assert_eq!(as_target.style_for_path(&link), Some(sgr_style("01;32")));
+}
+
+#[cfg(unix)]
+#[test]
+fn tree_styles_use_the_kind_read_when_listing() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let d = tempdir().expect("create tempdir");
+    let script = d.path().join("run.sh");
+    fs::write(&script, "#!/bin/sh\n").expect("write script");
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).expect("chmod");
+    let nodes = list_current_directory(d.path(), 10).expect("list");
+
+    // Drawing does not look at the file again until the next listing.
+    fs::remove_file(&script).expect("remove script");
+    let theme = ThemeProfile {
+        ls_colors: Some(LsColors::parse(DEFAULT_LS_COLORS)),
+        ..ThemeProfile::default()
+    };
+    assert_eq!(node_style(&nodes[0], &theme, None), sgr_style("01;32"));
+}
+
+#[test]
+fn node_style_prefers_ls_colors_when_enabled() {
+    let d = tempdir().expect("create tempdir");
+    let path = d.path().join("main.rs");
+    fs::write(&path, "fn main() {}\n").expect("write file");
+    let node = file_node(&path);
+
+    let mut theme = ThemeProfile::default();
+    assert_eq!(node_style(&node, &theme, None).fg, Some(Color::Cyan));
+
+    theme.ls_colors = Some(LsColors::parse("*.rs=38;5;214"));
+    assert_eq!(
+        node_style(&node, &theme, None).fg,
+        Some(Color::Indexed(214))
+    );
+
+    theme.ls_colors = Some(LsColors::parse("*.py=33"));
+    assert_eq!(node_style(&node, &theme, None).fg, Some(Color::Cyan));
+}
@@ -0,0 +1,178 @@
+use fpv::app::state::{ContentType, SessionState, StyledPreviewLine};
+use fpv::config::keymap::{action_from_name, Action};
+use fpv::fs::preview::load_preview;
+use fpv::highlight::markdown::{render_markdown, render_markdown_view};
+use fpv::highlight::syntax::HighlightContext;
+use fpv::tui::preview_pane::draw_preview;
+use ratatui::backend::TestBackend;
+use ratatui::style::Modifier;
+use ratatui::Terminal;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+fn texts(lines: &[StyledPreviewLine]) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| line.iter().map(|segment| segment.text.as_str()).collect())
+        .collect()
+}
+
+fn render(source: &str, width: usize) -> (Vec<StyledPreviewLine>, Vec<String>) {
+    let lines = render_markdown(&HighlightContext::new(), source, width);
+    let text = texts(&lines);
+    (lines, text)
+}
+
+#[test]
+fn headings_are_styled_by_level() {
+    let (lines, text) = render("# Title\n\n## Section\n\n### Detail\n", 40);
+    assert_eq!(
+        text,
+        vec!["Title", "═════", "", "Section", "───────", "", "### Detail"]
+    );
+    assert!(lines[0][0].style.add_modifier.contains(Modifier::BOLD));
+}
+
+#[test]
+fn paragraphs_are_joined_and_wrapped_to_the_width() {
+    let source = "The quick brown fox\njumps over the lazy dog and keeps running far away.\n";
+    let (_, text) = render(source, 24);
+    assert_eq!(
+        text,
+        vec![
+            "The quick brown fox",
+            "jumps over the lazy dog",
+            "and keeps running far",
+            "away.",
+        ]
+    );
+
+    let (lines_with_emphasis, text) = render("plain **bold words** end", 40);
+    assert_eq!(text, vec!["plain bold words end"]);
+    let bold = lines_with_emphasis[0]
+        .iter()
+        .find(|segment| segment.text == "bold words")
+        .expect("bold segment");
+    assert!(bold.style.add_modifier.contains(Modifier::BOLD));
+}
+
+#[test]
+fn lists_and_quotes_are_indented() {
+    let source =
+        "- one\n- two\n  - nested\n\n1. first\n2. second\n\n> a quoted line that wraps around\n";
+    let (_, text) = render(source, 24);
+    assert_eq!(
+        text,
+        vec![
+            "• one",
+            "• two",
+            "  • nested",
+            "",
+            "1. first",
+            "2. second",
+            "",
+            "│ a quoted line that",
+            "│ wraps around",
+        ]
+    );
+}
+
+#[test]
+fn tables_are_laid_out_as_columns() {
+    let source = "| Name | Qty |\n|:-----|----:|\n| apple | 3 |\n| kiwi | 12 |\n";
+    let (lines, text) = render(source, 40);
+    assert_eq!(
+        text,
+        vec!["Name  │ Qty", "──────┼────", "apple │   3", "kiwi  │  12"]
+    );
+    assert!(lines[0][0].style.add_modifier.contains(Modifier::BOLD));
+}
+
+#[test]
+fn fenced_code_is_highlighted_with_its_language() {
+    let (lines, text) = render("```rust\nfn main() {}\n```\n\n```\nplain\n```\n", 40);
+    assert_eq!(text, vec!["  fn main() {}", "", "  plain"]);
+    let keyword = lines[0]
+        .iter()
+        .find(|segment| segment.text == "fn")
+        .expect("keyword segment");
+    let theme = HighlightContext::new();
+    assert_eq!(
+        keyword.style,
+        theme.syntax_theme().style_for_capture("keyword")
+    );
+}
+
+#[test]
+fn links_show_their_url() {
+    let (_, text) = render(
+        "See [the docs](https://example.com/docs) or <https://a.b>.",
+        80,
+    );
+    assert_eq!(
+        text,
+        vec!["See the docs (https://example.com/docs) or https://a.b."]
+    );
+}
+
+#[test]
+fn markdown_view_rerenders_when_the_width_changes() {
+    let d = tempdir().expect("tempdir");
+    let path = d.path().join("README.md");
+    fs::write(
+        &path,
+        "# Notes\n\nalpha beta gamma delta epsilon zeta eta theta\n",
+    )
+    .expect("write");
+    let ctx = HighlightContext::new();
+    let mut doc = load_preview(&path, 4096, &ctx);
+    render_markdown_view(&mut doc, &ctx, 30);
+    assert_eq!(doc.content_type, ContentType::Highlighted);
+    assert_eq!(doc.source_line_count(), 5);
+    render_markdown_view(&mut doc, &ctx, 80);
+    assert_eq!(doc.source_line_count(), 4);
+    assert_eq!(
+        doc.source_lines()[3],
+        "alpha beta gamma delta epsilon zeta eta theta"
+    );
+
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.preview_render_markdown = true;
+    let mut terminal = Terminal::new(TestBackend::new(60, 8)).expect("terminal");
+    terminal
+        .draw(|frame| {
+            draw_preview(
+                frame,
+                frame.size(),
+                &doc,
+                &state,
+                &fpv::config::load::ThemeProfile::default(),
+            )
+        })
+        .expect("draw");
+    let rendered = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect::<String>();
+    assert!(rendered.contains("│Notes"), "{rendered}");
+    assert!(!rendered.contains("# Notes"), "{rendered}");
+}
+
+#[test]
+fn markdown_view_leaves_other_files_alone() {
+    let d = tempdir().expect("tempdir");
+    let path = d.path().join("main.rs");
+    fs::write(&path, "# not markdown\n").expect("write");
+    let ctx = HighlightContext::new();
+    let mut doc = load_preview(&path, 4096, &ctx);
+    render_markdown_view(&mut doc, &ctx, 40);
+    assert!(doc.rendered_markdown.is_none());
+    assert_eq!(
+        action_from_name("toggle_markdown_render"),
+        Some(Action::ToggleMarkdownRender)
+    );
+}
@@ -0,0 +1,213 @@
+use fpv::app::clipboard::preview_selection_text;
+use fpv::app::preview_controller::{refresh_preview, PAGED_WRAP_NOTICE};
+use fpv::app::state::{ContentType, LoadState, NodeType, SessionState, TreeNode};
+use fpv::fs::encoding::TextEncoding;
+use fpv::fs::paged::{IndexStatus, PagedFile, MAX_PAGED_LINE_BYTES};
+use fpv::fs::preview::{load_preview, load_preview_head, page_viewport};
+use fpv::highlight::syntax::HighlightContext;
+use fpv::tui::preview_pane::{draw_preview, preview_total_lines};
+use ratatui::backend::TestBackend;
+use ratatui::style::Style;
+use ratatui::Terminal;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::{tempdir, TempDir};
+
+fn numbered_lines(count: usize) -> String {
+    (0..count).map(|n| format!("line {n}\n")).collect()
+}
+
+fn write_file(name: &str, body: &str) -> (TempDir, PathBuf) {
+    let dir = tempdir().expect("tempdir");
+    let path = dir.path().join(name);
+    fs::write(&path, body).expect("write file");
+    (dir, path)
+}
+
+fn indexed(path: &Path) -> PagedFile {
+    let file = PagedFile::open(path, TextEncoding::Utf8).expect("open");
+    file.wait_until_indexed();
+    file
+}
+
+#[test]
+fn line_count_matches_split_semantics() {
+    for body in ["", "a", "a\nb", "a\nb\n", "\n\n"] {
+        let (_dir, path) = write_file("f.txt", body);
+        let file = indexed(&path);
+        assert!(file.is_indexed());
+        assert_eq!(file.line_count(), body.split('\n').count(), "{body:?}");
+        assert_eq!(file.len_bytes(), body.len() as u64);
+        let lines = file.read_lines(0..usize::MAX).expect("read");
+        assert_eq!(lines, body.split('\n').collect::<Vec<_>>(), "{body:?}");
+    }
+}
+
+#[test]
+fn a_read_error_stops_the_index_without_a_final_line_count() {
+    // A directory opens, but reading it fails.
+    let dir = tempdir().expect("tempdir");
+    let file = PagedFile::open(dir.path(), TextEncoding::Utf8).expect("open");
+    file.wait_until_indexed();
+    assert!(!file.is_indexed());
+    assert!(matches!(file.index_status(), IndexStatus::Stopped(_)));
+    assert!(
+        file.line_count_label()
+            .starts_with("1+ lines (index stopped: "),
+        "{}",
+        file.line_count_label()
+    );
+}
+
+#[test]
+fn read_lines_seeks_across_index_checkpoints() {
+    let (_dir, path) = write_file("big.log", &numbered_lines(5000));
+    let file = indexed(&path);
+    assert_eq!(file.line_count(), 5001);
+    assert_eq!(file.line_count_label(), "5001 lines");
+    assert_eq!(
+        file.read_lines(2046..2050).expect("read"),
+        vec!["line 2046", "line 2047", "line 2048", "line 2049"]
+    );
+    assert_eq!(
+        file.read_lines(4998..6000).expect("read"),
+        vec!["line 4998", "line 4999", ""]
+    );
+    assert!(file.read_lines(5001..5002).expect("read").is_empty());
+}
+
+#[test]
+fn read_lines_trims_crlf_and_replaces_invalid_utf8() {
+    let dir = tempdir().expect("tempdir");
+    let path = dir.path().join("dos.txt");
+    fs::write(&path, b"one\r\ntw\xffo\r\n").expect("write file");
+    let file = indexed(&path);
+    assert_eq!(
+        file.read_lines(0..2).expect("read"),
+        vec!["one".to_string(), "tw\u{fffd}o".to_string()]
+    );
+}
+
+#[test]
+fn read_lines_caps_very_long_lines() {
+    let long = "x".repeat(MAX_PAGED_LINE_BYTES * 3);
+    let (_dir, path) = write_file("minified.js", &format!("{long}\nnext\r\n{long}"));
+    let file = indexed(&path);
+    let lines = file.read_lines(0..3).expect("read");
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0].len(), MAX_PAGED_LINE_BYTES + '…'.len_utf8());
+    assert!(lines[0].ends_with("x…"));
+    assert_eq!(lines[1], "next");
+    assert_eq!(lines[2], lines[0]);
+    assert_eq!(file.read_lines(1..2).expect("read"), vec!["next"]);
+}
+
+#[test]
+fn paged_previews_say_that_wrap_is_off() {
+    let (_dir, path) = write_file("app.log", &numbered_lines(2000));
+    let nodes = vec![TreeNode {
+        path,
+        name: "app.log".to_string(),
+        node_type: NodeType::File,
+        depth: 0,
+        expanded: false,
+        readable: true,
+        children_loaded: false,
+        entry_class: None,
+    }];
+    let ctx = HighlightContext::new();
+    let mut state = SessionState::new(PathBuf::from("."));
+    let doc = refresh_preview(&mut state, &nodes, &ctx, 4096);
+    assert!(doc.paged.is_some());
+    assert_eq!(state.status_notice, None);
+
+    state.preview_wrap_enabled = true;
+    let doc = refresh_preview(&mut state, &nodes, &ctx, 4096);
+    assert!(doc.paged.is_some());
+    assert_eq!(state.status_notice.as_deref(), Some(PAGED_WRAP_NOTICE));
+}
+
+#[test]
+fn files_over_the_limit_are_paged_not_truncated() {
+    let (_dir, path) = write_file("app.log", &numbered_lines(20_000));
+    let ctx = HighlightContext::new();
+    let mut doc = load_preview(&path, 4096, &ctx);
+    assert_eq!(doc.load_state, LoadState::Ready);
+    assert_eq!(doc.content_type, ContentType::PlainText);
+    assert_eq!(doc.line_offset(), 0);
+    assert_eq!(doc.source_lines()[0], "line 0");
+
+    let paged = doc.paged.clone().expect("paged");
+    paged.file.wait_until_indexed();
+    assert_eq!(doc.source_line_count(), 20_001);
+    assert_eq!(preview_total_lines(&doc), 20_001);
+
+    page_viewport(&mut doc, 15_000..15_030);
+    assert!(doc.line_offset() <= 15_000);
+    let held = doc.source_lines();
+    assert_eq!(held[15_000 - doc.line_offset()], "line 15000");
+    assert!(doc.line_offset() + held.len() >= 15_030);
+
+    assert_eq!(
+        preview_selection_text(&doc, Some((2, 4))).as_deref(),
+        Some("line 2\nline 3\nline 4")
+    );
+}
+
+#[test]
+fn paged_source_files_are_highlighted_around_the_viewport() {
+    let body = "fn x() {}\n".repeat(20_000);
+    let (_dir, path) = write_file("gen.rs", &body);
+    let ctx = HighlightContext::new();
+    let mut doc = load_preview(&path, 4096, &ctx);
+    assert_eq!(doc.content_type, ContentType::Highlighted);
+    assert_eq!(doc.language_id.as_deref(), Some("Rust"));
+
+    page_viewport(&mut doc, 12_000..12_020);
+    fpv::highlight::viewport::highlight_viewport(&mut doc, &ctx, 12_000..12_020);
+    let line = &doc.styled_lines[12_000 - doc.line_offset()];
+    assert!(line.iter().any(|segment| segment.style != Style::default()));
+}
+
+#[test]
+fn paged_preview_numbers_lines_from_the_start_of_the_file() {
+    let (_dir, path) = write_file("app.log", &numbered_lines(20_000));
+    let ctx = HighlightContext::new();
+    let mut doc = load_preview(&path, 4096, &ctx);
+    doc.paged.clone().expect("paged").file.wait_until_indexed();
+
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.preview_scroll_row = 19_990;
+    page_viewport(&mut doc, 19_990..19_996);
+
+    let mut terminal = Terminal::new(TestBackend::new(40, 8)).expect("terminal");
+    terminal
+        .draw(|frame| {
+            draw_preview(
+                frame,
+                frame.size(),
+                &doc,
+                &state,
+                &fpv::config::load::ThemeProfile::default(),
+            )
+        })
+        .expect("draw");
+    let rendered = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|cell| cell.symbol())
+        .collect::<String>();
+    assert!(rendered.contains("19991 line 19990"), "{rendered}");
+    assert!(rendered.contains("19996 line 19995"), "{rendered}");
+}
+
+#[test]
+fn head_preview_still_truncates_for_print_output() {
+    let (_dir, path) = write_file("app.log", &numbered_lines(2000));
+    let doc = load_preview_head(&path, 100, &HighlightContext::new(), None);
+    assert!(doc.paged.is_none());
+    assert!(doc.truncated);
+    assert!(doc.content_excerpt.ends_with("[truncated]"));
+}
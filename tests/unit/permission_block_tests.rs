@@ -14,6 +14,7 @@ fn unreadable_directory_is_blocked() {
         expanded: false,
         readable: false,
         children_loaded: false,
+        entry_class: None,
     }];
     let result = enter_selected_directory(&mut state, &mut nodes).expect("enter");
     assert_eq!(result.outcome, ActionOutcome::Blocked);
@@ -28,6 +28,7 @@ fn permission_display_is_compact_symbolic_form() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     };
 
     let metadata = selected_entry_metadata(&node);
@@ -47,6 +48,7 @@ fn hidden_status_is_set_for_dot_entries() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     };
 
     let metadata = selected_entry_metadata(&node);
@@ -3,8 +3,10 @@ use fpv::app::state::{
     ContentType, LoadState, NodeType, PreviewFallbackReason, SelectedEntryMetadata, SessionState,
     TreeNode,
 };
-use fpv::fs::preview::load_preview;
+use fpv::fs::encoding::TextEncoding;
+use fpv::fs::preview::{load_preview, load_preview_with_encoding};
 use fpv::highlight::syntax::HighlightContext;
+use fpv::highlight::viewport::{highlight_viewport, VIEWPORT_MARGIN_LINES};
 use fpv::tui::preview_pane::{draw_preview, preview_total_lines};
 use fpv::tui::status_bar::compose_preview_metadata_line;
 use ratatui::backend::TestBackend;
@@ -12,6 +14,8 @@ use ratatui::style::Style;
 use ratatui::Terminal;
 use std::fs;
 use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
 use tempfile::tempdir;
 
 fn has_non_default_style(doc: &fpv::app::state::PreviewDocument) -> bool {
@@ -160,6 +164,53 @@ fn preview_selects_highlight_for_additional_common_languages() {
     }
 }
 
+#[cfg(feature = "all-languages")]
+#[test]
+fn preview_highlights_feature_gated_languages() {
+    let d = tempdir().expect("create tempdir");
+    let cases = [
+        (
+            "config.yaml",
+            "name: fpv # comment\nitems:\n  - 1\n",
+            "YAML",
+        ),
+        ("pom.xml", "<project><name>fpv</name></project>\n", "XML"),
+        ("query.sql", "SELECT id FROM users WHERE id = 1;\n", "SQL"),
+        ("app.rb", "def hi\n  puts 'hi'\nend\n", "Ruby"),
+        ("init.lua", "local x = 1\nif x then print(x) end\n", "Lua"),
+        ("index.php", "<?php echo \"hi\"; ?>\n", "PHP"),
+        ("Main.kt", "fun main() { val x = 1 }\n", "Kotlin"),
+        ("main.swift", "let x = 1\nif x > 0 { print(x) }\n", "Swift"),
+        ("Program.cs", "class A { void M() { return; } }\n", "C#"),
+        (
+            "Main.scala",
+            "object Main { def main(): Unit = () }\n",
+            "Scala",
+        ),
+        (
+            "Main.hs",
+            "main :: IO ()\nmain = putStrLn \"hi\"\n",
+            "Haskell",
+        ),
+        ("Dockerfile", "FROM rust:1\nRUN cargo build\n", "Dockerfile"),
+        ("default.nix", "{ pkgs }: pkgs.hello # c\n", "Nix"),
+    ];
+    let ctx = HighlightContext::new();
+
+    for (name, content, language) in cases {
+        let path = d.path().join(name);
+        fs::write(&path, content).expect("write sample");
+        let doc = load_preview(&path, 1024, &ctx);
+        assert_eq!(
+            doc.content_type,
+            ContentType::Highlighted,
+            "expected highlighting for {name}"
+        );
+        assert_eq!(doc.language_id.as_deref(), Some(language), "{name}");
+        assert!(has_non_default_style(&doc), "no styled spans for {name}");
+    }
+}
+
 #[test]
 fn preview_falls_back_plain_text_for_unknown_extension() {
     let d = tempdir().expect("create tempdir");
@@ -186,24 +237,94 @@ fn preview_marks_binary_files() {
 }
 
 #[test]
-fn large_supported_file_uses_plain_text_guard() {
+fn large_supported_file_is_highlighted_per_viewport() {
     let d = tempdir().expect("create tempdir");
     let p = d.path().join("large.rs");
     fs::write(&p, "fn x() {}\n".repeat(50_000)).expect("write file");
     let ctx = HighlightContext::new();
-    let doc = load_preview(&p, 1024 * 1024, &ctx);
+    let mut doc = load_preview(&p, 1024 * 1024, &ctx);
     assert_eq!(doc.load_state, LoadState::Ready);
-    assert_eq!(doc.content_type, ContentType::PlainText);
-    assert_eq!(doc.fallback_reason, Some(PreviewFallbackReason::TooLarge));
+    assert_eq!(doc.content_type, ContentType::Highlighted);
+    assert_eq!(doc.fallback_reason, None);
+    assert_eq!(doc.styled_lines.len(), 50_001);
+    assert_eq!(doc.viewport_highlight, Some(0..VIEWPORT_MARGIN_LINES));
+
+    let is_styled = |line: &fpv::app::state::StyledPreviewLine| {
+        line.iter().any(|segment| segment.style != Style::default())
+    };
+    assert!(is_styled(&doc.styled_lines[0]));
+    assert!(!is_styled(&doc.styled_lines[30_000]));
+
+    highlight_viewport(&mut doc, &ctx, 30_000..30_040);
+    assert!(is_styled(&doc.styled_lines[30_000]));
+    assert!(is_styled(&doc.styled_lines[30_039]));
+    assert_eq!(
+        doc.viewport_highlight,
+        Some(30_000 - VIEWPORT_MARGIN_LINES..30_040 + VIEWPORT_MARGIN_LINES)
+    );
+    assert_eq!(doc.source_lines()[30_000], "fn x() {}");
+}
+
+#[test]
+fn viewport_windows_never_split_a_block_comment() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("large.rs");
+    let mut source = "fn x() {}\n".repeat(30_000);
+    source.push_str("/*\n");
+    source.push_str(&"let commented = 1;\n".repeat(1_000));
+    source.push_str("*/\n");
+    source.push_str(&"fn y() {}\n".repeat(100));
+    fs::write(&p, source).expect("write file");
+    let ctx = HighlightContext::new();
+    let mut doc = load_preview(&p, 1024 * 1024, &ctx);
+    assert_eq!(doc.content_type, ContentType::Highlighted);
+
+    // Both margins around line 30 700 fall inside the comment, which the
+    // window is widened to once the file is parsed.
+    let started = Instant::now();
+    highlight_viewport(&mut doc, &ctx, 30_700..30_740);
+    while !doc
+        .viewport_tree
+        .as_ref()
+        .is_some_and(|tree| tree.is_parsed())
+    {
+        assert!(started.elapsed() < Duration::from_secs(30), "never parsed");
+        thread::sleep(Duration::from_millis(10));
+    }
+    highlight_viewport(&mut doc, &ctx, 30_700..30_740);
+    assert_eq!(
+        doc.viewport_highlight.as_ref().map(|w| w.start),
+        Some(30_000)
+    );
+    let comment_style = doc.styled_lines[30_000][0].style;
+    assert_ne!(comment_style, Style::default());
+    let line = &doc.styled_lines[30_700];
+    assert!(
+        line.iter().all(|segment| segment.style == comment_style),
+        "{line:?}"
+    );
 }
 
 #[test]
-fn decode_uncertain_bytes_fall_back_to_plain_text() {
+fn viewport_highlight_leaves_small_files_alone() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("small.rs");
+    fs::write(&p, "fn x() {}\n").expect("write file");
+    let ctx = HighlightContext::new();
+    let mut doc = load_preview(&p, 1024, &ctx);
+    let before = doc.styled_lines.clone();
+    highlight_viewport(&mut doc, &ctx, 0..10);
+    assert_eq!(doc.viewport_highlight, None);
+    assert_eq!(doc.styled_lines.len(), before.len());
+}
+
+#[test]
+fn forced_utf8_on_invalid_bytes_falls_back_to_plain_text() {
     let d = tempdir().expect("create tempdir");
     let p = d.path().join("broken.py");
     fs::write(&p, [0x66_u8, 0x6f, 0x80, 0x6f, 0x0a]).expect("write file");
     let ctx = HighlightContext::new();
-    let doc = load_preview(&p, 1024, &ctx);
+    let doc = load_preview_with_encoding(&p, 1024, &ctx, Some(TextEncoding::Utf8));
     assert_eq!(doc.load_state, LoadState::Ready);
     assert_eq!(doc.content_type, ContentType::PlainText);
     assert_eq!(
@@ -229,6 +350,7 @@ fn directory_selection_uses_neutral_preview() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     }];
 
     let mut state = SessionState::new(PathBuf::from("."));
@@ -363,7 +485,7 @@ fn unsupported_preview_strips_terminal_escape_controls() {
 #[test]
 fn unsupported_preview_expands_tabs_for_terminal_stability() {
     let d = tempdir().expect("create tempdir");
-    let p = d.path().join("Makefile");
+    let p = d.path().join("recipes.txt");
     fs::write(&p, "build:\n\t@echo ok\n").expect("write file");
     let ctx = HighlightContext::new();
     let doc = load_preview(&p, 1024, &ctx);
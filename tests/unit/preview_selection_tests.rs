@@ -0,0 +1,308 @@
+use crossterm::event::Event;
+use fpv::app::clipboard::{
+    copy_text_for_target, preview_selection_text, selection_reference, CopyTarget,
+};
+use fpv::app::selection_pipe::{pipe_confirmation, poll_selection_pipe, start_pipe_selection};
+use fpv::app::state::{
+    ContentType, FocusPane, LoadState, PreviewDocument, SessionState, WrappedRows,
+};
+use fpv::config::keymap::{default_keymap, Action};
+use fpv::config::load::ThemeProfile;
+use fpv::tui::event_loop::handle_event;
+use fpv::tui::preview_pane::{
+    draw_preview, preview_header_rows, refresh_wrapped_rows, wrapped_preview_rows,
+};
+use ratatui::backend::TestBackend;
+use ratatui::style::Color;
+use ratatui::Terminal;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn plain_doc(content: &str) -> PreviewDocument {
+    PreviewDocument {
+        load_state: LoadState::Ready,
+        content_type: ContentType::PlainText,
+        language_id: Some("Rust".to_string()),
+        content_excerpt: content.to_string(),
+        ..PreviewDocument::default()
+    }
+}
+
+#[test]
+fn visual_selection_starts_at_top_visible_line_and_extends() {
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.preview_scroll_row = 2;
+    state.start_visual_selection(10, 0);
+    assert_eq!(state.preview_selection_range(), Some((2, 2)));
+
+    state.move_preview_cursor(3, 10);
+    assert_eq!(state.preview_selection_range(), Some((2, 5)));
+
+    state.move_preview_cursor(-5, 10);
+    assert_eq!(state.preview_selection_range(), Some((0, 2)));
+
+    state.move_preview_cursor(50, 10);
+    assert_eq!(state.preview_selection_range(), Some((2, 9)));
+
+    state.cancel_visual_selection();
+    assert!(state.preview_selection_range().is_none());
+}
+
+#[test]
+fn visual_selection_requires_source_lines() {
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.start_visual_selection(0, 0);
+    assert!(!state.visual_selection_active());
+}
+
+#[test]
+fn cursor_movement_keeps_cursor_in_viewport() {
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.start_visual_selection(100, 0);
+    state.move_preview_cursor(20, 100);
+    state.ensure_preview_cursor_visible(0, 10);
+    assert_eq!(state.preview_scroll_row, 11);
+
+    state.move_preview_cursor(-15, 100);
+    state.ensure_preview_cursor_visible(0, 10);
+    assert_eq!(state.preview_scroll_row, 5);
+}
+
+#[test]
+fn resetting_preview_scroll_cancels_selection() {
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.start_visual_selection(5, 0);
+    state.reset_preview_scroll();
+    assert!(!state.visual_selection_active());
+}
+
+#[test]
+fn selection_text_and_reference_use_range() {
+    let doc = plain_doc("one\ntwo\nthree\nfour");
+    assert_eq!(
+        preview_selection_text(&doc, Some((1, 2))).as_deref(),
+        Some("two\nthree")
+    );
+    assert_eq!(
+        selection_reference(Path::new("/work"), Path::new("/work/src/a.rs"), (1, 2)),
+        "src/a.rs:2-3"
+    );
+    assert_eq!(
+        selection_reference(Path::new("/work"), Path::new("/work/src/a.rs"), (4, 4)),
+        "src/a.rs:5"
+    );
+
+    let mut state = SessionState::new(PathBuf::from("/work"));
+    state.selected_path = PathBuf::from("/work/src/a.rs");
+    state.preview_scroll_row = 1;
+    state.start_visual_selection(doc.source_line_count(), 0);
+    state.move_preview_cursor(2, doc.source_line_count());
+    assert_eq!(
+        copy_text_for_target(&state, &doc, CopyTarget::PreviewSelection).as_deref(),
+        Some("two\nthree\nfour")
+    );
+    assert_eq!(
+        copy_text_for_target(&state, &doc, CopyTarget::SelectionReference).as_deref(),
+        Some("src/a.rs:2-4")
+    );
+}
+
+#[test]
+fn fallback_header_offsets_selection_rows() {
+    let mut doc = plain_doc("a\nb");
+    assert_eq!(preview_header_rows(&doc), 0);
+    doc.fallback_reason = Some(fpv::app::state::PreviewFallbackReason::UnsupportedExtension);
+    assert_eq!(preview_header_rows(&doc), 1);
+
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.preview_scroll_row = 1;
+    state.start_visual_selection(doc.source_line_count(), preview_header_rows(&doc));
+    assert_eq!(state.preview_selection_range(), Some((0, 0)));
+}
+
+#[test]
+fn selected_rows_are_highlighted_when_rendered() {
+    let doc = plain_doc("alpha\nbeta\ngamma");
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.preview_show_line_numbers = false;
+    state.preview_scroll_row = 1;
+    state.start_visual_selection(doc.source_line_count(), 0);
+
+    let backend = TestBackend::new(30, 6);
+    let mut terminal = Terminal::new(backend).expect("terminal");
+    terminal
+        .draw(|frame| draw_preview(frame, frame.size(), &doc, &state, &ThemeProfile::default()))
+        .expect("draw");
+
+    let buffer = terminal.backend().buffer();
+    let selected_cell = buffer.get(1, 1);
+    assert_eq!(selected_cell.symbol(), "b");
+    assert_ne!(selected_cell.bg, Color::Reset);
+    let unselected_cell = buffer.get(1, 2);
+    assert_eq!(unselected_cell.symbol(), "g");
+    assert_eq!(unselected_cell.bg, Color::Reset);
+}
+
+/// Polls the running selection pipe until it reports back.
+fn finish_pipe(state: &mut SessionState) -> String {
+    let started = Instant::now();
+    while !poll_selection_pipe(state) {
+        assert!(
+            started.elapsed() < Duration::from_secs(10),
+            "pipe never finished"
+        );
+        thread::sleep(Duration::from_millis(10));
+    }
+    state.status_notice.clone().expect("notice")
+}
+
+#[test]
+fn pipe_selection_feeds_stdin_and_reports_first_output_line() {
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.selection_pipe = Some(start_pipe_selection(
+        "wc -l",
+        "one\ntwo",
+        Duration::from_secs(5),
+    ));
+    assert_eq!(finish_pipe(&mut state), "Piped 2 lines to `wc -l`: 2");
+    assert!(state.selection_pipe.is_none());
+    assert_eq!(
+        pipe_confirmation("wc -l", 1, ""),
+        "Piped 1 line to `wc -l`."
+    );
+
+    state.selection_pipe = Some(start_pipe_selection("exit 3", "x", Duration::from_secs(5)));
+    assert!(finish_pipe(&mut state).starts_with("Pipe failed: `exit 3` failed"));
+
+    // Output does not make a failed command a success.
+    state.selection_pipe = Some(start_pipe_selection(
+        "echo partial; echo 'bad input' >&2; exit 2",
+        "x",
+        Duration::from_secs(5),
+    ));
+    assert_eq!(
+        finish_pipe(&mut state),
+        "Pipe failed: `echo partial; echo 'bad input' >&2; exit 2` failed (exit status: 2): bad input"
+    );
+
+    state.selection_pipe = Some(start_pipe_selection(
+        "sleep 5",
+        "x",
+        Duration::from_millis(100),
+    ));
+    let notice = finish_pipe(&mut state);
+    assert!(notice.ends_with("timed out after 0.1s"), "{notice}");
+}
+
+#[test]
+fn piping_a_selection_does_not_block_the_event_loop() {
+    let doc = plain_doc("one\ntwo");
+    let bindings = default_keymap();
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.focus_pane = FocusPane::Preview;
+    state.selection_pipe_command = Some("cat >/dev/null; sleep 5".to_string());
+    state.start_visual_selection(doc.source_line_count(), 0);
+
+    let started = Instant::now();
+    handle_event(
+        &mut state,
+        &mut Vec::new(),
+        &bindings,
+        &doc,
+        2,
+        10,
+        Event::Key(bindings[&Action::PipeSelection]),
+    )
+    .expect("handle key");
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(
+        state.status_notice.as_deref(),
+        Some("Piping 1 line to `cat >/dev/null; sleep 5`…")
+    );
+    assert!(!poll_selection_pipe(&mut state));
+    // Dropping the pipe kills the command.
+    state.selection_pipe = None;
+}
+
+#[test]
+fn wrapped_rows_map_lines_to_the_rows_they_are_drawn_on() {
+    let mut doc = plain_doc("short\n0123456789012345678901234\nend");
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.preview_show_line_numbers = false;
+    assert_eq!(wrapped_preview_rows(&doc, &state, 10), None);
+
+    state.preview_wrap_enabled = true;
+    let rows = wrapped_preview_rows(&doc, &state, 10).expect("wrapped rows");
+    assert_eq!(rows, WrappedRows::from_row_counts([1, 3, 1]));
+    assert_eq!(rows.total_rows(), 5);
+    assert_eq!((rows.first_row(1), rows.last_row(1)), (1, 3));
+    assert_eq!(rows.line_at_row(3), 1);
+    assert_eq!(rows.line_at_row(4), 2);
+    assert_eq!(rows.line_at_row(99), 2);
+    assert_eq!(rows.lines_in_rows(2..4), 1..2);
+    assert_eq!(rows.lines_in_rows(0..5), 0..3);
+
+    // The selection starts on the line whose wrapped rows are at the top.
+    state.preview_scroll_row = 2;
+    state.start_visual_selection_with_rows(3, 0, Some(&rows));
+    assert_eq!(state.preview_selection_range(), Some((1, 1)));
+
+    // Every row of the cursor line is scrolled into view.
+    state.preview_scroll_row = 0;
+    state.ensure_preview_cursor_visible_with_rows(0, 2, Some(&rows));
+    assert_eq!(state.preview_scroll_row, 1);
+    state.move_preview_cursor(1, 3);
+    state.ensure_preview_cursor_visible_with_rows(0, 2, Some(&rows));
+    assert_eq!(state.preview_scroll_row, 3);
+    // A line taller than the viewport shows from its first row.
+    state.move_preview_cursor(-1, 3);
+    state.ensure_preview_cursor_visible_with_rows(0, 2, Some(&rows));
+    assert_eq!(state.preview_scroll_row, 1);
+
+    doc.wrapped_rows = Some(rows);
+    let buffer_rows = {
+        let mut terminal = Terminal::new(TestBackend::new(12, 7)).expect("terminal");
+        terminal
+            .draw(|frame| draw_preview(frame, frame.size(), &doc, &state, &ThemeProfile::default()))
+            .expect("draw");
+        let buffer = terminal.backend().buffer().clone();
+        (1..6)
+            .map(|y| {
+                (1..11)
+                    .map(|x| buffer.get(x, y).symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(buffer_rows[0], "0123456789");
+    assert_eq!(buffer_rows[2], "01234     ");
+    assert_eq!(buffer_rows[3], "end       ");
+}
+
+#[test]
+fn wrapped_rows_are_recomputed_only_when_the_layout_or_text_changes() {
+    let mut doc = plain_doc("0123456789012345678901234");
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.preview_show_line_numbers = false;
+    state.preview_wrap_enabled = true;
+    refresh_wrapped_rows(&mut doc, &state, 10);
+    assert_eq!(doc.wrapped_rows, Some(WrappedRows::from_row_counts([3])));
+
+    // The same layout keeps the rows it has.
+    doc.wrapped_rows = Some(WrappedRows::from_row_counts([7]));
+    refresh_wrapped_rows(&mut doc, &state, 10);
+    assert_eq!(doc.wrapped_rows, Some(WrappedRows::from_row_counts([7])));
+
+    refresh_wrapped_rows(&mut doc, &state, 5);
+    assert_eq!(doc.wrapped_rows, Some(WrappedRows::from_row_counts([5])));
+
+    doc.content_excerpt = "short".to_string();
+    doc.wrapped_layout = None;
+    refresh_wrapped_rows(&mut doc, &state, 5);
+    assert_eq!(doc.wrapped_rows, Some(WrappedRows::from_row_counts([1])));
+
+    state.preview_wrap_enabled = false;
+    refresh_wrapped_rows(&mut doc, &state, 5);
+    assert_eq!(doc.wrapped_rows, None);
+}
@@ -0,0 +1,103 @@
+use fpv::app::print::{print_preview, printable_lines};
+use fpv::app::state::{ContentType, LoadState, PreviewDocument, StyledPreviewSegment};
+use fpv::export::ansi::{render_ansi_lines, style_sgr_params, AnsiRenderOptions};
+use fpv::fs::preview::load_preview;
+use fpv::highlight::syntax::HighlightContext;
+use fpv::tui::color_support::ColorSupport;
+use ratatui::style::{Color, Modifier, Style};
+use std::fs;
+use tempfile::tempdir;
+
+fn segment(text: &str, style: Style) -> StyledPreviewSegment {
+    StyledPreviewSegment {
+        text: text.to_string(),
+        style,
+    }
+}
+
+#[test]
+fn sgr_params_cover_truecolor_and_modifiers() {
+    let style = Style::default()
+        .fg(Color::Rgb(220, 150, 80))
+        .bg(Color::Blue)
+        .add_modifier(Modifier::BOLD | Modifier::ITALIC);
+    assert_eq!(style_sgr_params(style), "1;3;38;2;220;150;80;44");
+    assert_eq!(style_sgr_params(Style::default()), "");
+}
+
+#[test]
+fn plain_render_has_no_escape_sequences() {
+    let lines = vec![vec![segment("fn", Style::default().fg(Color::Red))], vec![]];
+    let options = AnsiRenderOptions {
+        color: ColorSupport::Monochrome,
+        line_numbers: true,
+        wrap_width: None,
+    };
+    assert_eq!(render_ansi_lines(&lines, options), " 1 fn\n 2 \n");
+}
+
+#[test]
+fn color_render_wraps_segments_in_sgr() {
+    let lines = vec![vec![segment("fn", Style::default().fg(Color::Red))]];
+    let options = AnsiRenderOptions {
+        color: ColorSupport::TrueColor,
+        line_numbers: false,
+        wrap_width: None,
+    };
+    assert_eq!(render_ansi_lines(&lines, options), "\x1b[31mfn\x1b[0m\n");
+}
+
+#[test]
+fn wrap_width_splits_rows_and_blanks_continuation_gutter() {
+    let lines = vec![vec![segment("abcdefgh", Style::default())]];
+    let options = AnsiRenderOptions {
+        color: ColorSupport::Monochrome,
+        line_numbers: true,
+        wrap_width: Some(3),
+    };
+    assert_eq!(
+        render_ansi_lines(&lines, options),
+        " 1 abc\n   def\n   gh\n"
+    );
+}
+
+#[test]
+fn printable_lines_use_highlighting_and_drop_trailing_newline_row() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("main.rs");
+    fs::write(&p, "fn main() {}\n").expect("write");
+    let doc = load_preview(&p, 1024, &HighlightContext::new());
+
+    let lines = printable_lines(&doc).expect("printable");
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].iter().any(|s| s.style != Style::default()));
+}
+
+#[test]
+fn printable_lines_reject_binary_documents() {
+    let doc = PreviewDocument {
+        load_state: LoadState::Binary,
+        content_type: ContentType::Unsupported,
+        error_message: Some("Binary file preview is not supported.".to_string()),
+        ..PreviewDocument::default()
+    };
+    assert!(printable_lines(&doc).is_err());
+}
+
+#[test]
+fn print_highlights_large_files_past_the_first_viewport() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("large.rs");
+    fs::write(&p, "fn x() {}\n".repeat(40_000)).expect("write");
+    let options = AnsiRenderOptions {
+        color: ColorSupport::TrueColor,
+        line_numbers: false,
+        wrap_width: None,
+    };
+    let mut out = Vec::new();
+    print_preview(&p, 1024 * 1024, &HighlightContext::new(), options, &mut out).expect("print");
+
+    let text = String::from_utf8(out).expect("utf8");
+    let last = text.lines().last().expect("last line");
+    assert!(last.contains("\x1b["), "{last:?}");
+}
@@ -13,6 +13,7 @@ fn selection_revalidates_on_smaller_list() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     }];
     state.revalidate_selection(&nodes);
     assert_eq!(state.selected_index, 0);
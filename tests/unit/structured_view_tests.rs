@@ -0,0 +1,214 @@
+use fpv::app::state::{ContentType, FocusPane, PreviewDocument, SessionState};
+use fpv::config::keymap::{action_from_name, Action};
+use fpv::fs::preview::load_preview;
+use fpv::highlight::structured::{structured_lines, structured_view, StructuredFormat};
+use fpv::highlight::syntax::HighlightContext;
+use fpv::tui::preview_pane::draw_preview;
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::PathBuf;
+use tempfile::{tempdir, TempDir};
+
+const SERVERS_JSON: &str = r#"{"name":"fpv","servers":[{"host":"a","port":80},{"host":"b","port":81}],"tags":[],"a b":null}"#;
+
+fn load(name: &str, content: &str) -> (TempDir, PreviewDocument) {
+    let dir = tempdir().expect("tempdir");
+    let path = dir.path().join(name);
+    fs::write(&path, content).expect("write");
+    let doc = load_preview(&path, 64 * 1024, &HighlightContext::new());
+    (dir, doc)
+}
+
+fn folds(paths: &[&str]) -> BTreeSet<String> {
+    paths.iter().map(|path| path.to_string()).collect()
+}
+
+#[test]
+fn minified_json_is_laid_out_one_value_per_row() {
+    let (_dir, mut doc) = load("servers.json", SERVERS_JSON);
+    let error = structured_view(&mut doc, &HighlightContext::new(), &BTreeSet::new());
+    assert_eq!(error, None);
+    assert_eq!(doc.content_type, ContentType::Highlighted);
+    assert_eq!(
+        doc.source_lines(),
+        vec![
+            "▾ {4 keys}",
+            "    name: \"fpv\"",
+            "  ▾ servers: [2 items]",
+            "    ▾ [0]: {2 keys}",
+            "        host: \"a\"",
+            "        port: 80",
+            "    ▾ [1]: {2 keys}",
+            "        host: \"b\"",
+            "        port: 81",
+            "    tags: []",
+            "    a b: null",
+        ]
+    );
+}
+
+#[test]
+fn rows_carry_jq_style_paths() {
+    let ctx = HighlightContext::new();
+    let (view, _) = structured_lines(&ctx, StructuredFormat::Json, SERVERS_JSON, &BTreeSet::new())
+        .expect("valid json");
+    let paths = view
+        .rows
+        .iter()
+        .map(|row| row.path.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        paths,
+        vec![
+            ".",
+            ".name",
+            ".servers",
+            ".servers[0]",
+            ".servers[0].host",
+            ".servers[0].port",
+            ".servers[1]",
+            ".servers[1].host",
+            ".servers[1].port",
+            ".tags",
+            ".[\"a b\"]",
+        ]
+    );
+    assert_eq!(view.parent_row(8), Some(6));
+    assert_eq!(view.parent_row(6), Some(2));
+    assert_eq!(view.parent_row(0), None);
+    assert!(!view.rows[9].foldable);
+
+    let (view, _) = structured_lines(&ctx, StructuredFormat::Json, "[[1]]", &BTreeSet::new())
+        .expect("valid json");
+    assert_eq!(view.rows[2].path, ".[0][0]");
+}
+
+#[test]
+fn collapsed_nodes_hide_their_children() {
+    let (_dir, mut doc) = load("servers.json", SERVERS_JSON);
+    let ctx = HighlightContext::new();
+    structured_view(&mut doc, &ctx, &BTreeSet::new());
+    structured_view(&mut doc, &ctx, &folds(&[".servers[0]", ".servers[1].host"]));
+    assert_eq!(
+        doc.source_lines()[3..6],
+        [
+            "    ▸ [0]: {2 keys}",
+            "    ▾ [1]: {2 keys}",
+            "        host: \"b\""
+        ]
+    );
+    structured_view(&mut doc, &ctx, &folds(&[".servers"]));
+    assert_eq!(doc.source_line_count(), 5);
+    assert_eq!(doc.source_lines()[2], "  ▸ servers: [2 items]");
+    let view = doc
+        .structured
+        .as_ref()
+        .and_then(|view| view.as_ref().ok())
+        .expect("structured view");
+    assert!(view.rows[2].collapsed);
+}
+
+#[test]
+fn toml_and_multi_document_yaml_are_supported() {
+    let (_dir, mut doc) = load(
+        "Cargo.toml",
+        "[package]\nname = \"fpv\"\n\n[[bin]]\nname = \"fpv\"\npath = \"src/main.rs\"\n",
+    );
+    structured_view(&mut doc, &HighlightContext::new(), &BTreeSet::new());
+    assert_eq!(
+        doc.source_lines(),
+        vec![
+            "▾ {2 keys}",
+            "  ▾ package: {1 key}",
+            "      name: \"fpv\"",
+            "  ▾ bin: [1 item]",
+            "    ▾ [0]: {2 keys}",
+            "        name: \"fpv\"",
+            "        path: \"src/main.rs\"",
+        ]
+    );
+
+    let (_dir, mut doc) = load("docs.yaml", "kind: a\n---\nkind: b\nports: [1, 2]\n");
+    structured_view(&mut doc, &HighlightContext::new(), &folds(&[".[1].ports"]));
+    assert_eq!(
+        doc.source_lines(),
+        vec![
+            "▾ [2 items]",
+            "  ▾ [0]: {1 key}",
+            "      kind: \"a\"",
+            "  ▾ [1]: {2 keys}",
+            "      kind: \"b\"",
+            "    ▸ ports: [2 items]",
+        ]
+    );
+}
+
+#[test]
+fn invalid_source_is_reported_once_and_left_highlighted() {
+    let (_dir, mut doc) = load("broken.json", "{\"a\": [1, 2}\n");
+    let ctx = HighlightContext::new();
+    let error = structured_view(&mut doc, &ctx, &BTreeSet::new()).expect("parse error");
+    assert!(error.starts_with("JSON structure unavailable:"), "{error}");
+    assert_eq!(doc.source_lines()[0], "{\"a\": [1, 2}");
+    assert_eq!(structured_view(&mut doc, &ctx, &BTreeSet::new()), None);
+
+    let (_dir, mut doc) = load("main.rs", "fn main() {}\n");
+    assert_eq!(structured_view(&mut doc, &ctx, &BTreeSet::new()), None);
+    assert!(doc.structured.is_none());
+}
+
+#[test]
+fn folds_are_kept_per_file() {
+    let mut state = SessionState::new(PathBuf::from("."));
+    let path = PathBuf::from("a.json");
+    state.set_structured_fold(&path, ".servers", true);
+    state.set_structured_fold(&path, ".tags", true);
+    state.set_structured_fold(&path, ".tags", false);
+    assert_eq!(state.structured_folds_for(&path), folds(&[".servers"]));
+
+    let other = PathBuf::from("b.json");
+    assert!(state.structured_folds_for(&other).is_empty());
+    state.set_structured_fold(&other, ".x", true);
+    assert!(state.structured_folds_for(&path).is_empty());
+    assert_eq!(
+        action_from_name("toggle_structured_view"),
+        Some(Action::ToggleStructuredView)
+    );
+}
+
+#[test]
+fn border_shows_the_path_of_the_cursor_node() {
+    let (_dir, mut doc) = load("servers.json", SERVERS_JSON);
+    structured_view(&mut doc, &HighlightContext::new(), &BTreeSet::new());
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.preview_structured = true;
+    state.focus_pane = FocusPane::Preview;
+    state.preview_cursor_line = 5;
+
+    let mut terminal = Terminal::new(TestBackend::new(50, 14)).expect("terminal");
+    terminal
+        .draw(|frame| {
+            draw_preview(
+                frame,
+                frame.size(),
+                &doc,
+                &state,
+                &fpv::config::load::ThemeProfile::default(),
+            )
+        })
+        .expect("draw");
+    let buffer = terminal.backend().buffer();
+    let row = |y: u16| {
+        (0..buffer.area.width)
+            .map(|x| buffer.get(x, y).symbol())
+            .collect::<String>()
+    };
+    assert!(row(0).starts_with("┌ .servers[0].port "), "{}", row(0));
+    assert!(row(1).starts_with("│▾ {4 keys}"), "{}", row(1));
+    assert!(buffer
+        .get(9, 6)
+        .modifier
+        .contains(ratatui::style::Modifier::BOLD));
+}
@@ -0,0 +1,192 @@
+use fpv::app::state::{NodeType, TreeNode};
+use fpv::config::load::{load_user_config, ThemeProfile};
+use fpv::config::style_rules::{compile_style_rules, matching_rule, UserStyleRule};
+use fpv::fs::current_dir::list_current_directory;
+use fpv::fs::git::GitFileStatus;
+use fpv::tui::tree_pane::node_style;
+use ratatui::style::{Color, Modifier};
+use std::fs;
+use std::path::PathBuf;
+use tempfile::tempdir;
+
+fn node(path: &str, node_type: NodeType) -> TreeNode {
+    let path = PathBuf::from(path);
+    TreeNode {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path,
+        node_type,
+        depth: 0,
+        expanded: false,
+        readable: true,
+        children_loaded: false,
+        entry_class: None,
+    }
+}
+
+fn rule(glob: Option<&str>, name: Option<&str>, color: &str) -> UserStyleRule {
+    UserStyleRule {
+        glob: glob.map(str::to_string),
+        name: name.map(str::to_string),
+        color: Some(color.to_string()),
+        ..UserStyleRule::default()
+    }
+}
+
+fn theme_with(rules: &[UserStyleRule]) -> ThemeProfile {
+    let (compiled, warnings) = compile_style_rules(rules);
+    assert!(warnings.is_empty(), "{warnings:?}");
+    ThemeProfile {
+        rules: compiled,
+        ..ThemeProfile::default()
+    }
+}
+
+#[test]
+fn exact_names_and_globs_override_extension_colors() {
+    let theme = theme_with(&[
+        rule(None, Some("Cargo.toml"), "red"),
+        rule(Some("*.test.ts"), None, "#ff8800"),
+        rule(Some("Dockerfile*"), None, "blue"),
+    ]);
+    let style = |path: &str| node_style(&node(path, NodeType::File), &theme, None);
+    assert_eq!(style("Cargo.toml").fg, Some(Color::Red));
+    assert_eq!(style("other.toml").fg, Some(Color::Magenta));
+    assert_eq!(style("src/app.test.ts").fg, Some(Color::Rgb(255, 136, 0)));
+    assert_eq!(style("Dockerfile.dev").fg, Some(Color::Blue));
+}
+
+#[test]
+fn first_matching_rule_wins() {
+    let theme = theme_with(&[
+        rule(Some("*.rs"), None, "green"),
+        rule(None, Some("main.rs"), "red"),
+    ]);
+    let style = node_style(&node("main.rs", NodeType::File), &theme, None);
+    assert_eq!(style.fg, Some(Color::Green));
+}
+
+#[test]
+fn trailing_slash_globs_only_match_directories() {
+    let theme = theme_with(&[rule(Some("target/"), None, "darkgray")]);
+    assert_eq!(
+        node_style(&node("target", NodeType::Directory), &theme, None).fg,
+        Some(Color::DarkGray)
+    );
+    assert_eq!(
+        node_style(&node("target", NodeType::File), &theme, None).fg,
+        Some(Color::White)
+    );
+}
+
+#[test]
+fn path_globs_match_against_the_full_path() {
+    let theme = theme_with(&[rule(Some("**/tests/*.rs"), None, "yellow")]);
+    let tested = node_style(&node("./tests/a.rs", NodeType::File), &theme, None);
+    let other = node_style(&node("./src/a.rs", NodeType::File), &theme, None);
+    assert_eq!(tested.fg, Some(Color::Yellow));
+    assert_eq!(other.fg, Some(Color::Cyan));
+}
+
+#[test]
+fn type_and_git_status_rules_add_modifiers_and_icons() {
+    let theme = theme_with(&[
+        UserStyleRule {
+            git_status: Some("modified".to_string()),
+            modifiers: vec!["bold".to_string(), "italic".to_string()],
+            icon: Some("~".to_string()),
+            ..UserStyleRule::default()
+        },
+        UserStyleRule {
+            node_type: Some("symlink".to_string()),
+            color: Some("cyan".to_string()),
+            ..UserStyleRule::default()
+        },
+    ]);
+    let file = node("lib.rs", NodeType::File);
+    let style = node_style(&file, &theme, Some(GitFileStatus::Modified));
+    assert_eq!(style.fg, Some(Color::Cyan));
+    assert!(style
+        .add_modifier
+        .contains(Modifier::BOLD | Modifier::ITALIC));
+    let matched = matching_rule(&theme.rules, &file, Some(GitFileStatus::Modified));
+    assert_eq!(matched.and_then(|rule| rule.icon.as_deref()), Some("~"));
+    assert!(matching_rule(&theme.rules, &file, None).is_none());
+
+    let link = node("latest", NodeType::Symlink);
+    assert_eq!(node_style(&link, &theme, None).fg, Some(Color::Cyan));
+}
+
+#[cfg(unix)]
+#[test]
+fn executable_rules_check_permission_bits() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let d = tempdir().expect("create tempdir");
+    let script = d.path().join("build.sh");
+    let data = d.path().join("data.sh");
+    fs::write(&script, "#!/bin/sh\n").expect("write script");
+    fs::write(&data, "echo\n").expect("write data");
+    fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).expect("chmod");
+    fs::set_permissions(&data, fs::Permissions::from_mode(0o644)).expect("chmod");
+
+    let theme = theme_with(&[UserStyleRule {
+        executable: Some(true),
+        color: Some("green".to_string()),
+        ..UserStyleRule::default()
+    }]);
+    let nodes = list_current_directory(d.path(), 10).expect("list");
+    let style = |name: &str| {
+        let node = nodes.iter().find(|node| node.name == name).expect("node");
+        node_style(node, &theme, None)
+    };
+    assert_eq!(style("build.sh").fg, Some(Color::Green));
+    assert_eq!(style("data.sh").fg, Some(Color::White));
+    // Permission bits come from the listing; nodes known only by type are
+    // never executable.
+    let unlisted = node(&script.to_string_lossy(), NodeType::File);
+    assert_eq!(node_style(&unlisted, &theme, None).fg, Some(Color::White));
+}
+
+#[test]
+fn invalid_rules_are_reported_and_skipped() {
+    let (compiled, warnings) = compile_style_rules(&[
+        rule(Some("[oops"), None, "red"),
+        UserStyleRule {
+            node_type: Some("socket".to_string()),
+            ..UserStyleRule::default()
+        },
+        UserStyleRule {
+            git_status: Some("dirty".to_string()),
+            ..UserStyleRule::default()
+        },
+        UserStyleRule {
+            color: Some("red".to_string()),
+            ..UserStyleRule::default()
+        },
+        rule(Some("*.md"), None, "not-a-color"),
+        rule(Some("*.rs"), None, "red"),
+    ]);
+    assert_eq!(compiled.len(), 1);
+    assert_eq!(warnings.len(), 5, "{warnings:?}");
+    assert!(warnings[0].starts_with("theme rule 1 ignored: invalid glob"));
+    assert!(warnings[3].contains("no match criteria"));
+}
+
+#[test]
+fn rules_parse_from_config_in_order() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("config.toml");
+    fs::write(
+        &p,
+        "[[theme.rules]]\nname = 'Cargo.toml'\ncolor = 'red'\nicon = '📦'\n\n[[theme.rules]]\nglob = 'target/'\ntype = 'directory'\nmodifiers = ['dim']\n",
+    )
+    .expect("write config");
+    let cfg = load_user_config(&p).expect("load config");
+    assert_eq!(cfg.theme.rules.len(), 2);
+    assert_eq!(cfg.theme.rules[0].name.as_deref(), Some("Cargo.toml"));
+    assert_eq!(cfg.theme.rules[0].icon.as_deref(), Some("📦"));
+    assert_eq!(cfg.theme.rules[1].node_type.as_deref(), Some("directory"));
+}
@@ -0,0 +1,153 @@
+use fpv::config::load::UserThemeConfig;
+use fpv::config::merge::merge_syntax_theme;
+use fpv::fs::preview::load_preview;
+use fpv::highlight::syntax::HighlightContext;
+use fpv::highlight::theme::{parse_color, SyntaxPalette, SyntaxStyleConfig, SyntaxTheme};
+use ratatui::style::{Color, Modifier, Style};
+use std::collections::HashMap;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn dark_palette_matches_previous_builtin_colors() {
+    let theme = SyntaxTheme::default();
+    assert_eq!(
+        theme.style_for_capture("keyword"),
+        Style::default()
+            .fg(Color::Rgb(220, 150, 80))
+            .add_modifier(Modifier::BOLD)
+    );
+    assert_eq!(
+        theme.style_for_capture("variable.parameter"),
+        Style::default().fg(Color::Rgb(210, 170, 230))
+    );
+    assert_eq!(theme.style_for_capture("punctuation"), Style::default());
+}
+
+#[test]
+fn captures_fall_back_to_dotted_parent() {
+    let theme = SyntaxTheme::builtin(SyntaxPalette::Dark);
+    assert_eq!(
+        theme.style_for_capture("function.builtin"),
+        theme.style_for_capture("function")
+    );
+    assert_eq!(
+        theme.style_for_capture("constant.builtin"),
+        theme.style_for_capture("constant")
+    );
+    assert_eq!(
+        theme.style_for_capture("variable.builtin"),
+        Style::default()
+    );
+}
+
+#[test]
+fn palettes_are_selectable_by_name() {
+    assert_eq!(SyntaxPalette::from_name("dark"), Some(SyntaxPalette::Dark));
+    assert_eq!(
+        SyntaxPalette::from_name("Light"),
+        Some(SyntaxPalette::Light)
+    );
+    assert_eq!(
+        SyntaxPalette::from_name("high-contrast"),
+        Some(SyntaxPalette::HighContrast)
+    );
+    assert_eq!(
+        SyntaxPalette::from_name("high_contrast"),
+        Some(SyntaxPalette::HighContrast)
+    );
+    assert_eq!(SyntaxPalette::from_name("solarized"), None);
+    assert_ne!(
+        SyntaxTheme::builtin(SyntaxPalette::Light),
+        SyntaxTheme::builtin(SyntaxPalette::Dark)
+    );
+}
+
+#[test]
+fn parse_color_accepts_names_hex_and_indexes() {
+    assert_eq!(parse_color("red"), Some(Color::Red));
+    assert_eq!(parse_color("#6A737D"), Some(Color::Rgb(0x6a, 0x73, 0x7d)));
+    assert_eq!(parse_color("214"), Some(Color::Indexed(214)));
+    assert_eq!(parse_color("not-a-color"), None);
+}
+
+#[test]
+fn user_overrides_layer_on_selected_palette() {
+    let user = UserThemeConfig {
+        syntax_palette: Some("light".to_string()),
+        syntax: HashMap::from([
+            (
+                "comment".to_string(),
+                SyntaxStyleConfig {
+                    fg: Some("#808080".to_string()),
+                    bg: Some("black".to_string()),
+                    modifiers: vec!["bold".to_string(), "underline".to_string()],
+                },
+            ),
+            (
+                "function.builtin".to_string(),
+                SyntaxStyleConfig {
+                    fg: Some("cyan".to_string()),
+                    ..SyntaxStyleConfig::default()
+                },
+            ),
+        ]),
+        ..UserThemeConfig::default()
+    };
+    let (theme, warnings) = merge_syntax_theme(&user, None);
+    assert!(warnings.is_empty(), "{warnings:?}");
+    assert_eq!(
+        theme.style_for_capture("comment"),
+        Style::default()
+            .fg(Color::Rgb(128, 128, 128))
+            .bg(Color::Black)
+            .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+    );
+    assert_eq!(
+        theme.style_for_capture("function.builtin").fg,
+        Some(Color::Cyan)
+    );
+    let light = SyntaxTheme::builtin(SyntaxPalette::Light);
+    assert_eq!(
+        theme.style_for_capture("function.method"),
+        light.style_for_capture("function")
+    );
+}
+
+#[test]
+fn invalid_syntax_entries_produce_warnings() {
+    let user = UserThemeConfig {
+        syntax_palette: Some("neon".to_string()),
+        syntax: HashMap::from([
+            ("keyword.control".to_string(), SyntaxStyleConfig::default()),
+            (
+                "string".to_string(),
+                SyntaxStyleConfig {
+                    fg: Some("chartreuse-ish".to_string()),
+                    bg: None,
+                    modifiers: vec!["blink-fast".to_string()],
+                },
+            ),
+        ]),
+        ..UserThemeConfig::default()
+    };
+    let (theme, warnings) = merge_syntax_theme(&user, None);
+    assert_eq!(warnings.len(), 4, "{warnings:?}");
+    assert!(warnings[0].contains("unknown syntax palette 'neon'"));
+    assert!(warnings.iter().any(|w| w.contains("'keyword.control'")));
+    assert_eq!(theme.style_for_capture("string"), Style::default());
+}
+
+#[test]
+fn highlight_context_renders_with_configured_theme() {
+    let d = tempdir().expect("create tempdir");
+    let p = d.path().join("main.rs");
+    fs::write(&p, "fn main() {}\n").expect("write file");
+    let mut theme = SyntaxTheme::builtin(SyntaxPalette::HighContrast);
+    theme.set_style("keyword", Style::default().fg(Color::Magenta));
+    let ctx = HighlightContext::new().with_syntax_theme(theme);
+    let doc = load_preview(&p, 1024, &ctx);
+    let first = &doc.styled_lines[0][0];
+    assert_eq!(first.text, "fn");
+    assert_eq!(first.style.fg, Some(Color::Magenta));
+}
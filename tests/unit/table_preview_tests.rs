@@ -0,0 +1,171 @@
+use crossterm::event::Event;
+use fpv::app::state::{FocusPane, SelectedEntryMetadata, SessionState};
+use fpv::config::keymap::{action_from_name, default_keymap, Action};
+use fpv::fs::preview::load_preview;
+use fpv::fs::table::{parse_table, sniff_delimiter, ColumnAlignment};
+use fpv::highlight::syntax::HighlightContext;
+use fpv::tui::event_loop::handle_event;
+use fpv::tui::preview_pane::{draw_preview, preview_total_lines};
+use fpv::tui::status_bar::compose_preview_metadata_line_with_table;
+use ratatui::backend::TestBackend;
+use ratatui::Terminal;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+const FRUIT_CSV: &str = "name,qty,price\napple,3,0.5\nkiwi,12,1.25\n\"melon, honeydew\",1,\n";
+
+fn draw_rows(
+    doc: &fpv::app::state::PreviewDocument,
+    state: &SessionState,
+    width: u16,
+    height: u16,
+) -> Vec<String> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).expect("terminal");
+    terminal
+        .draw(|frame| {
+            draw_preview(
+                frame,
+                frame.size(),
+                doc,
+                state,
+                &fpv::config::load::ThemeProfile::default(),
+            )
+        })
+        .expect("draw");
+    let buffer = terminal.backend().buffer();
+    (0..height)
+        .map(|y| {
+            (0..width)
+                .map(|x| buffer.get(x, y).symbol())
+                .collect::<String>()
+        })
+        .collect()
+}
+
+#[test]
+fn csv_cells_are_parsed_with_column_widths_and_alignment() {
+    let table = parse_table(Path::new("fruit.csv"), None, FRUIT_CSV, false).expect("table");
+    assert_eq!(table.header, vec!["name", "qty", "price"]);
+    assert_eq!(table.rows[2], vec!["melon, honeydew", "1", ""]);
+    assert_eq!(table.widths, vec![15, 3, 5]);
+    assert_eq!(
+        table.alignments,
+        vec![
+            ColumnAlignment::Left,
+            ColumnAlignment::Right,
+            ColumnAlignment::Right
+        ]
+    );
+    assert_eq!(table.size_label(), "3 rows × 3 columns");
+}
+
+#[test]
+fn delimiters_are_sniffed() {
+    assert_eq!(sniff_delimiter("a;b;c\n1;2;3\n", 1), Some(b';'));
+    assert_eq!(sniff_delimiter("a\tb\n\"x\ty\"\tz\n", 1), Some(b'\t'));
+    assert_eq!(sniff_delimiter("a,b\n1,2,3\n", 1), None);
+
+    let table = parse_table(Path::new("eu.csv"), None, "a;b\n1,5;2\n", false).expect("table");
+    assert_eq!(table.delimiter, b';');
+    assert_eq!(table.rows[0], vec!["1,5", "2"]);
+
+    let piped = "id|user|shell\n1|root|/bin/sh\n2|daemon|/usr/sbin/nologin\n";
+    assert!(parse_table(Path::new("users.txt"), None, piped, false).is_some());
+    assert!(parse_table(Path::new("short.txt"), None, "a|b\n1|2\n", false).is_none());
+    assert!(parse_table(Path::new("notes.txt"), None, "Hello, world.\nBye.\n", false).is_none());
+    assert!(parse_table(Path::new("main.rs"), Some("Rust"), piped, false).is_none());
+}
+
+#[test]
+fn truncated_input_drops_its_partial_last_record() {
+    let table = parse_table(Path::new("fruit.csv"), None, "a,b\n1,2\n3,4", true).expect("table");
+    assert_eq!(table.rows, vec![vec!["1", "2"]]);
+}
+
+#[test]
+fn table_preview_keeps_the_header_while_scrolling() {
+    let d = tempdir().expect("tempdir");
+    let path = d.path().join("fruit.csv");
+    fs::write(&path, FRUIT_CSV).expect("write");
+    let doc = load_preview(&path, 4096, &HighlightContext::new());
+    assert_eq!(preview_total_lines(&doc), 5);
+
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.preview_scroll_row = 1;
+    let rows = draw_rows(&doc, &state, 40, 6);
+    // The last column holds the scroll indicator.
+    assert_eq!(rows[1], "│name            │ qty │ price        ││");
+    assert_eq!(rows[2], "│────────────────┼─────┼──────        █│");
+    assert_eq!(rows[3], "│kiwi            │  12 │  1.25        █│");
+    assert_eq!(rows[4], "│melon, honeydew │   1 │              █│");
+}
+
+#[test]
+fn wide_tables_scroll_a_column_at_a_time() {
+    let d = tempdir().expect("tempdir");
+    let path = d.path().join("fruit.csv");
+    fs::write(&path, FRUIT_CSV).expect("write");
+    let doc = load_preview(&path, 4096, &HighlightContext::new());
+
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.preview_table_column = 1;
+    let rows = draw_rows(&doc, &state, 24, 5);
+    assert!(rows[0].starts_with("┌ columns 2–3 of 3 "), "{}", rows[0]);
+    assert!(rows[1].starts_with("│qty │ price "), "{}", rows[1]);
+
+    let rows = draw_rows(&doc, &SessionState::new(PathBuf::from(".")), 24, 5);
+    assert!(rows[0].starts_with("┌ columns 1–2 of 3 "), "{}", rows[0]);
+    assert!(rows[3].starts_with("│apple           │   3"), "{}", rows[3]);
+}
+
+#[test]
+fn expand_and_collapse_scroll_a_focused_table() {
+    let d = tempdir().expect("tempdir");
+    let path = d.path().join("fruit.csv");
+    fs::write(&path, FRUIT_CSV).expect("write");
+    let doc = load_preview(&path, 4096, &HighlightContext::new());
+    let bindings = default_keymap();
+    let mut state = SessionState::new(PathBuf::from("."));
+    state.focus_pane = FocusPane::Preview;
+    let press = |state: &mut SessionState, action: Action| {
+        handle_event(
+            state,
+            &mut Vec::new(),
+            &bindings,
+            &doc,
+            0,
+            10,
+            Event::Key(bindings[&action]),
+        )
+        .expect("handle key");
+        state.preview_table_column
+    };
+
+    assert_eq!(press(&mut state, Action::Expand), 1);
+    assert_eq!(press(&mut state, Action::Expand), 2);
+    // The last column stays in view.
+    assert_eq!(press(&mut state, Action::Expand), 2);
+    assert_eq!(press(&mut state, Action::Collapse), 1);
+    assert_eq!(press(&mut state, Action::Collapse), 0);
+    assert_eq!(press(&mut state, Action::Collapse), 0);
+}
+
+#[test]
+fn metadata_line_shows_the_table_size() {
+    let metadata = SelectedEntryMetadata {
+        filename: "fruit.csv".to_string(),
+        size_text: "64 B".to_string(),
+        ..SelectedEntryMetadata::default()
+    };
+    let table = parse_table(Path::new("fruit.csv"), None, FRUIT_CSV, false).expect("table");
+    let line = compose_preview_metadata_line_with_table(&metadata, None, None, Some(&table), 200);
+    assert!(
+        line.starts_with("CSV(.csv) | 3 rows × 3 columns | 64 B"),
+        "{line}"
+    );
+    assert_eq!(
+        action_from_name("toggle_table_view"),
+        Some(Action::ToggleTableView)
+    );
+}
@@ -19,6 +19,7 @@ fn sample_nodes() -> Vec<TreeNode> {
             expanded: false,
             readable: true,
             children_loaded: true,
+            entry_class: None,
         },
         TreeNode {
             path: PathBuf::from("b"),
@@ -28,6 +29,7 @@ fn sample_nodes() -> Vec<TreeNode> {
             expanded: false,
             readable: true,
             children_loaded: true,
+            entry_class: None,
         },
     ]
 }
@@ -154,6 +156,7 @@ fn style_resolution_uses_theme_and_hidden_dimming() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     };
     let style = node_style(&node, &theme, None);
     assert_eq!(style.fg, Some(Color::Cyan));
@@ -172,6 +175,7 @@ fn gitignored_entries_use_dim_gray_style() {
         expanded: false,
         readable: true,
         children_loaded: false,
+        entry_class: None,
     };
     let style = node_style(&node, &theme, Some(GitFileStatus::Ignored));
     assert_eq!(style.fg, Some(Color::DarkGray));
@@ -256,6 +260,7 @@ fn directory_change_marker_detects_nested_uncommitted_changes() {
         expanded: false,
         readable: true,
         children_loaded: true,
+        entry_class: None,
     };
     let mut file_statuses = std::collections::HashMap::new();
     file_statuses.insert(PathBuf::from("src/lib/main.rs"), GitFileStatus::Modified);
@@ -280,6 +285,7 @@ fn directory_change_marker_ignores_gitignored_entries() {
         expanded: false,
         readable: true,
         children_loaded: true,
+        entry_class: None,
     };
     let mut file_statuses = std::collections::HashMap::new();
     file_statuses.insert(PathBuf::from("target/"), GitFileStatus::Ignored);
@@ -304,6 +310,7 @@ fn directory_change_marker_applies_to_directories_only() {
         expanded: false,
         readable: true,
         children_loaded: true,
+        entry_class: None,
     };
     let mut file_statuses = std::collections::HashMap::new();
     file_statuses.insert(PathBuf::from("src/main.rs"), GitFileStatus::Modified);
@@ -1,13 +1,45 @@
+#[path = "unit/archive_tests.rs"]
+mod archive_tests;
+#[path = "unit/cli_args_tests.rs"]
+mod cli_args_tests;
+#[path = "unit/clipboard_tests.rs"]
+mod clipboard_tests;
+#[path = "unit/color_scheme_tests.rs"]
+mod color_scheme_tests;
+#[path = "unit/color_support_tests.rs"]
+mod color_support_tests;
 #[path = "unit/config_conflict_tests.rs"]
 mod config_conflict_tests;
 #[path = "unit/config_parse_tests.rs"]
 mod config_parse_tests;
 #[path = "unit/current_dir_listing_tests.rs"]
 mod current_dir_listing_tests;
+#[path = "unit/custom_query_tests.rs"]
+mod custom_query_tests;
+#[path = "unit/encoding_tests.rs"]
+mod encoding_tests;
 #[path = "unit/enter_directory_tests.rs"]
 mod enter_directory_tests;
+#[path = "unit/external_previewer_tests.rs"]
+mod external_previewer_tests;
+#[path = "unit/html_export_tests.rs"]
+mod html_export_tests;
+#[path = "unit/icon_tests.rs"]
+mod icon_tests;
+#[path = "unit/image_preview_tests.rs"]
+mod image_preview_tests;
 #[path = "unit/input_mapping_tests.rs"]
 mod input_mapping_tests;
+#[path = "unit/language_detection_tests.rs"]
+mod language_detection_tests;
+#[path = "unit/launch_target_tests.rs"]
+mod launch_target_tests;
+#[path = "unit/ls_colors_tests.rs"]
+mod ls_colors_tests;
+#[path = "unit/markdown_render_tests.rs"]
+mod markdown_render_tests;
+#[path = "unit/paged_preview_tests.rs"]
+mod paged_preview_tests;
 #[path = "unit/permission_block_tests.rs"]
 mod permission_block_tests;
 #[path = "unit/permission_display_value_tests.rs"]
@@ -18,12 +50,24 @@ mod preview_metadata_footer_tests;
 mod preview_mode_tests;
 #[path = "unit/preview_panel_title_tests.rs"]
 mod preview_panel_title_tests;
+#[path = "unit/preview_selection_tests.rs"]
+mod preview_selection_tests;
+#[path = "unit/print_mode_tests.rs"]
+mod print_mode_tests;
 #[path = "unit/root_boundary_tests.rs"]
 mod root_boundary_tests;
 #[path = "unit/selection_bounds_tests.rs"]
 mod selection_bounds_tests;
 #[path = "unit/selection_revalidation_tests.rs"]
 mod selection_revalidation_tests;
+#[path = "unit/structured_view_tests.rs"]
+mod structured_view_tests;
+#[path = "unit/style_rules_tests.rs"]
+mod style_rules_tests;
+#[path = "unit/syntax_theme_tests.rs"]
+mod syntax_theme_tests;
+#[path = "unit/table_preview_tests.rs"]
+mod table_preview_tests;
 #[path = "unit/tree_state_tests.rs"]
 mod tree_state_tests;
 